@@ -1,8 +1,43 @@
 use actix_web::web;
+use log::warn;
 use sqlx::sqlite::SqliteConnectOptions;
 use sqlx::sqlite::SqlitePool;
 
-use crate::shared::utils::create_random_key;
+use crate::shared::utils::{create_random_key, public_key_part};
+use crate::user::domain::models::ownership::DELETED_USER_NAME;
+
+/// Columns added after a table was first released, as `(table, column, definition)`.
+/// `CREATE TABLE IF NOT EXISTS` leaves existing databases untouched, so these are added on startup.
+const ADDED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("urls", "password_hash", "TEXT"),
+    ("urls", "title", "TEXT"),
+    ("urls", "show_interstitial", "BOOLEAN NOT NULL DEFAULT 0"),
+    ("urls", "interstitial_clicks", "INTEGER NOT NULL DEFAULT 0"),
+    ("urls", "created_at", "DATETIME"),
+    ("urls", "single_use", "BOOLEAN NOT NULL DEFAULT 0"),
+    ("urls", "require_signature", "BOOLEAN NOT NULL DEFAULT 0"),
+    ("urls", "active_from", "DATETIME"),
+    ("urls", "active_until", "DATETIME"),
+    ("urls", "org_id", "INTEGER"),
+    ("urls", "custom_alias", "BOOLEAN NOT NULL DEFAULT 0"),
+    ("users", "plan", "TEXT"),
+    ("urls", "folder", "TEXT"),
+    ("urls", "description", "TEXT"),
+    ("urls", "notes", "TEXT"),
+    ("urls", "favicon_url", "TEXT"),
+    ("urls", "og_title", "TEXT"),
+    ("urls", "og_description", "TEXT"),
+    ("urls", "og_image_url", "TEXT"),
+    ("urls", "last_check_status", "INTEGER"),
+    ("urls", "last_check_url", "TEXT"),
+    ("urls", "last_check_latency_ms", "INTEGER"),
+    ("urls", "last_check_error", "TEXT"),
+    ("urls", "last_checked_at", "DATETIME"),
+    ("urls", "failed_checks", "INTEGER NOT NULL DEFAULT 0"),
+    ("urls", "fallback_url", "TEXT"),
+    ("urls", "max_clicks", "INTEGER"),
+    ("url_clicks", "source", "TEXT NOT NULL DEFAULT 'direct'"),
+];
 
 pub async fn connect_to_db() -> Result<sqlx::SqlitePool, sqlx::Error> {
     let options = SqliteConnectOptions::new()
@@ -10,7 +45,22 @@ pub async fn connect_to_db() -> Result<sqlx::SqlitePool, sqlx::Error> {
         .create_if_missing(true);
 
     let pool = sqlx::SqlitePool::connect_with(options).await?;
+    create_schema(&pool).await?;
+
+    // Sembrar la base de dades amb dades inicials (propaga l'error en lloc de `expect`)
+    seed_data(web::Data::new(pool.clone())).await?;
+
+    // El compte 'deleted-user' de versions anteriors tenia una clau d'API vàlida
+    migrate_deleted_user_placeholder(&pool).await?;
 
+    // Enllaços anteriors a les organitzacions: passen a l'espai personal del seu usuari
+    migrate_personal_workspaces(&pool).await?;
+
+    Ok(pool)
+}
+
+/// Create every table (and column) the repositories use that is still missing. Idempotent.
+pub async fn create_schema(pool: &SqlitePool) -> Result<(), sqlx::Error> {
     // Crear la taula 'users' si no existeix
     sqlx::query(
         r#"
@@ -18,11 +68,12 @@ pub async fn connect_to_db() -> Result<sqlx::SqlitePool, sqlx::Error> {
             id INTEGER PRIMARY KEY,
             username TEXT NOT NULL,
             email TEXT NOT NULL,
-            api_key TEXT NOT NULL
+            api_key TEXT NOT NULL,
+            plan TEXT
         );
         "#,
     )
-    .execute(&pool)
+    .execute(pool)
     .await?;
 
     // Crea la taula URL
@@ -36,12 +87,39 @@ pub async fn connect_to_db() -> Result<sqlx::SqlitePool, sqlx::Error> {
             is_active BOOLEAN NOT NULL,
             clicks INTEGER NOT NULL,
             user_id INTEGER NOT NULL,
-            FOREIGN KEY (user_id) REFERENCES users(id)
+            password_hash TEXT,
+            title TEXT,
+            show_interstitial BOOLEAN NOT NULL DEFAULT 0,
+            interstitial_clicks INTEGER NOT NULL DEFAULT 0,
+            created_at DATETIME,
+            single_use BOOLEAN NOT NULL DEFAULT 0,
+            require_signature BOOLEAN NOT NULL DEFAULT 0,
+            active_from DATETIME,
+            active_until DATETIME,
+            org_id INTEGER,
+            custom_alias BOOLEAN NOT NULL DEFAULT 0,
+            folder TEXT,
+            description TEXT,
+            notes TEXT,
+            favicon_url TEXT,
+            og_title TEXT,
+            og_description TEXT,
+            og_image_url TEXT,
+            last_check_status INTEGER,
+            last_check_url TEXT,
+            last_check_latency_ms INTEGER,
+            last_check_error TEXT,
+            last_checked_at DATETIME,
+            failed_checks INTEGER NOT NULL DEFAULT 0,
+            fallback_url TEXT,
+            max_clicks INTEGER,
+            FOREIGN KEY (user_id) REFERENCES users(id),
+            FOREIGN KEY (org_id) REFERENCES organizations(id)
         );
         CREATE INDEX IF NOT EXISTS idx_user_id ON urls (user_id);
         "#,
     )
-    .execute(&pool)
+    .execute(pool)
     .await?;
 
     sqlx::query(
@@ -58,13 +136,265 @@ pub async fn connect_to_db() -> Result<sqlx::SqlitePool, sqlx::Error> {
         CREATE INDEX IF NOT EXISTS idx_used_keys_key_value ON used_keys (key_value);
         "#,
     )
-    .execute(&pool)
+    .execute(pool)
     .await?;
 
-    // Sembrar la base de dades amb dades inicials (propaga l'error en lloc de `expect`)
-    seed_data(web::Data::new(pool.clone())).await?;
+    // Regles de geo-encaminament per enllaç i registre de clics individuals
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS url_geo_rules (
+            id INTEGER PRIMARY KEY,
+            url_key TEXT NOT NULL,
+            country_code TEXT NOT NULL,
+            target_url TEXT NOT NULL,
+            UNIQUE (url_key, country_code)
+        );
+        CREATE TABLE IF NOT EXISTS url_clicks (
+            id INTEGER PRIMARY KEY,
+            url_key TEXT NOT NULL,
+            country TEXT,
+            source TEXT NOT NULL DEFAULT 'direct',
+            clicked_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE INDEX IF NOT EXISTS idx_url_clicks_url_key ON url_clicks (url_key);
+        CREATE TABLE IF NOT EXISTS unknown_key_hits (
+            key TEXT PRIMARY KEY,
+            hits INTEGER NOT NULL,
+            first_seen_at DATETIME NOT NULL,
+            last_seen_at DATETIME NOT NULL
+        );
+        "#,
+    )
+    .execute(pool)
+    .await?;
 
-    Ok(pool)
+    create_org_tables(pool).await?;
+    create_tag_tables(pool).await?;
+
+    // Bases de dades creades amb versions anteriors: afegim les columnes noves
+    for (table, column, definition) in ADDED_COLUMNS {
+        add_column_if_missing(pool, table, column, definition).await?;
+    }
+
+    // Versions anteriors podien repetir claus, noms d'usuari i correus: els resolem (o avisem)
+    // abans de crear els índexs únics
+    migrate_duplicate_url_keys(pool).await?;
+    migrate_duplicate_usernames(pool).await?;
+    check_duplicate_emails(pool).await?;
+    sqlx::query(
+        r#"
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_users_username ON users (username COLLATE NOCASE);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_users_email ON users (email COLLATE NOCASE);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_urls_key ON urls (key);
+        CREATE UNIQUE INDEX IF NOT EXISTS idx_urls_secret_key ON urls (secret_key);
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Give a fresh key to every link sharing its public or secret key with an older link, which
+/// older versions could create, so the unique indexes can be built. The oldest link keeps the
+/// key (it is the one lookups returned), along with the clicks, rules and tags stored under it.
+/// New keys have the form of generated ones: a public key is the public part of a fresh pair,
+/// logged, reserved in `used_keys` and withdrawn from the `generated_keys` pool; a secret key is
+/// a whole pair. Idempotent.
+pub async fn migrate_duplicate_url_keys(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    for column in ["key", "secret_key"] {
+        let duplicates: Vec<(i64, String, i64)> = sqlx::query_as(&format!(
+            "SELECT id, {column}, user_id FROM urls WHERE id NOT IN (SELECT MIN(id) FROM urls GROUP BY {column}) ORDER BY id"
+        ))
+        .fetch_all(pool)
+        .await?;
+        for (id, old_key, user_id) in duplicates {
+            let mut tx = pool.begin().await?;
+            let new_key = loop {
+                let key_pair = create_random_key(8);
+                let candidate = if column == "key" { public_key_part(&key_pair).to_string() } else { key_pair };
+                let taken: (bool,) = sqlx::query_as(&format!(
+                    "SELECT EXISTS (SELECT 1 FROM urls WHERE {column} = $1) OR EXISTS (SELECT 1 FROM used_keys WHERE key_value = $1)"
+                ))
+                .bind(&candidate)
+                .fetch_one(&mut *tx)
+                .await?;
+                if !taken.0 {
+                    break candidate;
+                }
+            };
+            sqlx::query(&format!("UPDATE urls SET {column} = $1 WHERE id = $2"))
+                .bind(&new_key)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            if column == "key" {
+                sqlx::query("INSERT INTO used_keys (key_value, user_id) VALUES ($1, $2)")
+                    .bind(&new_key)
+                    .bind(user_id)
+                    .execute(&mut *tx)
+                    .await?;
+                sqlx::query("DELETE FROM generated_keys WHERE substr(key_value, 1, length($1) + 1) = $1 || '_'")
+                    .bind(&new_key)
+                    .execute(&mut *tx)
+                    .await?;
+            }
+            tx.commit().await?;
+            if column == "key" {
+                warn!("Link {} shared the key '{}' with an older link and is now served at '{}'", id, old_key, new_key);
+            } else {
+                warn!("Link {} shared its secret key with an older link and was given a new one", id);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Organizations (shared link workspaces) and their members.
+pub async fn create_org_tables(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS organizations (
+            id INTEGER PRIMARY KEY,
+            name TEXT NOT NULL,
+            personal_user_id INTEGER UNIQUE,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS organization_members (
+            org_id INTEGER NOT NULL,
+            user_id INTEGER NOT NULL,
+            role TEXT NOT NULL,
+            PRIMARY KEY (org_id, user_id),
+            FOREIGN KEY (org_id) REFERENCES organizations(id),
+            FOREIGN KEY (user_id) REFERENCES users(id)
+        );
+        CREATE INDEX IF NOT EXISTS idx_organization_members_user_id ON organization_members (user_id);
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Tags of a workspace and the links they are attached to.
+pub async fn create_tag_tables(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS tags (
+            id INTEGER PRIMARY KEY,
+            org_id INTEGER NOT NULL,
+            name TEXT NOT NULL,
+            created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE (org_id, name),
+            FOREIGN KEY (org_id) REFERENCES organizations(id)
+        );
+        CREATE TABLE IF NOT EXISTS url_tags (
+            url_key TEXT NOT NULL,
+            tag_id INTEGER NOT NULL,
+            PRIMARY KEY (url_key, tag_id),
+            FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+        );
+        CREATE INDEX IF NOT EXISTS idx_url_tags_tag_id ON url_tags (tag_id);
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Rename every user whose username matches an older user's, ignoring case, to
+/// `<username>-<id>`, so the unique index can be built. The oldest user keeps the name; each
+/// rename is logged. Idempotent.
+pub async fn migrate_duplicate_usernames(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let duplicates: Vec<(i64, String)> = sqlx::query_as(
+        "SELECT id, username FROM users WHERE id NOT IN (SELECT MIN(id) FROM users GROUP BY username COLLATE NOCASE) ORDER BY id",
+    )
+    .fetch_all(pool)
+    .await?;
+    for (id, username) in duplicates {
+        let mut new_username = format!("{username}-{id}");
+        while sqlx::query("SELECT 1 FROM users WHERE username = $1 COLLATE NOCASE").bind(&new_username).fetch_optional(pool).await?.is_some() {
+            new_username.push('_');
+        }
+        sqlx::query("UPDATE users SET username = $1 WHERE id = $2").bind(&new_username).bind(id).execute(pool).await?;
+        warn!("User {} shared the username '{}' with an older user and was renamed '{}'", id, username, new_username);
+    }
+    Ok(())
+}
+
+/// Fail with the users sharing an email, ignoring case: which account keeps it is the
+/// operator's call, so the unique index is only built once they were merged or changed.
+pub async fn check_duplicate_emails(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let duplicates: Vec<(String, String)> = sqlx::query_as(
+        r#"
+        SELECT MIN(email), group_concat(id, ', ') FROM users
+        GROUP BY email COLLATE NOCASE HAVING COUNT(*) > 1 ORDER BY MIN(id)
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+    if duplicates.is_empty() {
+        return Ok(());
+    }
+    let listed: Vec<String> = duplicates.iter().map(|(email, ids)| format!("'{email}' (users {ids})")).collect();
+    Err(sqlx::Error::Configuration(
+        format!("emails must be unique, change or merge the users sharing one: {}", listed.join("; ")).into(),
+    ))
+}
+
+/// Clear the API key of the `deleted-user` placeholder account, which older versions created
+/// with a random one: an empty key never authenticates and hides the account from listings.
+pub async fn migrate_deleted_user_placeholder(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE users SET api_key = '' WHERE username = $1 AND email = ''")
+        .bind(DELETED_USER_NAME)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Give every user without one a personal organization (owned by the user) and move links
+/// that predate organizations into their creator's personal workspace. Idempotent.
+pub async fn migrate_personal_workspaces(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"
+        INSERT INTO organizations (name, personal_user_id)
+        SELECT username, id FROM users
+        WHERE api_key != '' AND id NOT IN (SELECT personal_user_id FROM organizations WHERE personal_user_id IS NOT NULL);
+        "#,
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        r#"
+        INSERT OR IGNORE INTO organization_members (org_id, user_id, role)
+        SELECT id, personal_user_id, 'owner' FROM organizations WHERE personal_user_id IS NOT NULL;
+        "#,
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        r#"
+        UPDATE urls SET org_id = (SELECT id FROM organizations WHERE personal_user_id = urls.user_id)
+        WHERE org_id IS NULL;
+        "#,
+    )
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await
+}
+
+/// `ALTER TABLE ... ADD COLUMN` unless `table` already has `column` (SQLite has no `IF NOT EXISTS` for columns).
+pub async fn add_column_if_missing(
+    pool: &SqlitePool, table: &str, column: &str, definition: &str,
+) -> Result<(), sqlx::Error> {
+    let exists: (i64,) = sqlx::query_as(&format!("SELECT COUNT(*) FROM pragma_table_info('{table}') WHERE name = ?1"))
+        .bind(column)
+        .fetch_one(pool)
+        .await?;
+    if exists.0 == 0 {
+        sqlx::query(&format!("ALTER TABLE {table} ADD COLUMN {column} {definition}")).execute(pool).await?;
+    }
+    Ok(())
 }
 
 pub async fn seed_data(db_pool: web::Data<SqlitePool>) -> Result<(), sqlx::Error> {
@@ -80,7 +410,7 @@ pub async fn seed_data(db_pool: web::Data<SqlitePool>) -> Result<(), sqlx::Error
             INSERT INTO users (username, email, api_key)
             SELECT ?1, ?2, ?3
             WHERE NOT EXISTS (
-                SELECT 1 FROM users WHERE username = ?1 OR email = ?2
+                SELECT 1 FROM users WHERE username = ?1 COLLATE NOCASE OR email = ?2 COLLATE NOCASE
             );
             "#,
         )
@@ -158,6 +488,17 @@ mod connect_tests {
             .await?;
         assert!(row.0 >= 1);
 
+        let geo: (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name IN ('url_geo_rules', 'url_clicks')",
+        )
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(geo.0, 2);
+
+        // seeded links end up in their owner's personal workspace
+        let orphans: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM urls WHERE org_id IS NULL").fetch_one(&pool).await?;
+        assert_eq!(orphans.0, 0);
+
         // cleanup - remove file if created (best-effort)
         let path = Path::new("./sqlite:database.db");
         if path.exists() {
@@ -232,4 +573,111 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn add_column_if_missing_is_idempotent() -> Result<(), sqlx::Error> {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await?;
+        pool.execute("CREATE TABLE urls (id INTEGER PRIMARY KEY, key TEXT NOT NULL);").await?;
+
+        add_column_if_missing(&pool, "urls", "password_hash", "TEXT").await?;
+        add_column_if_missing(&pool, "urls", "password_hash", "TEXT").await?;
+
+        let count: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM pragma_table_info('urls') WHERE name = 'password_hash'")
+            .fetch_one(&pool)
+            .await?;
+        assert_eq!(count.0, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn migrate_personal_workspaces_moves_links_once() -> Result<(), sqlx::Error> {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await?;
+        pool.execute(r#"
+            CREATE TABLE users (id INTEGER PRIMARY KEY, username TEXT NOT NULL, email TEXT NOT NULL, api_key TEXT NOT NULL);
+            CREATE TABLE urls (id INTEGER PRIMARY KEY, key TEXT NOT NULL, user_id INTEGER NOT NULL, org_id INTEGER);
+            INSERT INTO users (id, username, email, api_key) VALUES (1, 'alice', 'a@x.com', 'ka'), (2, 'bob', 'b@x.com', 'kb');
+            INSERT INTO urls (key, user_id) VALUES ('a1', 1), ('a2', 1), ('b1', 2);
+        "#).await?;
+        create_org_tables(&pool).await?;
+
+        migrate_personal_workspaces(&pool).await?;
+        migrate_personal_workspaces(&pool).await?;
+
+        let orgs: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM organizations").fetch_one(&pool).await?;
+        assert_eq!(orgs.0, 2);
+        let owners: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM organization_members WHERE role = 'owner'").fetch_one(&pool).await?;
+        assert_eq!(owners.0, 2);
+        let alice: (i64,) = sqlx::query_as(
+            "SELECT COUNT(*) FROM urls JOIN organizations o ON o.id = urls.org_id WHERE o.personal_user_id = 1",
+        )
+        .fetch_one(&pool)
+        .await?;
+        assert_eq!(alice.0, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn create_schema_rekeys_duplicate_links_before_the_unique_indexes() -> Result<(), sqlx::Error> {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await?;
+        pool.execute(r#"
+            CREATE TABLE users (id INTEGER PRIMARY KEY, username TEXT NOT NULL, email TEXT NOT NULL, api_key TEXT NOT NULL);
+            CREATE TABLE urls (id INTEGER PRIMARY KEY, key TEXT NOT NULL, secret_key TEXT NOT NULL, target_url TEXT NOT NULL, is_active BOOLEAN NOT NULL, clicks INTEGER NOT NULL, user_id INTEGER NOT NULL);
+            INSERT INTO users (id, username, email, api_key) VALUES (1, 'alice', 'a@x.com', 'ka');
+            INSERT INTO urls (id, key, secret_key, target_url, is_active, clicks, user_id) VALUES
+                (1, 'promo', 'S1', 'http://a', 1, 0, 1), (2, 'promo', 'S2', 'http://b', 1, 0, 1), (3, 'other', 'S1', 'http://c', 1, 0, 1);
+        "#).await?;
+
+        create_schema(&pool).await?;
+        create_schema(&pool).await?;
+
+        let urls: Vec<(i64, String, String)> = sqlx::query_as("SELECT id, key, secret_key FROM urls ORDER BY id").fetch_all(&pool).await?;
+        assert_eq!((urls[0].1.as_str(), urls[0].2.as_str()), ("promo", "S1"), "the oldest link keeps its keys");
+        assert!(urls[1].1 != "promo" && urls[1].2 == "S2");
+        assert!(urls[2].1 == "other" && urls[2].2 != "S1");
+        // new keys look like generated ones: a public part for the key, a whole pair for the secret key
+        assert!(urls[1].1.len() == 8 && urls[1].1.chars().all(|c| c.is_ascii_alphanumeric()), "{}", urls[1].1);
+        assert_eq!(urls[2].2.split('_').map(str::len).collect::<Vec<_>>(), vec![8, 8]);
+        let reserved: Vec<(String,)> = sqlx::query_as("SELECT key_value FROM used_keys").fetch_all(&pool).await?;
+        assert_eq!(reserved, vec![(urls[1].1.clone(),)]);
+        let duplicate = pool.execute("INSERT INTO urls (key, secret_key, target_url, is_active, clicks, user_id) VALUES ('promo', 'S9', 'http://d', 1, 0, 1)").await;
+        assert!(duplicate.is_err(), "the unique index on key exists");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn create_schema_renames_duplicate_usernames_and_reports_duplicate_emails() -> Result<(), sqlx::Error> {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await?;
+        pool.execute(r#"
+            CREATE TABLE users (id INTEGER PRIMARY KEY, username TEXT NOT NULL, email TEXT NOT NULL, api_key TEXT NOT NULL);
+            INSERT INTO users (id, username, email, api_key) VALUES
+                (1, 'alice', 'a@x.com', 'k1'), (2, 'Alice', 'b@x.com', 'k2'), (3, 'bob', 'A@X.com', 'k3'), (4, 'carol', 'c@x.com', 'k4');
+        "#).await?;
+
+        let err = create_schema(&pool).await.unwrap_err().to_string();
+        assert!(err.to_lowercase().contains("'a@x.com' (users 1, 3)"), "{err}");
+        let names: Vec<(String,)> = sqlx::query_as("SELECT username FROM users ORDER BY id").fetch_all(&pool).await?;
+        assert_eq!(names[..2], [("alice".to_string(),), ("Alice-2".to_string(),)]);
+
+        pool.execute("UPDATE users SET email = 'bob@x.com' WHERE id = 3").await?;
+        create_schema(&pool).await?;
+        create_schema(&pool).await?;
+        let duplicate = pool.execute("INSERT INTO users (username, email, api_key) VALUES ('ALICE', 'd@x.com', 'k5')").await;
+        assert!(duplicate.is_err(), "the unique index on username exists");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn migrate_deleted_user_placeholder_clears_its_api_key() -> Result<(), sqlx::Error> {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await?;
+        pool.execute(r#"
+            CREATE TABLE users (id INTEGER PRIMARY KEY, username TEXT NOT NULL, email TEXT NOT NULL, api_key TEXT NOT NULL);
+            INSERT INTO users (id, username, email, api_key) VALUES (1, 'alice', 'a@x.com', 'ka'), (2, 'deleted-user', '', 'kd');
+        "#).await?;
+
+        migrate_deleted_user_placeholder(&pool).await?;
+
+        let keys: Vec<(String,)> = sqlx::query_as("SELECT api_key FROM users ORDER BY id").fetch_all(&pool).await?;
+        assert_eq!(keys, vec![("ka".to_string(),), (String::new(),)]);
+        Ok(())
+    }
 }
@@ -1,6 +1,43 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 #[cfg(not(test))]
 use dotenv::dotenv;
+use crate::shared::rate_limiter::RateLimitPolicy;
+use crate::user::domain::models::plan::Plan;
+use ipnet::IpNet;
+use std::net::IpAddr;
+
+pub const DEFAULT_UNLOCK_MAX_ATTEMPTS: u32 = 5;
+pub const DEFAULT_UNLOCK_WINDOW_SECS: u64 = 900;
+pub const DEFAULT_UNLOCK_COOKIE_TTL_SECS: i64 = 600;
+pub const DEFAULT_SIGNED_LINK_MAX_TTL_SECS: i64 = 30 * 24 * 3600;
+pub const DEFAULT_PLANS: &str = "free:1000/200/10,pro:100000/10000/1000,unlimited:*/*/*";
+pub const DEFAULT_PLAN: &str = "free";
+pub const DEFAULT_RATE_LIMIT_REDIRECT: &str = "300/60";
+pub const DEFAULT_RATE_LIMIT_API: &str = "60/60";
+pub const DEFAULT_RATE_LIMIT_USERS: &str = "10/60";
+pub const DEFAULT_METADATA_TIMEOUT_SECS: u64 = 5;
+pub const DEFAULT_METADATA_MAX_BYTES: usize = 256 * 1024;
+pub const DEFAULT_LINK_CHECK_INTERVAL_SECS: u64 = 24 * 3600;
+pub const DEFAULT_LINK_CHECK_TIMEOUT_SECS: u64 = 10;
+pub const DEFAULT_DEAD_LINK_THRESHOLD: i32 = 3;
+pub const DEFAULT_LINK_CACHE_CAPACITY: usize = 10_000;
+pub const DEFAULT_LINK_CACHE_TTL_SECS: u64 = 60;
+#[cfg(feature = "redis")]
+pub const DEFAULT_REDIS_KEY_PREFIX: &str = "shortener:";
+#[cfg(feature = "redis")]
+pub const DEFAULT_REDIS_CACHE_TTL_SECS: u64 = 300;
+#[cfg(feature = "redis")]
+pub const DEFAULT_REDIS_CLICK_SYNC_SECS: u64 = 10;
+
+/// Where users, workspaces and links are kept.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum Storage {
+    /// The SQLite database file, created on first start.
+    #[default]
+    Sqlite,
+    /// Process memory, seeded with the demo users and lost on exit.
+    Memory,
+}
 
 #[derive(Parser, Debug, Clone)]
 #[command(author, version, about)]
@@ -13,9 +50,232 @@ pub struct AppConfig {
 
     #[arg(short, long, env("PROTOCOL"), default_value = "https")]
     pub protocol: String,
+
+    /// Backend of the repositories: `sqlite`, or `memory` for demos.
+    #[arg(long, env("STORAGE"), value_enum, default_value_t = Storage::Sqlite)]
+    pub storage: Storage,
+
+    /// Path to a local MaxMind-format `.mmdb` country database used for geo routing.
+    /// Geo routing is disabled when unset; lookups never leave the process.
+    #[arg(long, env("GEOIP_DB_PATH"))]
+    pub geoip_db_path: Option<String>,
+
+    /// Proxies whose `X-Forwarded-For` header is trusted (comma separated IPs or CIDR ranges).
+    #[arg(long, env("TRUSTED_PROXIES"), value_delimiter = ',', value_parser = parse_trusted_proxy)]
+    pub trusted_proxies: Vec<IpNet>,
+
+    /// Secret used to sign the cookie issued after unlocking a password-protected link.
+    /// A random secret is generated at startup when unset (cookies then die with the process).
+    #[arg(long, env("COOKIE_SECRET"), hide_env_values = true)]
+    pub cookie_secret: Option<String>,
+
+    /// Failed password attempts allowed per link within `unlock_window_secs`.
+    #[arg(long, env("UNLOCK_MAX_ATTEMPTS"), default_value_t = DEFAULT_UNLOCK_MAX_ATTEMPTS)]
+    pub unlock_max_attempts: u32,
+
+    #[arg(long, env("UNLOCK_WINDOW_SECS"), default_value_t = DEFAULT_UNLOCK_WINDOW_SECS)]
+    pub unlock_window_secs: u64,
+
+    /// Lifetime of the signed unlock cookie.
+    #[arg(long, env("UNLOCK_COOKIE_TTL_SECS"), default_value_t = DEFAULT_UNLOCK_COOKIE_TTL_SECS)]
+    pub unlock_cookie_ttl_secs: i64,
+
+    /// Seconds before the preview page continues on its own; `0` disables the countdown.
+    #[arg(long, env("INTERSTITIAL_COUNTDOWN_SECS"), default_value_t = 0)]
+    pub interstitial_countdown_secs: u32,
+
+    /// Secret used to sign expiring link variants (`?exp=..&sig=..`). Signed links are disabled
+    /// when unset; rotating it invalidates every link minted so far.
+    #[arg(long, env("SIGNING_SECRET"), hide_env_values = true)]
+    pub signing_secret: Option<String>,
+
+    /// Longest lifetime a signed link may be minted with.
+    #[arg(long, env("SIGNED_LINK_MAX_TTL_SECS"), default_value_t = DEFAULT_SIGNED_LINK_MAX_TTL_SECS)]
+    pub signed_link_max_ttl_secs: i64,
+
+    /// Schemes target URLs may use (comma separated, case insensitive).
+    #[arg(long, env("ALLOWED_SCHEMES"), value_delimiter = ',', default_value = "http,https")]
+    pub allowed_schemes: Vec<String>,
+
+    /// Sort the query parameters of target URLs by name before storing / deduplicating them.
+    #[arg(long, env("SORT_QUERY_PARAMS"))]
+    pub sort_query_params: bool,
+
+    /// Local file with extra `block` / `allow` host rules for link targets, reloaded when it changes.
+    /// Internal hosts (localhost, private ranges, `.internal`) are blocked even without it.
+    #[arg(long, env("POLICY_FILE"))]
+    pub policy_file: Option<String>,
+
+    /// API keys allowed to call the admin endpoints (comma separated).
+    #[arg(long, env("ADMIN_API_KEYS"), value_delimiter = ',', hide_env_values = true)]
+    pub admin_api_keys: Vec<String>,
+
+    /// HTML file served before a scheduled link goes live (built-in page when unset).
+    #[arg(long, env("COMING_SOON_PAGE"))]
+    pub coming_soon_page: Option<String>,
+
+    /// HTML file served once a scheduled link has ended (built-in page when unset).
+    #[arg(long, env("ENDED_PAGE"))]
+    pub ended_page: Option<String>,
+
+    /// HTML file served with 404 for unknown and deactivated keys (built-in page when unset).
+    #[arg(long, env("NOT_FOUND_PAGE"), conflicts_with = "not_found_redirect_url")]
+    pub not_found_page: Option<String>,
+
+    /// Redirect unknown and deactivated keys to this absolute http(s) URL instead of a 404 page.
+    #[arg(long, env("NOT_FOUND_REDIRECT_URL"))]
+    pub not_found_redirect_url: Option<String>,
+
+    /// Plans users can be assigned to, as `name:max_active_links/max_links_per_day/max_custom_aliases`
+    /// (comma separated, `*` = unlimited).
+    #[arg(long, env("PLANS"), value_delimiter = ',', default_value = DEFAULT_PLANS)]
+    pub plans: Vec<Plan>,
+
+    /// Plan of users that were not assigned one.
+    #[arg(long, env("DEFAULT_PLAN"), default_value = DEFAULT_PLAN)]
+    pub default_plan: String,
+
+    /// Requests per valid API key, or else per client IP to short links, as `requests/seconds` or `off`.
+    #[arg(long, env("RATE_LIMIT_REDIRECT"), default_value = DEFAULT_RATE_LIMIT_REDIRECT)]
+    pub rate_limit_redirect: RateLimitPolicy,
+
+    /// Requests per valid API key, or else per client IP to the link and organization API.
+    #[arg(long, env("RATE_LIMIT_API"), default_value = DEFAULT_RATE_LIMIT_API)]
+    pub rate_limit_api: RateLimitPolicy,
+
+    /// Requests per valid API key, or else per client IP to `/users`.
+    #[arg(long, env("RATE_LIMIT_USERS"), default_value = DEFAULT_RATE_LIMIT_USERS)]
+    pub rate_limit_users: RateLimitPolicy,
+
+    /// Fetch the page title, description and icon of new links created without a title.
+    /// Destinations are only contacted when the link policy allows their addresses.
+    #[arg(long, env("FETCH_METADATA"), default_value_t = true, action = clap::ArgAction::Set)]
+    pub fetch_metadata: bool,
+
+    /// Time allowed for fetching the metadata of one destination, redirects included.
+    #[arg(long, env("METADATA_TIMEOUT_SECS"), default_value_t = DEFAULT_METADATA_TIMEOUT_SECS)]
+    pub metadata_timeout_secs: u64,
+
+    /// Bytes of the destination page read at most when looking for its metadata.
+    #[arg(long, env("METADATA_MAX_BYTES"), default_value_t = DEFAULT_METADATA_MAX_BYTES)]
+    pub metadata_max_bytes: usize,
+
+    /// Seconds between two health checks of the same link's destination; `0` disables the checks.
+    #[arg(long, env("LINK_CHECK_INTERVAL_SECS"), default_value_t = DEFAULT_LINK_CHECK_INTERVAL_SECS)]
+    pub link_check_interval_secs: u64,
+
+    /// Time allowed for one health check, redirects included.
+    #[arg(long, env("LINK_CHECK_TIMEOUT_SECS"), default_value_t = DEFAULT_LINK_CHECK_TIMEOUT_SECS)]
+    pub link_check_timeout_secs: u64,
+
+    /// Consecutive failed health checks after which a link is flagged broken; `0` never flags one.
+    #[arg(long, env("DEAD_LINK_THRESHOLD"), default_value_t = DEFAULT_DEAD_LINK_THRESHOLD)]
+    pub dead_link_threshold: i32,
+
+    /// Absolute http(s) URL visitors of broken links are sent to instead of the dead destination.
+    #[arg(long, env("DEAD_LINK_FALLBACK_URL"))]
+    pub dead_link_fallback_url: Option<String>,
+
+    /// Links kept in memory to answer redirects without a database query; `0` disables the cache,
+    /// which is also off while Redis shares the links between instances.
+    #[arg(long, env("LINK_CACHE_CAPACITY"), default_value_t = DEFAULT_LINK_CACHE_CAPACITY)]
+    pub link_cache_capacity: usize,
+
+    /// Seconds a cached link (or unknown key) is served before it is read again.
+    #[arg(long, env("LINK_CACHE_TTL_SECS"), default_value_t = DEFAULT_LINK_CACHE_TTL_SECS)]
+    pub link_cache_ttl_secs: u64,
+
+    /// Redis shared by the instances for the link cache and click counters (`redis://host:6379`);
+    /// links are read and counted in the database alone when unset or unreachable at startup.
+    #[cfg(feature = "redis")]
+    #[arg(long, env("REDIS_URL"))]
+    pub redis_url: Option<String>,
+
+    /// Prefix of every Redis key written, to share a Redis between deployments.
+    #[cfg(feature = "redis")]
+    #[arg(long, env("REDIS_KEY_PREFIX"), default_value = DEFAULT_REDIS_KEY_PREFIX)]
+    pub redis_key_prefix: String,
+
+    /// Seconds a link (or unknown key) stays cached in Redis.
+    #[cfg(feature = "redis")]
+    #[arg(long, env("REDIS_CACHE_TTL_SECS"), default_value_t = DEFAULT_REDIS_CACHE_TTL_SECS)]
+    pub redis_cache_ttl_secs: u64,
+
+    /// Seconds between two moves of the clicks counted in Redis to the database.
+    #[cfg(feature = "redis")]
+    #[arg(long, env("REDIS_CLICK_SYNC_SECS"), default_value_t = DEFAULT_REDIS_CLICK_SYNC_SECS)]
+    pub redis_click_sync_secs: u64,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "localhost".into(),
+            server_port: "8080".into(),
+            protocol: "https".into(),
+            storage: Storage::Sqlite,
+            geoip_db_path: None,
+            trusted_proxies: Vec::new(),
+            cookie_secret: None,
+            unlock_max_attempts: DEFAULT_UNLOCK_MAX_ATTEMPTS,
+            unlock_window_secs: DEFAULT_UNLOCK_WINDOW_SECS,
+            unlock_cookie_ttl_secs: DEFAULT_UNLOCK_COOKIE_TTL_SECS,
+            interstitial_countdown_secs: 0,
+            signing_secret: None,
+            signed_link_max_ttl_secs: DEFAULT_SIGNED_LINK_MAX_TTL_SECS,
+            allowed_schemes: vec!["http".into(), "https".into()],
+            sort_query_params: false,
+            policy_file: None,
+            admin_api_keys: Vec::new(),
+            coming_soon_page: None,
+            ended_page: None,
+            not_found_page: None,
+            not_found_redirect_url: None,
+            plans: DEFAULT_PLANS.split(',').filter_map(|spec| spec.parse().ok()).collect(),
+            default_plan: DEFAULT_PLAN.into(),
+            rate_limit_redirect: DEFAULT_RATE_LIMIT_REDIRECT.parse().unwrap_or_else(|_| RateLimitPolicy::disabled()),
+            rate_limit_api: DEFAULT_RATE_LIMIT_API.parse().unwrap_or_else(|_| RateLimitPolicy::disabled()),
+            rate_limit_users: DEFAULT_RATE_LIMIT_USERS.parse().unwrap_or_else(|_| RateLimitPolicy::disabled()),
+            fetch_metadata: true,
+            metadata_timeout_secs: DEFAULT_METADATA_TIMEOUT_SECS,
+            metadata_max_bytes: DEFAULT_METADATA_MAX_BYTES,
+            link_check_interval_secs: DEFAULT_LINK_CHECK_INTERVAL_SECS,
+            link_check_timeout_secs: DEFAULT_LINK_CHECK_TIMEOUT_SECS,
+            dead_link_threshold: DEFAULT_DEAD_LINK_THRESHOLD,
+            dead_link_fallback_url: None,
+            link_cache_capacity: DEFAULT_LINK_CACHE_CAPACITY,
+            link_cache_ttl_secs: DEFAULT_LINK_CACHE_TTL_SECS,
+            #[cfg(feature = "redis")]
+            redis_url: None,
+            #[cfg(feature = "redis")]
+            redis_key_prefix: DEFAULT_REDIS_KEY_PREFIX.into(),
+            #[cfg(feature = "redis")]
+            redis_cache_ttl_secs: DEFAULT_REDIS_CACHE_TTL_SECS,
+            #[cfg(feature = "redis")]
+            redis_click_sync_secs: DEFAULT_REDIS_CLICK_SYNC_SECS,
+        }
+    }
+}
+
+/// Accept either a CIDR range (`10.0.0.0/8`) or a bare address (`10.0.0.1`, treated as a single host).
+fn parse_trusted_proxy(value: &str) -> Result<IpNet, String> {
+    let value = value.trim();
+    value
+        .parse::<IpNet>()
+        .or_else(|_| value.parse::<IpAddr>().map(IpNet::from))
+        .map_err(|_| format!("invalid trusted proxy '{value}': expected an IP address or CIDR range"))
 }
 
 impl AppConfig {
+    pub fn is_admin_key(&self, api_key: &str) -> bool {
+        !api_key.is_empty() && self.admin_api_keys.iter().any(|key| key == api_key)
+    }
+
+    /// Public origin of the service, e.g. `https://localhost:8080`.
+    pub fn public_base_url(&self) -> String {
+        format!("{}://{}:{}", self.protocol, self.base_url, self.server_port)
+    }
+
     pub fn from_env_and_args() -> Self {
         // Do NOT load `.env` during tests to keep test environment deterministic.
         #[cfg(not(test))]
@@ -63,6 +323,13 @@ mod tests {
         let cfg = AppConfig::from_env_and_args();
         assert_eq!(cfg.base_url, "localhost");
         assert_eq!(cfg.server_port, "8080");
+        assert_eq!(cfg.storage, Storage::Sqlite);
+        assert_eq!(cfg.unlock_max_attempts, AppConfig::default().unlock_max_attempts);
+        assert_eq!(cfg.allowed_schemes, AppConfig::default().allowed_schemes);
+        assert_eq!(cfg.plans, AppConfig::default().plans);
+        assert_eq!(cfg.plans.len(), 3);
+        assert_eq!(cfg.rate_limit_api, AppConfig::default().rate_limit_api);
+        assert_eq!(cfg.rate_limit_users.capacity, 10);
 
         // override via env
         env::set_var("BASE_URL", "example.com");
@@ -80,6 +347,14 @@ mod tests {
         env::remove_var("PROTOCOL");
     }
 
+    #[test]
+    fn parse_trusted_proxy_accepts_addresses_and_ranges() {
+        assert_eq!(parse_trusted_proxy("10.0.0.0/8").unwrap().to_string(), "10.0.0.0/8");
+        assert_eq!(parse_trusted_proxy(" 127.0.0.1 ").unwrap().to_string(), "127.0.0.1/32");
+        assert_eq!(parse_trusted_proxy("::1").unwrap().to_string(), "::1/128");
+        assert!(parse_trusted_proxy("proxy.local").is_err());
+    }
+
     #[test]
     fn from_env_and_args_ignores_test_harness_flags() {
         // Simply calling the function should not panic even if the test harness injected flags
@@ -0,0 +1,218 @@
+use crate::org::domain::models::organization::{Membership, Organization, Role};
+use crate::shared::utils::{create_random_key, public_key_part};
+use crate::url::domain::models::schema::{ClickRecord, GeoRule, UnknownKeyHits, URL};
+use crate::url::domain::models::tag::Tag;
+use sqlx::error::{DatabaseError, ErrorKind};
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard};
+use thiserror::Error;
+
+/// In-memory counterpart of the SQLite database, shared by the memory adapters of every port
+/// (`--storage memory`). Nothing survives the process: meant for demos and tests.
+#[derive(Default)]
+pub struct MemoryDatabase {
+    state: Mutex<MemoryState>,
+}
+
+/// Row of the `users` table.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct StoredUser {
+    pub(crate) id: i64,
+    pub(crate) username: String,
+    pub(crate) email: String,
+    pub(crate) api_key: String,
+    pub(crate) plan: Option<String>,
+}
+
+/// The tables, each kept in insertion (row id) order. Every adapter call holds the lock for its
+/// whole duration, which makes it as atomic as the SQL transactions it stands for.
+#[derive(Default)]
+pub(crate) struct MemoryState {
+    pub(crate) users: Vec<StoredUser>,
+    pub(crate) urls: Vec<URL>,
+    /// Keys handed out so far with their owner; they are never reused, even after a delete.
+    pub(crate) used_keys: Vec<(String, Option<i32>)>,
+    pub(crate) organizations: Vec<Organization>,
+    pub(crate) members: Vec<Membership>,
+    pub(crate) tags: Vec<Tag>,
+    /// `(url_key, tag_id)` pairs.
+    pub(crate) url_tags: Vec<(String, i64)>,
+    pub(crate) geo_rules: Vec<GeoRule>,
+    pub(crate) clicks: Vec<ClickRecord>,
+    pub(crate) unknown_keys: HashMap<String, UnknownKeyHits>,
+    last_user_id: i64,
+    last_org_id: i64,
+    last_tag_id: i64,
+}
+
+impl MemoryDatabase {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Database holding the same demo users and link as `seed_data`.
+    pub fn with_demo_data() -> Self {
+        let db = Self::new();
+        db.insert_user("JordiM", "marcaljordi@google.com", "1234567890");
+        db.insert_user("Pepet", "pepet@example.com", "0987654321");
+        db.insert_url(URL {
+            key: "ERW8S".into(),
+            secret_key: "ERW8S_BD6EZEUN".into(),
+            target_url: "http://www.jordimp.net/".into(),
+            is_active: true,
+            user_id: 1,
+            ..Default::default()
+        });
+        db
+    }
+
+    /// Add a user with its personal workspace and return its id.
+    pub fn insert_user(&self, username: &str, email: &str, api_key: &str) -> i64 {
+        self.lock().insert_user(username, email, api_key)
+    }
+
+    /// Store `url` as given, reserving its key; links without a workspace go to the personal
+    /// workspace of their creator, like `migrate_personal_workspaces` does for old rows.
+    pub fn insert_url(&self, mut url: URL) {
+        let mut state = self.lock();
+        if url.org_id.is_none() {
+            url.org_id = state.personal_org_id(url.user_id);
+        }
+        state.used_keys.push((url.key.clone(), Some(url.user_id)));
+        state.urls.push(url);
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<'_, MemoryState> {
+        // a panic in another holder does not leave the tables half written: every change is a
+        // plain push / assignment, so the data is still consistent
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl MemoryState {
+    pub(crate) fn insert_user(&mut self, username: &str, email: &str, api_key: &str) -> i64 {
+        let id = self.insert_account(username, email, api_key);
+        // Cada usuari té el seu espai de treball personal
+        let org = self.insert_organization(username, Some(id));
+        self.members.push(Membership { org_id: org.id, user_id: id, role: Role::Owner });
+        id
+    }
+
+    /// Add a user row alone, without the personal workspace every real user gets.
+    pub(crate) fn insert_account(&mut self, username: &str, email: &str, api_key: &str) -> i64 {
+        self.last_user_id += 1;
+        self.users.push(StoredUser {
+            id: self.last_user_id,
+            username: username.to_string(),
+            email: email.to_string(),
+            api_key: api_key.to_string(),
+            plan: None,
+        });
+        self.last_user_id
+    }
+
+    pub(crate) fn insert_organization(&mut self, name: &str, personal_user_id: Option<i64>) -> Organization {
+        self.last_org_id += 1;
+        let org = Organization { id: self.last_org_id, name: name.to_string(), personal_user_id };
+        self.organizations.push(org.clone());
+        org
+    }
+
+    pub(crate) fn insert_tag(&mut self, org_id: i64, name: String) -> Tag {
+        self.last_tag_id += 1;
+        let tag = Tag { id: self.last_tag_id, org_id, name };
+        self.tags.push(tag.clone());
+        tag
+    }
+
+    pub(crate) fn user(&self, id: i64) -> Option<&StoredUser> {
+        self.users.iter().find(|user| user.id == id)
+    }
+
+    /// Never matches the empty key of the `deleted-user` placeholder.
+    pub(crate) fn user_id_by_api_key(&self, api_key: &str) -> Option<i64> {
+        self.users.iter().find(|user| !api_key.is_empty() && user.api_key == api_key).map(|user| user.id)
+    }
+
+    pub(crate) fn personal_org_id(&self, user_id: i32) -> Option<i64> {
+        self.organizations
+            .iter()
+            .find(|org| org.personal_user_id == Some(user_id as i64))
+            .map(|org| org.id)
+    }
+
+    pub(crate) fn member_role(&self, org_id: i64, user_id: i64) -> Option<Role> {
+        self.members
+            .iter()
+            .find(|member| member.org_id == org_id && member.user_id == user_id)
+            .map(|member| member.role)
+    }
+
+    pub(crate) fn url(&self, key: &str) -> Option<&URL> {
+        self.urls.iter().find(|url| url.key == key)
+    }
+
+    pub(crate) fn url_mut(&mut self, key: &str) -> Option<&mut URL> {
+        self.urls.iter_mut().find(|url| url.key == key)
+    }
+
+    pub(crate) fn key_exists(&self, key: &str) -> bool {
+        self.url(key).is_some() || self.used_keys.iter().any(|(used, _)| used == key)
+    }
+
+    /// Fresh `PUBLIC_SECRET` key pair whose public part is not taken; stands for the pool of
+    /// `generated_keys`.
+    pub(crate) fn generate_key(&self) -> String {
+        loop {
+            let key = create_random_key(8);
+            if !self.key_exists(public_key_part(&key)) {
+                return key;
+            }
+        }
+    }
+
+    /// Drop the links' geo rules, click history and tag attachments.
+    pub(crate) fn remove_link_rows(&mut self, keys: &[String]) {
+        self.geo_rules.retain(|rule| !keys.contains(&rule.url_key));
+        self.clicks.retain(|click| !keys.contains(&click.url_key));
+        self.url_tags.retain(|(url_key, _)| !keys.contains(url_key));
+    }
+}
+
+/// What SQLite reports when a `UNIQUE` constraint fails, so callers can tell duplicates apart
+/// with `is_unique_violation()` whatever the storage.
+#[derive(Debug, Error)]
+#[error("UNIQUE constraint failed: {0}")]
+pub(crate) struct UniqueViolation(pub(crate) &'static str);
+
+impl UniqueViolation {
+    pub(crate) fn into_sqlx(self) -> sqlx::Error {
+        sqlx::Error::Database(Box::new(self))
+    }
+}
+
+impl DatabaseError for UniqueViolation {
+    fn message(&self) -> &str {
+        "UNIQUE constraint failed"
+    }
+
+    fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+        self
+    }
+
+    fn constraint(&self) -> Option<&str> {
+        Some(self.0)
+    }
+
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::UniqueViolation
+    }
+}
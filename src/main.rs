@@ -4,10 +4,9 @@ use actix_web::HttpServer;
 
 #[cfg(not(test))]
 use log::info;
-#[cfg(not(test))]
-use log4rs;
 
 mod config;
+mod org;
 mod shared;
 mod url;
 mod user;
@@ -15,42 +14,146 @@ mod user;
 #[cfg(not(test))]
 use crate::config::database::connect_to_db;
 #[cfg(not(test))]
-use crate::config::env::AppConfig;
+use crate::config::env::{AppConfig, Storage};
+#[cfg(not(test))]
+use crate::config::memory::MemoryDatabase;
+use crate::org::application::controllers::org_controller::{
+    create_organization, list_members, list_organizations, remove_member, set_member_role,
+};
+#[cfg(not(test))]
+use crate::org::domain::services::org_service::OrgService;
+#[cfg(not(test))]
+use crate::org::domain::repositories::org_repository_port::OrgRepositoryPort;
+#[cfg(not(test))]
+use crate::org::infra::memory_org_repository::MemoryOrgRepository;
+#[cfg(not(test))]
+use crate::org::infra::sqlx_org_repository::SqlxOrgRepository;
+use crate::url::application::controllers::tag_controller::{
+    attach_tag, create_tag, delete_tag, detach_tag, list_tags, rename_tag, tag_stats,
+};
 use crate::url::application::controllers::url_controller::{
-    create_url, delete_url, forward_to_target_url, get_url_info,
+    continue_to_target_url, create_url, delete_owned_url, delete_url, forward_to_target_url, get_admin_url_qr,
+    get_cache_stats, get_owned_url, get_url_info, get_url_qr, get_usage, list_broken_urls, list_org_urls, list_scheduled_urls,
+    list_unknown_keys, patch_owned_url, patch_url, rescan_link_policy, sign_url, unlock_url,
 };
 #[cfg(not(test))]
+use crate::shared::rate_limit::RateLimit;
+#[cfg(not(test))]
+use crate::url::application::views::html::{NotFoundPage, SchedulePages};
+#[cfg(not(test))]
+use crate::url::infra::file_link_policy::{self, FileLinkPolicy};
+#[cfg(not(test))]
+use crate::url::domain::models::health::DeadLinkPolicy;
+#[cfg(not(test))]
+use crate::url::infra::http_link_probe::HttpLinkProbe;
+#[cfg(not(test))]
+use crate::url::infra::http_metadata_fetcher::HttpMetadataFetcher;
+#[cfg(not(test))]
+use crate::url::infra::maxmind_geo_locator::MaxMindGeoLocator;
+#[cfg(not(test))]
+use crate::url::infra::cached_url_repository::CachedURLRepository;
+#[cfg(not(test))]
+use crate::url::infra::memory_url_repository::MemoryURLRepository;
+#[cfg(not(test))]
 use crate::url::infra::sqlx_url_repository::SqlxURLRepository;
+#[cfg(all(feature = "redis", not(test)))]
+use crate::url::infra::redis_url_repository::RedisURLRepository;
+#[cfg(not(test))]
+use crate::url::domain::repositories::tag_repository_port::TagRepositoryPort;
 #[cfg(not(test))]
 use crate::url::domain::repositories::url_repository_port::URLRepositoryPort;
 #[cfg(not(test))]
+use crate::url::domain::models::policy::PolicyRules;
+#[cfg(not(test))]
+use crate::url::domain::ports::link_policy_port::LinkPolicyPort;
+#[cfg(not(test))]
+use crate::url::domain::services::target_url_policy::TargetUrlPolicy;
+#[cfg(not(test))]
+use crate::url::domain::services::tag_service::TagService;
+#[cfg(not(test))]
 use crate::url::domain::services::url_service::URLService;
-use crate::user::application::controllers::user_controller::{create_user, delete_user, get_users};
+use crate::user::application::controllers::user_controller::{
+    create_user, delete_user, get_user, get_users, set_user_plan, transfer_links, update_user,
+};
+#[cfg(not(test))]
+use crate::user::domain::models::plan::PlanCatalog;
+#[cfg(not(test))]
+use crate::user::infra::memory_user_repository::MemoryUserRepository;
 #[cfg(not(test))]
 use crate::user::infra::sqlx_user_repository::SqlxUserRepository;
 #[cfg(not(test))]
+use crate::user::infra::link_cache_user_repository::LinkCacheUserRepository;
+#[cfg(not(test))]
+use crate::url::domain::ports::link_cache_port::LinkCachePort;
+#[cfg(not(test))]
 use crate::user::domain::repositories::user_repository_port::UserRepositoryPort;
 #[cfg(not(test))]
 use crate::user::domain::services::user_service::UserService;
 
 use std::sync::Arc;
 
+// Adaptadors dels ports d'usuaris, organitzacions, enllaços i etiquetes
+#[cfg(not(test))]
+type Repositories = (
+    Arc<dyn UserRepositoryPort + Send + Sync>,
+    Arc<dyn OrgRepositoryPort>,
+    Arc<dyn URLRepositoryPort + Send + Sync>,
+    Arc<dyn TagRepositoryPort>,
+);
+
 pub fn configure_services(
     cfg: &mut web::ServiceConfig,
     user_service: crate::user::domain::services::user_service::UserService,
     url_service: crate::url::domain::services::url_service::URLService,
+    org_service: crate::org::domain::services::org_service::OrgService,
+    tag_service: crate::url::domain::services::tag_service::TagService,
     app_config: crate::config::env::AppConfig,
 ) {
     cfg
         .app_data(web::Data::new(Arc::new(user_service.clone())))
         .app_data(web::Data::new(Arc::new(url_service.clone())))
-        .app_data(app_config)
+        .app_data(web::Data::new(Arc::new(org_service)))
+        .app_data(web::Data::new(Arc::new(tag_service)))
+        .app_data(web::Data::new(app_config))
         .service(create_user)
         .service(get_users)
+        .service(get_user)
+        .service(update_user)
         .service(delete_user)
+        .service(transfer_links)
+        .service(set_user_plan)
+        .service(create_organization)
+        .service(list_organizations)
+        .service(list_members)
+        .service(set_member_role)
+        .service(remove_member)
+        .service(list_org_urls)
+        .service(create_tag)
+        .service(list_tags)
+        .service(tag_stats)
+        .service(rename_tag)
+        .service(delete_tag)
+        .service(attach_tag)
+        .service(detach_tag)
         .service(create_url)
+        .service(list_scheduled_urls)
+        .service(sign_url)
+        .service(get_owned_url)
+        .service(patch_owned_url)
+        .service(delete_owned_url)
+        .service(get_usage)
         .service(forward_to_target_url)
+        .service(continue_to_target_url)
+        .service(unlock_url)
+        .service(get_url_qr)
+        // rutes fixes de /admin abans de /admin/{secret_key}, que també les capturaria
+        .service(rescan_link_policy)
+        .service(list_broken_urls)
+        .service(list_unknown_keys)
+        .service(get_cache_stats)
         .service(get_url_info)
+        .service(get_admin_url_qr)
+        .service(patch_url)
         .service(delete_url);
 }
 
@@ -65,37 +168,228 @@ async fn main() -> std::io::Result<()> {
     }
 
     // Carrega les variables d'entorn i els arguments de la línia de comandes
-    let config = AppConfig::from_env_and_args();
+    let mut config = AppConfig::from_env_and_args();
+    if config.cookie_secret.is_none() {
+        log::warn!("COOKIE_SECRET not set — generating a random one, unlock cookies will not survive restarts");
+        config.cookie_secret = Some(crate::shared::utils::create_api_key());
+    }
     let server_port = config.server_port.clone();
     let base_url = config.base_url.clone();
     let protocol = config.protocol.clone();
 
-    // Estableix la connexió a la base de dades — propaguem l'error amb `?` i registrem detalls
-    let pool = match connect_to_db().await {
-        Ok(p) => p,
+    // Repositoris: base de dades SQLite o memòria del procés (per a demostracions)
+    let (user_repository, org_repository, mut url_repository, tag_repository): Repositories = match config.storage {
+        Storage::Sqlite => {
+            // Estableix la connexió a la base de dades — propaguem l'error amb `?` i registrem detalls
+            let pool = match connect_to_db().await {
+                Ok(p) => p,
+                Err(e) => {
+                    eprintln!("Failed to connect to DB: {}", e);
+                    return Err(std::io::Error::other("database connection failed"));
+                }
+            };
+            let url_repository = Arc::new(SqlxURLRepository::new(pool.clone()).await);
+            (
+                Arc::new(SqlxUserRepository::new(pool.clone()).await),
+                Arc::new(SqlxOrgRepository::new(pool).await),
+                url_repository.clone(),
+                url_repository,
+            )
+        }
+        Storage::Memory => {
+            log::warn!("Storage in memory — users and links are lost when the process exits");
+            let db = Arc::new(MemoryDatabase::with_demo_data());
+            let url_repository = Arc::new(MemoryURLRepository::new(db.clone()));
+            (
+                Arc::new(MemoryUserRepository::new(db.clone())),
+                Arc::new(MemoryOrgRepository::new(db)),
+                url_repository.clone(),
+                url_repository,
+            )
+        }
+    };
+
+    // Plans amb els límits d'enllaços de cada usuari
+    let plans = match PlanCatalog::new(config.plans.clone(), &config.default_plan) {
+        Ok(plans) => Arc::new(plans),
         Err(e) => {
-            eprintln!("Failed to connect to DB: {}", e);
-            return Err(std::io::Error::new(std::io::ErrorKind::Other, "database connection failed"));
+            eprintln!("Invalid plan configuration: {}", e);
+            return Err(std::io::Error::other("plan configuration is invalid"));
         }
     };
 
-    let user_repository: Arc<dyn UserRepositoryPort + Send + Sync> = Arc::new(
-        SqlxUserRepository::new(pool.clone()).await,
-    );
+    let org_service = OrgService::new(org_repository);
+
+    // Memòries cau d'enllaços que cal buidar quan els usuaris en canvien fora del repositori d'enllaços
+    let mut link_caches: Vec<Arc<dyn LinkCachePort>> = Vec::new();
+
+    // Memòria cau i comptadors de clics compartits entre instàncies a Redis
+    #[cfg(feature = "redis")]
+    if let Some(redis_url) = config.redis_url.as_deref() {
+        let ttl = std::time::Duration::from_secs(config.redis_cache_ttl_secs);
+        match RedisURLRepository::connect(url_repository.clone(), redis_url, &config.redis_key_prefix, ttl).await {
+            Ok(redis) => {
+                info!("Links cached and clicks counted in Redis");
+                let redis = Arc::new(redis);
+                redis.spawn_click_sync(std::time::Duration::from_secs(config.redis_click_sync_secs.max(1)));
+                link_caches.push(redis.clone());
+                url_repository = redis;
+            }
+            Err(e) => log::warn!("Redis unavailable, links are read and counted in the database: {}", e),
+        }
+    }
+    // Memòria cau dels enllaços més visitats, davant de la base de dades; desactivada amb Redis,
+    // que ja comparteix la memòria cau entre instàncies (només Redis ha omplert `link_caches`)
+    let mut link_cache = None;
+    if link_caches.is_empty() && config.link_cache_capacity > 0 {
+        let ttl = std::time::Duration::from_secs(config.link_cache_ttl_secs);
+        let cached = Arc::new(CachedURLRepository::new(url_repository, config.link_cache_capacity, ttl));
+        url_repository = cached.clone();
+        link_caches.push(cached.clone());
+        link_cache = Some(cached);
+    }
 
     // Crear una nova instància de UserService amb UserRepositoryPort
-    let user_service = UserService::new(user_repository.clone());
+    let user_repository: Arc<dyn UserRepositoryPort + Send + Sync> = if link_caches.is_empty() {
+        user_repository
+    } else {
+        Arc::new(LinkCacheUserRepository::new(user_repository, link_caches))
+    };
+    let user_service = UserService::new(user_repository).with_plans(plans.clone());
+    let tag_service = TagService::new(url_repository.clone(), tag_repository.clone());
 
-    let url_repository: Arc<dyn URLRepositoryPort + Send + Sync> = Arc::new(
-        SqlxURLRepository::new(pool.clone()).await,
-    );
-    let url_service = URLService::new(url_repository.clone());
+    let mut url_service = URLService::new(url_repository.clone())
+        .with_tag_repository(tag_repository)
+        .with_plans(plans)
+        .with_unlock_limits(config.unlock_max_attempts, std::time::Duration::from_secs(config.unlock_window_secs))
+        .with_target_url_policy(TargetUrlPolicy {
+            allowed_schemes: config.allowed_schemes.iter().map(|scheme| scheme.trim().to_ascii_lowercase()).collect(),
+            sort_query_params: config.sort_query_params,
+        });
+
+    if let Some(link_cache) = link_cache {
+        url_service = url_service.with_link_cache(link_cache);
+    }
+
+    match config.signing_secret.as_deref() {
+        Some(secret) => url_service = url_service.with_signing_secret(secret, config.signed_link_max_ttl_secs),
+        None => info!("SIGNING_SECRET not set — signed links are disabled"),
+    }
+
+    // Regles de bloqueig/permís de destinacions, recarregades quan canvia el fitxer
+    let link_policy: Arc<dyn LinkPolicyPort> = match config.policy_file.as_deref() {
+        Some(path) => match FileLinkPolicy::open(path, file_link_policy::DEFAULT_CHECK_INTERVAL) {
+            Ok(policy) => {
+                info!("Link policy loaded from {path}");
+                Arc::new(policy)
+            }
+            Err(e) => {
+                eprintln!("Failed to load link policy {}: {}", path, e);
+                return Err(std::io::Error::other("link policy could not be loaded"));
+            }
+        },
+        None => Arc::new(PolicyRules::builtin()),
+    };
+    url_service = url_service.with_link_policy(link_policy.clone());
+
+    // Títol, descripció i icona de les destinacions, obtinguts en segon pla
+    if config.fetch_metadata {
+        let timeout = std::time::Duration::from_secs(config.metadata_timeout_secs);
+        match HttpMetadataFetcher::new(link_policy.clone(), timeout, config.metadata_max_bytes) {
+            Ok(fetcher) => url_service = url_service.with_metadata_fetcher(Arc::new(fetcher)),
+            Err(e) => {
+                eprintln!("Failed to build the metadata fetcher: {}", e);
+                return Err(std::io::Error::other("metadata fetcher could not be built"));
+            }
+        }
+    }
+
+    // Enllaços trencats: quants errors seguits els marquen i cap on s'envien els visitants
+    if let Some(fallback_url) = config.dead_link_fallback_url.as_deref() {
+        if !::url::Url::parse(fallback_url).is_ok_and(|url| matches!(url.scheme(), "http" | "https")) {
+            eprintln!("Invalid DEAD_LINK_FALLBACK_URL {}: an absolute http(s) URL is required", fallback_url);
+            return Err(std::io::Error::other("dead link fallback URL is invalid"));
+        }
+    }
+    url_service = url_service.with_dead_link_policy(DeadLinkPolicy {
+        failure_threshold: config.dead_link_threshold,
+        fallback_url: config.dead_link_fallback_url.clone(),
+    });
+    if config.link_check_interval_secs > 0 {
+        let timeout = std::time::Duration::from_secs(config.link_check_timeout_secs);
+        match HttpLinkProbe::new(link_policy, timeout) {
+            Ok(probe) => url_service = url_service.with_link_probe(Arc::new(probe)),
+            Err(e) => {
+                eprintln!("Failed to build the link checker: {}", e);
+                return Err(std::io::Error::other("link checker could not be built"));
+            }
+        }
+    }
+
+    // Geo-encaminament opcional a partir d'una base de dades MaxMind local
+    if let Some(path) = config.geoip_db_path.as_deref() {
+        match MaxMindGeoLocator::open(path) {
+            Ok(locator) => {
+                info!("GeoIP database loaded from {path}");
+                url_service = url_service.with_geo_locator(Arc::new(locator));
+            }
+            Err(e) => {
+                eprintln!("Failed to open GeoIP database {}: {}", path, e);
+                return Err(std::io::Error::other("geoip database could not be loaded"));
+            }
+        }
+    }
+
+    // Comprovació periòdica de les destinacions, amb el servei ja configurat del tot
+    if config.link_check_interval_secs > 0 {
+        url_service.spawn_link_health_checks(std::time::Duration::from_secs(config.link_check_interval_secs));
+    }
+
+    // Pàgines "coming soon" / "ended" configurables per als enllaços programats
+    let schedule_pages = match SchedulePages::load(config.coming_soon_page.as_deref(), config.ended_page.as_deref()) {
+        Ok(pages) => web::Data::new(pages),
+        Err(e) => {
+            eprintln!("Failed to read schedule page templates: {}", e);
+            return Err(std::io::Error::other("schedule page templates could not be loaded"));
+        }
+    };
+
+    // Pàgina 404 (o redirecció) per a claus desconegudes i enllaços desactivats
+    if let Some(redirect_url) = config.not_found_redirect_url.as_deref() {
+        if !::url::Url::parse(redirect_url).is_ok_and(|url| matches!(url.scheme(), "http" | "https")) {
+            eprintln!("Invalid NOT_FOUND_REDIRECT_URL {}: an absolute http(s) URL is required", redirect_url);
+            return Err(std::io::Error::other("not found redirect URL is invalid"));
+        }
+    }
+    let not_found_page = match NotFoundPage::load(config.not_found_page.as_deref(), config.not_found_redirect_url.as_deref()) {
+        Ok(page) => web::Data::new(page),
+        Err(e) => {
+            eprintln!("Failed to read the not found page template: {}", e);
+            return Err(std::io::Error::other("not found page template could not be loaded"));
+        }
+    };
+
+    // Límits de peticions compartits per tots els workers
+    let rate_limit = RateLimit::from_config(&config).with_api_keys(Arc::new(user_service.clone()));
 
     info!("Server up in {protocol}://{base_url}:{server_port}");
 
     // Configura el servidor Actix-web (separa la configuració a `configure_services` per facilitar tests)
     HttpServer::new(move || {
-        App::new().configure(|cfg| configure_services(cfg, user_service.clone(), url_service.clone(), config.clone()))
+        App::new()
+            .wrap(rate_limit.clone())
+            .app_data(schedule_pages.clone())
+            .app_data(not_found_page.clone())
+            .configure(|cfg| {
+                configure_services(
+                    cfg,
+                    user_service.clone(),
+                    url_service.clone(),
+                    org_service.clone(),
+                    tag_service.clone(),
+                    config.clone(),
+                )
+            })
     })
     .bind(format!("{base_url}:{server_port}"))?
     .run()
@@ -105,51 +399,59 @@ async fn main() -> std::io::Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use actix_web::test::{init_service, call_service, TestRequest};
+    use actix_web::test::{init_service, call_service, read_body_json, TestRequest};
+    use crate::config::memory::MemoryDatabase;
+    use crate::org::domain::services::org_service::OrgService;
+    use crate::org::infra::memory_org_repository::MemoryOrgRepository;
+    use crate::url::domain::services::tag_service::TagService;
+    use crate::url::domain::services::url_service::URLService;
+    use crate::config::env::AppConfig;
+    use crate::url::infra::cached_url_repository::CachedURLRepository;
+    use crate::url::infra::memory_url_repository::MemoryURLRepository;
+    use crate::user::domain::services::user_service::UserService;
+    use crate::user::infra::memory_user_repository::MemoryUserRepository;
     use std::sync::Arc;
-    use async_trait::async_trait;
-    use sqlx::Error;
-
-    // Minimal fake UserRepo to construct a UserService for the test
-    struct FakeUserRepo;
-    impl FakeUserRepo { fn new() -> Self { Self } }
-
-    #[async_trait]
-    impl crate::user::domain::repositories::user_repository_port::UserRepositoryPort for FakeUserRepo {
-        async fn create_user(&self, user_dto: crate::user::application::dtos::user_dto::UserDtoCreate, _api_key: String) -> Result<crate::user::application::dtos::user_dto::UserDtoCreateResponse, Error> {
-            Ok(crate::user::application::dtos::user_dto::UserDtoCreateResponse { user: user_dto, api_key: "fake-key".into() })
-        }
-        async fn get_users(&self) -> Result<Vec<crate::user::application::dtos::user_dto::UserDto>, Error> { Ok(vec![]) }
-        async fn delete_user(&self, _id: i32) -> Result<(), Error> { Ok(()) }
-    }
-
-    // Minimal fake URLRepo to construct a URLService for the test
-    struct FakeURLRepo;
-    #[async_trait]
-    impl crate::url::domain::repositories::url_repository_port::URLRepositoryPort for FakeURLRepo {
-        async fn create_url(&self, target_url: String, user_id: i32) -> Result<crate::url::domain::models::schema::URL, sqlx::Error> {
-            Ok(crate::url::domain::models::schema::URL { key: "k".into(), secret_key: "s".into(), target_url, is_active: true, clicks: 0, user_id })
-        }
-        async fn get_db_url_by_key(&self, _url_key: String) -> Result<crate::url::domain::models::schema::URL, sqlx::Error> { Err(sqlx::Error::RowNotFound) }
-        async fn get_db_url_by_user_and_target_url(&self, _user_id: i32, _target_url: String) -> Result<crate::url::domain::models::schema::URL, sqlx::Error> { Err(sqlx::Error::RowNotFound) }
-        async fn get_user_by_apy_key(&self, _api_key: String) -> Result<i32, ()> { Ok(1) }
-        async fn increment_clicks(&self, _url_key: String) -> sqlx::Result<()> { Ok(()) }
-    }
 
     #[actix_web::test]
     async fn configure_services_registers_routes() {
-        let user_repo = Arc::new(FakeUserRepo::new());
-        let user_service = crate::user::domain::services::user_service::UserService::new(user_repo);
-        let url_repo = Arc::new(FakeURLRepo);
-        let url_service = crate::url::domain::services::url_service::URLService::new(url_repo);
+        let db = Arc::new(MemoryDatabase::with_demo_data());
+        let user_service = UserService::new(Arc::new(MemoryUserRepository::new(db.clone())));
+        let url_repo = Arc::new(MemoryURLRepository::new(db.clone()));
+        let url_service = URLService::new(url_repo.clone()).with_tag_repository(url_repo.clone());
+        let tag_service = TagService::new(url_repo.clone(), url_repo);
 
-        let cfg = crate::config::env::AppConfig::from_env_and_args();
-        let app = init_service(App::new().configure(|c| configure_services(c, user_service.clone(), url_service.clone(), cfg.clone()))).await;
+        let cfg = crate::config::env::AppConfig { admin_api_keys: vec!["admin".into()], ..crate::config::env::AppConfig::from_env_and_args() };
+        let org_service = OrgService::new(Arc::new(MemoryOrgRepository::new(db)));
+        let app = init_service(App::new().configure(|c| configure_services(c, user_service.clone(), url_service.clone(), org_service.clone(), tag_service.clone(), cfg.clone()))).await;
 
         // Call a registered route to ensure wiring ran
-        let req = TestRequest::get().uri("/users").to_request();
+        let req = TestRequest::get().uri("/users").insert_header(("X-API-Key", "admin")).to_request();
         let resp = call_service(&app, req).await;
         assert!(resp.status().is_success());
+        let users: serde_json::Value = read_body_json(resp).await;
+        assert_eq!(users.as_array().map(Vec::len), Some(2));
+    }
+
+    #[actix_web::test]
+    async fn static_admin_routes_win_over_secret_keys() {
+        let db = Arc::new(MemoryDatabase::with_demo_data());
+        let user_service = UserService::new(Arc::new(MemoryUserRepository::new(db.clone())));
+        let url_repo = Arc::new(MemoryURLRepository::new(db.clone()));
+        let link_cache = Arc::new(CachedURLRepository::new(url_repo.clone(), 10, std::time::Duration::from_secs(60)));
+        let url_service = URLService::new(link_cache.clone()).with_link_cache(link_cache);
+        let tag_service = TagService::new(url_repo.clone(), url_repo);
+        let org_service = OrgService::new(Arc::new(MemoryOrgRepository::new(db)));
+        let cfg = AppConfig { admin_api_keys: vec!["admin".into()], ..AppConfig::from_env_and_args() };
+        let app = init_service(App::new().configure(|c| configure_services(c, user_service, url_service, org_service, tag_service, cfg))).await;
+
+        let admin_get = |uri: &str| TestRequest::get().uri(uri).insert_header(("X-API-Key", "admin")).to_request();
+        let resp = call_service(&app, admin_get("/admin/cache")).await;
+        assert!(resp.status().is_success());
+        let stats: serde_json::Value = read_body_json(resp).await;
+        assert_eq!(stats["capacity"], 10);
+        for uri in ["/admin/links/unknown", "/admin/links/broken"] {
+            assert!(call_service(&app, admin_get(uri)).await.status().is_success(), "{uri}");
+        }
     }
 }
 
@@ -0,0 +1,155 @@
+use actix_web::{delete, get, post, put, web, HttpResponse, Responder};
+
+use crate::org::application::dtos::org_dto::{CreateOrganizationDto, MemberRoleDto, OrganizationDto};
+use crate::org::domain::models::organization::Role;
+use crate::org::domain::services::org_service::{OrgError, OrgService};
+use crate::shared::api_key::ApiKey;
+
+use std::sync::Arc;
+
+#[post("/orgs")]
+pub async fn create_organization(
+    api_key: ApiKey, body: web::Json<CreateOrganizationDto>, org_service: web::Data<Arc<OrgService>>,
+) -> impl Responder {
+    let result = async {
+        let user_id = org_service.authenticate(api_key.0).await?;
+        org_service.create_organization(user_id, &body.name).await
+    };
+    match result.await {
+        Ok(org) => HttpResponse::Created().json(OrganizationDto::new(org, Role::Owner)),
+        Err(err) => org_error_response(err),
+    }
+}
+
+#[get("/orgs")]
+pub async fn list_organizations(api_key: ApiKey, org_service: web::Data<Arc<OrgService>>) -> impl Responder {
+    let result = async {
+        let user_id = org_service.authenticate(api_key.0).await?;
+        org_service.list_organizations(user_id).await
+    };
+    match result.await {
+        Ok(orgs) => {
+            let dtos: Vec<_> = orgs.into_iter().map(|(org, role)| OrganizationDto::new(org, role)).collect();
+            HttpResponse::Ok().json(dtos)
+        }
+        Err(err) => org_error_response(err),
+    }
+}
+
+#[get("/orgs/{org_id}/members")]
+pub async fn list_members(
+    api_key: ApiKey, org_id: web::Path<i64>, org_service: web::Data<Arc<OrgService>>,
+) -> impl Responder {
+    let result = async {
+        let user_id = org_service.authenticate(api_key.0).await?;
+        org_service.list_members(user_id, org_id.into_inner()).await
+    };
+    match result.await {
+        Ok(members) => HttpResponse::Ok().json(members),
+        Err(err) => org_error_response(err),
+    }
+}
+
+#[put("/orgs/{org_id}/members/{user_id}")]
+pub async fn set_member_role(
+    api_key: ApiKey, path: web::Path<(i64, i32)>, body: web::Json<MemberRoleDto>, org_service: web::Data<Arc<OrgService>>,
+) -> impl Responder {
+    let (org_id, member_id) = path.into_inner();
+    let result = async {
+        let user_id = org_service.authenticate(api_key.0).await?;
+        org_service.set_member_role(user_id, org_id, member_id, body.role).await
+    };
+    match result.await {
+        Ok(membership) => HttpResponse::Ok().json(membership),
+        Err(err) => org_error_response(err),
+    }
+}
+
+#[delete("/orgs/{org_id}/members/{user_id}")]
+pub async fn remove_member(
+    api_key: ApiKey, path: web::Path<(i64, i32)>, org_service: web::Data<Arc<OrgService>>,
+) -> impl Responder {
+    let (org_id, member_id) = path.into_inner();
+    let result = async {
+        let user_id = org_service.authenticate(api_key.0).await?;
+        org_service.remove_member(user_id, org_id, member_id).await
+    };
+    match result.await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(err) => org_error_response(err),
+    }
+}
+
+fn org_error_response(err: OrgError) -> HttpResponse {
+    match err {
+        OrgError::Unauthorized => HttpResponse::Unauthorized().body(err.to_string()),
+        OrgError::InvalidName => HttpResponse::BadRequest().body(err.to_string()),
+        OrgError::NotFound => HttpResponse::NotFound().body(err.to_string()),
+        OrgError::Forbidden => HttpResponse::Forbidden().body(err.to_string()),
+        OrgError::LastOwner => HttpResponse::Conflict().body(err.to_string()),
+        OrgError::Database(db_err) => {
+            eprintln!("Error occurred[org_ctrl]: {}", db_err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::database::create_org_tables;
+    use crate::org::infra::sqlx_org_repository::SqlxOrgRepository;
+    use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+    use actix_web::App;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::Executor;
+
+    #[actix_web::test]
+    async fn controller_creates_organizations_and_manages_members() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.unwrap();
+        pool.execute(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, username TEXT NOT NULL, email TEXT NOT NULL, api_key TEXT NOT NULL);
+             INSERT INTO users (id, username, email, api_key) VALUES (1, 'a', 'a@x.com', 'ka'), (2, 'b', 'b@x.com', 'kb');",
+        )
+        .await
+        .unwrap();
+        create_org_tables(&pool).await.unwrap();
+        let service = OrgService::new(Arc::new(SqlxOrgRepository::new(pool).await));
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(service)))
+                .service(create_organization)
+                .service(list_organizations)
+                .service(list_members)
+                .service(set_member_role)
+                .service(remove_member),
+        )
+        .await;
+
+        let body = CreateOrganizationDto { name: "Team".into() };
+        let resp = call_service(&app, TestRequest::post().uri("/orgs").set_json(&body).to_request()).await;
+        assert_eq!(resp.status(), 401);
+        let req = TestRequest::post().uri("/orgs").insert_header(("X-API-Key", "ka")).set_json(&body).to_request();
+        let created: OrganizationDto = read_body_json(call_service(&app, req).await).await;
+        assert_eq!((created.name.as_str(), created.role, created.personal), ("Team", Role::Owner, false));
+
+        let members_uri = format!("/orgs/{}/members", created.id);
+        let req = TestRequest::get().uri(&members_uri).insert_header(("X-API-Key", "kb")).to_request();
+        assert_eq!(call_service(&app, req).await.status(), 403);
+
+        let req = TestRequest::put()
+            .uri(&format!("{members_uri}/2"))
+            .insert_header(("X-API-Key", "ka"))
+            .set_json(MemberRoleDto { role: Role::Viewer })
+            .to_request();
+        assert!(call_service(&app, req).await.status().is_success());
+        let req = TestRequest::get().uri("/orgs").insert_header(("X-API-Key", "kb")).to_request();
+        let orgs: Vec<OrganizationDto> = read_body_json(call_service(&app, req).await).await;
+        assert_eq!(orgs, vec![OrganizationDto { role: Role::Viewer, ..created.clone() }]);
+
+        let req = TestRequest::delete().uri(&format!("{members_uri}/1")).insert_header(("X-API-Key", "ka")).to_request();
+        assert_eq!(call_service(&app, req).await.status(), 409);
+        let req = TestRequest::delete().uri(&format!("{members_uri}/2")).insert_header(("X-API-Key", "kb")).to_request();
+        assert_eq!(call_service(&app, req).await.status(), 204);
+    }
+}
@@ -0,0 +1,30 @@
+use crate::org::domain::models::organization::{Organization, Role};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CreateOrganizationDto {
+    pub name: String,
+}
+
+/// An organization as seen by one of its members.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrganizationDto {
+    pub id: i64,
+    pub name: String,
+    /// Personal workspace created with the user's account.
+    pub personal: bool,
+    /// Role of the caller in the organization.
+    pub role: Role,
+}
+
+impl OrganizationDto {
+    pub fn new(org: Organization, role: Role) -> Self {
+        Self { id: org.id, name: org.name, personal: org.personal_user_id.is_some(), role }
+    }
+}
+
+/// Body of `PUT /orgs/{id}/members/{user_id}`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemberRoleDto {
+    pub role: Role,
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Shared workspace that owns links. Every user gets a personal one (`personal_user_id`).
+#[derive(Clone, Debug, Default, PartialEq, FromRow, Serialize, Deserialize)]
+pub struct Organization {
+    pub id: i64,
+    pub name: String,
+    pub personal_user_id: Option<i64>,
+}
+
+/// What a member may do inside an organization.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(rename_all = "lowercase")]
+pub enum Role {
+    /// Everything an editor can do, plus managing members.
+    Owner,
+    /// Create, edit and delete the workspace's links.
+    Editor,
+    /// Read-only access to links and their stats.
+    Viewer,
+}
+
+impl Role {
+    pub fn can_edit_links(self) -> bool {
+        matches!(self, Role::Owner | Role::Editor)
+    }
+
+    pub fn can_manage_members(self) -> bool {
+        self == Role::Owner
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, FromRow, Serialize, Deserialize)]
+pub struct Membership {
+    pub org_id: i64,
+    pub user_id: i64,
+    pub role: Role,
+}
@@ -0,0 +1,18 @@
+use async_trait::async_trait;
+use crate::org::domain::models::organization::{Membership, Organization, Role};
+use sqlx::Error;
+
+#[async_trait]
+pub trait OrgRepositoryPort: Send + Sync {
+    async fn get_user_by_api_key(&self, api_key: String) -> Result<i32, Error>;
+    /// Create an organization with `owner_id` as its first owner.
+    async fn create_organization(&self, name: String, owner_id: i32) -> Result<Organization, Error>;
+    /// Organizations `user_id` belongs to, with the role held in each.
+    async fn list_user_organizations(&self, user_id: i32) -> Result<Vec<(Organization, Role)>, Error>;
+    async fn get_member_role(&self, org_id: i64, user_id: i32) -> Result<Option<Role>, Error>;
+    async fn list_members(&self, org_id: i64) -> Result<Vec<Membership>, Error>;
+    /// Add `user_id` to the organization or change its role. Fails with `RowNotFound` when the
+    /// organization or the user does not exist.
+    async fn set_member_role(&self, org_id: i64, user_id: i32, role: Role) -> Result<Membership, Error>;
+    async fn remove_member(&self, org_id: i64, user_id: i32) -> Result<bool, Error>;
+}
@@ -0,0 +1 @@
+pub mod org_service;
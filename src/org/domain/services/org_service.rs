@@ -0,0 +1,163 @@
+use crate::org::domain::models::organization::{Membership, Organization, Role};
+use crate::org::domain::repositories::org_repository_port::OrgRepositoryPort;
+use sqlx::Error;
+use std::sync::Arc;
+use thiserror::Error;
+
+const MAX_ORG_NAME_LEN: usize = 100;
+
+/// Why an organization request was refused.
+#[derive(Debug, Error)]
+pub enum OrgError {
+    #[error("No valid API_KEY")]
+    Unauthorized,
+    #[error("Organization name must be between 1 and {MAX_ORG_NAME_LEN} characters")]
+    InvalidName,
+    #[error("Organization or member not found")]
+    NotFound,
+    #[error("Your role in this organization does not allow this")]
+    Forbidden,
+    #[error("An organization must keep at least one owner")]
+    LastOwner,
+    #[error("Database error: {0}")]
+    Database(Error),
+}
+
+impl From<Error> for OrgError {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::RowNotFound => OrgError::NotFound,
+            other => OrgError::Database(other),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct OrgService {
+    org_repository: Arc<dyn OrgRepositoryPort>,
+}
+
+impl OrgService {
+    pub fn new(org_repository: Arc<dyn OrgRepositoryPort>) -> Self {
+        Self { org_repository }
+    }
+
+    /// User behind `api_key`.
+    pub async fn authenticate(&self, api_key: String) -> Result<i32, OrgError> {
+        self.org_repository.get_user_by_api_key(api_key).await.map_err(|err| match err {
+            Error::RowNotFound => OrgError::Unauthorized,
+            other => OrgError::Database(other),
+        })
+    }
+
+    pub async fn create_organization(&self, user_id: i32, name: &str) -> Result<Organization, OrgError> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_ORG_NAME_LEN {
+            return Err(OrgError::InvalidName);
+        }
+        Ok(self.org_repository.create_organization(name.to_string(), user_id).await?)
+    }
+
+    pub async fn list_organizations(&self, user_id: i32) -> Result<Vec<(Organization, Role)>, OrgError> {
+        Ok(self.org_repository.list_user_organizations(user_id).await?)
+    }
+
+    /// Members of `org_id`; visible to any member.
+    pub async fn list_members(&self, user_id: i32, org_id: i64) -> Result<Vec<Membership>, OrgError> {
+        self.require_role(user_id, org_id).await?;
+        Ok(self.org_repository.list_members(org_id).await?)
+    }
+
+    /// Add `member_id` to the organization or change its role. Owners only; the last owner
+    /// cannot be demoted.
+    pub async fn set_member_role(
+        &self, user_id: i32, org_id: i64, member_id: i32, role: Role,
+    ) -> Result<Membership, OrgError> {
+        if !self.require_role(user_id, org_id).await?.can_manage_members() {
+            return Err(OrgError::Forbidden);
+        }
+        if role != Role::Owner {
+            self.ensure_not_last_owner(org_id, member_id).await?;
+        }
+        Ok(self.org_repository.set_member_role(org_id, member_id, role).await?)
+    }
+
+    /// Remove `member_id` from the organization. Owners may remove anyone; any member may leave.
+    pub async fn remove_member(&self, user_id: i32, org_id: i64, member_id: i32) -> Result<(), OrgError> {
+        let role = self.require_role(user_id, org_id).await?;
+        if member_id != user_id && !role.can_manage_members() {
+            return Err(OrgError::Forbidden);
+        }
+        self.ensure_not_last_owner(org_id, member_id).await?;
+        if !self.org_repository.remove_member(org_id, member_id).await? {
+            return Err(OrgError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// Role of `user_id` in `org_id`; non-members are refused.
+    async fn require_role(&self, user_id: i32, org_id: i64) -> Result<Role, OrgError> {
+        self.org_repository.get_member_role(org_id, user_id).await?.ok_or(OrgError::Forbidden)
+    }
+
+    async fn ensure_not_last_owner(&self, org_id: i64, member_id: i32) -> Result<(), OrgError> {
+        let members = self.org_repository.list_members(org_id).await?;
+        let owners: Vec<_> = members.iter().filter(|m| m.role == Role::Owner).collect();
+        if owners.len() == 1 && owners[0].user_id == member_id as i64 {
+            return Err(OrgError::LastOwner);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::memory::MemoryDatabase;
+    use crate::org::infra::memory_org_repository::MemoryOrgRepository;
+
+    /// Users 1 to 3, authenticated with `key-1` to `key-3`.
+    fn memory_repo() -> Arc<MemoryOrgRepository> {
+        let db = Arc::new(MemoryDatabase::new());
+        for id in 1..=3 {
+            db.insert_user(&format!("user{id}"), &format!("user{id}@x.com"), &format!("key-{id}"));
+        }
+        Arc::new(MemoryOrgRepository::new(db))
+    }
+
+    #[tokio::test]
+    async fn owners_manage_members_and_keep_one_owner() {
+        let service = OrgService::new(memory_repo());
+        assert_eq!(service.authenticate("key-1".into()).await.unwrap(), 1);
+        assert!(matches!(service.authenticate("nope".into()).await, Err(OrgError::Unauthorized)));
+        assert!(matches!(service.create_organization(1, "   ").await, Err(OrgError::InvalidName)));
+
+        let org = service.create_organization(1, " Marketing ").await.unwrap();
+        assert_eq!(org.name, "Marketing");
+        service.set_member_role(1, org.id, 2, Role::Editor).await.unwrap();
+        service.set_member_role(1, org.id, 3, Role::Viewer).await.unwrap();
+        assert_eq!(service.list_members(3, org.id).await.unwrap().len(), 3);
+
+        // only owners change memberships, outsiders see nothing
+        assert!(matches!(service.set_member_role(2, org.id, 3, Role::Editor).await, Err(OrgError::Forbidden)));
+        assert!(matches!(service.remove_member(2, org.id, 3).await, Err(OrgError::Forbidden)));
+        assert!(matches!(service.list_members(9, org.id).await, Err(OrgError::Forbidden)));
+
+        assert!(matches!(service.set_member_role(1, org.id, 1, Role::Viewer).await, Err(OrgError::LastOwner)));
+        assert!(matches!(service.remove_member(1, org.id, 1).await, Err(OrgError::LastOwner)));
+
+        // members may leave on their own
+        service.remove_member(3, org.id, 3).await.unwrap();
+        assert!(matches!(service.remove_member(1, org.id, 3).await, Err(OrgError::NotFound)));
+        // the personal workspace of user 2 comes first
+        let personal = Organization { id: 2, name: "user2".into(), personal_user_id: Some(2) };
+        assert_eq!(service.list_organizations(2).await.unwrap(), vec![(personal, Role::Owner), (org, Role::Editor)]);
+    }
+
+    #[test]
+    fn roles_grant_increasing_rights() {
+        assert!(Role::Owner.can_manage_members() && Role::Owner.can_edit_links());
+        assert!(!Role::Editor.can_manage_members() && Role::Editor.can_edit_links());
+        assert!(!Role::Viewer.can_edit_links());
+    }
+}
@@ -0,0 +1,103 @@
+use crate::config::memory::MemoryDatabase;
+use crate::org::domain::models::organization::{Membership, Organization, Role};
+use crate::org::domain::repositories::org_repository_port::OrgRepositoryPort;
+use async_trait::async_trait;
+use sqlx::Error;
+use std::sync::Arc;
+
+/// `OrgRepositoryPort` kept in a `MemoryDatabase`, with the same behaviour as `SqlxOrgRepository`.
+pub struct MemoryOrgRepository {
+    db: Arc<MemoryDatabase>,
+}
+
+impl MemoryOrgRepository {
+    pub fn new(db: Arc<MemoryDatabase>) -> Self {
+        MemoryOrgRepository { db }
+    }
+}
+
+#[async_trait]
+impl OrgRepositoryPort for MemoryOrgRepository {
+    async fn get_user_by_api_key(&self, api_key: String) -> Result<i32, Error> {
+        self.db.lock().user_id_by_api_key(&api_key).map(|id| id as i32).ok_or(Error::RowNotFound)
+    }
+
+    async fn create_organization(&self, name: String, owner_id: i32) -> Result<Organization, Error> {
+        let mut state = self.db.lock();
+        let org = state.insert_organization(&name, None);
+        state.members.push(Membership { org_id: org.id, user_id: owner_id as i64, role: Role::Owner });
+        Ok(org)
+    }
+
+    async fn list_user_organizations(&self, user_id: i32) -> Result<Vec<(Organization, Role)>, Error> {
+        let state = self.db.lock();
+        Ok(state
+            .organizations
+            .iter()
+            .filter_map(|org| state.member_role(org.id, user_id as i64).map(|role| (org.clone(), role)))
+            .collect())
+    }
+
+    async fn get_member_role(&self, org_id: i64, user_id: i32) -> Result<Option<Role>, Error> {
+        Ok(self.db.lock().member_role(org_id, user_id as i64))
+    }
+
+    async fn list_members(&self, org_id: i64) -> Result<Vec<Membership>, Error> {
+        let state = self.db.lock();
+        let mut members: Vec<Membership> = state.members.iter().filter(|member| member.org_id == org_id).cloned().collect();
+        members.sort_by_key(|member| member.user_id);
+        Ok(members)
+    }
+
+    async fn set_member_role(&self, org_id: i64, user_id: i32, role: Role) -> Result<Membership, Error> {
+        let mut state = self.db.lock();
+        let user_id = user_id as i64;
+        if !state.organizations.iter().any(|org| org.id == org_id) || state.user(user_id).is_none() {
+            return Err(Error::RowNotFound);
+        }
+        let membership = Membership { org_id, user_id, role };
+        match state.members.iter_mut().find(|member| member.org_id == org_id && member.user_id == user_id) {
+            Some(member) => member.role = role,
+            None => state.members.push(membership.clone()),
+        }
+        Ok(membership)
+    }
+
+    async fn remove_member(&self, org_id: i64, user_id: i32) -> Result<bool, Error> {
+        let mut state = self.db.lock();
+        let before = state.members.len();
+        state.members.retain(|member| !(member.org_id == org_id && member.user_id == user_id as i64));
+        Ok(state.members.len() < before)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn organizations_and_memberships_roundtrip() -> Result<(), Error> {
+        let db = Arc::new(MemoryDatabase::new());
+        db.insert_user("a", "a@x.com", "ka");
+        db.insert_user("b", "b@x.com", "kb");
+        let repo = MemoryOrgRepository::new(db);
+
+        assert_eq!(repo.get_user_by_api_key("kb".into()).await?, 2);
+        let org = repo.create_organization("Team".into(), 1).await?;
+        assert_eq!(repo.get_member_role(org.id, 1).await?, Some(Role::Owner));
+        assert_eq!(repo.get_member_role(org.id, 2).await?, None);
+
+        repo.set_member_role(org.id, 2, Role::Viewer).await?;
+        assert_eq!(repo.set_member_role(org.id, 2, Role::Editor).await?.role, Role::Editor);
+        assert_eq!(repo.list_members(org.id).await?.len(), 2);
+        // b's personal workspace, then the team
+        let personal = Organization { id: 2, name: "b".into(), personal_user_id: Some(2) };
+        assert_eq!(repo.list_user_organizations(2).await?, vec![(personal, Role::Owner), (org.clone(), Role::Editor)]);
+        assert!(matches!(repo.set_member_role(org.id, 99, Role::Viewer).await, Err(Error::RowNotFound)));
+        assert!(matches!(repo.set_member_role(99, 2, Role::Viewer).await, Err(Error::RowNotFound)));
+
+        assert!(repo.remove_member(org.id, 2).await?);
+        assert!(!repo.remove_member(org.id, 2).await?);
+        Ok(())
+    }
+}
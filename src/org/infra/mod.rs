@@ -0,0 +1,2 @@
+pub mod memory_org_repository;
+pub mod sqlx_org_repository;
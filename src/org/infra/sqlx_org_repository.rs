@@ -0,0 +1,153 @@
+use crate::org::domain::models::organization::{Membership, Organization, Role};
+use crate::org::domain::repositories::org_repository_port::OrgRepositoryPort;
+use async_trait::async_trait;
+use sqlx::sqlite::SqlitePool;
+use sqlx::Error;
+
+/// SQLx implementation of `OrgRepositoryPort` over the `organizations` and
+/// `organization_members` tables.
+pub struct SqlxOrgRepository {
+    db_pool: SqlitePool,
+}
+
+impl SqlxOrgRepository {
+    pub async fn new(db_pool: SqlitePool) -> Self {
+        SqlxOrgRepository { db_pool }
+    }
+}
+
+/// Role of `user_id` in `org_id`, `None` when it is not a member. Shared with the URL repository,
+/// which checks link permissions against the same table.
+pub async fn member_role(pool: &SqlitePool, org_id: i64, user_id: i32) -> Result<Option<Role>, Error> {
+    let row: Option<(Role,)> = sqlx::query_as("SELECT role FROM organization_members WHERE org_id = $1 AND user_id = $2")
+        .bind(org_id)
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|(role,)| role))
+}
+
+#[async_trait]
+impl OrgRepositoryPort for SqlxOrgRepository {
+    async fn get_user_by_api_key(&self, api_key: String) -> Result<i32, Error> {
+        let row: (i32,) = sqlx::query_as("SELECT id FROM users WHERE api_key = $1 AND api_key != ''")
+            .bind(api_key)
+            .fetch_one(&self.db_pool)
+            .await?;
+        Ok(row.0)
+    }
+
+    async fn create_organization(&self, name: String, owner_id: i32) -> Result<Organization, Error> {
+        let mut tx = self.db_pool.begin().await?;
+        let org = sqlx::query_as::<_, Organization>("INSERT INTO organizations (name) VALUES ($1) RETURNING *")
+            .bind(name)
+            .fetch_one(&mut *tx)
+            .await?;
+        sqlx::query("INSERT INTO organization_members (org_id, user_id, role) VALUES ($1, $2, $3)")
+            .bind(org.id)
+            .bind(owner_id)
+            .bind(Role::Owner)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(org)
+    }
+
+    async fn list_user_organizations(&self, user_id: i32) -> Result<Vec<(Organization, Role)>, Error> {
+        let rows: Vec<(i64, String, Option<i64>, Role)> = sqlx::query_as(
+            "SELECT o.id, o.name, o.personal_user_id, m.role FROM organizations o
+             JOIN organization_members m ON m.org_id = o.id
+             WHERE m.user_id = $1 ORDER BY o.id",
+        )
+        .bind(user_id)
+        .fetch_all(&self.db_pool)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(id, name, personal_user_id, role)| (Organization { id, name, personal_user_id }, role))
+            .collect())
+    }
+
+    async fn get_member_role(&self, org_id: i64, user_id: i32) -> Result<Option<Role>, Error> {
+        member_role(&self.db_pool, org_id, user_id).await
+    }
+
+    async fn list_members(&self, org_id: i64) -> Result<Vec<Membership>, Error> {
+        sqlx::query_as::<_, Membership>("SELECT * FROM organization_members WHERE org_id = $1 ORDER BY user_id")
+            .bind(org_id)
+            .fetch_all(&self.db_pool)
+            .await
+    }
+
+    async fn set_member_role(&self, org_id: i64, user_id: i32, role: Role) -> Result<Membership, Error> {
+        let mut tx = self.db_pool.begin().await?;
+        let found: (i64,) = sqlx::query_as(
+            "SELECT (SELECT COUNT(*) FROM organizations WHERE id = $1) * (SELECT COUNT(*) FROM users WHERE id = $2)",
+        )
+        .bind(org_id)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if found.0 == 0 {
+            return Err(Error::RowNotFound);
+        }
+        let membership = sqlx::query_as::<_, Membership>(
+            "INSERT INTO organization_members (org_id, user_id, role) VALUES ($1, $2, $3)
+             ON CONFLICT (org_id, user_id) DO UPDATE SET role = excluded.role
+             RETURNING *",
+        )
+        .bind(org_id)
+        .bind(user_id)
+        .bind(role)
+        .fetch_one(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(membership)
+    }
+
+    async fn remove_member(&self, org_id: i64, user_id: i32) -> Result<bool, Error> {
+        let result = sqlx::query("DELETE FROM organization_members WHERE org_id = $1 AND user_id = $2")
+            .bind(org_id)
+            .bind(user_id)
+            .execute(&self.db_pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::database::create_org_tables;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::Executor;
+
+    #[tokio::test]
+    async fn organizations_and_memberships_roundtrip() -> Result<(), Error> {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await?;
+        pool.execute(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, username TEXT NOT NULL, email TEXT NOT NULL, api_key TEXT NOT NULL);
+             INSERT INTO users (id, username, email, api_key) VALUES (1, 'a', 'a@x.com', 'ka'), (2, 'b', 'b@x.com', 'kb');",
+        )
+        .await?;
+        create_org_tables(&pool).await?;
+        let repo = SqlxOrgRepository::new(pool).await;
+
+        assert_eq!(repo.get_user_by_api_key("kb".into()).await?, 2);
+        let org = repo.create_organization("Team".into(), 1).await?;
+        assert_eq!(repo.get_member_role(org.id, 1).await?, Some(Role::Owner));
+        assert_eq!(repo.get_member_role(org.id, 2).await?, None);
+
+        repo.set_member_role(org.id, 2, Role::Viewer).await?;
+        let promoted = repo.set_member_role(org.id, 2, Role::Editor).await?;
+        assert_eq!(promoted.role, Role::Editor);
+        assert_eq!(repo.list_members(org.id).await?.len(), 2);
+        assert_eq!(repo.list_user_organizations(2).await?, vec![(org.clone(), Role::Editor)]);
+        assert!(matches!(repo.set_member_role(org.id, 99, Role::Viewer).await, Err(Error::RowNotFound)));
+        assert!(matches!(repo.set_member_role(99, 2, Role::Viewer).await, Err(Error::RowNotFound)));
+
+        assert!(repo.remove_member(org.id, 2).await?);
+        assert!(!repo.remove_member(org.id, 2).await?);
+        Ok(())
+    }
+}
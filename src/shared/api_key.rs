@@ -0,0 +1,42 @@
+use actix_web::dev::Payload;
+use actix_web::error::ErrorUnauthorized;
+use actix_web::{FromRequest, HttpRequest};
+use std::future::{ready, Ready};
+
+pub const API_KEY_HEADER: &str = "X-API-Key";
+
+/// API key sent in the `X-API-Key` header; requests without one are rejected with 401.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApiKey(pub String);
+
+impl FromRequest for ApiKey {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let key = req
+            .headers()
+            .get(API_KEY_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|value| !value.is_empty());
+        ready(match key {
+            Some(key) => Ok(ApiKey(key.to_string())),
+            None => Err(ErrorUnauthorized("Missing X-API-Key header")),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    #[actix_web::test]
+    async fn extracts_header_or_rejects() {
+        let req = TestRequest::default().insert_header((API_KEY_HEADER, " abc ")).to_http_request();
+        assert_eq!(ApiKey::extract(&req).await.unwrap(), ApiKey("abc".into()));
+        let req = TestRequest::default().to_http_request();
+        assert!(ApiKey::extract(&req).await.is_err());
+    }
+}
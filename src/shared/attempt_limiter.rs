@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Fixed-window counter of failed attempts per key (e.g. wrong passwords for a short link).
+///
+/// Once `max_failures` failures are registered within `window`, the key stays blocked until
+/// the window that started with the first failure has elapsed.
+pub struct AttemptLimiter {
+    max_failures: u32,
+    window: Duration,
+    failures: Mutex<HashMap<String, (u32, Instant)>>,
+}
+
+impl AttemptLimiter {
+    pub fn new(max_failures: u32, window: Duration) -> Self {
+        Self { max_failures, window, failures: Mutex::new(HashMap::new()) }
+    }
+
+    /// Remaining block time when `key` has exhausted its attempts, `None` otherwise.
+    pub fn blocked_for(&self, key: &str) -> Option<Duration> {
+        let mut failures = self.failures.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let (count, started) = *failures.get(key)?;
+        let elapsed = started.elapsed();
+        if elapsed >= self.window {
+            failures.remove(key);
+            return None;
+        }
+        (count >= self.max_failures).then(|| self.window - elapsed)
+    }
+
+    pub fn register_failure(&self, key: &str) {
+        let mut failures = self.failures.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let now = Instant::now();
+        let entry = failures.entry(key.to_string()).or_insert((0, now));
+        if now.duration_since(entry.1) >= self.window {
+            *entry = (0, now);
+        }
+        entry.0 += 1;
+    }
+
+    pub fn reset(&self, key: &str) {
+        let mut failures = self.failures.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        failures.remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_after_max_failures_until_reset() {
+        let limiter = AttemptLimiter::new(2, Duration::from_secs(60));
+        limiter.register_failure("k");
+        assert!(limiter.blocked_for("k").is_none());
+        limiter.register_failure("k");
+        assert!(limiter.blocked_for("k").is_some());
+        assert!(limiter.blocked_for("other").is_none());
+        limiter.reset("k");
+        assert!(limiter.blocked_for("k").is_none());
+    }
+
+    #[test]
+    fn window_expiry_unblocks() {
+        let limiter = AttemptLimiter::new(1, Duration::from_millis(10));
+        limiter.register_failure("k");
+        assert!(limiter.blocked_for("k").is_some());
+        std::thread::sleep(Duration::from_millis(15));
+        assert!(limiter.blocked_for("k").is_none());
+    }
+}
@@ -0,0 +1,70 @@
+use actix_web::HttpRequest;
+use ipnet::IpNet;
+use std::net::IpAddr;
+
+/// Resolve the address of the client that originated `req`.
+///
+/// The socket peer is used unless it belongs to one of `trusted_proxies`, in which case the
+/// `X-Forwarded-For` chain is walked right-to-left and the first hop that is not a trusted
+/// proxy is returned. Entries appended by untrusted hops are never believed.
+pub fn resolve_client_ip(req: &HttpRequest, trusted_proxies: &[IpNet]) -> Option<IpAddr> {
+    let peer = req.peer_addr()?.ip();
+    if !is_trusted(peer, trusted_proxies) {
+        return Some(peer);
+    }
+
+    let forwarded: Vec<IpAddr> = req
+        .headers()
+        .get_all("x-forwarded-for")
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|hop| hop.trim().parse::<IpAddr>().ok())
+        .collect();
+
+    // Every hop is a trusted proxy: the left-most one is the best we know about.
+    forwarded
+        .iter()
+        .rev()
+        .find(|ip| !is_trusted(**ip, trusted_proxies))
+        .or_else(|| forwarded.first())
+        .copied()
+        .or(Some(peer))
+}
+
+fn is_trusted(ip: IpAddr, trusted_proxies: &[IpNet]) -> bool {
+    trusted_proxies.iter().any(|net| net.contains(&ip))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn proxies() -> Vec<IpNet> {
+        vec!["10.0.0.0/8".parse().unwrap()]
+    }
+
+    #[test]
+    fn untrusted_peer_ignores_forwarded_header() {
+        let req = TestRequest::default()
+            .peer_addr("203.0.113.9:4000".parse().unwrap())
+            .insert_header(("X-Forwarded-For", "1.2.3.4"))
+            .to_http_request();
+        assert_eq!(resolve_client_ip(&req, &proxies()), Some("203.0.113.9".parse().unwrap()));
+    }
+
+    #[test]
+    fn trusted_peer_uses_last_untrusted_forwarded_hop() {
+        let req = TestRequest::default()
+            .peer_addr("10.0.0.2:4000".parse().unwrap())
+            .insert_header(("X-Forwarded-For", "6.6.6.6, 81.2.69.160, 10.0.0.7"))
+            .to_http_request();
+        assert_eq!(resolve_client_ip(&req, &proxies()), Some("81.2.69.160".parse().unwrap()));
+    }
+
+    #[test]
+    fn trusted_peer_without_header_falls_back_to_peer() {
+        let req = TestRequest::default().peer_addr("10.0.0.2:4000".parse().unwrap()).to_http_request();
+        assert_eq!(resolve_client_ip(&req, &proxies()), Some("10.0.0.2".parse().unwrap()));
+    }
+}
@@ -1 +1,12 @@
+pub mod api_key;
+pub mod attempt_limiter;
+pub mod client_ip;
+pub mod password;
+pub mod qr;
+pub mod rate_limit;
+pub mod rate_limiter;
+#[cfg(test)]
+pub(crate) mod repository_conformance;
+pub mod signing;
+pub mod user_agent;
 pub mod utils;
@@ -0,0 +1,32 @@
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use rand::rngs::OsRng;
+
+/// Hash `password` with Argon2id and a random salt, returning the PHC string to store.
+pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Error> {
+    let salt = SaltString::generate(&mut OsRng);
+    Ok(Argon2::default().hash_password(password.as_bytes(), &salt)?.to_string())
+}
+
+/// Check `password` against a PHC string produced by [`hash_password`].
+/// A malformed stored hash never verifies.
+pub fn verify_password(password: &str, stored_hash: &str) -> bool {
+    PasswordHash::new(stored_hash)
+        .map(|parsed| Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok())
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hash_and_verify_password() {
+        let hash = hash_password("s3cret").expect("hash");
+        assert!(hash.starts_with("$argon2id$"));
+        assert_ne!(hash, hash_password("s3cret").expect("hash"), "salt must be random");
+        assert!(verify_password("s3cret", &hash));
+        assert!(!verify_password("wrong", &hash));
+        assert!(!verify_password("s3cret", "not-a-phc-string"));
+    }
+}
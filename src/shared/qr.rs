@@ -0,0 +1,252 @@
+use qrcode::{Color, EcLevel, QrCode};
+use std::fmt::Write;
+use std::str::FromStr;
+use thiserror::Error;
+
+pub const DEFAULT_QR_SIZE: u32 = 256;
+pub const MAX_QR_SIZE: u32 = 2048;
+pub const DEFAULT_QR_MARGIN: u32 = 4;
+pub const MAX_QR_MARGIN: u32 = 32;
+
+#[derive(Debug, Error)]
+pub enum QrError {
+    #[error("Invalid color '{0}': expected a hex color such as 000000 or #1a2b3c")]
+    InvalidColor(String),
+    #[error("Invalid error correction level '{0}': expected L, M, Q or H")]
+    InvalidEcLevel(String),
+    #[error("Invalid format '{0}': expected png or svg")]
+    InvalidFormat(String),
+    #[error("size must be between 1 and {MAX_QR_SIZE}")]
+    InvalidSize,
+    #[error("margin must be at most {MAX_QR_MARGIN}")]
+    InvalidMargin,
+    #[error("Cannot encode data as a QR code: {0}")]
+    Encode(#[from] qrcode::types::QrError),
+    #[error("Cannot encode PNG: {0}")]
+    Png(#[from] png::EncodingError),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QrFormat {
+    #[default]
+    Png,
+    Svg,
+}
+
+impl QrFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            QrFormat::Png => "image/png",
+            QrFormat::Svg => "image/svg+xml",
+        }
+    }
+}
+
+impl FromStr for QrFormat {
+    type Err = QrError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "png" => Ok(QrFormat::Png),
+            "svg" => Ok(QrFormat::Svg),
+            _ => Err(QrError::InvalidFormat(value.to_string())),
+        }
+    }
+}
+
+/// Opaque RGB color, parsed from `rrggbb` / `#rrggbb` / `rgb` / `#rgb`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QrColor(pub [u8; 3]);
+
+impl QrColor {
+    pub const BLACK: QrColor = QrColor([0, 0, 0]);
+    pub const WHITE: QrColor = QrColor([255, 255, 255]);
+
+    fn to_hex(self) -> String {
+        format!("#{}", hex::encode(self.0))
+    }
+}
+
+impl FromStr for QrColor {
+    type Err = QrError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let digits = value.trim_start_matches('#');
+        let expanded: String = match digits.len() {
+            3 => digits.chars().flat_map(|c| [c, c]).collect(),
+            6 => digits.to_string(),
+            _ => return Err(QrError::InvalidColor(value.to_string())),
+        };
+        let bytes = hex::decode(expanded).map_err(|_| QrError::InvalidColor(value.to_string()))?;
+        Ok(QrColor([bytes[0], bytes[1], bytes[2]]))
+    }
+}
+
+/// Parse an error correction level (`L`, `M`, `Q`, `H`, case insensitive).
+pub fn parse_ec_level(value: &str) -> Result<EcLevel, QrError> {
+    match value.to_ascii_uppercase().as_str() {
+        "L" => Ok(EcLevel::L),
+        "M" => Ok(EcLevel::M),
+        "Q" => Ok(EcLevel::Q),
+        "H" => Ok(EcLevel::H),
+        _ => Err(QrError::InvalidEcLevel(value.to_string())),
+    }
+}
+
+/// How a QR code is rendered.
+///
+/// `size` is the requested width in pixels; modules are scaled by a whole factor so the image can
+/// come out slightly smaller, and never smaller than one pixel per module. `margin` is the quiet
+/// zone in modules.
+#[derive(Clone, Debug, PartialEq)]
+pub struct QrOptions {
+    pub format: QrFormat,
+    pub size: u32,
+    pub margin: u32,
+    pub ec_level: EcLevel,
+    pub foreground: QrColor,
+    pub background: QrColor,
+}
+
+impl Default for QrOptions {
+    fn default() -> Self {
+        Self {
+            format: QrFormat::Png,
+            size: DEFAULT_QR_SIZE,
+            margin: DEFAULT_QR_MARGIN,
+            ec_level: EcLevel::M,
+            foreground: QrColor::BLACK,
+            background: QrColor::WHITE,
+        }
+    }
+}
+
+impl QrOptions {
+    pub fn validate(&self) -> Result<(), QrError> {
+        if self.size == 0 || self.size > MAX_QR_SIZE {
+            return Err(QrError::InvalidSize);
+        }
+        if self.margin > MAX_QR_MARGIN {
+            return Err(QrError::InvalidMargin);
+        }
+        Ok(())
+    }
+}
+
+/// Encode `data` as a QR code image (PNG or SVG bytes, see `options.format`).
+pub fn render(data: &str, options: &QrOptions) -> Result<Vec<u8>, QrError> {
+    options.validate()?;
+    let code = QrCode::with_error_correction_level(data.as_bytes(), options.ec_level)?;
+    let modules = ModuleGrid::new(&code, options.margin);
+    match options.format {
+        QrFormat::Png => render_png(&modules, options),
+        QrFormat::Svg => Ok(render_svg(&modules, options).into_bytes()),
+    }
+}
+
+/// Dark/light modules including the quiet zone.
+struct ModuleGrid {
+    width: usize,
+    dark: Vec<bool>,
+}
+
+impl ModuleGrid {
+    fn new(code: &QrCode, margin: u32) -> Self {
+        let inner = code.width();
+        let margin = margin as usize;
+        let width = inner + 2 * margin;
+        let mut dark = vec![false; width * width];
+        for (i, color) in code.to_colors().into_iter().enumerate() {
+            if color == Color::Dark {
+                dark[(i / inner + margin) * width + i % inner + margin] = true;
+            }
+        }
+        Self { width, dark }
+    }
+
+    fn is_dark(&self, x: usize, y: usize) -> bool {
+        self.dark[y * self.width + x]
+    }
+}
+
+// PNG indexat d'1 bit: paleta [fons, primer pla]
+fn render_png(modules: &ModuleGrid, options: &QrOptions) -> Result<Vec<u8>, QrError> {
+    let scale = (options.size as usize / modules.width).max(1);
+    let pixels = modules.width * scale;
+    let row_bytes = pixels.div_ceil(8);
+    let mut data = vec![0u8; row_bytes * pixels];
+    for y in 0..pixels {
+        for x in 0..pixels {
+            if modules.is_dark(x / scale, y / scale) {
+                data[y * row_bytes + x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, pixels as u32, pixels as u32);
+    encoder.set_color(png::ColorType::Indexed);
+    encoder.set_depth(png::BitDepth::One);
+    encoder.set_palette([options.background.0, options.foreground.0].concat());
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&data)?;
+    writer.finish()?;
+    Ok(out)
+}
+
+fn render_svg(modules: &ModuleGrid, options: &QrOptions) -> String {
+    let mut path = String::new();
+    for y in 0..modules.width {
+        for x in 0..modules.width {
+            if modules.is_dark(x, y) {
+                let _ = write!(path, "M{x} {y}h1v1h-1z");
+            }
+        }
+    }
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<svg xmlns="http://www.w3.org/2000/svg" width="{size}" height="{size}" viewBox="0 0 {w} {w}" shape-rendering="crispEdges"><rect width="{w}" height="{w}" fill="{bg}"/><path fill="{fg}" d="{path}"/></svg>"#,
+        size = options.size,
+        w = modules.width,
+        bg = options.background.to_hex(),
+        fg = options.foreground.to_hex(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn colors_and_levels_parse() {
+        assert_eq!("#ff8000".parse::<QrColor>().unwrap(), QrColor([255, 128, 0]));
+        assert_eq!("fff".parse::<QrColor>().unwrap(), QrColor::WHITE);
+        assert!("red".parse::<QrColor>().is_err());
+        assert_eq!(parse_ec_level("h").unwrap(), EcLevel::H);
+        assert!(parse_ec_level("X").is_err());
+        assert_eq!("SVG".parse::<QrFormat>().unwrap(), QrFormat::Svg);
+    }
+
+    #[test]
+    fn png_is_scaled_to_requested_size() {
+        let options = QrOptions { size: 300, margin: 4, ..Default::default() };
+        let png = render("https://sho.rt/abc", &options).expect("png");
+        let decoder = png::Decoder::new(png.as_slice());
+        let reader = decoder.read_info().expect("valid png");
+        let info = reader.info();
+        // version 2 code: 25 modules + 2 * 4 quiet zone = 33 modules, 9 px each
+        assert_eq!((info.width, info.height), (297, 297));
+        assert_eq!(info.palette.as_deref(), Some(&[255, 255, 255, 0, 0, 0][..]));
+    }
+
+    #[test]
+    fn svg_uses_requested_colors_and_rejects_bad_options() {
+        let options = QrOptions { format: QrFormat::Svg, foreground: QrColor([0x11, 0x22, 0x33]), margin: 0, ..Default::default() };
+        let svg = String::from_utf8(render("https://sho.rt/abc", &options).unwrap()).unwrap();
+        assert!(svg.contains(r##"fill="#112233""##));
+        assert!(svg.contains(r#"viewBox="0 0 25 25""#));
+
+        assert!(matches!(render("x", &QrOptions { size: 0, ..Default::default() }), Err(QrError::InvalidSize)));
+        assert!(matches!(render("x", &QrOptions { margin: 99, ..Default::default() }), Err(QrError::InvalidMargin)));
+    }
+}
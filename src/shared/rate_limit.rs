@@ -0,0 +1,267 @@
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::{HeaderName, HeaderValue, RETRY_AFTER};
+use actix_web::{Error, HttpRequest, HttpResponse};
+use ipnet::IpNet;
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::env::AppConfig;
+use crate::shared::api_key::API_KEY_HEADER;
+use crate::shared::client_ip::resolve_client_ip;
+use crate::shared::rate_limiter::{RateLimitDecision, RateLimitPolicy, TokenBucketLimiter};
+use crate::user::domain::services::user_service::UserService;
+
+const RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+const RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+const RATELIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+
+/// Routes sharing a rate limit policy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RouteGroup {
+    /// Visits of short links: `/{key}`, previews, unlock and QR codes.
+    Redirect,
+    /// Link and organization management (`/url`, `/orgs`, `/admin`, `/usage`).
+    Api,
+    /// Account management under `/users`.
+    Users,
+}
+
+impl RouteGroup {
+    pub fn of(path: &str) -> Self {
+        match path.trim_start_matches('/').split('/').next().unwrap_or_default() {
+            "users" => RouteGroup::Users,
+            "url" | "orgs" | "admin" | "usage" => RouteGroup::Api,
+            _ => RouteGroup::Redirect,
+        }
+    }
+}
+
+/// Token-bucket rate limiting middleware with one policy per `RouteGroup`.
+///
+/// Requests with a valid `X-API-Key` (an admin key, or a user's key once `with_api_keys` is
+/// set) are limited per key, every other request per client IP: a made-up key falls back to
+/// the IP, so it cannot be used to get a fresh bucket. Limited responses carry `RateLimit-Limit`,
+/// `RateLimit-Remaining` and `RateLimit-Reset`, rejected ones (429) also `Retry-After`.
+/// Buckets live in the process, so clone one instance into every worker.
+#[derive(Clone)]
+pub struct RateLimit {
+    redirect: Arc<TokenBucketLimiter>,
+    api: Arc<TokenBucketLimiter>,
+    users: Arc<TokenBucketLimiter>,
+    trusted_proxies: Arc<[IpNet]>,
+    admin_api_keys: Arc<[String]>,
+    user_service: Option<Arc<UserService>>,
+}
+
+impl RateLimit {
+    pub fn new(redirect: RateLimitPolicy, api: RateLimitPolicy, users: RateLimitPolicy, trusted_proxies: Vec<IpNet>) -> Self {
+        Self {
+            redirect: Arc::new(TokenBucketLimiter::new(redirect)),
+            api: Arc::new(TokenBucketLimiter::new(api)),
+            users: Arc::new(TokenBucketLimiter::new(users)),
+            trusted_proxies: trusted_proxies.into(),
+            admin_api_keys: Arc::from([]),
+            user_service: None,
+        }
+    }
+
+    pub fn from_config(config: &AppConfig) -> Self {
+        let mut limits = Self::new(
+            config.rate_limit_redirect,
+            config.rate_limit_api,
+            config.rate_limit_users,
+            config.trusted_proxies.clone(),
+        );
+        limits.admin_api_keys = config.admin_api_keys.clone().into();
+        limits
+    }
+
+    /// Validate users' API keys with `user_service`, so their requests get a bucket per key.
+    pub fn with_api_keys(mut self, user_service: Arc<UserService>) -> Self {
+        self.user_service = Some(user_service);
+        self
+    }
+
+    async fn check(&self, req: &HttpRequest) -> Option<RateLimitDecision> {
+        let group = RouteGroup::of(req.path());
+        let limiter = match group {
+            RouteGroup::Redirect => &self.redirect,
+            RouteGroup::Api => &self.api,
+            RouteGroup::Users => &self.users,
+        };
+        if !limiter.policy().is_enabled() {
+            return None;
+        }
+        let key = match self.api_key_bucket(req).await {
+            Some(key) => key,
+            None => match resolve_client_ip(req, &self.trusted_proxies) {
+                Some(ip) => format!("ip:{ip}"),
+                None => "ip:unknown".to_string(),
+            },
+        };
+        limiter.check(&key)
+    }
+
+    /// Bucket of the `X-API-Key` sent, `None` when it is missing or not a valid key.
+    async fn api_key_bucket(&self, req: &HttpRequest) -> Option<String> {
+        let api_key = req.headers().get(API_KEY_HEADER)?.to_str().ok()?.trim();
+        if api_key.is_empty() {
+            return None;
+        }
+        if self.admin_api_keys.iter().any(|key| key == api_key) {
+            return Some(format!("admin:{api_key}"));
+        }
+        let user_id = self.user_service.as_ref()?.find_user_id(api_key.to_string()).await?;
+        Some(format!("user:{user_id}"))
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware { service: Rc::new(service), limits: self.clone() }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
+    limits: RateLimit,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let limits = self.limits.clone();
+        let service = Rc::clone(&self.service);
+        Box::pin(async move {
+            let decision = limits.check(req.request()).await;
+            if let Some(decision) = decision.filter(|decision| !decision.allowed) {
+                let mut response = HttpResponse::TooManyRequests();
+                response.insert_header((RETRY_AFTER, ceil_secs(decision.retry_after)));
+                for header in rate_limit_headers(&decision) {
+                    response.insert_header(header);
+                }
+                let response = response.body("Too many requests");
+                return Ok(req.into_response(response).map_into_right_body());
+            }
+            let mut response = service.call(req).await?;
+            if let Some(decision) = decision {
+                for (name, value) in rate_limit_headers(&decision) {
+                    response.headers_mut().insert(name, value);
+                }
+            }
+            Ok(response.map_into_left_body())
+        })
+    }
+}
+
+fn rate_limit_headers(decision: &RateLimitDecision) -> [(HeaderName, HeaderValue); 3] {
+    [
+        (RATELIMIT_LIMIT, HeaderValue::from(decision.limit)),
+        (RATELIMIT_REMAINING, HeaderValue::from(decision.remaining)),
+        (RATELIMIT_RESET, HeaderValue::from(ceil_secs(decision.reset_after))),
+    ]
+}
+
+fn ceil_secs(duration: Duration) -> u64 {
+    duration.as_secs() + u64::from(duration.subsec_nanos() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::memory::MemoryDatabase;
+    use crate::user::infra::memory_user_repository::MemoryUserRepository;
+    use actix_web::test::{call_service, init_service, TestRequest};
+    use actix_web::{web, App};
+
+    #[test]
+    fn routes_are_grouped_by_first_segment() {
+        assert_eq!(RouteGroup::of("/users/3/plan"), RouteGroup::Users);
+        assert_eq!(RouteGroup::of("/url"), RouteGroup::Api);
+        assert_eq!(RouteGroup::of("/orgs/1/urls"), RouteGroup::Api);
+        assert_eq!(RouteGroup::of("/abc123"), RouteGroup::Redirect);
+        assert_eq!(RouteGroup::of("/urlish/qr"), RouteGroup::Redirect);
+    }
+
+    #[actix_web::test]
+    async fn limits_per_group_and_client_ip_with_headers() {
+        let config = AppConfig {
+            rate_limit_redirect: RateLimitPolicy::new(1, Duration::from_secs(60)),
+            rate_limit_api: RateLimitPolicy::new(2, Duration::from_secs(60)),
+            rate_limit_users: RateLimitPolicy::disabled(),
+            ..Default::default()
+        };
+        let db = Arc::new(MemoryDatabase::new());
+        let limits = RateLimit::from_config(&config).with_api_keys(Arc::new(UserService::new(Arc::new(MemoryUserRepository::new(db)))));
+        let app = init_service(
+            App::new()
+                .wrap(limits)
+                .route("/url", web::post().to(HttpResponse::Ok))
+                .route("/users", web::post().to(HttpResponse::Ok))
+                .route("/{key}", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+        let peer = "203.0.113.9:4000".parse().unwrap();
+
+        let visit = || TestRequest::get().uri("/abc").peer_addr(peer).to_request();
+        let resp = call_service(&app, visit()).await;
+        assert_eq!(resp.headers().get("ratelimit-limit").unwrap(), "1");
+        assert_eq!(resp.headers().get("ratelimit-remaining").unwrap(), "0");
+        let resp = call_service(&app, visit()).await;
+        assert_eq!(resp.status(), 429);
+        assert_eq!(resp.headers().get("retry-after").unwrap(), "60");
+        assert_eq!(resp.headers().get("ratelimit-reset").unwrap(), "60");
+
+        // the API group has a bucket of its own, per client IP whatever made-up API key is sent
+        let create = |key: &str| TestRequest::post().uri("/url").peer_addr(peer).insert_header((API_KEY_HEADER, key)).to_request();
+        assert!(call_service(&app, create("a")).await.status().is_success());
+        assert!(call_service(&app, create("b")).await.status().is_success());
+        assert_eq!(call_service(&app, create("c")).await.status(), 429);
+        let other_peer = "198.51.100.7:4000".parse().unwrap();
+        let other = TestRequest::post().uri("/url").peer_addr(other_peer).insert_header((API_KEY_HEADER, "c")).to_request();
+        assert!(call_service(&app, other).await.status().is_success());
+
+        // valid keys get a bucket of their own, wherever they are sent from
+        let db = Arc::new(MemoryDatabase::new());
+        db.insert_user("alice", "alice@example.com", "alice-key");
+        let users = UserService::new(Arc::new(MemoryUserRepository::new(db)));
+        let config = AppConfig { admin_api_keys: vec!["root".into()], ..config };
+        let keyed = init_service(App::new().wrap(RateLimit::from_config(&config).with_api_keys(Arc::new(users))).route("/url", web::post().to(HttpResponse::Ok))).await;
+        let create = |key: &str, peer| TestRequest::post().uri("/url").peer_addr(peer).insert_header((API_KEY_HEADER, key)).to_request();
+        assert!(call_service(&keyed, create("alice-key", peer)).await.status().is_success());
+        assert!(call_service(&keyed, create("alice-key", other_peer)).await.status().is_success());
+        assert_eq!(call_service(&keyed, create("alice-key", "192.0.2.1:4000".parse().unwrap())).await.status(), 429);
+        assert!(call_service(&keyed, create("made-up", peer)).await.status().is_success(), "the client IP bucket is untouched");
+        assert!(call_service(&keyed, create("root", peer)).await.status().is_success());
+        assert!(call_service(&keyed, create("root", other_peer)).await.status().is_success());
+        assert_eq!(call_service(&keyed, create("root", peer)).await.status(), 429);
+
+        // disabled policies add no headers
+        let resp = call_service(&app, TestRequest::post().uri("/users").peer_addr(peer).to_request()).await;
+        assert!(resp.status().is_success());
+        assert!(resp.headers().get("ratelimit-limit").is_none());
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Buckets tracked before full (idle) ones are dropped.
+const MAX_TRACKED_KEYS: usize = 10_000;
+
+/// `capacity` requests per `period`, refilled continuously; a capacity of 0 disables the limit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RateLimitPolicy {
+    pub capacity: u32,
+    pub period: Duration,
+}
+
+impl RateLimitPolicy {
+    pub fn new(capacity: u32, period: Duration) -> Self {
+        Self { capacity, period }
+    }
+
+    pub fn disabled() -> Self {
+        Self { capacity: 0, period: Duration::from_secs(1) }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.capacity > 0
+    }
+}
+
+/// `requests/seconds`, e.g. `60/60`, or `off`.
+impl FromStr for RateLimitPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("off") {
+            return Ok(Self::disabled());
+        }
+        let invalid = || format!("invalid rate limit '{value}': expected requests/seconds (e.g. 60/60) or off");
+        let (capacity, secs) = value.split_once('/').ok_or_else(invalid)?;
+        let capacity = capacity.trim().parse::<u32>().map_err(|_| invalid())?;
+        let secs = secs.trim().parse::<u64>().ok().filter(|secs| *secs > 0).ok_or_else(invalid)?;
+        Ok(Self::new(capacity, Duration::from_secs(secs)))
+    }
+}
+
+/// Outcome of taking a token for one request.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub limit: u32,
+    pub remaining: u32,
+    /// Time until the bucket is full again.
+    pub reset_after: Duration,
+    /// Time until the next request would be accepted; zero when allowed.
+    pub retry_after: Duration,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token bucket per key (validated API key or client IP) sharing one policy.
+pub struct TokenBucketLimiter {
+    policy: RateLimitPolicy,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl TokenBucketLimiter {
+    pub fn new(policy: RateLimitPolicy) -> Self {
+        Self { policy, buckets: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn policy(&self) -> RateLimitPolicy {
+        self.policy
+    }
+
+    /// Take a token from the bucket of `key`, `None` when the policy is disabled.
+    pub fn check(&self, key: &str) -> Option<RateLimitDecision> {
+        self.check_at(key, Instant::now())
+    }
+
+    fn check_at(&self, key: &str, now: Instant) -> Option<RateLimitDecision> {
+        if !self.policy.is_enabled() {
+            return None;
+        }
+        let capacity = f64::from(self.policy.capacity);
+        let per_sec = capacity / self.policy.period.as_secs_f64();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if buckets.len() >= MAX_TRACKED_KEYS && !buckets.contains_key(key) {
+            buckets.retain(|_, bucket| bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * per_sec < capacity);
+        }
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket { tokens: capacity, updated: now });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_sec).min(capacity);
+        bucket.updated = now;
+
+        let allowed = bucket.tokens >= 1.0;
+        if allowed {
+            bucket.tokens -= 1.0;
+        }
+        let retry_after = if allowed { 0.0 } else { (1.0 - bucket.tokens) / per_sec };
+        Some(RateLimitDecision {
+            allowed,
+            limit: self.policy.capacity,
+            remaining: bucket.tokens.floor() as u32,
+            reset_after: Duration::from_secs_f64((capacity - bucket.tokens) / per_sec),
+            retry_after: Duration::from_secs_f64(retry_after),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_policies() {
+        assert_eq!("60/60".parse::<RateLimitPolicy>(), Ok(RateLimitPolicy::new(60, Duration::from_secs(60))));
+        assert!(!"off".parse::<RateLimitPolicy>().unwrap().is_enabled());
+        assert!(!"0/1".parse::<RateLimitPolicy>().unwrap().is_enabled());
+        for value in ["60", "60/0", "a/60", "60/-1"] {
+            assert!(value.parse::<RateLimitPolicy>().is_err(), "{value} should be rejected");
+        }
+    }
+
+    #[test]
+    fn bucket_drains_and_refills_per_key() {
+        let limiter = TokenBucketLimiter::new(RateLimitPolicy::new(2, Duration::from_secs(10)));
+        let start = Instant::now();
+        let first = limiter.check_at("a", start).unwrap();
+        assert_eq!((first.allowed, first.limit, first.remaining), (true, 2, 1));
+        assert!(limiter.check_at("a", start).unwrap().allowed);
+
+        let denied = limiter.check_at("a", start).unwrap();
+        assert!(!denied.allowed);
+        assert_eq!(denied.retry_after, Duration::from_secs(5));
+        assert_eq!(denied.reset_after, Duration::from_secs(10));
+        assert!(limiter.check_at("b", start).unwrap().allowed);
+
+        // one token every 5 seconds
+        let later = limiter.check_at("a", start + Duration::from_secs(5)).unwrap();
+        assert_eq!((later.allowed, later.remaining), (true, 0));
+    }
+
+    #[test]
+    fn disabled_policy_never_limits() {
+        let limiter = TokenBucketLimiter::new(RateLimitPolicy::disabled());
+        assert_eq!(limiter.check("a"), None);
+    }
+}
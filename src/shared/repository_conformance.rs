@@ -0,0 +1,311 @@
+//! Behaviour every pair of `URLRepositoryPort` / `UserRepositoryPort` adapters must share, run
+//! against each backend from its own tests with `check_repositories`.
+
+use crate::url::domain::models::filter::URLFilter;
+use crate::url::domain::models::health::LinkCheck;
+use crate::url::domain::models::schema::URL;
+use crate::url::domain::repositories::url_repository_port::URLRepositoryPort;
+use crate::user::application::dtos::user_dto::{UserDtoCreate, UserDtoUpdate};
+use crate::user::domain::models::ownership::{DeleteUserStrategy, DELETED_USER_NAME};
+use crate::user::domain::repositories::user_repository_port::UserRepositoryPort;
+use crate::config::database::create_schema;
+use crate::shared::utils::create_random_key;
+use chrono::Utc;
+use sqlx::sqlite::SqlitePoolOptions;
+use sqlx::{Error, SqlitePool};
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::Arc;
+
+/// Both ports over one fresh, empty store.
+pub(crate) struct Repositories {
+    pub(crate) urls: Arc<dyn URLRepositoryPort>,
+    pub(crate) users: Arc<dyn UserRepositoryPort>,
+}
+
+/// Run every check, each against a store returned by a new call to `new_repositories`. The
+/// store must hand out at least 32 generated keys.
+pub(crate) async fn check_repositories<F, Fut>(new_repositories: F)
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Repositories>,
+{
+    keys_are_allocated_once(new_repositories().await).await;
+    reusable_links_are_reused(new_repositories().await).await;
+    inactive_keys_are_found_but_not_served(new_repositories().await).await;
+    concurrent_clicks_are_all_counted(new_repositories().await).await;
+    click_limits_hold_under_concurrent_visits(new_repositories().await).await;
+    clicks_on_unknown_keys_are_rejected(new_repositories().await).await;
+    unknown_keys_are_capped(new_repositories().await).await;
+    new_targets_clear_health_checks(new_repositories().await).await;
+    deleted_users_leave_their_links_as_asked(new_repositories().await).await;
+    usernames_and_emails_stay_unique(new_repositories().await).await;
+}
+
+/// Empty in-memory SQLite database with every table and enough generated keys for the checks.
+pub(crate) async fn sqlite_pool() -> SqlitePool {
+    let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.expect("open SQLite");
+    create_schema(&pool).await.expect("create schema");
+    for _ in 0..64 {
+        sqlx::query("INSERT OR IGNORE INTO generated_keys (key_value) VALUES (?)")
+            .bind(create_random_key(8))
+            .execute(&pool)
+            .await
+            .expect("generate key");
+    }
+    pool
+}
+
+async fn create_user(repos: &Repositories, username: &str) -> i32 {
+    let dto = UserDtoCreate { username: username.into(), email: format!("{username}@example.com") };
+    let created = repos.users.create_user(dto, format!("key-{username}")).await.expect("create user");
+    assert_eq!(repos.users.get_user(created.id as i32).await.expect("get created user").username, username);
+    created.id as i32
+}
+
+/// Column whose unique constraint `result` violated.
+fn violated_column<T>(result: Result<T, Error>) -> String {
+    match result {
+        Err(Error::Database(e)) if e.is_unique_violation() => e.constraint().unwrap_or(e.message()).to_string(),
+        Err(err) => panic!("expected a unique violation, got {err}"),
+        Ok(_) => panic!("expected a unique violation, the write succeeded"),
+    }
+}
+
+async fn keys_are_allocated_once(repos: Repositories) {
+    let alice = create_user(&repos, "alice").await;
+    let mut keys = HashSet::new();
+    let mut secret_keys = HashSet::new();
+    for i in 0..20 {
+        let url = repos.urls.create_url(format!("http://a.com/{i}"), alice, None, None, false).await.expect("create link");
+        assert_ne!(url.key, url.secret_key, "public and admin keys differ");
+        assert!(keys.insert(url.key.clone()), "key {} handed out twice", url.key);
+        assert!(secret_keys.insert(url.secret_key.clone()), "secret key {} handed out twice", url.secret_key);
+        assert!(repos.urls.key_exists(url.key.clone()).await.unwrap());
+        assert_eq!(repos.urls.find_url_by_secret_key(url.secret_key).await.unwrap().key, url.key);
+    }
+
+    let alias = repos.urls.create_url("http://b.com".into(), alice, None, Some("promo".into()), false).await.unwrap();
+    assert_eq!(alias.key, "promo");
+    assert!(alias.custom_alias);
+    let taken = repos.urls.create_url("http://c.com".into(), alice, None, Some("promo".into()), false).await;
+    assert!(matches!(taken, Err(Error::Database(e)) if e.is_unique_violation()), "a key belongs to one link");
+    assert!(!repos.urls.key_exists("unused".into()).await.unwrap());
+
+    // deleted links keep their key reserved
+    assert!(repos.urls.delete_url("promo".into()).await.unwrap());
+    assert!(!repos.urls.delete_url("promo".into()).await.unwrap());
+    assert!(repos.urls.key_exists("promo".into()).await.unwrap());
+    assert!(matches!(repos.urls.find_url_by_key("promo".into()).await, Err(Error::RowNotFound)));
+}
+
+async fn reusable_links_are_reused(repos: Repositories) {
+    let alice = create_user(&repos, "alice").await;
+    let bob = create_user(&repos, "bob").await;
+    let target = "http://a.com/".to_string();
+
+    let first = repos.urls.create_url(target.clone(), alice, None, None, true).await.unwrap();
+    assert_eq!(repos.urls.create_url(target.clone(), alice, None, None, true).await.unwrap().key, first.key);
+    let second = repos.urls.create_url(target.clone(), alice, None, None, false).await.unwrap();
+    assert_ne!(second.key, first.key, "links are only reused when asked");
+    let found = repos.urls.get_db_url_by_user_and_target_url(alice, target.clone()).await.unwrap();
+    assert!(found.key == first.key || found.key == second.key);
+
+    // links of other users and one-time links are never handed out again
+    assert_ne!(repos.urls.create_url(target.clone(), bob, None, None, true).await.unwrap().key, first.key);
+    repos.urls.update_url(URL { single_use: true, ..first.clone() }).await.unwrap();
+    assert_eq!(repos.urls.create_url(target.clone(), alice, None, None, true).await.unwrap().key, second.key);
+    repos.urls.update_url(URL { single_use: true, ..second }).await.unwrap();
+    let fresh = repos.urls.create_url(target.clone(), alice, None, None, true).await.unwrap();
+    assert!(!fresh.single_use && fresh.key != first.key);
+    assert!(matches!(repos.urls.get_db_url_by_user_and_target_url(alice, "http://none/".into()).await, Err(Error::RowNotFound)));
+}
+
+async fn inactive_keys_are_found_but_not_served(repos: Repositories) {
+    let alice = create_user(&repos, "alice").await;
+    let url = repos.urls.create_url("http://a.com/".into(), alice, None, None, false).await.unwrap();
+    assert!(repos.urls.get_db_url_by_key(url.key.clone()).await.unwrap().is_active);
+
+    assert!(repos.urls.deactivate_url(url.key.clone()).await.unwrap());
+    assert!(!repos.urls.deactivate_url(url.key.clone()).await.unwrap(), "only the first deactivation counts");
+    assert!(matches!(repos.urls.get_db_url_by_key(url.key.clone()).await, Err(Error::RowNotFound)));
+    assert!(!repos.urls.find_url_by_key(url.key.clone()).await.unwrap().is_active);
+    assert!(!repos.urls.find_url_by_secret_key(url.secret_key.clone()).await.unwrap().is_active);
+    assert!(repos.urls.key_exists(url.key.clone()).await.unwrap());
+
+    assert!(!repos.urls.deactivate_url("unknown".into()).await.unwrap());
+    assert!(matches!(repos.urls.get_db_url_by_key("unknown".into()).await, Err(Error::RowNotFound)));
+    assert!(matches!(repos.urls.find_url_by_key("unknown".into()).await, Err(Error::RowNotFound)));
+}
+
+async fn concurrent_clicks_are_all_counted(repos: Repositories) {
+    let alice = create_user(&repos, "alice").await;
+    let url = repos.urls.create_url("http://a.com/".into(), alice, None, None, false).await.unwrap();
+    let visits: Vec<_> = (0..50)
+        .map(|i| {
+            let (urls, key) = (repos.urls.clone(), url.key.clone());
+            tokio::spawn(async move {
+                match i % 5 {
+                    0 => urls.increment_interstitial_clicks(key).await,
+                    _ => urls.increment_clicks(key).await,
+                }
+            })
+        })
+        .collect();
+    for visit in visits {
+        visit.await.expect("visit task").expect("visit counted");
+    }
+    repos.urls.add_clicks(url.key.clone(), 5, 1).await.unwrap();
+
+    let counted = repos.urls.find_url_by_key(url.key).await.unwrap();
+    assert_eq!((counted.clicks, counted.interstitial_clicks), (45, 11));
+}
+
+async fn click_limits_hold_under_concurrent_visits(repos: Repositories) {
+    let alice = create_user(&repos, "alice").await;
+    let url = repos.urls.create_url("http://a.com/".into(), alice, None, None, false).await.unwrap();
+    repos.urls.update_url(URL { max_clicks: Some(10), ..url.clone() }).await.unwrap();
+    let visits: Vec<_> = (0..30)
+        .map(|i| {
+            let (urls, key) = (repos.urls.clone(), url.key.clone());
+            tokio::spawn(async move {
+                match i % 3 {
+                    0 => urls.increment_interstitial_clicks(key).await,
+                    _ => urls.increment_clicks(key).await,
+                }
+            })
+        })
+        .collect();
+    let mut counted = 0;
+    for visit in visits {
+        counted += visit.await.expect("visit task").expect("visit checked") as i32;
+    }
+    assert_eq!(counted, 10, "only the visits allowed by max_clicks are counted");
+    let stored = repos.urls.find_url_by_key(url.key).await.unwrap();
+    assert_eq!(stored.clicks + stored.interstitial_clicks, 10);
+}
+
+async fn clicks_on_unknown_keys_are_rejected(repos: Repositories) {
+    assert!(matches!(repos.urls.increment_clicks("nope".into()).await, Err(Error::RowNotFound)));
+    assert!(matches!(repos.urls.increment_interstitial_clicks("nope".into()).await, Err(Error::RowNotFound)));
+    assert!(matches!(repos.urls.add_clicks("nope".into(), 2, 1).await, Err(Error::RowNotFound)));
+}
+
+async fn unknown_keys_are_capped(repos: Repositories) {
+    for key in ["flyer", "flyer", "typo", "poster"] {
+        repos.urls.record_unknown_key(key.into(), 2).await.unwrap();
+    }
+    let hits = repos.urls.list_unknown_keys(10).await.unwrap();
+    let keys: Vec<(&str, i64)> = hits.iter().map(|hit| (hit.key.as_str(), hit.hits)).collect();
+    assert_eq!(keys, vec![("flyer", 2), ("poster", 1)], "the least visited, least recent key made room");
+}
+
+async fn new_targets_clear_health_checks(repos: Repositories) {
+    let alice = create_user(&repos, "alice").await;
+    let mut keys = vec![];
+    for i in 0..3 {
+        keys.push(repos.urls.create_url(format!("http://a.com/{i}"), alice, None, None, false).await.unwrap().key);
+    }
+    let dead = LinkCheck { status_code: Some(404), final_url: None, latency_ms: 5, error: None, checked_at: Utc::now() };
+    repos.urls.record_link_check(keys[0].clone(), dead.clone()).await.unwrap();
+    repos.urls.record_link_check(keys[0].clone(), dead).await.unwrap();
+
+    let url = repos.urls.find_url_by_key(keys[0].clone()).await.unwrap();
+    let kept = repos.urls.update_url(URL { title: Some("Docs".into()), ..url }).await.unwrap();
+    assert_eq!((kept.failed_checks, kept.last_check_status), (2, Some(404)), "same target, same results");
+    let moved = repos.urls.update_url(URL { target_url: "http://b.com/".into(), ..kept }).await.unwrap();
+    assert_eq!((moved.failed_checks, moved.last_check_status, moved.last_checked_at), (0, None, None));
+    assert_eq!(repos.urls.find_url_by_key(keys[0].clone()).await.unwrap().failed_checks, 0);
+
+    let due = URLFilter { active: true, checked_before: Some(Utc::now()), limit: Some(2), ..Default::default() };
+    let listed: Vec<String> = repos.urls.list_urls(due).await.unwrap().into_iter().map(|url| url.key).collect();
+    assert_eq!(listed, keys[..2], "at most `limit` links, the oldest first");
+}
+
+async fn deleted_users_leave_their_links_as_asked(repos: Repositories) {
+    let [alice, bob, carol, dave] = [
+        create_user(&repos, "alice").await,
+        create_user(&repos, "bob").await,
+        create_user(&repos, "carol").await,
+        create_user(&repos, "dave").await,
+    ];
+    let link = |user_id: i32, n: i32| {
+        let urls = repos.urls.clone();
+        async move { urls.create_url(format!("http://a.com/{n}"), user_id, None, None, false).await.unwrap().key }
+    };
+    let alice_links = [link(alice, 1).await, link(alice, 2).await];
+    let carol_link = link(carol, 3).await;
+    let dave_link = link(dave, 4).await;
+    // read every link first, so a caching adapter serves them from its cache afterwards
+    for key in alice_links.iter().chain([&carol_link, &dave_link]) {
+        let url = repos.urls.get_db_url_by_key(key.clone()).await.unwrap();
+        assert!(url.is_active);
+        assert_eq!(repos.urls.find_url_by_key(key.clone()).await.unwrap().user_id, url.user_id);
+    }
+
+    assert!(matches!(repos.users.delete_user(999, DeleteUserStrategy::Cascade).await, Err(Error::RowNotFound)));
+    let to_nobody = DeleteUserStrategy::Transfer { to_user_id: 999 };
+    assert!(matches!(repos.users.delete_user(alice, to_nobody).await, Err(Error::RowNotFound)));
+    assert_eq!(repos.urls.get_user_by_apy_key("key-alice".into()).await, Ok(alice), "failed deletions change nothing");
+
+    assert_eq!(repos.users.delete_user(alice, DeleteUserStrategy::Transfer { to_user_id: bob }).await.unwrap(), 2);
+    for key in &alice_links {
+        let url = repos.urls.get_db_url_by_key(key.clone()).await.unwrap();
+        assert_eq!(url.user_id, bob);
+        assert_eq!(repos.urls.find_url_by_key(key.clone()).await.unwrap().user_id, bob);
+    }
+    assert_eq!(repos.urls.get_user_by_apy_key("key-alice".into()).await, Err(()));
+
+    assert_eq!(repos.users.delete_user(carol, DeleteUserStrategy::Deactivate).await.unwrap(), 1);
+    let kept = repos.urls.find_url_by_key(carol_link.clone()).await.unwrap();
+    assert!(!kept.is_active && kept.user_id != carol);
+    assert!(matches!(repos.urls.get_db_url_by_key(carol_link).await, Err(Error::RowNotFound)));
+    // the placeholder that keeps them cannot sign in and is not a listed user
+    assert_eq!(repos.urls.get_user_by_apy_key(String::new()).await, Err(()));
+    assert!(matches!(repos.users.get_user_by_api_key(String::new()).await, Err(Error::RowNotFound)));
+    assert!(matches!(repos.users.get_user(kept.user_id).await, Err(Error::RowNotFound)));
+    let hide = UserDtoUpdate { username: Some("ghost".into()), email: None };
+    assert!(matches!(repos.users.update_user(kept.user_id, hide).await, Err(Error::RowNotFound)));
+    // nor does it receive links or go away
+    assert!(matches!(repos.users.transfer_links(bob, kept.user_id, None).await, Err(Error::RowNotFound)));
+    let to_placeholder = DeleteUserStrategy::Transfer { to_user_id: kept.user_id };
+    assert!(matches!(repos.users.delete_user(bob, to_placeholder).await, Err(Error::RowNotFound)));
+    assert!(matches!(repos.users.delete_user(kept.user_id, DeleteUserStrategy::Cascade).await, Err(Error::RowNotFound)));
+    assert_eq!(repos.urls.get_db_url_by_key(alice_links[0].clone()).await.unwrap().user_id, bob);
+
+    assert_eq!(repos.users.delete_user(dave, DeleteUserStrategy::Cascade).await.unwrap(), 1);
+    assert!(matches!(repos.urls.get_db_url_by_key(dave_link.clone()).await, Err(Error::RowNotFound)));
+    assert!(matches!(repos.urls.find_url_by_key(dave_link.clone()).await, Err(Error::RowNotFound)));
+    assert!(repos.urls.key_exists(dave_link).await.unwrap(), "keys of deleted links stay reserved");
+
+    let names: Vec<String> = repos.users.get_users().await.unwrap().into_iter().map(|user| user.username).collect();
+    assert!(names.contains(&"bob".to_string()));
+    assert!(!names.iter().any(|name| ["alice", "carol", "dave", DELETED_USER_NAME].contains(&name.as_str())));
+}
+
+async fn usernames_and_emails_stay_unique(repos: Repositories) {
+    let alice = create_user(&repos, "alice").await;
+    let bob = create_user(&repos, "bob").await;
+    let new_user = |username: &str, email: &str| UserDtoCreate { username: username.into(), email: email.into() };
+    let taken = repos.users.create_user(new_user("ALICE", "other@example.com"), "k1".into()).await;
+    assert!(violated_column(taken).contains("users.username"));
+    let taken = repos.users.create_user(new_user("carol", "Alice@Example.com"), "k2".into()).await;
+    assert!(violated_column(taken).contains("users.email"));
+
+    let rename = UserDtoUpdate { username: Some("alicia".into()), email: None };
+    let renamed = repos.users.update_user(alice, rename).await.unwrap();
+    assert_eq!((renamed.username.as_str(), renamed.email.as_str()), ("alicia", "alice@example.com"));
+    assert_eq!(repos.users.get_user(alice).await.unwrap().username, "alicia");
+    // a user may change the case of its own username
+    let own = UserDtoUpdate { username: Some("BOB".into()), email: Some("bob@example.org".into()) };
+    assert_eq!(repos.users.update_user(bob, own).await.unwrap().email, "bob@example.org");
+
+    let steal = UserDtoUpdate { username: Some("Alicia".into()), email: None };
+    assert!(violated_column(repos.users.update_user(bob, steal).await).contains("users.username"));
+    let steal = UserDtoUpdate { username: None, email: Some("ALICE@example.com".into()) };
+    assert!(violated_column(repos.users.update_user(bob, steal).await).contains("users.email"));
+    assert_eq!(repos.users.get_user(bob).await.unwrap().username, "BOB", "failed updates change nothing");
+
+    assert!(matches!(repos.users.get_user(999).await, Err(Error::RowNotFound)));
+    assert!(matches!(repos.users.update_user(999, UserDtoUpdate::default()).await, Err(Error::RowNotFound)));
+}
@@ -0,0 +1,36 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Hex encoded HMAC-SHA256 of `payload` under `secret`.
+pub fn sign(secret: &str, payload: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(payload.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Check a signature produced by [`sign`] in constant time.
+pub fn verify(secret: &str, payload: &str, signature: &str) -> bool {
+    let Ok(expected) = hex::decode(signature) else {
+        return false;
+    };
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(payload.as_bytes());
+    mac.verify_slice(&expected).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_and_verify_roundtrip() {
+        let sig = sign("secret", "payload");
+        assert_eq!(sig.len(), 64);
+        assert!(verify("secret", "payload", &sig));
+        assert!(!verify("secret", "other", &sig));
+        assert!(!verify("other", "payload", &sig));
+        assert!(!verify("secret", "payload", "not-hex"));
+    }
+}
@@ -0,0 +1,53 @@
+/// User agent fragments (lowercase) of the bots that fetch a link to render its preview card.
+const PREVIEW_CRAWLERS: &[&str] = &[
+    "slackbot",
+    "twitterbot",
+    "facebookexternalhit",
+    "facebookcatalog",
+    "linkedinbot",
+    "discordbot",
+    "telegrambot",
+    "whatsapp",
+    "skypeuripreview",
+    "mattermost",
+    "redditbot",
+    "pinterestbot",
+    "embedly",
+    "iframely",
+    "mastodon",
+    "bluesky cardyb",
+    "google-pagerenderer",
+    "vkshare",
+];
+
+/// Whether `user_agent` belongs to a chat app or social network building a link preview.
+pub fn is_preview_crawler(user_agent: &str) -> bool {
+    let user_agent = user_agent.to_ascii_lowercase();
+    PREVIEW_CRAWLERS.iter().any(|crawler| user_agent.contains(crawler))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recognises_preview_crawlers_only() {
+        for crawler in [
+            "Slackbot-LinkExpanding 1.0 (+https://api.slack.com/robots)",
+            "Twitterbot/1.0",
+            "facebookexternalhit/1.1 (+http://www.facebook.com/externalhit_uatext.php)",
+            "Mozilla/5.0 (compatible; Discordbot/2.0; +https://discordapp.com)",
+            "WhatsApp/2.23.20.0",
+            "TelegramBot (like TwitterBot)",
+        ] {
+            assert!(is_preview_crawler(crawler), "{crawler}");
+        }
+        for browser in [
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0 Safari/537.36",
+            "curl/8.5.0",
+            "",
+        ] {
+            assert!(!is_preview_crawler(browser), "{browser}");
+        }
+    }
+}
@@ -12,6 +12,11 @@ pub fn create_api_key() -> String {
     api_key
 }
 
+/// Public key of a generated `PUBLIC_SECRET` pair: the part links are served at.
+pub fn public_key_part(key_pair: &str) -> &str {
+    key_pair.split('_').next().unwrap_or(key_pair)
+}
+
 pub fn create_random_key(length: usize) -> String {
     let key1 = generate_key_part(length);
     let key2 = generate_key_part(length);
@@ -46,5 +51,6 @@ mod tests {
         assert_eq!(parts.len(), 2);
         assert_eq!(parts[0].len(), 8);
         assert_eq!(parts[1].len(), 8);
+        assert_eq!(public_key_part(&key), parts[0]);
     }
 }
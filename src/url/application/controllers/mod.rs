@@ -1 +1,2 @@
 pub mod url_controller;
+pub mod tag_controller;
@@ -0,0 +1,227 @@
+use actix_web::{delete, get, patch, post, put, web, HttpResponse, Responder};
+
+use crate::config::env::AppConfig;
+use crate::shared::api_key::ApiKey;
+use crate::url::application::dtos::url_dto::TagNameDto;
+use crate::url::domain::models::access::Caller;
+use crate::url::domain::services::tag_service::{TagError, TagService};
+
+use std::sync::Arc;
+
+#[post("/orgs/{org_id}/tags")]
+pub async fn create_tag(
+    api_key: ApiKey, org_id: web::Path<i64>, body: web::Json<TagNameDto>, tag_service: web::Data<Arc<TagService>>,
+    config: web::Data<AppConfig>,
+) -> impl Responder {
+    let result = async {
+        let caller = authenticate(api_key, &tag_service, &config).await?;
+        tag_service.create_tag(caller, org_id.into_inner(), &body.name).await
+    };
+    match result.await {
+        Ok(tag) => HttpResponse::Created().json(tag),
+        Err(err) => tag_error_response(err),
+    }
+}
+
+#[get("/orgs/{org_id}/tags")]
+pub async fn list_tags(
+    api_key: ApiKey, org_id: web::Path<i64>, tag_service: web::Data<Arc<TagService>>, config: web::Data<AppConfig>,
+) -> impl Responder {
+    let result = async {
+        let caller = authenticate(api_key, &tag_service, &config).await?;
+        tag_service.list_tags(caller, org_id.into_inner()).await
+    };
+    match result.await {
+        Ok(tags) => HttpResponse::Ok().json(tags),
+        Err(err) => tag_error_response(err),
+    }
+}
+
+/// Links and clicks per tag of the workspace.
+#[get("/orgs/{org_id}/tags/stats")]
+pub async fn tag_stats(
+    api_key: ApiKey, org_id: web::Path<i64>, tag_service: web::Data<Arc<TagService>>, config: web::Data<AppConfig>,
+) -> impl Responder {
+    let result = async {
+        let caller = authenticate(api_key, &tag_service, &config).await?;
+        tag_service.tag_stats(caller, org_id.into_inner()).await
+    };
+    match result.await {
+        Ok(stats) => HttpResponse::Ok().json(stats),
+        Err(err) => tag_error_response(err),
+    }
+}
+
+#[patch("/orgs/{org_id}/tags/{tag_id}")]
+pub async fn rename_tag(
+    api_key: ApiKey, path: web::Path<(i64, i64)>, body: web::Json<TagNameDto>, tag_service: web::Data<Arc<TagService>>,
+    config: web::Data<AppConfig>,
+) -> impl Responder {
+    let (org_id, tag_id) = path.into_inner();
+    let result = async {
+        let caller = authenticate(api_key, &tag_service, &config).await?;
+        tag_service.rename_tag(caller, org_id, tag_id, &body.name).await
+    };
+    match result.await {
+        Ok(tag) => HttpResponse::Ok().json(tag),
+        Err(err) => tag_error_response(err),
+    }
+}
+
+#[delete("/orgs/{org_id}/tags/{tag_id}")]
+pub async fn delete_tag(
+    api_key: ApiKey, path: web::Path<(i64, i64)>, tag_service: web::Data<Arc<TagService>>, config: web::Data<AppConfig>,
+) -> impl Responder {
+    let (org_id, tag_id) = path.into_inner();
+    let result = async {
+        let caller = authenticate(api_key, &tag_service, &config).await?;
+        tag_service.delete_tag(caller, org_id, tag_id).await
+    };
+    match result.await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(err) => tag_error_response(err),
+    }
+}
+
+#[put("/url/{url_key}/tags/{tag_id}")]
+pub async fn attach_tag(
+    api_key: ApiKey, path: web::Path<(String, i64)>, tag_service: web::Data<Arc<TagService>>, config: web::Data<AppConfig>,
+) -> impl Responder {
+    let (url_key, tag_id) = path.into_inner();
+    let result = async {
+        let caller = authenticate(api_key, &tag_service, &config).await?;
+        tag_service.attach_tag(caller, url_key, tag_id).await
+    };
+    match result.await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(err) => tag_error_response(err),
+    }
+}
+
+#[delete("/url/{url_key}/tags/{tag_id}")]
+pub async fn detach_tag(
+    api_key: ApiKey, path: web::Path<(String, i64)>, tag_service: web::Data<Arc<TagService>>, config: web::Data<AppConfig>,
+) -> impl Responder {
+    let (url_key, tag_id) = path.into_inner();
+    let result = async {
+        let caller = authenticate(api_key, &tag_service, &config).await?;
+        tag_service.detach_tag(caller, url_key, tag_id).await
+    };
+    match result.await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(err) => tag_error_response(err),
+    }
+}
+
+async fn authenticate(api_key: ApiKey, tag_service: &TagService, config: &AppConfig) -> Result<Caller, TagError> {
+    if config.is_admin_key(&api_key.0) {
+        return Ok(Caller::Admin);
+    }
+    tag_service.authenticate(api_key.0).await
+}
+
+fn tag_error_response(err: TagError) -> HttpResponse {
+    match err {
+        TagError::Unauthorized => HttpResponse::Unauthorized().body(err.to_string()),
+        TagError::InvalidName | TagError::OtherWorkspace => HttpResponse::BadRequest().body(err.to_string()),
+        TagError::NotFound => HttpResponse::NotFound().body(err.to_string()),
+        TagError::Forbidden => HttpResponse::Forbidden().body(err.to_string()),
+        TagError::Duplicate => HttpResponse::Conflict().body(err.to_string()),
+        TagError::Database(db_err) => {
+            eprintln!("Error occurred[tag_ctrl]: {}", db_err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::database::{create_org_tables, create_tag_tables};
+    use crate::url::domain::models::tag::{Tag, TagStats};
+    use crate::url::infra::sqlx_url_repository::SqlxURLRepository;
+    use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
+    use actix_web::App;
+    use sqlx::sqlite::SqlitePoolOptions;
+    use sqlx::Executor;
+
+    #[actix_web::test]
+    async fn controller_manages_and_attaches_workspace_tags() {
+        let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await.unwrap();
+        pool.execute(
+            "CREATE TABLE users (id INTEGER PRIMARY KEY, username TEXT NOT NULL, email TEXT NOT NULL, api_key TEXT NOT NULL);
+             INSERT INTO users (id, username, email, api_key) VALUES (1, 'a', 'a@x.com', 'ka'), (2, 'b', 'b@x.com', 'kb');
+             CREATE TABLE urls (id INTEGER PRIMARY KEY, key TEXT NOT NULL, secret_key TEXT NOT NULL, target_url TEXT NOT NULL, is_active BOOLEAN NOT NULL, clicks INTEGER NOT NULL, user_id INTEGER NOT NULL, interstitial_clicks INTEGER NOT NULL DEFAULT 0, org_id INTEGER, folder TEXT);
+             INSERT INTO urls (key, secret_key, target_url, is_active, clicks, user_id, org_id) VALUES ('k1', 's1', 'http://a', 1, 2, 1, 5), ('k2', 's2', 'http://b', 1, 0, 1, 6);",
+        )
+        .await
+        .unwrap();
+        create_org_tables(&pool).await.unwrap();
+        create_tag_tables(&pool).await.unwrap();
+        pool.execute(
+            "INSERT INTO organizations (id, name) VALUES (5, 'Team'), (6, 'Other');
+             INSERT INTO organization_members (org_id, user_id, role) VALUES (5, 1, 'editor'), (5, 2, 'viewer'), (6, 1, 'owner');",
+        )
+        .await
+        .unwrap();
+        let repo = Arc::new(SqlxURLRepository::new(pool).await);
+        let service = TagService::new(repo.clone(), repo);
+        let config = AppConfig { admin_api_keys: vec!["root".into()], ..Default::default() };
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(service)))
+                .app_data(web::Data::new(config))
+                .service(create_tag)
+                .service(list_tags)
+                .service(tag_stats)
+                .service(rename_tag)
+                .service(delete_tag)
+                .service(attach_tag)
+                .service(detach_tag),
+        )
+        .await;
+        let name = |name: &str| TagNameDto { name: name.into() };
+
+        let req = TestRequest::post().uri("/orgs/5/tags").insert_header(("X-API-Key", "kb")).set_json(name("promo")).to_request();
+        assert_eq!(call_service(&app, req).await.status(), 403);
+        let req = TestRequest::post().uri("/orgs/5/tags").insert_header(("X-API-Key", "ka")).set_json(name("  ")).to_request();
+        assert_eq!(call_service(&app, req).await.status(), 400);
+        let req = TestRequest::post().uri("/orgs/5/tags").insert_header(("X-API-Key", "ka")).set_json(name(" promo ")).to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), 201);
+        let promo: Tag = read_body_json(resp).await;
+        assert_eq!((promo.org_id, promo.name.as_str()), (5, "promo"));
+        let req = TestRequest::post().uri("/orgs/5/tags").insert_header(("X-API-Key", "ka")).set_json(name("promo")).to_request();
+        assert_eq!(call_service(&app, req).await.status(), 409);
+
+        let attach = |url_key: &str, key: &str| {
+            TestRequest::put().uri(&format!("/url/{url_key}/tags/{}", promo.id)).insert_header(("X-API-Key", key)).to_request()
+        };
+        assert_eq!(call_service(&app, attach("k1", "kb")).await.status(), 403);
+        assert_eq!(call_service(&app, attach("k2", "ka")).await.status(), 400);
+        assert_eq!(call_service(&app, attach("k1", "ka")).await.status(), 204);
+
+        // viewers read the workspace's tags and their stats
+        let req = TestRequest::get().uri("/orgs/5/tags/stats").insert_header(("X-API-Key", "kb")).to_request();
+        let stats: Vec<TagStats> = read_body_json(call_service(&app, req).await).await;
+        assert_eq!((stats[0].links, stats[0].clicks), (1, 2));
+
+        let req = TestRequest::patch()
+            .uri(&format!("/orgs/5/tags/{}", promo.id))
+            .insert_header(("X-API-Key", "root"))
+            .set_json(name("launch"))
+            .to_request();
+        let renamed: Tag = read_body_json(call_service(&app, req).await).await;
+        assert_eq!(renamed.name, "launch");
+        let req = TestRequest::get().uri("/orgs/5/tags").insert_header(("X-API-Key", "kb")).to_request();
+        let tags: Vec<Tag> = read_body_json(call_service(&app, req).await).await;
+        assert_eq!(tags, vec![renamed]);
+
+        let detach = TestRequest::delete().uri(&format!("/url/k1/tags/{}", promo.id)).insert_header(("X-API-Key", "ka")).to_request();
+        assert_eq!(call_service(&app, detach).await.status(), 204);
+        let req = TestRequest::delete().uri(&format!("/orgs/6/tags/{}", promo.id)).insert_header(("X-API-Key", "ka")).to_request();
+        assert_eq!(call_service(&app, req).await.status(), 404);
+        let req = TestRequest::delete().uri(&format!("/orgs/5/tags/{}", promo.id)).insert_header(("X-API-Key", "ka")).to_request();
+        assert_eq!(call_service(&app, req).await.status(), 204);
+    }
+}
@@ -1,13 +1,29 @@
-use actix_web::{delete, get, post, web, HttpResponse, Responder, http};
+use actix_web::cookie::{time, Cookie, SameSite};
+use actix_web::{delete, get, patch, post, web, HttpRequest, HttpResponse, Responder, http};
 
 use crate::config::env::AppConfig;
-use crate::url::application::dtos::url_dto::URLBaseDto;
-use crate::url::application::mappers::mappers::map_url_to_dto;
-use crate::url::domain::services::url_service::URLService;
+use crate::shared::api_key::ApiKey;
+use crate::shared::client_ip::resolve_client_ip;
+use crate::shared::qr;
+use crate::shared::signing;
+use crate::shared::user_agent::is_preview_crawler;
+use crate::url::application::dtos::url_dto::{
+    CustomError, OrgURLsQueryDto, QrQueryDto, ScheduledURLsQueryDto, SignURLDto, SignedLinkQueryDto, SignedURLDto,
+    URLBaseDto, URLPatchDto, UnlockFormDto,
+};
+use crate::url::application::mappers::mappers::{map_url_to_dto, map_url_to_dto_with_access};
+use crate::url::application::views::html::{self, NotFoundPage, SchedulePages};
+use crate::url::domain::models::access::Caller;
+use crate::url::domain::models::schema::URL;
+use crate::url::domain::models::visit::{ForwardOutcome, LinkSignature, VisitContext};
+use crate::url::domain::services::url_service::{URLService, UnlockError, ManageURLError};
 
+use chrono::{TimeZone, Utc};
 use log::debug;
 use std::sync::Arc;
 
+const UNLOCK_COOKIE_PREFIX: &str = "rc_unlock_";
+
 #[post("/url")]
 pub async fn create_url(
     url_base_dto: web::Json<URLBaseDto>, url_service: web::Data<Arc<URLService>>, config: web::Data<AppConfig>,
@@ -18,7 +34,43 @@ pub async fn create_url(
             let dto = map_url_to_dto(&url_model, config.get_ref().clone());
             HttpResponse::Ok().json(dto)
         }
-        Err(e) => HttpResponse::InternalServerError().json(e),
+        Err(e) => error_response(e),
+    }
+}
+
+/// JSON error response whose status is the error code (500 for codes that are not HTTP statuses).
+fn error_response(err: CustomError) -> HttpResponse {
+    let status = http::StatusCode::from_u16(err.code() as u16).unwrap_or(http::StatusCode::INTERNAL_SERVER_ERROR);
+    HttpResponse::build(status).json(err)
+}
+
+#[get("/url/scheduled")]
+pub async fn list_scheduled_urls(
+    api_key: ApiKey, query: web::Query<ScheduledURLsQueryDto>, url_service: web::Data<Arc<URLService>>,
+    config: web::Data<AppConfig>,
+) -> impl Responder {
+    match url_service.list_scheduled_urls(api_key.0, query.into_inner().state).await {
+        Ok(urls) => {
+            let dtos: Vec<_> = urls.iter().map(|url| map_url_to_dto(url, config.get_ref().clone())).collect();
+            HttpResponse::Ok().json(dtos)
+        }
+        Err(e) => error_response(e),
+    }
+}
+
+#[post("/url/{url_key}/sign")]
+pub async fn sign_url(
+    url_key: web::Path<String>, sign_dto: web::Json<SignURLDto>, url_service: web::Data<Arc<URLService>>,
+    config: web::Data<AppConfig>,
+) -> impl Responder {
+    let key = url_key.into_inner();
+    let SignURLDto { api_key, expires_in_secs } = sign_dto.into_inner();
+    match url_service.sign_url(key.clone(), api_key, expires_in_secs).await {
+        Ok(LinkSignature { expires_at, signature }) => HttpResponse::Ok().json(SignedURLDto {
+            url: format!("{}/{key}?exp={expires_at}&sig={signature}", config.public_base_url()),
+            expires_at: Utc.timestamp_opt(expires_at, 0).single().unwrap_or_default(),
+        }),
+        Err(e) => error_response(e),
     }
 }
 
@@ -28,40 +80,53 @@ mod tests {
     use actix_web::App;
     use actix_web::test::{call_service, init_service, read_body_json, TestRequest};
     use crate::config::env::AppConfig;
+    use crate::config::memory::MemoryDatabase;
+    use crate::org::domain::models::organization::{Membership, Role};
+    use crate::url::domain::models::health::DeadLinkPolicy;
     use crate::url::domain::models::schema::URL;
-    use std::sync::{Arc, Mutex};
-    use async_trait::async_trait;
+    use crate::url::domain::repositories::url_repository_port::URLRepositoryPort;
+    use crate::url::infra::memory_url_repository::MemoryURLRepository;
+    use std::sync::Arc;
     use serde_json::Value;
 
-    struct FakeRepo {
-        url: Mutex<Option<URL>>,
-        incremented: Mutex<bool>,
+    /// Memory storage where the API key `valid` belongs to user 1, holding `url` when given.
+    fn memory_repo(url: Option<URL>) -> Arc<MemoryURLRepository> {
+        let db = Arc::new(MemoryDatabase::new());
+        db.insert_user("owner", "owner@example.com", "valid");
+        if let Some(url) = url {
+            db.insert_url(url);
+        }
+        Arc::new(MemoryURLRepository::new(db))
     }
 
-    impl FakeRepo {
-        fn new(u: Option<URL>) -> Self { Self { url: Mutex::new(u), incremented: Mutex::new(false) } }
+    async fn clicks(repo: &MemoryURLRepository, key: &str) -> i32 {
+        repo.find_url_by_key(key.into()).await.map(|url| url.clicks).unwrap_or_default()
     }
 
-    #[async_trait]
-    impl crate::url::domain::repositories::url_repository_port::URLRepositoryPort for FakeRepo {
-        async fn create_url(&self, _target_url: String, _user_id: i32) -> Result<URL, sqlx::Error> {
-            let mut guard = self.url.lock().unwrap();
-            if let Some(u) = guard.clone() { Ok(u) } else { let new = URL{ key: "k".into(), secret_key: "s".into(), target_url: "http://t".into(), is_active: true, clicks: 0, user_id: 1 }; *guard = Some(new.clone()); Ok(new) }
-        }
-        async fn get_db_url_by_key(&self, _url_key: String) -> Result<URL, sqlx::Error> { self.url.lock().unwrap().clone().ok_or(sqlx::Error::RowNotFound) }
-        async fn get_db_url_by_user_and_target_url(&self, _user_id: i32, _target_url: String) -> Result<URL, sqlx::Error> { self.url.lock().unwrap().clone().ok_or(sqlx::Error::RowNotFound) }
-        async fn get_user_by_apy_key(&self, api_key: String) -> Result<i32, ()> { if api_key == "valid" { Ok(1) } else { Err(()) } }
-        async fn increment_clicks(&self, _url_key: String) -> sqlx::Result<()> { *(self.incremented.lock().unwrap()) = true; Ok(()) }
+    #[actix_web::test]
+    async fn controller_reports_plan_usage() {
+        let url = URL { key: "k".into(), secret_key: "s".into(), target_url: "http://t".into(), is_active: true, user_id: 1, ..Default::default() };
+        let service = URLService::new(memory_repo(Some(url)));
+        let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).service(get_usage).service(forward_to_target_url)).await;
+
+        let anonymous = TestRequest::get().uri("/usage").insert_header(("X-API-Key", "nope")).to_request();
+        assert_eq!(call_service(&app, anonymous).await.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+        let req = TestRequest::get().uri("/usage").insert_header(("X-API-Key", "valid")).to_request();
+        let body: Value = read_body_json(call_service(&app, req).await).await;
+        assert_eq!(body["plan"]["name"], "unlimited");
+        assert_eq!(body["plan"]["max_active_links"], Value::Null);
+        assert_eq!(body["usage"]["active_links"], 1);
+        assert!(body["daily_reset_at"].is_string());
     }
 
     #[actix_web::test]
     async fn controller_create_and_map_to_dto() {
-        let repo = Arc::new(FakeRepo::new(None));
+        let repo = memory_repo(None);
         let service = URLService::new(repo.clone());
-        let cfg = AppConfig { base_url: "localhost".into(), server_port: "8080".into(), protocol: "http".into() };
+        let cfg = AppConfig { base_url: "localhost".into(), server_port: "8080".into(), protocol: "http".into(), ..Default::default() };
         let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(cfg.clone())).service(create_url)).await;
 
-        let req = TestRequest::post().uri("/url").set_json(&URLBaseDto{ target_url: "http://x".into(), api_key: "valid".into() }).to_request();
+        let req = TestRequest::post().uri("/url").set_json(&URLBaseDto{ target_url: "http://x".into(), api_key: "valid".into(), ..Default::default() }).to_request();
         let resp = call_service(&app, req).await;
         assert!(resp.status().is_success());
         let body: Value = read_body_json(resp).await;
@@ -70,10 +135,10 @@ mod tests {
 
     #[actix_web::test]
     async fn controller_forward_sets_location() {
-        let url = URL{ key: "k".into(), secret_key: "s".into(), target_url: "http://target".into(), is_active: true, clicks: 0, user_id: 1 };
-        let repo = Arc::new(FakeRepo::new(Some(url)));
+        let url = URL{ key: "k".into(), secret_key: "s".into(), target_url: "http://target".into(), is_active: true, clicks: 0, user_id: 1, ..Default::default() };
+        let repo = memory_repo(Some(url));
         let service = URLService::new(repo.clone());
-        let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).service(forward_to_target_url)).await;
+        let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(AppConfig::default())).service(forward_to_target_url)).await;
 
         let req = TestRequest::get().uri("/k").to_request();
         let resp = call_service(&app, req).await;
@@ -82,12 +147,300 @@ mod tests {
         assert!(hdr.contains("http://target"));
     }
 
+    #[actix_web::test]
+    async fn controller_protected_link_serves_form_then_unlocks_with_cookie() {
+        let hash = crate::shared::password::hash_password("pw").unwrap();
+        let url = URL{ key: "k".into(), secret_key: "s".into(), target_url: "http://target".into(), is_active: true, password_hash: Some(hash), ..Default::default() };
+        let repo = memory_repo(Some(url));
+        let service = URLService::new(repo.clone());
+        let cfg = AppConfig { cookie_secret: Some("test-secret".into()), ..Default::default() };
+        let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(cfg)).service(forward_to_target_url).service(unlock_url)).await;
+
+        let resp = call_service(&app, TestRequest::get().uri("/k").to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        assert_eq!(clicks(&repo, "k").await, 0);
+
+        let wrong = TestRequest::post().uri("/k/unlock").set_form([("password", "nope")]).to_request();
+        assert_eq!(call_service(&app, wrong).await.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+
+        let right = TestRequest::post().uri("/k/unlock").set_form([("password", "pw")]).to_request();
+        let resp = call_service(&app, right).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        let cookie = resp.response().cookies().find(|c| c.name() == "rc_unlock_k").expect("unlock cookie").into_owned();
+
+        let again = TestRequest::get().uri("/k").cookie(cookie).to_request();
+        let resp = call_service(&app, again).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+
+        let forged = TestRequest::get().uri("/k").cookie(Cookie::new("rc_unlock_k", "99999999999.deadbeef")).to_request();
+        assert_eq!(call_service(&app, forged).await.status(), actix_web::http::StatusCode::OK);
+    }
+
+    #[actix_web::test]
+    async fn controller_plus_suffix_serves_interstitial_and_continue_redirects() {
+        let url = URL{ key: "k".into(), secret_key: "s".into(), target_url: "http://target".into(), is_active: true, ..Default::default() };
+        let repo = memory_repo(Some(url));
+        let service = URLService::new(repo.clone());
+        let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(AppConfig::default())).service(continue_to_target_url).service(forward_to_target_url)).await;
+
+        let resp = call_service(&app, TestRequest::get().uri("/k+").to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let body = actix_web::test::read_body(resp).await;
+        assert!(std::str::from_utf8(&body).unwrap().contains("http://target"));
+        assert_eq!(clicks(&repo, "k").await, 0);
+
+        let resp = call_service(&app, TestRequest::get().uri("/k/continue").to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert_eq!(clicks(&repo, "k").await, 0, "interstitial clicks are not direct clicks");
+    }
+
+    #[actix_web::test]
+    async fn controller_plus_suffix_hides_and_keeps_single_use_links() {
+        let url = URL{ key: "k".into(), secret_key: "s".into(), target_url: "http://target".into(), is_active: true, single_use: true, ..Default::default() };
+        let repo = memory_repo(Some(url));
+        let service = URLService::new(repo.clone());
+        let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(AppConfig::default())).service(continue_to_target_url).service(forward_to_target_url)).await;
+
+        let resp = call_service(&app, TestRequest::get().uri("/k+").to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let body = actix_web::test::read_body(resp).await;
+        assert!(!std::str::from_utf8(&body).unwrap().contains("http://target"), "destination withheld");
+
+        let resp = call_service(&app, TestRequest::get().uri("/k").to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER, "the preview did not use the link up");
+        let resp = call_service(&app, TestRequest::get().uri("/k").to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::GONE);
+    }
+
+    #[actix_web::test]
+    async fn controller_signed_link_is_minted_and_verified() {
+        let url = URL{ key: "k".into(), secret_key: "s".into(), target_url: "http://target".into(), is_active: true, user_id: 1, require_signature: true, ..Default::default() };
+        let repo = memory_repo(Some(url));
+        let service = URLService::new(repo.clone()).with_signing_secret("secret", 3600);
+        let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(AppConfig::default())).service(sign_url).service(forward_to_target_url)).await;
+
+        let resp = call_service(&app, TestRequest::get().uri("/k").to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::GONE);
+
+        let forbidden = TestRequest::post().uri("/url/k/sign").set_json(SignURLDto { api_key: "invalid".into(), expires_in_secs: 60 }).to_request();
+        assert_eq!(call_service(&app, forbidden).await.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::post().uri("/url/k/sign").set_json(SignURLDto { api_key: "valid".into(), expires_in_secs: 60 }).to_request();
+        let body: Value = read_body_json(call_service(&app, req).await).await;
+        let signed = body.get("url").and_then(|v| v.as_str()).expect("signed url");
+        let path = &signed[signed.find("/k?").expect("path and query")..];
+
+        let resp = call_service(&app, TestRequest::get().uri(path).to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::SEE_OTHER);
+        let tampered = path.replace("sig=", "sig=00");
+        assert_eq!(call_service(&app, TestRequest::get().uri(&tampered).to_request()).await.status(), actix_web::http::StatusCode::GONE);
+    }
+
+    #[actix_web::test]
+    async fn controller_single_use_link_answers_410_after_first_visit() {
+        let url = URL{ key: "k".into(), secret_key: "s".into(), target_url: "http://target".into(), is_active: true, single_use: true, ..Default::default() };
+        let repo = memory_repo(Some(url));
+        let service = URLService::new(repo.clone());
+        let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(AppConfig::default())).service(forward_to_target_url)).await;
+
+        assert_eq!(call_service(&app, TestRequest::get().uri("/k").to_request()).await.status(), actix_web::http::StatusCode::SEE_OTHER);
+        assert_eq!(call_service(&app, TestRequest::get().uri("/k").to_request()).await.status(), actix_web::http::StatusCode::GONE);
+    }
+
+    #[actix_web::test]
+    async fn controller_serves_social_card_to_crawlers_and_redirects_browsers() {
+        let url = URL{ key: "k".into(), secret_key: "s".into(), target_url: "http://target".into(), is_active: true, og_title: Some("Launch day".into()), ..Default::default() };
+        let repo = memory_repo(Some(url));
+        let service = URLService::new(repo.clone());
+        let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(AppConfig::default())).service(forward_to_target_url)).await;
+
+        let req = TestRequest::get().uri("/k").insert_header(("User-Agent", "Slackbot-LinkExpanding 1.0 (+https://api.slack.com/robots)")).to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let body = actix_web::test::read_body(resp).await;
+        let body = std::str::from_utf8(&body).unwrap();
+        assert!(body.contains(r#"<meta property="og:title" content="Launch day">"#));
+        assert!(body.contains(r#"content="0;url=http://target""#));
+        assert_eq!(clicks(&repo, "k").await, 0);
+
+        let req = TestRequest::get().uri("/k").insert_header(("User-Agent", "Mozilla/5.0 (X11; Linux x86_64) Firefox/128.0")).to_request();
+        assert_eq!(call_service(&app, req).await.status(), actix_web::http::StatusCode::SEE_OTHER);
+    }
+
+    #[actix_web::test]
+    async fn controller_scheduled_link_serves_configured_page_and_is_listed() {
+        let url = URL{ key: "k".into(), secret_key: "s".into(), target_url: "http://target".into(), is_active: true, user_id: 1, active_from: Some(Utc::now() + chrono::Duration::hours(1)), ..Default::default() };
+        let repo = memory_repo(Some(url));
+        let service = URLService::new(repo.clone());
+        let pages = SchedulePages { coming_soon: Some("soon: {{key}}".into()), ended: None };
+        let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(AppConfig::default())).app_data(web::Data::new(pages)).service(list_scheduled_urls).service(forward_to_target_url)).await;
+
+        let resp = call_service(&app, TestRequest::get().uri("/k").to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        assert_eq!(actix_web::test::read_body(resp).await, "soon: k");
+        assert_eq!(clicks(&repo, "k").await, 0);
+
+        let listed = TestRequest::get().uri("/url/scheduled?state=upcoming").insert_header(("X-API-Key", "valid")).to_request();
+        let body: Value = read_body_json(call_service(&app, listed).await).await;
+        assert_eq!(body.as_array().map(|a| a.len()), Some(1));
+        let in_query = TestRequest::get().uri("/url/scheduled?api_key=valid&state=upcoming").to_request();
+        assert_eq!(call_service(&app, in_query).await.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[actix_web::test]
+    async fn controller_qr_endpoints_render_png_and_svg() {
+        let url = URL{ key: "k".into(), secret_key: "s".into(), target_url: "http://target".into(), is_active: true, ..Default::default() };
+        let repo = memory_repo(Some(url));
+        let service = URLService::new(repo.clone());
+        let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(AppConfig::default())).service(get_url_qr).service(get_admin_url_qr)).await;
+
+        let resp = call_service(&app, TestRequest::get().uri("/k/qr?size=128").to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        assert_eq!(resp.headers().get(actix_web::http::header::CONTENT_TYPE).unwrap(), "image/png");
+        assert!(actix_web::test::read_body(resp).await.starts_with(b"\x89PNG"));
+
+        let resp = call_service(&app, TestRequest::get().uri("/admin/s/qr?format=svg&fg=%23ff0000&ecc=H").to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::OK);
+        let body = actix_web::test::read_body(resp).await;
+        assert!(std::str::from_utf8(&body).unwrap().contains(r##"fill="#ff0000""##));
+
+        let bad = call_service(&app, TestRequest::get().uri("/k/qr?fg=red").to_request()).await;
+        assert_eq!(bad.status(), actix_web::http::StatusCode::BAD_REQUEST);
+        let missing = call_service(&app, TestRequest::get().uri("/admin/nope/qr").to_request()).await;
+        assert_eq!(missing.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn controller_patch_and_rescan_apply_link_policy() {
+        let url = URL{ key: "k".into(), secret_key: "s".into(), target_url: "http://127.0.0.1/".into(), is_active: true, ..Default::default() };
+        let repo = memory_repo(Some(url));
+        let service = URLService::new(repo.clone());
+        let cfg = AppConfig { admin_api_keys: vec!["root".into()], ..Default::default() };
+        let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(cfg)).service(rescan_link_policy).service(patch_url)).await;
+
+        let patch = TestRequest::patch().uri("/admin/s").set_json(URLPatchDto { target_url: Some("http://10.0.0.1/".into()), ..Default::default() }).to_request();
+        assert_eq!(call_service(&app, patch).await.status(), actix_web::http::StatusCode::FORBIDDEN);
+
+        let denied = TestRequest::post().uri("/admin/policy/rescan").insert_header(("X-API-Key", "valid")).to_request();
+        assert_eq!(call_service(&app, denied).await.status(), actix_web::http::StatusCode::FORBIDDEN);
+        let in_body = TestRequest::post().uri("/admin/policy/rescan").set_json(serde_json::json!({ "api_key": "root" })).to_request();
+        assert_eq!(call_service(&app, in_body).await.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+        let rescan = TestRequest::post().uri("/admin/policy/rescan").insert_header(("X-API-Key", "root")).to_request();
+        let body: Value = read_body_json(call_service(&app, rescan).await).await;
+        assert_eq!(body["deactivated"], serde_json::json!(["k"]));
+    }
+
+    #[actix_web::test]
+    async fn controller_unknown_keys_get_the_configured_not_found_page() {
+        let repo = memory_repo(None);
+        let service = Arc::new(URLService::new(repo.clone()));
+        let cfg = AppConfig { admin_api_keys: vec!["root".into()], ..Default::default() };
+        let app = |page: NotFoundPage| {
+            init_service(App::new().app_data(web::Data::new(service.clone())).app_data(web::Data::new(cfg.clone())).app_data(web::Data::new(page)).service(list_unknown_keys).service(forward_to_target_url))
+        };
+
+        let builtin = app(NotFoundPage::Builtin).await;
+        let resp = call_service(&builtin, TestRequest::get().uri("/typo").to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+        assert!(std::str::from_utf8(&actix_web::test::read_body(resp).await).unwrap().contains("does not exist"));
+
+        let template = app(NotFoundPage::Template("missing: {{key}}".into())).await;
+        let resp = call_service(&template, TestRequest::get().uri("/gone1").to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::NOT_FOUND);
+        assert_eq!(actix_web::test::read_body(resp).await, "missing: gone1");
+
+        let redirect = app(NotFoundPage::Redirect("https://example.com/".into())).await;
+        let resp = call_service(&redirect, TestRequest::get().uri("/typo").to_request()).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::FOUND);
+        assert_eq!(resp.headers().get("location").unwrap(), "https://example.com/");
+
+        let denied = TestRequest::get().uri("/admin/links/unknown").insert_header(("X-API-Key", "valid")).to_request();
+        assert_eq!(call_service(&redirect, denied).await.status(), actix_web::http::StatusCode::FORBIDDEN);
+        let req = TestRequest::get().uri("/admin/links/unknown").insert_header(("X-API-Key", "root")).to_request();
+        let body: Value = read_body_json(call_service(&redirect, req).await).await;
+        let keys: Vec<&str> = body.as_array().unwrap().iter().filter_map(|hit| hit["key"].as_str()).collect();
+        assert_eq!(keys, vec!["typo", "gone1"]);
+        assert_eq!(body[0]["hits"], 2);
+    }
+
+    #[actix_web::test]
+    async fn controller_lists_broken_links_to_admins() {
+        let url = URL{ key: "k".into(), secret_key: "s".into(), target_url: "http://target".into(), is_active: true, last_checked_at: Some(Utc::now()), last_check_status: Some(503), failed_checks: 2, ..Default::default() };
+        let repo = memory_repo(Some(url));
+        let service = URLService::new(repo.clone()).with_dead_link_policy(DeadLinkPolicy { failure_threshold: 2, fallback_url: None });
+        let cfg = AppConfig { admin_api_keys: vec!["root".into()], dead_link_threshold: 2, ..Default::default() };
+        let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(cfg)).service(list_broken_urls)).await;
+
+        let denied = TestRequest::get().uri("/admin/links/broken").insert_header(("X-API-Key", "valid")).to_request();
+        assert_eq!(call_service(&app, denied).await.status(), actix_web::http::StatusCode::FORBIDDEN);
+        let req = TestRequest::get().uri("/admin/links/broken").insert_header(("X-API-Key", "root")).to_request();
+        let body: Value = read_body_json(call_service(&app, req).await).await;
+        assert_eq!(body[0]["health"]["status_code"], 503);
+        assert_eq!(body[0]["health"]["broken"], true);
+    }
+
+    #[actix_web::test]
+    async fn controller_viewers_do_not_receive_admin_urls() {
+        let db = Arc::new(MemoryDatabase::new());
+        let owner = db.insert_user("owner", "owner@example.com", "valid");
+        let viewer = db.insert_user("viewer", "viewer@example.com", "viewer-key");
+        let team = {
+            let mut state = db.lock();
+            let team = state.insert_organization("Team", None);
+            state.members.push(Membership { org_id: team.id, user_id: owner, role: Role::Owner });
+            state.members.push(Membership { org_id: team.id, user_id: viewer, role: Role::Viewer });
+            team
+        };
+        db.insert_url(URL{ key: "k".into(), secret_key: "s".into(), target_url: "http://target".into(), is_active: true, user_id: owner as i32, org_id: Some(team.id), ..Default::default() });
+        let service = URLService::new(Arc::new(MemoryURLRepository::new(db)));
+        let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(AppConfig::default())).service(get_owned_url).service(list_org_urls)).await;
+        let get = |uri: String, key: &str| TestRequest::get().uri(&uri).insert_header(("X-API-Key", key)).to_request();
+
+        let listed: Value = read_body_json(call_service(&app, get(format!("/orgs/{}/urls", team.id), "viewer-key")).await).await;
+        assert_eq!(listed[0]["target_url"], "http://target");
+        assert!(listed[0].get("admin_url").is_none(), "viewers cannot learn the secret key");
+        let link: Value = read_body_json(call_service(&app, get("/url/k".into(), "viewer-key")).await).await;
+        assert_eq!(link["target_url"], "http://target");
+        assert!(link.get("admin_url").is_none());
+
+        let listed: Value = read_body_json(call_service(&app, get(format!("/orgs/{}/urls", team.id), "valid")).await).await;
+        assert!(listed[0]["admin_url"].as_str().is_some_and(|admin_url| admin_url.ends_with("/admin/s")));
+        let link: Value = read_body_json(call_service(&app, get("/url/k".into(), "valid")).await).await;
+        assert!(link.get("admin_url").is_some());
+    }
+
+    #[actix_web::test]
+    async fn controller_owner_and_admin_manage_link_by_public_key() {
+        let url = URL{ key: "k".into(), secret_key: "s".into(), target_url: "http://target".into(), is_active: true, user_id: 1, ..Default::default() };
+        let repo = memory_repo(Some(url));
+        let service = URLService::new(repo.clone());
+        let cfg = AppConfig { admin_api_keys: vec!["root".into()], ..Default::default() };
+        let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(cfg)).service(get_owned_url).service(patch_owned_url).service(delete_owned_url)).await;
+
+        assert_eq!(call_service(&app, TestRequest::get().uri("/url/k").to_request()).await.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+        let stranger = TestRequest::get().uri("/url/k").insert_header(("X-API-Key", "nobody")).to_request();
+        assert_eq!(call_service(&app, stranger).await.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+
+        let owner = TestRequest::get().uri("/url/k").insert_header(("X-API-Key", "valid")).to_request();
+        let body: Value = read_body_json(call_service(&app, owner).await).await;
+        assert_eq!(body["target_url"], "http://target");
+
+        let patch = TestRequest::patch().uri("/url/k").insert_header(("X-API-Key", "root")).set_json(URLPatchDto { title: Some("Admin edit".into()), ..Default::default() }).to_request();
+        let body: Value = read_body_json(call_service(&app, patch).await).await;
+        assert_eq!(body["title"], "Admin edit");
+
+        let delete = TestRequest::delete().uri("/url/k").insert_header(("X-API-Key", "valid")).to_request();
+        assert!(call_service(&app, delete).await.status().is_success());
+        let gone = TestRequest::get().uri("/url/k").insert_header(("X-API-Key", "valid")).to_request();
+        assert_eq!(call_service(&app, gone).await.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+
     #[actix_web::test]
     async fn controller_get_url_info_returns_dto() {
-        let url = URL{ key: "k".into(), secret_key: "s".into(), target_url: "http://target".into(), is_active: true, clicks: 2, user_id: 1 };
-        let repo = Arc::new(FakeRepo::new(Some(url)));
+        let url = URL{ key: "k".into(), secret_key: "s".into(), target_url: "http://target".into(), is_active: true, clicks: 2, user_id: 1, ..Default::default() };
+        let repo = memory_repo(Some(url));
         let service = URLService::new(repo.clone());
-        let cfg = AppConfig { base_url: "localhost".into(), server_port: "8080".into(), protocol: "http".into() };
+        let cfg = AppConfig { base_url: "localhost".into(), server_port: "8080".into(), protocol: "http".into(), ..Default::default() };
         let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(cfg.clone())).service(get_url_info)).await;
 
         let req = TestRequest::get().uri("/admin/s").to_request();
@@ -98,22 +451,26 @@ mod tests {
     }
 
     #[actix_web::test]
-    async fn controller_create_returns_500_on_invalid_api_key() {
-        let repo = Arc::new(FakeRepo::new(None));
+    async fn controller_create_returns_400_on_invalid_api_key() {
+        let repo = memory_repo(None);
         let service = URLService::new(repo.clone());
-        let cfg = AppConfig { base_url: "localhost".into(), server_port: "8080".into(), protocol: "http".into() };
+        let cfg = AppConfig { base_url: "localhost".into(), server_port: "8080".into(), protocol: "http".into(), ..Default::default() };
         let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(cfg.clone())).service(create_url)).await;
 
-        let req = TestRequest::post().uri("/url").set_json(&URLBaseDto{ target_url: "http://x".into(), api_key: "invalid".into() }).to_request();
+        let req = TestRequest::post().uri("/url").set_json(&URLBaseDto{ target_url: "http://x".into(), api_key: "invalid".into(), ..Default::default() }).to_request();
         let resp = call_service(&app, req).await;
-        assert_eq!(resp.status(), actix_web::http::StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(resp.status(), actix_web::http::StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::post().uri("/url").set_json(&URLBaseDto{ target_url: "http://localhost/".into(), api_key: "valid".into(), ..Default::default() }).to_request();
+        let resp = call_service(&app, req).await;
+        assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
     }
 
     #[actix_web::test]
     async fn controller_get_url_info_not_found_returns_500() {
-        let repo = Arc::new(FakeRepo::new(None));
+        let repo = memory_repo(None);
         let service = URLService::new(repo.clone());
-        let cfg = AppConfig { base_url: "localhost".into(), server_port: "8080".into(), protocol: "http".into() };
+        let cfg = AppConfig { base_url: "localhost".into(), server_port: "8080".into(), protocol: "http".into(), ..Default::default() };
         let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(cfg.clone())).service(get_url_info)).await;
 
         let req = TestRequest::get().uri("/admin/unknown").to_request();
@@ -123,10 +480,10 @@ mod tests {
 
     #[actix_web::test]
     async fn controller_delete_url_returns_dto_on_success() {
-        let url = URL{ key: "k".into(), secret_key: "s".into(), target_url: "http://target".into(), is_active: true, clicks: 0, user_id: 1 };
-        let repo = Arc::new(FakeRepo::new(Some(url)));
+        let url = URL{ key: "k".into(), secret_key: "s".into(), target_url: "http://target".into(), is_active: true, clicks: 0, user_id: 1, ..Default::default() };
+        let repo = memory_repo(Some(url));
         let service = URLService::new(repo.clone());
-        let cfg = AppConfig { base_url: "localhost".into(), server_port: "8080".into(), protocol: "http".into() };
+        let cfg = AppConfig { base_url: "localhost".into(), server_port: "8080".into(), protocol: "http".into(), ..Default::default() };
         let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(cfg.clone())).service(delete_url)).await;
 
         let req = TestRequest::delete().uri("/admin/s").to_request();
@@ -138,9 +495,9 @@ mod tests {
 
     #[actix_web::test]
     async fn controller_delete_url_not_found_returns_500() {
-        let repo = Arc::new(FakeRepo::new(None));
+        let repo = memory_repo(None);
         let service = URLService::new(repo.clone());
-        let cfg = AppConfig { base_url: "localhost".into(), server_port: "8080".into(), protocol: "http".into() };
+        let cfg = AppConfig { base_url: "localhost".into(), server_port: "8080".into(), protocol: "http".into(), ..Default::default() };
         let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(cfg.clone())).service(delete_url)).await;
 
         let req = TestRequest::delete().uri("/admin/unknown").to_request();
@@ -152,20 +509,217 @@ mod tests {
 
 
 #[get("/{url_key}")]
-pub async fn forward_to_target_url(url_key: web::Path<String>, url_service: web::Data<Arc<URLService>>) -> impl Responder {
+pub async fn forward_to_target_url(
+    req: HttpRequest, url_key: web::Path<String>, url_service: web::Data<Arc<URLService>>, config: web::Data<AppConfig>,
+) -> impl Responder {
     debug!("controller Forwarding to target URL: {}", url_key.clone());
-    match url_service.forward_to_target_url(url_key.into_inner()).await {
-        Ok(target_url) => HttpResponse::SeeOther()
+    // `GET /{key}+` asks for the preview page instead of the redirect
+    let raw_key = url_key.into_inner();
+    let (key, preview_requested) = match raw_key.strip_suffix('+') {
+        Some(key) => (key.to_string(), true),
+        None => (raw_key, false),
+    };
+    let visit = VisitContext {
+        client_ip: resolve_client_ip(&req, &config.trusted_proxies),
+        password_verified: has_valid_unlock_cookie(&req, &key, &config),
+        preview_requested,
+        signature: link_signature(&req),
+        preview_crawler: req
+            .headers()
+            .get(http::header::USER_AGENT)
+            .and_then(|user_agent| user_agent.to_str().ok())
+            .is_some_and(is_preview_crawler),
+    };
+    match url_service.forward_to_target_url(key.clone(), visit).await {
+        Ok(outcome) => outcome_response(&req, &key, outcome, &config),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[get("/{url_key}/continue")]
+pub async fn continue_to_target_url(
+    req: HttpRequest, url_key: web::Path<String>, url_service: web::Data<Arc<URLService>>, config: web::Data<AppConfig>,
+) -> impl Responder {
+    let key = url_key.into_inner();
+    let visit = VisitContext {
+        client_ip: resolve_client_ip(&req, &config.trusted_proxies),
+        password_verified: has_valid_unlock_cookie(&req, &key, &config),
+        signature: link_signature(&req),
+        ..Default::default()
+    };
+    match url_service.continue_from_interstitial(key.clone(), visit).await {
+        Ok(outcome) => outcome_response(&req, &key, outcome, &config),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[post("/{url_key}/unlock")]
+pub async fn unlock_url(
+    req: HttpRequest, url_key: web::Path<String>, form: web::Form<UnlockFormDto>, url_service: web::Data<Arc<URLService>>,
+    config: web::Data<AppConfig>,
+) -> impl Responder {
+    let key = url_key.into_inner();
+    let visit = VisitContext {
+        client_ip: resolve_client_ip(&req, &config.trusted_proxies),
+        signature: link_signature(&req),
+        ..Default::default()
+    };
+    match url_service.unlock(key.clone(), &form.password, visit).await {
+        Ok(outcome) => {
+            let mut response = outcome_response(&req, &key, outcome, &config);
+            if let Some(cookie) = unlock_cookie(&key, &config) {
+                let _ = response.add_cookie(&cookie);
+            }
+            response
+        }
+        Err(err @ UnlockError::WrongPassword) => {
+            html_response(HttpResponse::Unauthorized(), html::password_form(&unlock_action(&req, &key), Some(&err.to_string())))
+        }
+        Err(err @ UnlockError::TooManyAttempts(retry_after)) => {
+            let mut response = HttpResponse::TooManyRequests();
+            response.append_header((http::header::RETRY_AFTER, retry_after.as_secs().max(1).to_string()));
+            html_response(response, html::password_form(&unlock_action(&req, &key), Some(&err.to_string())))
+        }
+        Err(UnlockError::NotFound) => HttpResponse::NotFound().finish(),
+        Err(UnlockError::Database(_)) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[get("/{url_key}/qr")]
+pub async fn get_url_qr(
+    url_key: web::Path<String>, query: web::Query<QrQueryDto>, url_service: web::Data<Arc<URLService>>,
+    config: web::Data<AppConfig>,
+) -> impl Responder {
+    match url_service.get_url_info(url_key.into_inner()).await {
+        Ok(url) => qr_response(&url, &query, &config, "public, max-age=3600"),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// QR image encoding the short URL of `url`, rendered as the query asks.
+fn qr_response(url: &URL, query: &QrQueryDto, config: &AppConfig, cache_control: &'static str) -> HttpResponse {
+    let options = match query.to_options().and_then(|options| options.validate().map(|_| options)) {
+        Ok(options) => options,
+        Err(err) => return HttpResponse::BadRequest().json(CustomError::new(400, &err.to_string())),
+    };
+    let short_url = format!("{}/{}", config.public_base_url(), url.key);
+    match qr::render(&short_url, &options) {
+        Ok(image) => HttpResponse::Ok()
+            .content_type(options.format.content_type())
+            .append_header((http::header::CACHE_CONTROL, cache_control))
+            .body(image),
+        Err(err) => {
+            eprintln!("Error occurred[qr_ctrl]: {}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+fn outcome_response(req: &HttpRequest, url_key: &str, outcome: ForwardOutcome, config: &AppConfig) -> HttpResponse {
+    match outcome {
+        ForwardOutcome::Redirect(target_url) | ForwardOutcome::Fallback(target_url) => HttpResponse::SeeOther()
             .append_header((http::header::LOCATION, target_url))
             .finish(),
-        Err(_) => HttpResponse::InternalServerError().finish(),
+        ForwardOutcome::NotFound => match req.app_data::<web::Data<NotFoundPage>>().map(|page| page.get_ref()) {
+            Some(NotFoundPage::Redirect(location)) => {
+                HttpResponse::Found().append_header((http::header::LOCATION, location.as_str())).finish()
+            }
+            Some(NotFoundPage::Template(template)) => html_response(HttpResponse::NotFound(), html::not_found(url_key, Some(template))),
+            Some(NotFoundPage::Builtin) | None => html_response(HttpResponse::NotFound(), html::not_found(url_key, None)),
+        },
+        ForwardOutcome::PasswordRequired => {
+            html_response(HttpResponse::Ok(), html::password_form(&unlock_action(req, url_key), None))
+        }
+        ForwardOutcome::Interstitial { url, target_url } => html_response(
+            HttpResponse::Ok(),
+            html::interstitial(
+                &url,
+                target_url.as_deref(),
+                &format!("/{url_key}/continue{}", query_suffix(req)),
+                config.interstitial_countdown_secs,
+            ),
+        ),
+        ForwardOutcome::SocialPreview { url, target_url } => html_response(
+            HttpResponse::Ok(),
+            html::social_preview(&url, &format!("{}/{}", config.public_base_url(), url.key), target_url.as_deref()),
+        ),
+        ForwardOutcome::Gone => html_response(HttpResponse::Gone(), html::gone()),
+        ForwardOutcome::NotYetActive(url) => {
+            let template = req.app_data::<web::Data<SchedulePages>>().and_then(|pages| pages.coming_soon.clone());
+            html_response(HttpResponse::Ok(), html::coming_soon(&url, template.as_deref()))
+        }
+        ForwardOutcome::Ended(url) => {
+            let template = req.app_data::<web::Data<SchedulePages>>().and_then(|pages| pages.ended.clone());
+            html_response(HttpResponse::Gone(), html::ended(&url, template.as_deref()))
+        }
+    }
+}
+
+/// The pages served on the way to the target keep the query string so signed links stay signed.
+fn query_suffix(req: &HttpRequest) -> String {
+    match req.query_string() {
+        "" => String::new(),
+        query => format!("?{query}"),
+    }
+}
+
+fn unlock_action(req: &HttpRequest, url_key: &str) -> String {
+    format!("/{url_key}/unlock{}", query_suffix(req))
+}
+
+/// `exp` / `sig` of a signed link; a link carrying only one of them is treated as badly signed.
+fn link_signature(req: &HttpRequest) -> Option<LinkSignature> {
+    let query = web::Query::<SignedLinkQueryDto>::from_query(req.query_string()).ok()?.into_inner();
+    if query.exp.is_none() && query.sig.is_none() {
+        return None;
+    }
+    Some(LinkSignature { expires_at: query.exp.unwrap_or_default(), signature: query.sig.unwrap_or_default() })
+}
+
+fn html_response(mut builder: actix_web::HttpResponseBuilder, body: String) -> HttpResponse {
+    builder
+        .content_type("text/html; charset=utf-8")
+        .append_header((http::header::CACHE_CONTROL, "no-store"))
+        .body(body)
+}
+
+/// Signed cookie proving the visitor unlocked `url_key`: `<expires>.<hmac(unlock:key:expires)>`.
+fn unlock_cookie(url_key: &str, config: &AppConfig) -> Option<Cookie<'static>> {
+    let secret = config.cookie_secret.as_deref()?;
+    let expires = Utc::now().timestamp() + config.unlock_cookie_ttl_secs;
+    let signature = signing::sign(secret, &format!("unlock:{url_key}:{expires}"));
+    Some(
+        Cookie::build(format!("{UNLOCK_COOKIE_PREFIX}{url_key}"), format!("{expires}.{signature}"))
+            .path("/")
+            .http_only(true)
+            .secure(config.protocol == "https")
+            .same_site(SameSite::Lax)
+            .max_age(time::Duration::seconds(config.unlock_cookie_ttl_secs))
+            .finish(),
+    )
+}
+
+fn has_valid_unlock_cookie(req: &HttpRequest, url_key: &str, config: &AppConfig) -> bool {
+    let (Some(secret), Some(cookie)) = (config.cookie_secret.as_deref(), req.cookie(&format!("{UNLOCK_COOKIE_PREFIX}{url_key}")))
+    else {
+        return false;
+    };
+    let Some((expires, signature)) = cookie.value().split_once('.') else {
+        return false;
+    };
+    match expires.parse::<i64>() {
+        Ok(expires) if expires > Utc::now().timestamp() => signing::verify(secret, &format!("unlock:{url_key}:{expires}"), signature),
+        _ => false,
     }
 }
 
 #[get("/admin/{secret_key}")]
-pub async fn get_url_info(url_key: String, url_service: web::Data<Arc<URLService>>, config: web::Data<AppConfig>) -> impl Responder {
+pub async fn get_url_info(
+    secret_key: web::Path<String>, url_service: web::Data<Arc<URLService>>, config: web::Data<AppConfig>,
+) -> impl Responder {
     debug!("Getting URL info");
-    match url_service.get_url_info(url_key).await {
+    match url_service.get_url_by_secret_key(secret_key.into_inner()).await {
         Ok(url_model) => {
             let dto = map_url_to_dto(&url_model, config.get_ref().clone());
             HttpResponse::Ok().json(dto)
@@ -175,8 +729,10 @@ pub async fn get_url_info(url_key: String, url_service: web::Data<Arc<URLService
 }
 
 #[delete("/admin/{secret_key}")]
-pub async fn delete_url(url_key: String, url_service: web::Data<Arc<URLService>>, config: web::Data<AppConfig>) -> impl Responder {
-    match url_service.delete_url(url_key).await {
+pub async fn delete_url(
+    secret_key: web::Path<String>, url_service: web::Data<Arc<URLService>>, config: web::Data<AppConfig>,
+) -> impl Responder {
+    match url_service.delete_url(secret_key.into_inner()).await {
         Ok(url_model) => {
             let dto = map_url_to_dto(&url_model, config.get_ref().clone());
             HttpResponse::Ok().json(dto)
@@ -184,3 +740,197 @@ pub async fn delete_url(url_key: String, url_service: web::Data<Arc<URLService>>
         Err(_) => HttpResponse::InternalServerError().finish(),
     }
 }
+
+#[get("/admin/{secret_key}/qr")]
+pub async fn get_admin_url_qr(
+    secret_key: web::Path<String>, query: web::Query<QrQueryDto>, url_service: web::Data<Arc<URLService>>,
+    config: web::Data<AppConfig>,
+) -> impl Responder {
+    match url_service.get_url_by_secret_key(secret_key.into_inner()).await {
+        Ok(url) => qr_response(&url, &query, &config, "private, no-store"),
+        Err(sqlx::Error::RowNotFound) => HttpResponse::NotFound().finish(),
+        Err(_) => HttpResponse::InternalServerError().finish(),
+    }
+}
+
+#[patch("/admin/{secret_key}")]
+pub async fn patch_url(
+    secret_key: web::Path<String>, patch_dto: web::Json<URLPatchDto>, url_service: web::Data<Arc<URLService>>,
+    config: web::Data<AppConfig>,
+) -> impl Responder {
+    match url_service.patch_url(secret_key.into_inner(), patch_dto.into_inner()).await {
+        Ok(url_model) => HttpResponse::Ok().json(map_url_to_dto(&url_model, config.get_ref().clone())),
+        Err(err) => manage_error_response(err),
+    }
+}
+
+fn manage_error_response(err: ManageURLError) -> HttpResponse {
+    let code = match &err {
+        ManageURLError::InvalidTarget(_)
+        | ManageURLError::InvalidFolder
+        | ManageURLError::TooLong { .. }
+        | ManageURLError::InvalidImageUrl
+        | ManageURLError::InvalidClickLimit => 400,
+        ManageURLError::PolicyViolation(_) | ManageURLError::Forbidden => 403,
+        ManageURLError::NotFound => 404,
+        ManageURLError::Database(db_err) => {
+            eprintln!("Error occurred[manage_url_ctrl]: {}", db_err);
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+    error_response(CustomError::new(code, &err.to_string()))
+}
+
+/// Admin API keys act on every link; any other key must belong to a user.
+async fn authenticate(api_key: &ApiKey, url_service: &URLService, config: &AppConfig) -> Result<Caller, HttpResponse> {
+    if config.is_admin_key(&api_key.0) {
+        return Ok(Caller::Admin);
+    }
+    url_service
+        .find_caller(api_key.0.clone())
+        .await
+        .ok_or_else(|| error_response(CustomError::new(401, "Invalid API key")))
+}
+
+#[get("/url/{url_key}")]
+pub async fn get_owned_url(
+    api_key: ApiKey, url_key: web::Path<String>, url_service: web::Data<Arc<URLService>>, config: web::Data<AppConfig>,
+) -> impl Responder {
+    let caller = match authenticate(&api_key, &url_service, &config).await {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+    match url_service.get_managed_url(caller, url_key.into_inner()).await {
+        Ok((url_model, access)) => HttpResponse::Ok().json(map_url_to_dto_with_access(&url_model, config.get_ref().clone(), access)),
+        Err(err) => manage_error_response(err),
+    }
+}
+
+/// Plan of the API key owner with its current consumption of the limits.
+#[get("/usage")]
+pub async fn get_usage(api_key: ApiKey, url_service: web::Data<Arc<URLService>>) -> impl Responder {
+    let Some(Caller::User(user_id)) = url_service.find_caller(api_key.0).await else {
+        return error_response(CustomError::new(401, "Invalid API key"));
+    };
+    match url_service.get_usage(user_id).await {
+        Ok(usage) => HttpResponse::Ok().json(usage),
+        Err(e) => error_response(e),
+    }
+}
+
+/// Links of an organization workspace, with their stats; open to every member.
+/// `?tag=`, `?folder=` and the text search `?q=` narrow the list.
+#[get("/orgs/{org_id}/urls")]
+pub async fn list_org_urls(
+    api_key: ApiKey, org_id: web::Path<i64>, query: web::Query<OrgURLsQueryDto>, url_service: web::Data<Arc<URLService>>,
+    config: web::Data<AppConfig>,
+) -> impl Responder {
+    let caller = match authenticate(&api_key, &url_service, &config).await {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+    match url_service.list_org_urls(caller, org_id.into_inner(), query.into_inner()).await {
+        Ok((urls, access)) => {
+            let dtos: Vec<_> = urls.iter().map(|url| map_url_to_dto_with_access(url, config.get_ref().clone(), access)).collect();
+            HttpResponse::Ok().json(dtos)
+        }
+        Err(err) => manage_error_response(err),
+    }
+}
+
+#[patch("/url/{url_key}")]
+pub async fn patch_owned_url(
+    api_key: ApiKey, url_key: web::Path<String>, patch_dto: web::Json<URLPatchDto>, url_service: web::Data<Arc<URLService>>,
+    config: web::Data<AppConfig>,
+) -> impl Responder {
+    let caller = match authenticate(&api_key, &url_service, &config).await {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+    match url_service.patch_managed_url(caller, url_key.into_inner(), patch_dto.into_inner()).await {
+        Ok(url_model) => HttpResponse::Ok().json(map_url_to_dto(&url_model, config.get_ref().clone())),
+        Err(err) => manage_error_response(err),
+    }
+}
+
+#[delete("/url/{url_key}")]
+pub async fn delete_owned_url(
+    api_key: ApiKey, url_key: web::Path<String>, url_service: web::Data<Arc<URLService>>, config: web::Data<AppConfig>,
+) -> impl Responder {
+    let caller = match authenticate(&api_key, &url_service, &config).await {
+        Ok(caller) => caller,
+        Err(response) => return response,
+    };
+    match url_service.delete_managed_url(caller, url_key.into_inner()).await {
+        Ok(url_model) => HttpResponse::Ok().json(map_url_to_dto(&url_model, config.get_ref().clone())),
+        Err(err) => manage_error_response(err),
+    }
+}
+
+/// Deactivate active links whose target now violates the policy (admin key in `X-API-Key`).
+#[post("/admin/policy/rescan")]
+pub async fn rescan_link_policy(
+    api_key: ApiKey, url_service: web::Data<Arc<URLService>>, config: web::Data<AppConfig>,
+) -> impl Responder {
+    if !config.is_admin_key(&api_key.0) {
+        return error_response(CustomError::new(403, "Admin API key required"));
+    }
+    match url_service.rescan_link_policy().await {
+        Ok(report) => HttpResponse::Ok().json(report),
+        Err(err) => {
+            eprintln!("Error occurred[rescan_link_policy_ctrl]: {}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Keys visited most often without matching any link (admin key in `X-API-Key`).
+#[get("/admin/links/unknown")]
+pub async fn list_unknown_keys(
+    api_key: ApiKey, url_service: web::Data<Arc<URLService>>, config: web::Data<AppConfig>,
+) -> impl Responder {
+    if !config.is_admin_key(&api_key.0) {
+        return error_response(CustomError::new(403, "Admin API key required"));
+    }
+    match url_service.list_unknown_keys().await {
+        Ok(hits) => HttpResponse::Ok().json(hits),
+        Err(err) => {
+            eprintln!("Error occurred[list_unknown_keys_ctrl]: {}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/// Hit and miss counters of the in-process link cache (admin key in `X-API-Key`).
+#[get("/admin/cache")]
+pub async fn get_cache_stats(
+    api_key: ApiKey, url_service: web::Data<Arc<URLService>>, config: web::Data<AppConfig>,
+) -> impl Responder {
+    if !config.is_admin_key(&api_key.0) {
+        return error_response(CustomError::new(403, "Admin API key required"));
+    }
+    match url_service.cache_stats() {
+        Some(stats) => HttpResponse::Ok().json(stats),
+        None => error_response(CustomError::new(404, "Link cache is disabled")),
+    }
+}
+
+/// Active links whose destination failed its recent health checks (admin key in `X-API-Key`).
+#[get("/admin/links/broken")]
+pub async fn list_broken_urls(
+    api_key: ApiKey, url_service: web::Data<Arc<URLService>>, config: web::Data<AppConfig>,
+) -> impl Responder {
+    if !config.is_admin_key(&api_key.0) {
+        return error_response(CustomError::new(403, "Admin API key required"));
+    }
+    match url_service.list_broken_urls().await {
+        Ok(urls) => {
+            let dtos: Vec<_> = urls.iter().map(|url| map_url_to_dto(url, config.get_ref().clone())).collect();
+            HttpResponse::Ok().json(dtos)
+        }
+        Err(err) => {
+            eprintln!("Error occurred[list_broken_urls_ctrl]: {}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
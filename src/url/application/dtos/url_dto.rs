@@ -1,14 +1,72 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use crate::shared::qr::{parse_ec_level, QrError, QrOptions};
+use crate::url::domain::models::schema::ScheduleState;
+use std::collections::HashMap;
 use thiserror::Error;
 
 // Definim l'estructura URL que hereta de URLBase
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct URLBaseDto {
     pub target_url: String,
     pub api_key: String,
+    /// Optional country code (ISO 3166-1 alpha-2) → destination overrides, e.g. `{"US": "https://us.example.com"}`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub geo_rules: HashMap<String, String>,
+    /// Optional password visitors must enter before being redirected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password: Option<String>,
+    /// Human readable title shown on the preview page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    /// Summary of the destination, for listings and search.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Internal notes, never shown to visitors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Social preview shown by chat apps and social networks (`title` / `description` when unset).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub og_title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub og_description: Option<String>,
+    /// Absolute http(s) URL of the preview image.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub og_image_url: Option<String>,
+    /// Always show the preview page before redirecting.
+    #[serde(default)]
+    pub show_interstitial: bool,
+    /// The link stops working after its first redirect.
+    #[serde(default)]
+    pub single_use: bool,
+    /// Only signed variants minted through `POST /url/{key}/sign` are accepted.
+    #[serde(default)]
+    pub require_signature: bool,
+    /// Go live at this moment (RFC 3339); a "coming soon" page is served before.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_from: Option<DateTime<Utc>>,
+    /// Stop redirecting at this moment (RFC 3339).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_until: Option<DateTime<Utc>>,
+    /// Organization workspace for the link; the creator's personal workspace when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub org_id: Option<i64>,
+    /// Custom key for the link instead of a generated one, if the plan allows it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// Folder grouping the link inside its workspace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub folder: Option<String>,
+    /// Destination once the link is deactivated, used up, ended or over `max_clicks`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_url: Option<String>,
+    /// Visits after which the link stops redirecting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_clicks: Option<i32>,
 }
 
 // Definim l'estructura URL que hereta de URLBase
+#[allow(dead_code)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct URLDto {
     pub target_url: String,
@@ -23,7 +81,148 @@ pub struct URLInfoDto {
     pub is_active: bool,
     pub clicks: i32,
     pub url: String,
-    pub admin_url: String,
+    /// Carries the secret key; left out for callers who may only read the link.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub admin_url: Option<String>,
+    pub qr_url: String,
+    pub password_protected: bool,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub notes: Option<String>,
+    pub favicon_url: Option<String>,
+    pub og_title: Option<String>,
+    pub og_description: Option<String>,
+    pub og_image_url: Option<String>,
+    pub show_interstitial: bool,
+    pub interstitial_clicks: i32,
+    pub created_at: Option<DateTime<Utc>>,
+    pub single_use: bool,
+    pub require_signature: bool,
+    pub active_from: Option<DateTime<Utc>>,
+    pub active_until: Option<DateTime<Utc>>,
+    pub org_id: Option<i64>,
+    pub folder: Option<String>,
+    pub tags: Vec<String>,
+    pub fallback_url: Option<String>,
+    pub max_clicks: Option<i32>,
+    /// Latest health check of the destination; `None` until the link has been checked.
+    pub health: Option<LinkHealthDto>,
+}
+
+/// Latest health check of a link, as shown in `URLInfoDto`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkHealthDto {
+    pub checked_at: DateTime<Utc>,
+    pub status_code: Option<i32>,
+    pub final_url: Option<String>,
+    pub latency_ms: Option<i64>,
+    pub error: Option<String>,
+    pub failed_checks: i32,
+    /// The destination failed enough checks in a row to be considered dead.
+    pub broken: bool,
+}
+
+/// Form posted by the unlock page of a password-protected link.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UnlockFormDto {
+    pub password: String,
+}
+
+/// Partial update of a link; absent fields are left untouched (an empty text field, `og_image_url`
+/// or `fallback_url` clears it, and so does a `max_clicks` of 0).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct URLPatchDto {
+    pub target_url: Option<String>,
+    pub title: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub og_title: Option<String>,
+    #[serde(default)]
+    pub og_description: Option<String>,
+    #[serde(default)]
+    pub og_image_url: Option<String>,
+    pub show_interstitial: Option<bool>,
+    pub is_active: Option<bool>,
+    #[serde(default)]
+    pub folder: Option<String>,
+    #[serde(default)]
+    pub fallback_url: Option<String>,
+    #[serde(default)]
+    pub max_clicks: Option<i32>,
+}
+
+/// Query of `GET /orgs/{org_id}/urls`.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct OrgURLsQueryDto {
+    pub tag: Option<String>,
+    pub folder: Option<String>,
+    /// Text searched in the key, target, title, description and notes.
+    pub q: Option<String>,
+}
+
+/// Name of a tag to create or rename.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagNameDto {
+    pub name: String,
+}
+
+/// Query of `GET /url/scheduled`; the caller is identified by the `X-API-Key` header.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScheduledURLsQueryDto {
+    pub state: Option<ScheduleState>,
+}
+
+/// Query of the QR code endpoints; unset parameters use the renderer defaults.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct QrQueryDto {
+    /// `png` (default) or `svg`.
+    pub format: Option<String>,
+    /// Requested width in pixels.
+    pub size: Option<u32>,
+    /// Quiet zone in modules.
+    pub margin: Option<u32>,
+    /// Error correction level: `L`, `M` (default), `Q` or `H`.
+    pub ecc: Option<String>,
+    /// Foreground / background hex colors, e.g. `000000`.
+    pub fg: Option<String>,
+    pub bg: Option<String>,
+}
+
+impl QrQueryDto {
+    pub fn to_options(&self) -> Result<QrOptions, QrError> {
+        let defaults = QrOptions::default();
+        Ok(QrOptions {
+            format: self.format.as_deref().map(str::parse).transpose()?.unwrap_or(defaults.format),
+            size: self.size.unwrap_or(defaults.size),
+            margin: self.margin.unwrap_or(defaults.margin),
+            ec_level: self.ecc.as_deref().map(parse_ec_level).transpose()?.unwrap_or(defaults.ec_level),
+            foreground: self.fg.as_deref().map(str::parse).transpose()?.unwrap_or(defaults.foreground),
+            background: self.bg.as_deref().map(str::parse).transpose()?.unwrap_or(defaults.background),
+        })
+    }
+}
+
+/// Request to mint a signed, expiring variant of a link.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignURLDto {
+    pub api_key: String,
+    pub expires_in_secs: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedURLDto {
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// `exp` / `sig` query parameters carried by a signed link.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SignedLinkQueryDto {
+    pub exp: Option<i64>,
+    pub sig: Option<String>,
 }
 
 #[derive(Error, Debug, Serialize)]
@@ -45,4 +244,8 @@ impl CustomError {
             message: message.to_string(),
         }
     }
+
+    pub fn code(&self) -> i32 {
+        self.code
+    }
 }
@@ -1,21 +1,52 @@
 use crate::config::env::AppConfig;
-use crate::url::application::dtos::url_dto::URLInfoDto;
+use crate::url::application::dtos::url_dto::{LinkHealthDto, URLInfoDto};
+use crate::url::domain::models::access::LinkAccess;
 use crate::url::domain::models::schema::URL;
 
 // Funció per mapejar URL a URLInfoDto
 pub fn map_url_to_dto(url: &URL, config: AppConfig) -> URLInfoDto {
-    let base_url = format!(
-        "{protocol}://{base_url}:{server_port}",
-        protocol = config.protocol,
-        base_url = config.base_url,
-        server_port = config.server_port
-    );
+    map_url_to_dto_with_access(url, config, LinkAccess::Write)
+}
+
+// Els lectors (rol Viewer) no reben l'URL d'administració, que conté la clau secreta
+pub fn map_url_to_dto_with_access(url: &URL, config: AppConfig, access: LinkAccess) -> URLInfoDto {
+    let base_url = config.public_base_url();
     URLInfoDto {
         target_url: url.target_url.clone(),
         clicks: url.clicks,
         is_active: url.is_active,
         url: format!("{base_url}/{}", url.key),
-        admin_url: format!("{base_url}/admin/{}", url.secret_key),
+        admin_url: Some(format!("{base_url}/admin/{}", url.secret_key)).filter(|_| access == LinkAccess::Write),
+        qr_url: format!("{base_url}/{}/qr", url.key),
+        password_protected: url.password_hash.is_some(),
+        title: url.title.clone(),
+        description: url.description.clone(),
+        notes: url.notes.clone(),
+        favicon_url: url.favicon_url.clone(),
+        og_title: url.og_title.clone(),
+        og_description: url.og_description.clone(),
+        og_image_url: url.og_image_url.clone(),
+        show_interstitial: url.show_interstitial,
+        interstitial_clicks: url.interstitial_clicks,
+        created_at: url.created_at,
+        single_use: url.single_use,
+        require_signature: url.require_signature,
+        active_from: url.active_from,
+        active_until: url.active_until,
+        org_id: url.org_id,
+        folder: url.folder.clone(),
+        tags: url.tags.clone(),
+        fallback_url: url.fallback_url.clone(),
+        max_clicks: url.max_clicks,
+        health: url.last_checked_at.map(|checked_at| LinkHealthDto {
+            checked_at,
+            status_code: url.last_check_status,
+            final_url: url.last_check_url.clone(),
+            latency_ms: url.last_check_latency_ms,
+            error: url.last_check_error.clone(),
+            failed_checks: url.failed_checks,
+            broken: url.is_broken(config.dead_link_threshold),
+        }),
     }
 }
 
@@ -26,11 +57,30 @@ mod tests {
 
     #[test]
     fn map_url_to_dto_builds_correct_urls() {
-        let url = URL { key: "K".into(), secret_key: "S".into(), target_url: "http://t".into(), is_active: true, clicks: 3, user_id: 1 };
-        let cfg = AppConfig { base_url: "localhost".into(), server_port: "8080".into(), protocol: "http".into() };
+        let url = URL { key: "K".into(), secret_key: "S".into(), target_url: "http://t".into(), is_active: true, clicks: 3, user_id: 1, ..Default::default() };
+        let cfg = AppConfig { base_url: "localhost".into(), server_port: "8080".into(), protocol: "http".into(), ..Default::default() };
         let dto = map_url_to_dto(&url, cfg);
         assert!(dto.url.contains("localhost:8080/K"));
-        assert!(dto.admin_url.contains("localhost:8080/admin/S"));
+        assert!(dto.admin_url.as_deref().is_some_and(|admin_url| admin_url.contains("localhost:8080/admin/S")));
+        assert_eq!(dto.qr_url, "http://localhost:8080/K/qr");
         assert_eq!(dto.clicks, 3);
+        assert!(dto.health.is_none());
+    }
+
+    #[test]
+    fn read_only_dto_leaves_out_the_admin_url() {
+        let url = URL { key: "K".into(), secret_key: "S".into(), ..Default::default() };
+        let dto = map_url_to_dto_with_access(&url, AppConfig::default(), LinkAccess::Read);
+        assert!(dto.admin_url.is_none());
+        assert!(!serde_json::to_string(&dto).unwrap().contains("admin_url"));
+    }
+
+    #[test]
+    fn map_url_to_dto_flags_broken_links() {
+        let url = URL { key: "K".into(), last_checked_at: Some(chrono::Utc::now()), last_check_status: Some(404), failed_checks: 3, ..Default::default() };
+        let health = map_url_to_dto(&url, AppConfig { dead_link_threshold: 3, ..Default::default() }).health.unwrap();
+        assert_eq!((health.status_code, health.failed_checks, health.broken), (Some(404), 3, true));
+        let health = map_url_to_dto(&url, AppConfig { dead_link_threshold: 4, ..Default::default() }).health.unwrap();
+        assert!(!health.broken);
     }
 }
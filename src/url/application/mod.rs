@@ -1,3 +1,4 @@
 pub mod controllers;
 pub mod dtos;
 pub mod mappers;
+pub mod views;
@@ -0,0 +1,298 @@
+// Pàgines HTML mínimes servides pel camí de redirecció (sense motor de plantilles)
+use crate::url::domain::models::schema::URL;
+use std::path::Path;
+
+/// Escape text for safe interpolation in HTML element content and attribute values.
+pub fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn page(title: &str, head_extra: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta name="robots" content="noindex">
+<title>{title}</title>
+{head_extra}
+</head>
+<body style="font-family: sans-serif; max-width: 32rem; margin: 4rem auto; padding: 0 1rem;">
+{body}
+</body>
+</html>"#,
+        title = escape_html(title),
+    )
+}
+
+/// Password prompt for a protected link; the form posts to `action` (`/{url_key}/unlock`, plus the
+/// query string of signed links).
+pub fn password_form(action: &str, error: Option<&str>) -> String {
+    let error = error
+        .map(|message| format!(r#"<p role="alert" style="color: #b00020;">{}</p>"#, escape_html(message)))
+        .unwrap_or_default();
+    let body = format!(
+        r#"<h1>This link is password protected</h1>
+{error}
+<form method="post" action="{action}">
+<label for="password">Password</label>
+<input id="password" name="password" type="password" autocomplete="current-password" required autofocus>
+<button type="submit">Continue</button>
+</form>"#,
+        action = escape_html(action),
+    );
+    page("Password required", "", &body)
+}
+
+/// Preview page for `url`: destination, title, creation date and a continue button pointing to
+/// `continue_url` (`/{key}/continue`). With `countdown_secs > 0` the page also continues on its own.
+pub fn interstitial(url: &URL, target_url: Option<&str>, continue_url: &str, countdown_secs: u32) -> String {
+    let continue_url = escape_html(continue_url);
+    let target = match target_url {
+        Some(target_url) => format!(
+            r#"<p>This short link points to:</p>
+<p><code style="word-break: break-all;">{}</code></p>"#,
+            escape_html(target_url)
+        ),
+        None => "<p>This link can only be followed once; its destination is revealed when you continue.</p>".to_string(),
+    };
+    let title = url
+        .title
+        .as_deref()
+        .map(|title| format!("<h1>{}</h1>", escape_html(title)))
+        .unwrap_or_else(|| "<h1>You are leaving this site</h1>".to_string());
+    let created = url
+        .created_at
+        .map(|created_at| format!("<p>Link created on {}</p>", created_at.format("%Y-%m-%d")))
+        .unwrap_or_default();
+    let (head_extra, countdown) = if countdown_secs > 0 {
+        (
+            format!(r#"<meta http-equiv="refresh" content="{countdown_secs};url={continue_url}">"#),
+            format!("<p>You will be redirected automatically in {countdown_secs} seconds.</p>"),
+        )
+    } else {
+        (String::new(), String::new())
+    };
+    let body = format!(
+        r#"{title}
+{target}
+{created}
+{countdown}
+<p><a href="{continue_url}" rel="noreferrer noopener"><button type="button">Continue to destination</button></a></p>"#
+    );
+    page("Link preview", &head_extra, &body)
+}
+
+/// Operator-provided templates for links outside their activation window, loaded once at startup.
+///
+/// Templates may use the `{{key}}`, `{{title}}`, `{{active_from}}` and `{{active_until}}`
+/// placeholders (RFC 3339 dates); values are HTML-escaped.
+#[derive(Clone, Debug, Default)]
+pub struct SchedulePages {
+    pub coming_soon: Option<String>,
+    pub ended: Option<String>,
+}
+
+impl SchedulePages {
+    pub fn load(coming_soon_path: Option<&str>, ended_path: Option<&str>) -> std::io::Result<Self> {
+        fn read(path: Option<&str>) -> std::io::Result<Option<String>> {
+            path.map(|path| std::fs::read_to_string(Path::new(path))).transpose()
+        }
+        Ok(Self { coming_soon: read(coming_soon_path)?, ended: read(ended_path)? })
+    }
+}
+
+/// What visitors of unknown or deactivated keys get, chosen by the operator at startup.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum NotFoundPage {
+    /// Built-in 404 page.
+    #[default]
+    Builtin,
+    /// 404 page from an HTML template; `{{key}}` is replaced by the (escaped) key visited.
+    Template(String),
+    /// Redirect to this URL, e.g. the homepage.
+    Redirect(String),
+}
+
+impl NotFoundPage {
+    pub fn load(template_path: Option<&str>, redirect_url: Option<&str>) -> std::io::Result<Self> {
+        match (template_path, redirect_url) {
+            (_, Some(redirect_url)) => Ok(Self::Redirect(redirect_url.to_string())),
+            (Some(path), None) => Ok(Self::Template(std::fs::read_to_string(Path::new(path))?)),
+            (None, None) => Ok(Self::Builtin),
+        }
+    }
+}
+
+fn render_schedule_template(template: &str, url: &URL) -> String {
+    let date = |value: Option<chrono::DateTime<chrono::Utc>>| value.map(|d| d.to_rfc3339()).unwrap_or_default();
+    template
+        .replace("{{key}}", &escape_html(&url.key))
+        .replace("{{title}}", &escape_html(url.title.as_deref().unwrap_or_default()))
+        .replace("{{active_from}}", &escape_html(&date(url.active_from)))
+        .replace("{{active_until}}", &escape_html(&date(url.active_until)))
+}
+
+/// Served while a scheduled link has not gone live yet.
+pub fn coming_soon(url: &URL, template: Option<&str>) -> String {
+    if let Some(template) = template {
+        return render_schedule_template(template, url);
+    }
+    let title = url.title.as_deref().map(escape_html).unwrap_or_else(|| "Coming soon".to_string());
+    let starts = url
+        .active_from
+        .map(|from| format!("<p>This link goes live on {} UTC.</p>", from.format("%Y-%m-%d %H:%M")))
+        .unwrap_or_default();
+    page("Coming soon", "", &format!("<h1>{title}</h1>\n{starts}"))
+}
+
+/// Served once the activation window of a link is over.
+pub fn ended(url: &URL, template: Option<&str>) -> String {
+    if let Some(template) = template {
+        return render_schedule_template(template, url);
+    }
+    page("Link ended", "", "<h1>This link has ended</h1>\n<p>It is no longer active.</p>")
+}
+
+/// Card served to link-preview crawlers: OpenGraph and Twitter tags of `url`, falling back to its
+/// title and description, plus an immediate refresh to `target_url` when there is one.
+pub fn social_preview(url: &URL, short_url: &str, target_url: Option<&str>) -> String {
+    let title = url.og_title.as_deref().or(url.title.as_deref()).unwrap_or(short_url);
+    let description = url.og_description.as_deref().or(url.description.as_deref());
+    let mut tags = vec![
+        ("property", "og:type", "website"),
+        ("property", "og:url", short_url),
+        ("property", "og:title", title),
+        ("name", "twitter:title", title),
+    ];
+    if let Some(description) = description {
+        tags.push(("property", "og:description", description));
+        tags.push(("name", "twitter:description", description));
+    }
+    match url.og_image_url.as_deref() {
+        Some(image) => {
+            tags.push(("property", "og:image", image));
+            tags.push(("name", "twitter:image", image));
+            tags.push(("name", "twitter:card", "summary_large_image"));
+        }
+        None => tags.push(("name", "twitter:card", "summary")),
+    }
+    let mut head_extra: Vec<String> = tags
+        .into_iter()
+        .map(|(attribute, name, content)| format!(r#"<meta {attribute}="{name}" content="{}">"#, escape_html(content)))
+        .collect();
+    if let Some(target_url) = target_url {
+        head_extra.push(format!(r#"<meta http-equiv="refresh" content="0;url={}">"#, escape_html(target_url)));
+    }
+    let body = match description {
+        Some(description) => format!("<h1>{}</h1>\n<p>{}</p>", escape_html(title), escape_html(description)),
+        None => format!("<h1>{}</h1>", escape_html(title)),
+    };
+    page(title, &head_extra.join("\n"), &body)
+}
+
+/// Served with 404 for unknown and deactivated keys, unless the operator redirects them.
+pub fn not_found(url_key: &str, template: Option<&str>) -> String {
+    if let Some(template) = template {
+        return template.replace("{{key}}", &escape_html(url_key));
+    }
+    page("Link not found", "", "<h1>This link does not exist</h1>\n<p>Check that it was typed or copied correctly.</p>")
+}
+
+/// Served with 410 for used one-time links and expired or invalid signed links.
+pub fn gone() -> String {
+    page(
+        "Link no longer available",
+        "",
+        "<h1>This link is no longer available</h1>\n<p>It has already been used or has expired.</p>",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_html_escapes_markup() {
+        assert_eq!(escape_html(r#"<a href="x">'&'</a>"#), "&lt;a href=&quot;x&quot;&gt;&#39;&amp;&#39;&lt;/a&gt;");
+    }
+
+    #[test]
+    fn password_form_posts_to_unlock_and_escapes_error() {
+        let html = password_form("/abc/unlock?exp=1&sig=ff", Some("<bad>"));
+        assert!(html.contains(r#"action="/abc/unlock?exp=1&amp;sig=ff""#));
+        assert!(html.contains("&lt;bad&gt;"));
+        assert!(!html.contains("<bad>"));
+    }
+
+    #[test]
+    fn schedule_pages_use_templates_with_escaped_placeholders() {
+        let url = URL { key: "k".into(), title: Some("<Launch>".into()), active_from: "2030-01-01T09:00:00Z".parse().ok(), ..Default::default() };
+        assert!(coming_soon(&url, None).contains("2030-01-01 09:00"));
+        let html = coming_soon(&url, Some("<p>{{title}} at {{active_from}}</p>"));
+        assert_eq!(html, "<p>&lt;Launch&gt; at 2030-01-01T09:00:00+00:00</p>");
+        assert!(ended(&url, None).contains("has ended"));
+        assert!(SchedulePages::load(Some("./missing-page.html"), None).is_err());
+    }
+
+    #[test]
+    fn not_found_page_uses_template_or_redirect() {
+        assert!(not_found("k", None).contains("does not exist"));
+        assert_eq!(not_found("<k>", Some("<p>No {{key}} here</p>")), "<p>No &lt;k&gt; here</p>");
+        assert_eq!(NotFoundPage::load(None, None).unwrap(), NotFoundPage::Builtin);
+        assert_eq!(NotFoundPage::load(None, Some("https://example.com/")).unwrap(), NotFoundPage::Redirect("https://example.com/".into()));
+        assert!(NotFoundPage::load(Some("./missing-page.html"), None).is_err());
+    }
+
+    #[test]
+    fn interstitial_shows_destination_and_optional_countdown() {
+        let url = URL { key: "k".into(), title: Some("Docs <v2>".into()), created_at: "2024-05-01T10:00:00Z".parse().ok(), ..Default::default() };
+        let html = interstitial(&url, Some("https://example.com/?a=1&b=2"), "/k/continue", 0);
+        assert!(html.contains("https://example.com/?a=1&amp;b=2"));
+        assert!(html.contains("Docs &lt;v2&gt;"));
+        assert!(html.contains("2024-05-01"));
+        assert!(html.contains(r#"href="/k/continue""#));
+        assert!(!html.contains("http-equiv"));
+
+        let html = interstitial(&url, Some("https://example.com"), "/k/continue", 5);
+        assert!(html.contains(r#"content="5;url=/k/continue""#));
+
+        let html = interstitial(&url, None, "/k/continue", 0);
+        assert!(html.contains("followed once") && !html.contains("points to"));
+    }
+
+    #[test]
+    fn social_preview_prefers_custom_card_and_escapes_it() {
+        let url = URL {
+            key: "k".into(),
+            title: Some("Page title".into()),
+            description: Some("Fetched description".into()),
+            og_title: Some("Spring <sale>".into()),
+            og_image_url: Some("https://cdn.example.com/card.png?w=1&h=2".into()),
+            ..Default::default()
+        };
+        let html = social_preview(&url, "http://s.io/k", Some("https://example.com/?a=1&b=2"));
+        assert!(html.contains(r#"<meta property="og:title" content="Spring &lt;sale&gt;">"#));
+        assert!(html.contains(r#"<meta property="og:description" content="Fetched description">"#));
+        assert!(html.contains(r#"<meta property="og:image" content="https://cdn.example.com/card.png?w=1&amp;h=2">"#));
+        assert!(html.contains(r#"<meta name="twitter:card" content="summary_large_image">"#));
+        assert!(html.contains(r#"content="0;url=https://example.com/?a=1&amp;b=2""#));
+
+        let html = social_preview(&URL { key: "k".into(), ..Default::default() }, "http://s.io/k", None);
+        assert!(html.contains(r#"<meta property="og:title" content="http://s.io/k">"#));
+        assert!(html.contains(r#"<meta name="twitter:card" content="summary">"#));
+        assert!(!html.contains("og:description") && !html.contains("http-equiv"));
+    }
+}
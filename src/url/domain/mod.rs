@@ -1,3 +1,4 @@
 pub mod models;
+pub mod ports;
 pub mod repositories;
 pub mod services;
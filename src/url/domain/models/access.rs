@@ -0,0 +1,29 @@
+use crate::org::domain::models::organization::Role;
+
+/// Who is acting on links through the authenticated API.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Caller {
+    /// Holder of an admin API key: may act on every link.
+    Admin,
+    /// Regular user, identified by its API key.
+    User(i32),
+}
+
+/// What a caller wants to do with a link.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LinkAccess {
+    /// See the link and its stats.
+    Read,
+    /// Edit, sign or delete the link.
+    Write,
+}
+
+impl LinkAccess {
+    /// Whether a member holding `role` in the link's workspace is allowed this access.
+    pub fn granted_to(self, role: Role) -> bool {
+        match self {
+            LinkAccess::Read => true,
+            LinkAccess::Write => role.can_edit_links(),
+        }
+    }
+}
@@ -0,0 +1,12 @@
+use serde::Serialize;
+
+/// Counters of the in-process link cache, for monitoring.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct CacheStats {
+    /// Lookups answered from the cache, unknown keys included.
+    pub hits: u64,
+    /// Lookups that had to reach the database.
+    pub misses: u64,
+    pub entries: usize,
+    pub capacity: usize,
+}
@@ -0,0 +1,24 @@
+use chrono::{DateTime, Utc};
+
+/// Criteria for listing links; unset fields do not restrict the result.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct URLFilter {
+    /// Only links that are still active.
+    pub active: bool,
+    pub user_id: Option<i32>,
+    pub org_id: Option<i64>,
+    /// Only links with an activation window (`active_from` and/or `active_until`).
+    pub scheduled: bool,
+    /// Only links carrying the tag with this name (in the link's own workspace).
+    pub tag: Option<String>,
+    /// Only links filed under this folder.
+    pub folder: Option<String>,
+    /// Case-insensitive text searched in the key, target, title, description and notes.
+    pub search: Option<String>,
+    /// Only links never health-checked, or last checked before this moment.
+    pub checked_before: Option<DateTime<Utc>>,
+    /// Only links whose last health checks failed at least this many times in a row.
+    pub min_failed_checks: Option<i32>,
+    /// At most this many links, the oldest first.
+    pub limit: Option<i64>,
+}
@@ -0,0 +1,55 @@
+use chrono::{DateTime, Utc};
+
+/// Outcome of one health check of a link's destination.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LinkCheck {
+    /// Status of the last response, after following redirects; `None` when no response came back.
+    pub status_code: Option<u16>,
+    /// URL that answered last, after following redirects.
+    pub final_url: Option<String>,
+    /// Time taken by the whole exchange, redirects included.
+    pub latency_ms: i64,
+    /// Why the destination could not be reached (timeout, refused connection, blocked by policy…).
+    pub error: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl LinkCheck {
+    /// The destination looks dead: unreachable, gone (404/410) or failing (5xx). Other client
+    /// errors (401, 403, 429…) usually come from login walls or bot protection and are ignored.
+    pub fn is_failure(&self) -> bool {
+        match self.status_code {
+            _ if self.error.is_some() => true,
+            Some(status) => status == 404 || status == 410 || status >= 500,
+            None => true,
+        }
+    }
+}
+
+/// When links count as broken and where their visitors are sent then.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DeadLinkPolicy {
+    /// Consecutive failed checks after which a link is flagged broken; 0 never flags one.
+    pub failure_threshold: i32,
+    /// Destination used instead of the target (and geo rules) of broken links; without it their
+    /// visitors are still sent to the original destination.
+    pub fallback_url: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_unreachable_gone_and_server_errors_fail() {
+        let check = |status_code: Option<u16>, error: Option<&str>| LinkCheck { status_code, error: error.map(Into::into), ..Default::default() };
+        for status in [200, 204, 301, 401, 403, 429] {
+            assert!(!check(Some(status), None).is_failure(), "{status}");
+        }
+        for status in [404, 410, 500, 503] {
+            assert!(check(Some(status), None).is_failure(), "{status}");
+        }
+        assert!(check(None, Some("connection refused")).is_failure());
+        assert!(check(Some(302), Some("too many redirects")).is_failure());
+    }
+}
@@ -0,0 +1,32 @@
+use crate::url::domain::models::policy::PolicyViolation;
+use thiserror::Error;
+
+/// What a link's destination says about itself in its HTML.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LinkMetadata {
+    /// Content of `<title>`, or `og:title` when the page has none.
+    pub title: Option<String>,
+    /// `og:description`, or the `description` meta tag.
+    pub description: Option<String>,
+    /// Absolute URL of the page icon.
+    pub favicon_url: Option<String>,
+}
+
+/// Why the metadata of a destination could not be fetched.
+#[derive(Debug, Error)]
+pub enum MetadataFetchError {
+    #[error("only http and https destinations are fetched")]
+    UnsupportedScheme,
+    #[error(transparent)]
+    Blocked(#[from] PolicyViolation),
+    #[error("too many redirects")]
+    TooManyRedirects,
+    #[error("destination answered {0}")]
+    Status(u16),
+    #[error("destination is not an HTML page")]
+    NotHtml,
+    #[error("request timed out")]
+    Timeout,
+    #[error("request failed: {0}")]
+    Request(String),
+}
@@ -1 +1,9 @@
+pub mod access;
+pub mod cache;
+pub mod filter;
+pub mod health;
+pub mod metadata;
+pub mod policy;
 pub mod schema;
+pub mod tag;
+pub mod visit;
@@ -0,0 +1,200 @@
+use crate::url::domain::ports::link_policy_port::LinkPolicyPort;
+use ipnet::IpNet;
+use serde::Serialize;
+use std::fmt;
+use std::net::IpAddr;
+use thiserror::Error;
+use url::{Host, Url};
+
+/// Host part of a policy rule.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HostPattern {
+    /// A domain together with all of its subdomains (`example.com` also covers `www.example.com`).
+    Domain(String),
+    /// An address range; bare addresses are single-host ranges.
+    Network(IpNet),
+}
+
+impl HostPattern {
+    fn matches(&self, host: &Host<&str>) -> bool {
+        match (self, host) {
+            (HostPattern::Domain(domain), Host::Domain(name)) => {
+                let name = name.trim_end_matches('.');
+                name == domain || name.strip_suffix(domain.as_str()).is_some_and(|prefix| prefix.ends_with('.'))
+            }
+            (HostPattern::Network(net), Host::Ipv4(ip)) => net.contains(&IpAddr::V4(*ip)),
+            (HostPattern::Network(net), Host::Ipv6(ip)) => {
+                net.contains(&IpAddr::V6(*ip)) || ip.to_ipv4_mapped().is_some_and(|v4| net.contains(&IpAddr::V4(v4)))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for HostPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HostPattern::Domain(domain) => write!(f, "{domain}"),
+            HostPattern::Network(net) => write!(f, "{net}"),
+        }
+    }
+}
+
+/// Why a target URL is refused by the link policy.
+#[derive(Debug, Error, PartialEq)]
+pub enum PolicyViolation {
+    #[error("Target host '{host}' is blocked by policy rule '{rule}'")]
+    Blocked { host: String, rule: String },
+    #[error("Target URL '{0}' cannot be checked against the link policy")]
+    Unparseable(String),
+}
+
+/// Malformed line in a policy file.
+#[derive(Debug, Error, PartialEq)]
+#[error("line {line}: {message}")]
+pub struct PolicyParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+/// Block and allow rules for the hosts links may point to. Allow rules are exceptions: a host
+/// matching both lists is accepted.
+///
+/// The text format has one rule per line, `block <host>` or `allow <host>`, where `<host>` is a
+/// domain (`phish.example`, `.internal`), an address or a CIDR range. `#` starts a comment.
+/// Only literal hosts are checked; names are not resolved.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PolicyRules {
+    pub block: Vec<HostPattern>,
+    pub allow: Vec<HostPattern>,
+}
+
+impl PolicyRules {
+    /// Loopback, private, link-local and unspecified ranges plus `localhost`, `.internal` and
+    /// `.local`: always blocked unless explicitly allowed.
+    pub fn builtin() -> Self {
+        let domains = ["localhost", "internal", "local"].map(|d| HostPattern::Domain(d.to_string()));
+        let networks = [
+            "0.0.0.0/8", "10.0.0.0/8", "127.0.0.0/8", "169.254.0.0/16", "172.16.0.0/12", "192.168.0.0/16",
+            "100.64.0.0/10", "::/128", "::1/128", "fc00::/7", "fe80::/10",
+        ]
+        .map(|net| HostPattern::Network(net.parse().expect("valid built-in range")));
+        Self { block: domains.into_iter().chain(networks).collect(), allow: Vec::new() }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, PolicyParseError> {
+        let mut rules = Self::default();
+        for (index, raw) in text.lines().enumerate() {
+            let line = raw.split('#').next().unwrap_or_default().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = |message: String| PolicyParseError { line: index + 1, message };
+            let mut parts = line.split_whitespace();
+            let (Some(action), Some(host), None) = (parts.next(), parts.next(), parts.next()) else {
+                return Err(error(format!("expected '<block|allow> <host>', got '{line}'")));
+            };
+            let pattern = parse_host_pattern(host).map_err(error)?;
+            match action.to_ascii_lowercase().as_str() {
+                "block" => rules.block.push(pattern),
+                "allow" => rules.allow.push(pattern),
+                other => return Err(error(format!("unknown action '{other}'"))),
+            }
+        }
+        Ok(rules)
+    }
+
+    /// Rules of `self` followed by those of `other`.
+    pub fn merged(mut self, other: PolicyRules) -> Self {
+        self.block.extend(other.block);
+        self.allow.extend(other.allow);
+        self
+    }
+
+    pub fn evaluate(&self, target_url: &str) -> Result<(), PolicyViolation> {
+        let url = Url::parse(target_url).map_err(|_| PolicyViolation::Unparseable(target_url.to_string()))?;
+        let Some(host) = url.host() else {
+            // e.g. `mailto:` targets, when their scheme is allowed
+            return Ok(());
+        };
+        if self.allow.iter().any(|rule| rule.matches(&host)) {
+            return Ok(());
+        }
+        match self.block.iter().find(|rule| rule.matches(&host)) {
+            Some(rule) => Err(PolicyViolation::Blocked { host: host.to_string(), rule: rule.to_string() }),
+            None => Ok(()),
+        }
+    }
+}
+
+impl LinkPolicyPort for PolicyRules {
+    fn check(&self, target_url: &str) -> Result<(), PolicyViolation> {
+        self.evaluate(target_url)
+    }
+}
+
+fn parse_host_pattern(host: &str) -> Result<HostPattern, String> {
+    if let Ok(net) = host.parse::<IpNet>() {
+        return Ok(HostPattern::Network(net));
+    }
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Ok(HostPattern::Network(IpNet::from(ip)));
+    }
+    let domain = host.trim_matches('.');
+    // reuse the URL parser for lowercasing and punycode so rules compare like normalized targets
+    match Url::parse(&format!("http://{domain}/")).ok().and_then(|url| url.host_str().map(str::to_string)) {
+        Some(normalized) if !domain.is_empty() => Ok(HostPattern::Domain(normalized)),
+        _ => Err(format!("invalid host '{host}'")),
+    }
+}
+
+/// Outcome of re-checking existing links against the current policy.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct PolicyRescanReport {
+    pub scanned: usize,
+    /// Keys of the links deactivated by this scan.
+    pub deactivated: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_rules_block_internal_hosts() {
+        let rules = PolicyRules::builtin();
+        for target in [
+            "http://localhost:8080/",
+            "http://api.internal/",
+            "http://10.1.2.3/",
+            "http://192.168.0.10/",
+            "http://[::1]/",
+            "http://[::ffff:127.0.0.1]/",
+            "http://2130706433/",
+        ] {
+            assert!(rules.evaluate(target).is_err(), "{target} should be blocked");
+        }
+        assert!(rules.evaluate("https://example.com/").is_ok());
+        assert!(rules.evaluate("https://notlocalhost.com/").is_ok());
+    }
+
+    #[test]
+    fn parsed_rules_block_subdomains_and_allow_exceptions() {
+        let rules = PolicyRules::builtin()
+            .merged(PolicyRules::parse("# phishing\nblock Phish.Example\nallow 10.0.0.5 # build server\n\n").unwrap());
+        assert_eq!(
+            rules.evaluate("https://login.phish.example/x"),
+            Err(PolicyViolation::Blocked { host: "login.phish.example".into(), rule: "phish.example".into() })
+        );
+        assert!(rules.evaluate("http://10.0.0.5/").is_ok());
+        assert!(rules.evaluate("http://10.0.0.6/").is_err());
+    }
+
+    #[test]
+    fn parse_reports_line_of_malformed_rule() {
+        let err = PolicyRules::parse("block a.example\ndeny b.example").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(PolicyRules::parse("block").is_err());
+        assert!(PolicyRules::parse("block bad host").is_err());
+    }
+}
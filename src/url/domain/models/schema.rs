@@ -1,8 +1,10 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
 // Definim l'estructura URL
-#[derive(Clone, Debug, FromRow, Serialize, Deserialize)]
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Clone, Debug, Default, PartialEq, FromRow, Serialize, Deserialize)]
 pub struct URL {
     pub key: String,
     pub secret_key: String,
@@ -10,6 +12,150 @@ pub struct URL {
     pub is_active: bool,
     pub clicks: i32,
     pub user_id: i32,
+    /// Argon2 PHC hash; when present visitors must unlock the link with its password.
+    #[sqlx(default)]
+    #[serde(default, skip_serializing)]
+    pub password_hash: Option<String>,
+    /// Owner-provided, human readable title.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Owner-provided summary of what the link points to.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Free-form internal notes, never shown to visitors.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub notes: Option<String>,
+    /// Icon of the destination page, found when its metadata was fetched.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub favicon_url: Option<String>,
+    /// Social preview (OpenGraph / Twitter card) served to link-preview crawlers; `title` and
+    /// `description` are used when unset.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub og_title: Option<String>,
+    #[sqlx(default)]
+    #[serde(default)]
+    pub og_description: Option<String>,
+    #[sqlx(default)]
+    #[serde(default)]
+    pub og_image_url: Option<String>,
+    /// Always show the preview page instead of redirecting straight away.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub show_interstitial: bool,
+    /// Visits that reached the destination through the preview page (not included in `clicks`).
+    #[sqlx(default)]
+    #[serde(default)]
+    pub interstitial_clicks: i32,
+    #[sqlx(default)]
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    /// The link deactivates itself after its first redirect.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub single_use: bool,
+    /// Only signed variants (`?exp=..&sig=..`) of the link are honoured.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub require_signature: bool,
+    /// The link redirects from this moment on (inclusive); a "coming soon" page is served before.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub active_from: Option<DateTime<Utc>>,
+    /// The link stops redirecting at this moment (exclusive).
+    #[sqlx(default)]
+    #[serde(default)]
+    pub active_until: Option<DateTime<Utc>>,
+    /// Organization workspace that owns the link; `user_id` is only its creator.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub org_id: Option<i64>,
+    /// The key was chosen by the creator instead of taken from the generated pool.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub custom_alias: bool,
+    /// Optional folder the link is filed under (one per link).
+    #[sqlx(default)]
+    #[serde(default)]
+    pub folder: Option<String>,
+    /// Where visitors go once the link is deactivated, used up, past its `active_until` or over
+    /// `max_clicks`, instead of an error page.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub fallback_url: Option<String>,
+    /// Visits (direct and through the preview page) after which the link stops redirecting.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub max_clicks: Option<i32>,
+    /// Last health check of the destination (see `LinkCheck`); all `None` until the first one.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub last_check_status: Option<i32>,
+    #[sqlx(default)]
+    #[serde(default)]
+    pub last_check_url: Option<String>,
+    #[sqlx(default)]
+    #[serde(default)]
+    pub last_check_latency_ms: Option<i64>,
+    #[sqlx(default)]
+    #[serde(default)]
+    pub last_check_error: Option<String>,
+    #[sqlx(default)]
+    #[serde(default)]
+    pub last_checked_at: Option<DateTime<Utc>>,
+    /// Consecutive failed health checks; reset by the first successful one.
+    #[sqlx(default)]
+    #[serde(default)]
+    pub failed_checks: i32,
+    /// Names of the tags attached to the link; loaded separately from `url_tags`.
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl URL {
+    /// Where the link stands at `now` relative to its activation window.
+    pub fn schedule_state(&self, now: DateTime<Utc>) -> ScheduleState {
+        if self.active_from.is_some_and(|from| now < from) {
+            ScheduleState::Upcoming
+        } else if self.active_until.is_some_and(|until| now >= until) {
+            ScheduleState::Ended
+        } else {
+            ScheduleState::Live
+        }
+    }
+
+    /// Visits allowed by `max_clicks` have all been used.
+    pub fn click_limit_reached(&self) -> bool {
+        self.max_clicks.is_some_and(|max_clicks| self.clicks + self.interstitial_clicks >= max_clicks)
+    }
+
+    /// The destination failed its last `threshold` health checks in a row (never with `threshold` 0).
+    pub fn is_broken(&self, threshold: i32) -> bool {
+        threshold > 0 && self.failed_checks >= threshold
+    }
+}
+
+// Estat d'un enllaç respecte de la seva finestra d'activació
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScheduleState {
+    Upcoming,
+    Live,
+    Ended,
+}
+
+// Visites a claus que no existeixen (enllaços impresos trencats, errors de tecleig)
+#[derive(Clone, Debug, PartialEq, FromRow, Serialize, Deserialize)]
+pub struct UnknownKeyHits {
+    pub key: String,
+    pub hits: i64,
+    pub first_seen_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
 }
 
 #[derive(Clone, Debug, FromRow, Serialize, Deserialize)]
@@ -17,6 +163,7 @@ pub struct GeneratedKey {
     pub key_value: String,
 }
 
+#[allow(dead_code)]
 #[derive(Clone, Debug, FromRow, Serialize, Deserialize)]
 pub struct UsedKey {
     pub id: i32,
@@ -24,16 +171,60 @@ pub struct UsedKey {
     pub user_id: i32,
 }
 
+// Regla de geo-encaminament: visitants de `country_code` van a `target_url`
+#[derive(Clone, Debug, FromRow, Serialize, Deserialize, PartialEq)]
+pub struct GeoRule {
+    pub url_key: String,
+    pub country_code: String,
+    pub target_url: String,
+}
+
+// Com ha arribat el visitant a la destinació
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ClickSource {
+    #[default]
+    Direct,
+    Interstitial,
+}
+
+// Registre d'un clic individual (per a informes)
+#[derive(Clone, Debug, FromRow, Serialize, Deserialize)]
+pub struct ClickRecord {
+    pub url_key: String,
+    pub country: Option<String>,
+    pub source: ClickSource,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn url_serde_roundtrip() {
-        let url = URL { key: "k".into(), secret_key: "s".into(), target_url: "http://t".into(), is_active: true, clicks: 5, user_id: 1 };
+        let url = URL { key: "k".into(), secret_key: "s".into(), target_url: "http://t".into(), is_active: true, clicks: 5, user_id: 1, ..Default::default() };
         let j = serde_json::to_string(&url).expect("serialize");
         let back: URL = serde_json::from_str(&j).expect("deserialize");
         assert_eq!(back.key, url.key);
         assert_eq!(back.clicks, 5);
     }
+
+    #[test]
+    fn schedule_state_honours_window_bounds() {
+        let from: DateTime<Utc> = "2030-01-01T09:00:00Z".parse().unwrap();
+        let until: DateTime<Utc> = "2030-01-02T09:00:00Z".parse().unwrap();
+        let url = URL { active_from: Some(from), active_until: Some(until), ..Default::default() };
+        assert_eq!(url.schedule_state(from - chrono::Duration::seconds(1)), ScheduleState::Upcoming);
+        assert_eq!(url.schedule_state(from), ScheduleState::Live);
+        assert_eq!(url.schedule_state(until), ScheduleState::Ended);
+        assert_eq!(URL::default().schedule_state(from), ScheduleState::Live);
+    }
+
+    #[test]
+    fn url_never_serializes_password_hash() {
+        let url = URL { key: "k".into(), password_hash: Some("$argon2id$hash".into()), ..Default::default() };
+        let j = serde_json::to_string(&url).expect("serialize");
+        assert!(!j.contains("password_hash"));
+    }
 }
@@ -0,0 +1,20 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+/// Label of a workspace that can be attached to any number of its links.
+#[derive(Clone, Debug, Default, PartialEq, FromRow, Serialize, Deserialize)]
+pub struct Tag {
+    pub id: i64,
+    pub org_id: i64,
+    pub name: String,
+}
+
+/// Totals of the links carrying a tag.
+#[derive(Clone, Debug, Default, PartialEq, FromRow, Serialize, Deserialize)]
+pub struct TagStats {
+    pub tag_id: i64,
+    pub name: String,
+    pub links: i64,
+    pub clicks: i64,
+    pub interstitial_clicks: i64,
+}
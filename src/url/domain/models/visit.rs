@@ -0,0 +1,50 @@
+use crate::url::domain::models::schema::URL;
+use std::net::IpAddr;
+
+/// What the redirect path knows about the visitor following a short link.
+///
+/// Built by the controller from the HTTP request so the domain service never touches actix types.
+#[derive(Clone, Debug, Default)]
+pub struct VisitContext {
+    pub client_ip: Option<IpAddr>,
+    /// The visitor presented a valid unlock cookie for this link.
+    pub password_verified: bool,
+    /// The visitor asked for the preview page (`GET /{key}+`).
+    pub preview_requested: bool,
+    /// Signature carried in the query string of a signed link, if any.
+    pub signature: Option<LinkSignature>,
+    /// The visitor is a chat app or social network fetching the link to render a preview card.
+    pub preview_crawler: bool,
+}
+
+/// `exp` (unix seconds) and `sig` (hex HMAC) query parameters of a signed link.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinkSignature {
+    pub expires_at: i64,
+    pub signature: String,
+}
+
+/// Result of resolving a short link for a visitor.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ForwardOutcome {
+    /// Send the visitor to this destination (the click has been counted).
+    Redirect(String),
+    /// The link is password protected and the visitor has not unlocked it yet.
+    PasswordRequired,
+    /// Show the preview page for `url`; nothing has been counted yet. `target_url` is withheld
+    /// for single-use links, whose destination is only revealed by following them.
+    Interstitial { url: URL, target_url: Option<String> },
+    /// One-time link already used, link over its click limit, or signed link expired / tampered with.
+    Gone,
+    /// No link has this key, or it was deactivated.
+    NotFound,
+    /// The link can no longer be used; send the visitor to its fallback destination (not counted).
+    Fallback(String),
+    /// The activation window of `url` has not started yet.
+    NotYetActive(URL),
+    /// The activation window of `url` is over.
+    Ended(URL),
+    /// Serve the social preview of `url` to a crawler; nothing has been counted or consumed.
+    /// `target_url` is withheld for single-use links.
+    SocialPreview { url: URL, target_url: Option<String> },
+}
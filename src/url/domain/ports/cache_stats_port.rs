@@ -0,0 +1,6 @@
+use crate::url::domain::models::cache::CacheStats;
+
+/// Reports the counters of a cache sitting in front of the link repository.
+pub trait CacheStatsPort: Send + Sync {
+    fn cache_stats(&self) -> CacheStats;
+}
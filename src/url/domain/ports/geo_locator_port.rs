@@ -0,0 +1,8 @@
+use std::net::IpAddr;
+
+/// Resolves a visitor address to an ISO 3166-1 alpha-2 country code (e.g. "ES", "US").
+///
+/// Implementations must answer from local data only; the redirect path calls this synchronously.
+pub trait GeoLocatorPort: Send + Sync {
+    fn country_code(&self, ip: IpAddr) -> Option<String>;
+}
@@ -0,0 +1,8 @@
+use async_trait::async_trait;
+
+/// Cache of link rows kept in front of the link repository.
+#[async_trait]
+pub trait LinkCachePort: Send + Sync {
+    /// Drop every cached row, after links were written without going through the cache.
+    async fn clear(&self);
+}
@@ -0,0 +1,8 @@
+use crate::url::domain::models::policy::PolicyViolation;
+
+/// Decides whether links may point to a (normalized) target URL.
+///
+/// Called synchronously on link creation and updates; implementations answer from memory.
+pub trait LinkPolicyPort: Send + Sync {
+    fn check(&self, target_url: &str) -> Result<(), PolicyViolation>;
+}
@@ -0,0 +1,12 @@
+use crate::url::domain::models::health::LinkCheck;
+use async_trait::async_trait;
+
+/// Checks whether a link's destination still answers.
+///
+/// Runs periodically in the background for every active link. Like `MetadataFetcherPort`,
+/// implementations reach out to arbitrary hosts and must apply the link policy to every URL and
+/// address they connect to. Failures are reported in the returned `LinkCheck`, never as errors.
+#[async_trait]
+pub trait LinkProbePort: Send + Sync {
+    async fn probe(&self, target_url: &str) -> LinkCheck;
+}
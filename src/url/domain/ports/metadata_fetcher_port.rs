@@ -0,0 +1,11 @@
+use crate::url::domain::models::metadata::{LinkMetadata, MetadataFetchError};
+use async_trait::async_trait;
+
+/// Looks up the title, description and icon of a link's destination.
+///
+/// Runs in the background after a link is created. Implementations reach out to arbitrary hosts,
+/// so they must apply the link policy to every URL and address they connect to.
+#[async_trait]
+pub trait MetadataFetcherPort: Send + Sync {
+    async fn fetch(&self, target_url: &str) -> Result<LinkMetadata, MetadataFetchError>;
+}
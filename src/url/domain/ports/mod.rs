@@ -0,0 +1,6 @@
+pub mod cache_stats_port;
+pub mod geo_locator_port;
+pub mod link_cache_port;
+pub mod link_policy_port;
+pub mod link_probe_port;
+pub mod metadata_fetcher_port;
@@ -1 +1,2 @@
 pub mod url_repository_port;
+pub mod tag_repository_port;
@@ -0,0 +1,24 @@
+use async_trait::async_trait;
+use crate::url::domain::models::tag::{Tag, TagStats};
+use sqlx::Error;
+
+/// Tags of a workspace and the links they are attached to.
+#[async_trait]
+pub trait TagRepositoryPort: Send + Sync {
+    /// Tags of the `org_id` workspace, by name.
+    async fn list_tags(&self, org_id: i64) -> Result<Vec<Tag>, Error>;
+    async fn get_tag(&self, tag_id: i64) -> Result<Tag, Error>;
+    /// Fails with a unique violation when the workspace already has a tag called `name`.
+    async fn create_tag(&self, org_id: i64, name: String) -> Result<Tag, Error>;
+    async fn rename_tag(&self, tag_id: i64, name: String) -> Result<Tag, Error>;
+    /// Delete the tag, detaching it from every link; `false` when it did not exist.
+    async fn delete_tag(&self, tag_id: i64) -> Result<bool, Error>;
+    /// Attach the tag to `url_key`; `false` when it already was.
+    async fn attach_tag(&self, url_key: String, tag_id: i64) -> Result<bool, Error>;
+    /// Detach the tag from `url_key`; `false` when it was not attached.
+    async fn detach_tag(&self, url_key: String, tag_id: i64) -> Result<bool, Error>;
+    /// `(url_key, tag name)` pairs of the given links, by tag name.
+    async fn get_url_tags(&self, url_keys: Vec<String>) -> Result<Vec<(String, String)>, Error>;
+    /// Links, clicks and preview clicks per tag of the `org_id` workspace.
+    async fn get_tag_stats(&self, org_id: i64) -> Result<Vec<TagStats>, Error>;
+}
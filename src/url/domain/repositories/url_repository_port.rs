@@ -1,13 +1,70 @@
 use async_trait::async_trait;
-use crate::url::domain::models::schema::URL;
+use crate::org::domain::models::organization::Role;
+use crate::url::domain::models::health::LinkCheck;
+use crate::url::domain::models::metadata::LinkMetadata;
+use crate::user::domain::models::plan::LinkUsage;
+use chrono::{DateTime, Utc};
+use crate::url::domain::models::filter::URLFilter;
+use crate::url::domain::models::schema::{ClickRecord, GeoRule, UnknownKeyHits, URL};
 use sqlx::Error;
 
 #[async_trait]
 pub trait URLRepositoryPort: Send + Sync {
     /// Create a new URL and return the domain `URL` model (mapping to DTOs happens in the application layer).
-    async fn create_url(&self, target_url: String, user_id: i32) -> Result<URL, Error>;
+    /// The link belongs to the `org_id` workspace, or to the user's personal one when `None`, and uses
+    /// `custom_key` as its key when given. With `reuse_existing` an existing reusable link of the user in
+    /// that workspace for the same target is returned instead.
+    async fn create_url(
+        &self, target_url: String, user_id: i32, org_id: Option<i64>, custom_key: Option<String>, reuse_existing: bool,
+    ) -> Result<URL, Error>;
+    /// Whether `key` is taken by a link or reserved in `used_keys`.
+    async fn key_exists(&self, key: String) -> Result<bool, Error>;
+    /// Active link by public key.
     async fn get_db_url_by_key(&self, url_key: String) -> Result<URL, Error>;
+    /// Link by public key, whatever its state.
+    async fn find_url_by_key(&self, url_key: String) -> Result<URL, Error>;
+    /// Link by its admin (secret) key, whatever its state.
+    async fn find_url_by_secret_key(&self, secret_key: String) -> Result<URL, Error>;
+    /// Atomically deactivate an active link; `false` when it was already inactive (or unknown).
+    async fn deactivate_url(&self, url_key: String) -> Result<bool, Error>;
+    /// Remove a link with its geo rules and click history; `false` when there was no such link.
+    async fn delete_url(&self, url_key: String) -> Result<bool, Error>;
     async fn get_db_url_by_user_and_target_url(&self, user_id: i32, target_url: String) -> Result<URL, Error>;
     async fn get_user_by_apy_key(&self, api_key: String) -> Result<i32, ()>;
-    async fn increment_clicks(&self, url_key: String) -> sqlx::Result<()>;
+    /// Count a direct visit unless the link used up its `max_clicks`, in which case it returns
+    /// `false`: the limit is checked in the same write, so concurrent visitors cannot exceed it.
+    /// `RowNotFound` when there is no such link.
+    async fn increment_clicks(&self, url_key: String) -> sqlx::Result<bool>;
+    /// Count a visit that reached the destination through the preview page, like `increment_clicks`.
+    async fn increment_interstitial_clicks(&self, url_key: String) -> sqlx::Result<bool>;
+    /// Add visits counted elsewhere (e.g. in a shared counter store) to the link's counters;
+    /// `RowNotFound` when there is no such link, like the increments.
+    async fn add_clicks(&self, url_key: String, clicks: i32, interstitial_clicks: i32) -> sqlx::Result<()>;
+    /// Persist the mutable fields of `url`, identified by its public key. A new `target_url`
+    /// clears the health check results, which described the previous destination.
+    async fn update_url(&self, url: URL) -> Result<URL, Error>;
+    /// Replace every country routing rule of `url_key` with `rules`.
+    async fn set_geo_rules(&self, url_key: String, rules: Vec<GeoRule>) -> sqlx::Result<()>;
+    async fn get_geo_rules(&self, url_key: String) -> Result<Vec<GeoRule>, Error>;
+    /// Store the fetched metadata of `url_key`: the title and description only fill in empty
+    /// fields, so values set by the owner in the meantime are kept.
+    async fn set_link_metadata(&self, url_key: String, metadata: LinkMetadata) -> Result<(), Error>;
+    /// Store the latest health check of `url_key`, counting it in `failed_checks` when it failed
+    /// and resetting that count otherwise.
+    async fn record_link_check(&self, url_key: String, check: LinkCheck) -> Result<(), Error>;
+    /// Count a visit to `key`, which matches no link. Once `max_keys` keys are tracked, a new one
+    /// evicts the least visited key (the least recently seen among equals).
+    async fn record_unknown_key(&self, key: String, max_keys: i64) -> Result<(), Error>;
+    /// Most visited unknown keys, at most `limit` of them.
+    async fn list_unknown_keys(&self, limit: i64) -> Result<Vec<UnknownKeyHits>, Error>;
+    /// Store an individual click (with the resolved country, if any) for reporting.
+    async fn record_click(&self, click: ClickRecord) -> sqlx::Result<()>;
+    /// Links matching `filter`, oldest first.
+    async fn list_urls(&self, filter: URLFilter) -> Result<Vec<URL>, Error>;
+    /// Role of `user_id` in the `org_id` workspace, `None` for non-members.
+    async fn get_member_role(&self, org_id: i64, user_id: i32) -> Result<Option<Role>, Error>;
+    /// Name of the plan assigned to `user_id`, if any.
+    async fn get_user_plan(&self, user_id: i32) -> Result<Option<String>, Error>;
+    /// Links created by `user_id` that count against its plan; `links_today` counts those created from `since`.
+    async fn get_link_usage(&self, user_id: i32, since: DateTime<Utc>) -> Result<LinkUsage, Error>;
 }
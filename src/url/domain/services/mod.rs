@@ -1 +1,3 @@
+pub mod tag_service;
+pub mod target_url_policy;
 pub mod url_service;
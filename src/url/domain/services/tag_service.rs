@@ -0,0 +1,141 @@
+use crate::url::domain::models::access::{Caller, LinkAccess};
+use crate::url::domain::models::tag::{Tag, TagStats};
+use crate::url::domain::repositories::tag_repository_port::TagRepositoryPort;
+use crate::url::domain::repositories::url_repository_port::URLRepositoryPort;
+
+use sqlx::Error;
+use std::sync::Arc;
+use thiserror::Error;
+
+const MAX_TAG_NAME_LEN: usize = 50;
+
+/// Why a tag could not be managed, attached or detached.
+#[derive(Debug, Error)]
+pub enum TagError {
+    #[error("Invalid API key")]
+    Unauthorized,
+    #[error("Tag names must be 1 to {MAX_TAG_NAME_LEN} characters")]
+    InvalidName,
+    #[error("Tag or link not found")]
+    NotFound,
+    #[error("Your role does not allow this")]
+    Forbidden,
+    #[error("A tag with this name already exists in the workspace")]
+    Duplicate,
+    #[error("The tag belongs to another workspace than the link")]
+    OtherWorkspace,
+    #[error("Database error: {0}")]
+    Database(Error),
+}
+
+impl From<Error> for TagError {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::RowNotFound => TagError::NotFound,
+            Error::Database(db_err) if db_err.is_unique_violation() => TagError::Duplicate,
+            other => TagError::Database(other),
+        }
+    }
+}
+
+/// Tags of a workspace: any member may read them, owners and editors manage them and attach
+/// them to the workspace's links.
+#[derive(Clone)]
+pub struct TagService {
+    url_repository: Arc<dyn URLRepositoryPort + Send + Sync>,
+    tag_repository: Arc<dyn TagRepositoryPort>,
+}
+
+impl TagService {
+    pub fn new(url_repository: Arc<dyn URLRepositoryPort + Send + Sync>, tag_repository: Arc<dyn TagRepositoryPort>) -> Self {
+        Self { url_repository, tag_repository }
+    }
+
+    /// Caller behind a user API key; admin keys are resolved by the controller.
+    pub async fn authenticate(&self, api_key: String) -> Result<Caller, TagError> {
+        self.url_repository.get_user_by_apy_key(api_key).await.map(Caller::User).map_err(|_| TagError::Unauthorized)
+    }
+
+    pub async fn list_tags(&self, caller: Caller, org_id: i64) -> Result<Vec<Tag>, TagError> {
+        self.check_access(caller, org_id, LinkAccess::Read).await?;
+        Ok(self.tag_repository.list_tags(org_id).await?)
+    }
+
+    pub async fn create_tag(&self, caller: Caller, org_id: i64, name: &str) -> Result<Tag, TagError> {
+        let name = validate_tag_name(name)?;
+        self.check_access(caller, org_id, LinkAccess::Write).await?;
+        Ok(self.tag_repository.create_tag(org_id, name).await?)
+    }
+
+    pub async fn rename_tag(&self, caller: Caller, org_id: i64, tag_id: i64, name: &str) -> Result<Tag, TagError> {
+        let name = validate_tag_name(name)?;
+        self.load_tag(caller, org_id, tag_id).await?;
+        Ok(self.tag_repository.rename_tag(tag_id, name).await?)
+    }
+
+    /// Delete the tag; the links that carried it are kept.
+    pub async fn delete_tag(&self, caller: Caller, org_id: i64, tag_id: i64) -> Result<(), TagError> {
+        self.load_tag(caller, org_id, tag_id).await?;
+        self.tag_repository.delete_tag(tag_id).await?;
+        Ok(())
+    }
+
+    /// Links and clicks per tag of the workspace.
+    pub async fn tag_stats(&self, caller: Caller, org_id: i64) -> Result<Vec<TagStats>, TagError> {
+        self.check_access(caller, org_id, LinkAccess::Read).await?;
+        Ok(self.tag_repository.get_tag_stats(org_id).await?)
+    }
+
+    pub async fn attach_tag(&self, caller: Caller, url_key: String, tag_id: i64) -> Result<(), TagError> {
+        let url_key = self.check_link_tag(caller, url_key, tag_id).await?;
+        self.tag_repository.attach_tag(url_key, tag_id).await?;
+        Ok(())
+    }
+
+    pub async fn detach_tag(&self, caller: Caller, url_key: String, tag_id: i64) -> Result<(), TagError> {
+        let url_key = self.check_link_tag(caller, url_key, tag_id).await?;
+        if !self.tag_repository.detach_tag(url_key, tag_id).await? {
+            return Err(TagError::NotFound);
+        }
+        Ok(())
+    }
+
+    /// The tag must exist in `org_id` and the caller may edit that workspace.
+    async fn load_tag(&self, caller: Caller, org_id: i64, tag_id: i64) -> Result<Tag, TagError> {
+        let tag = self.tag_repository.get_tag(tag_id).await?;
+        if tag.org_id != org_id {
+            return Err(TagError::NotFound);
+        }
+        self.check_access(caller, org_id, LinkAccess::Write).await?;
+        Ok(tag)
+    }
+
+    /// The caller may edit the link, and the tag lives in the link's workspace.
+    async fn check_link_tag(&self, caller: Caller, url_key: String, tag_id: i64) -> Result<String, TagError> {
+        let url = self.url_repository.find_url_by_key(url_key).await?;
+        let tag = self.tag_repository.get_tag(tag_id).await?;
+        self.check_access(caller, tag.org_id, LinkAccess::Write).await?;
+        if url.org_id != Some(tag.org_id) {
+            return Err(TagError::OtherWorkspace);
+        }
+        Ok(url.key)
+    }
+
+    async fn check_access(&self, caller: Caller, org_id: i64, access: LinkAccess) -> Result<(), TagError> {
+        let Caller::User(user_id) = caller else {
+            return Ok(());
+        };
+        match self.url_repository.get_member_role(org_id, user_id).await? {
+            Some(role) if access.granted_to(role) => Ok(()),
+            _ => Err(TagError::Forbidden),
+        }
+    }
+}
+
+fn validate_tag_name(name: &str) -> Result<String, TagError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_TAG_NAME_LEN || name.chars().any(char::is_control) {
+        return Err(TagError::InvalidName);
+    }
+    Ok(name.to_string())
+}
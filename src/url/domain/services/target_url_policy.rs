@@ -0,0 +1,81 @@
+use thiserror::Error;
+use url::Url;
+
+/// Why a target URL was refused.
+#[derive(Debug, Error, PartialEq)]
+pub enum TargetUrlError {
+    #[error("Invalid target URL '{0}': {1}")]
+    Invalid(String, url::ParseError),
+    #[error("Scheme '{0}' is not allowed")]
+    SchemeNotAllowed(String),
+}
+
+/// Validation and canonical form of the destinations links point to.
+///
+/// Parsing already lowercases the host, converts internationalised names to punycode, drops the
+/// port when it is the scheme default and gives empty paths a `/`, so `HTTPS://Bücher.example:443`
+/// and `https://xn--bcher-kva.example/` end up identical. Non-root trailing slashes are kept:
+/// servers are free to treat `/a` and `/a/` differently.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TargetUrlPolicy {
+    /// Lowercase schemes a target may use.
+    pub allowed_schemes: Vec<String>,
+    /// Reorder query parameters by name so `?b=1&a=2` and `?a=2&b=1` deduplicate.
+    pub sort_query_params: bool,
+}
+
+impl Default for TargetUrlPolicy {
+    fn default() -> Self {
+        Self { allowed_schemes: vec!["http".into(), "https".into()], sort_query_params: false }
+    }
+}
+
+impl TargetUrlPolicy {
+    pub fn normalize(&self, raw: &str) -> Result<String, TargetUrlError> {
+        let raw = raw.trim();
+        let mut url = Url::parse(raw).map_err(|err| TargetUrlError::Invalid(raw.to_string(), err))?;
+        if !self.allowed_schemes.iter().any(|scheme| scheme.eq_ignore_ascii_case(url.scheme())) {
+            return Err(TargetUrlError::SchemeNotAllowed(url.scheme().to_string()));
+        }
+        if self.sort_query_params {
+            let mut pairs: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+            // stable: repeated parameters keep their relative order
+            pairs.sort_by(|a, b| a.0.cmp(&b.0));
+            if pairs.is_empty() {
+                url.set_query(None);
+            } else {
+                url.query_pairs_mut().clear().extend_pairs(pairs);
+            }
+        }
+        Ok(url.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_host_port_and_root_path() {
+        let policy = TargetUrlPolicy::default();
+        assert_eq!(policy.normalize(" HTTPS://Example.COM:443").unwrap(), "https://example.com/");
+        assert_eq!(policy.normalize("http://Bücher.example:8080/Path/").unwrap(), "http://xn--bcher-kva.example:8080/Path/");
+        assert_eq!(policy.normalize("http://example.com/?b=1&a=2").unwrap(), "http://example.com/?b=1&a=2");
+    }
+
+    #[test]
+    fn rejects_missing_and_disallowed_schemes() {
+        let policy = TargetUrlPolicy::default();
+        assert!(matches!(policy.normalize("example.com/page"), Err(TargetUrlError::Invalid(..))));
+        assert_eq!(policy.normalize("javascript:alert(1)"), Err(TargetUrlError::SchemeNotAllowed("javascript".into())));
+        let ftp = TargetUrlPolicy { allowed_schemes: vec!["ftp".into()], ..Default::default() };
+        assert_eq!(ftp.normalize("ftp://Files.example").unwrap(), "ftp://files.example/");
+    }
+
+    #[test]
+    fn sorts_query_params_when_enabled() {
+        let policy = TargetUrlPolicy { sort_query_params: true, ..Default::default() };
+        assert_eq!(policy.normalize("https://example.com/?b=1&a=2&b=0").unwrap(), "https://example.com/?a=2&b=1&b=0");
+        assert_eq!(policy.normalize("https://example.com/?").unwrap(), "https://example.com/");
+    }
+}
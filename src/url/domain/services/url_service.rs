@@ -1,19 +1,215 @@
-use crate::url::application::dtos::url_dto::{CustomError, URLBaseDto};
-use crate::url::domain::models::schema::URL;
+use crate::config::env::{DEFAULT_SIGNED_LINK_MAX_TTL_SECS, DEFAULT_UNLOCK_MAX_ATTEMPTS, DEFAULT_UNLOCK_WINDOW_SECS};
+use crate::shared::attempt_limiter::AttemptLimiter;
+use crate::shared::password::{hash_password, verify_password};
+use crate::shared::signing;
+use crate::url::application::dtos::url_dto::{CustomError, OrgURLsQueryDto, URLBaseDto, URLPatchDto};
+use crate::url::domain::models::access::{Caller, LinkAccess};
+use crate::url::domain::models::cache::CacheStats;
+use crate::url::domain::models::filter::URLFilter;
+use crate::url::domain::models::health::DeadLinkPolicy;
+use crate::url::domain::models::metadata::LinkMetadata;
+use crate::url::domain::models::policy::{PolicyRescanReport, PolicyRules, PolicyViolation};
+use crate::url::domain::models::schema::{ClickRecord, ClickSource, GeoRule, ScheduleState, UnknownKeyHits, URL};
+use crate::url::domain::models::visit::{ForwardOutcome, LinkSignature, VisitContext};
+use crate::url::domain::ports::cache_stats_port::CacheStatsPort;
+use crate::url::domain::ports::geo_locator_port::GeoLocatorPort;
+use crate::url::domain::ports::link_policy_port::LinkPolicyPort;
+use crate::url::domain::ports::link_probe_port::LinkProbePort;
+use crate::url::domain::ports::metadata_fetcher_port::MetadataFetcherPort;
+use crate::url::domain::repositories::tag_repository_port::TagRepositoryPort;
 use crate::url::domain::repositories::url_repository_port::URLRepositoryPort;
+use crate::url::domain::services::target_url_policy::{TargetUrlError, TargetUrlPolicy};
+use crate::user::domain::models::plan::{PlanCatalog, PlanUsage};
 
-use log::debug;
+use chrono::{DateTime, Days, Utc};
+use log::{debug, info};
 use sqlx::Error;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Why a password-protected link could not be unlocked.
+#[derive(Debug, Error)]
+pub enum UnlockError {
+    #[error("Incorrect password")]
+    WrongPassword,
+    #[error("Too many failed attempts, retry in {} seconds", .0.as_secs())]
+    TooManyAttempts(Duration),
+    #[error("URL not found")]
+    NotFound,
+    #[error("Database error: {0}")]
+    Database(Error),
+}
+
+/// Result of the checks every visit goes through before the link is resolved.
+enum Admission {
+    Allowed(URL),
+    Denied(ForwardOutcome),
+}
+
+/// Why a link could not be read, updated or deleted through the management API.
+#[derive(Debug, Error)]
+pub enum ManageURLError {
+    #[error(transparent)]
+    InvalidTarget(#[from] TargetUrlError),
+    #[error(transparent)]
+    PolicyViolation(#[from] PolicyViolation),
+    #[error("URL not found")]
+    NotFound,
+    #[error("This link belongs to another user")]
+    Forbidden,
+    #[error("Folder names must be at most {MAX_FOLDER_LEN} characters")]
+    InvalidFolder,
+    #[error("{field} must be at most {max} characters")]
+    TooLong { field: &'static str, max: usize },
+    #[error("og_image_url must be an absolute http(s) URL of at most {MAX_IMAGE_URL_LEN} characters")]
+    InvalidImageUrl,
+    #[error("max_clicks must be a positive number")]
+    InvalidClickLimit,
+    #[error("Database error: {0}")]
+    Database(Error),
+}
+
+impl From<Error> for ManageURLError {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::RowNotFound => ManageURLError::NotFound,
+            other => ManageURLError::Database(other),
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct URLService {
     url_repository: Arc<dyn URLRepositoryPort + Send + Sync>,
+    tag_repository: Option<Arc<dyn TagRepositoryPort>>,
+    geo_locator: Option<Arc<dyn GeoLocatorPort>>,
+    unlock_attempts: Arc<AttemptLimiter>,
+    signing_secret: Option<Arc<str>>,
+    signed_link_max_ttl_secs: i64,
+    target_url_policy: TargetUrlPolicy,
+    link_policy: Arc<dyn LinkPolicyPort>,
+    plans: Arc<PlanCatalog>,
+    metadata_fetcher: Option<Arc<dyn MetadataFetcherPort>>,
+    link_probe: Option<Arc<dyn LinkProbePort>>,
+    dead_links: DeadLinkPolicy,
+    link_cache: Option<Arc<dyn CacheStatsPort>>,
 }
 
+/// Keys that would clash with the routes of the service.
+const RESERVED_ALIASES: &[&str] = &["admin", "orgs", "url", "usage", "users"];
+
+const MAX_FOLDER_LEN: usize = 100;
+const MAX_DESCRIPTION_LEN: usize = 500;
+const MAX_NOTES_LEN: usize = 5000;
+const MAX_TITLE_LEN: usize = 200;
+const MAX_FAVICON_URL_LEN: usize = 2048;
+const MAX_OG_TITLE_LEN: usize = 200;
+const MAX_IMAGE_URL_LEN: usize = 2048;
+/// Longer keys hitting the 404 page are not counted (they are not mistyped short links).
+const MAX_RECORDED_KEY_LEN: usize = 64;
+/// Unknown keys counted at once; rarely visited ones make room for new ones.
+const MAX_TRACKED_UNKNOWN_KEYS: i64 = 10_000;
+/// Unknown keys reported by `list_unknown_keys`.
+const MAX_LISTED_UNKNOWN_KEYS: i64 = 100;
+/// How often the background link checker looks for links due for a new check.
+const LINK_CHECK_TICK: Duration = Duration::from_secs(60);
+/// Links probed by one `check_link_health` run; the others stay due for the next one.
+const MAX_LINK_CHECKS_PER_RUN: i64 = 100;
+
 impl URLService {
     pub fn new(url_repository: Arc<dyn URLRepositoryPort + Send + Sync>) -> Self {
-        Self { url_repository }
+        Self {
+            url_repository,
+            tag_repository: None,
+            geo_locator: None,
+            unlock_attempts: Arc::new(AttemptLimiter::new(
+                DEFAULT_UNLOCK_MAX_ATTEMPTS,
+                Duration::from_secs(DEFAULT_UNLOCK_WINDOW_SECS),
+            )),
+            signing_secret: None,
+            signed_link_max_ttl_secs: DEFAULT_SIGNED_LINK_MAX_TTL_SECS,
+            target_url_policy: TargetUrlPolicy::default(),
+            link_policy: Arc::new(PolicyRules::builtin()),
+            plans: Arc::new(PlanCatalog::default()),
+            metadata_fetcher: None,
+            link_probe: None,
+            dead_links: DeadLinkPolicy::default(),
+            link_cache: None,
+        }
+    }
+
+    /// Return links with the names of their tags; without it links come without tags.
+    pub fn with_tag_repository(mut self, tag_repository: Arc<dyn TagRepositoryPort>) -> Self {
+        self.tag_repository = Some(tag_repository);
+        self
+    }
+
+    /// Enforce the limits of `plans` on link creation; without it nobody is limited.
+    pub fn with_plans(mut self, plans: Arc<PlanCatalog>) -> Self {
+        self.plans = plans;
+        self
+    }
+
+    /// Replace the built-in block rules (internal hosts) with `link_policy`.
+    pub fn with_link_policy(mut self, link_policy: Arc<dyn LinkPolicyPort>) -> Self {
+        self.link_policy = link_policy;
+        self
+    }
+
+    /// Override the accepted schemes and canonical form of target URLs.
+    pub fn with_target_url_policy(mut self, policy: TargetUrlPolicy) -> Self {
+        self.target_url_policy = policy;
+        self
+    }
+
+    /// Enable signed links (`?exp=..&sig=..`); `max_ttl_secs` caps how far ahead they may expire.
+    pub fn with_signing_secret(mut self, secret: &str, max_ttl_secs: i64) -> Self {
+        self.signing_secret = Some(Arc::from(secret));
+        self.signed_link_max_ttl_secs = max_ttl_secs;
+        self
+    }
+
+    /// Override how many wrong passwords a link tolerates per `window` before answering 429.
+    pub fn with_unlock_limits(mut self, max_failures: u32, window: Duration) -> Self {
+        self.unlock_attempts = Arc::new(AttemptLimiter::new(max_failures, window));
+        self
+    }
+
+    /// Enable country based routing; without a locator every visitor gets the default target.
+    pub fn with_geo_locator(mut self, geo_locator: Arc<dyn GeoLocatorPort>) -> Self {
+        self.geo_locator = Some(geo_locator);
+        self
+    }
+
+    /// Fetch the title, description and icon of new links created without a title.
+    pub fn with_metadata_fetcher(mut self, metadata_fetcher: Arc<dyn MetadataFetcherPort>) -> Self {
+        self.metadata_fetcher = Some(metadata_fetcher);
+        self
+    }
+
+    /// Probe link destinations with `link_probe` when `check_link_health` runs.
+    pub fn with_link_probe(mut self, link_probe: Arc<dyn LinkProbePort>) -> Self {
+        self.link_probe = Some(link_probe);
+        self
+    }
+
+    /// Flag links as broken after repeated failed checks, optionally redirecting them elsewhere.
+    pub fn with_dead_link_policy(mut self, dead_links: DeadLinkPolicy) -> Self {
+        self.dead_links = dead_links;
+        self
+    }
+
+    /// Report the counters of the cache in front of the repository through `cache_stats`.
+    pub fn with_link_cache(mut self, link_cache: Arc<dyn CacheStatsPort>) -> Self {
+        self.link_cache = Some(link_cache);
+        self
+    }
+
+    /// Hits and misses of the link cache, `None` when links are not cached.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.link_cache.as_ref().map(|cache| cache.cache_stats())
     }
 
     /// Create a URL and return the domain `URL` model. Mapping to DTO is done in application layer.
@@ -25,60 +221,850 @@ impl URLService {
             .await
             .map_err(|_| CustomError::new(400, "No valid API_KEY"))?;
         debug!("User id: {}", user_id);
-        let result = self
+        if url_base.require_signature && self.signing_secret.is_none() {
+            return Err(CustomError::new(400, "Link signing is not configured"));
+        }
+        let target_url = self.normalize_target(&url_base.target_url)?;
+        let geo_rules = validate_geo_rules(&url_base.geo_rules)?
+            .into_iter()
+            .map(|(country_code, target_url)| Ok((country_code, self.normalize_target(&target_url)?)))
+            .collect::<Result<Vec<_>, CustomError>>()?;
+        if let (Some(from), Some(until)) = (url_base.active_from, url_base.active_until) {
+            if until <= from {
+                return Err(CustomError::new(400, "active_until must be later than active_from"));
+            }
+        }
+        let alias = url_base.alias.as_deref().map(validate_alias).transpose()?;
+        let folder = normalize_folder(url_base.folder.as_deref().unwrap_or_default())
+            .map_err(|err| CustomError::new(400, &err.to_string()))?;
+        let title = normalize_text(url_base.title.as_deref().unwrap_or_default(), "title", MAX_TITLE_LEN)
+            .map_err(|err| CustomError::new(400, &err.to_string()))?;
+        let description = normalize_text(url_base.description.as_deref().unwrap_or_default(), "description", MAX_DESCRIPTION_LEN)
+            .map_err(|err| CustomError::new(400, &err.to_string()))?;
+        let notes = normalize_text(url_base.notes.as_deref().unwrap_or_default(), "notes", MAX_NOTES_LEN)
+            .map_err(|err| CustomError::new(400, &err.to_string()))?;
+        let og_title = normalize_text(url_base.og_title.as_deref().unwrap_or_default(), "og_title", MAX_OG_TITLE_LEN)
+            .map_err(|err| CustomError::new(400, &err.to_string()))?;
+        let og_description =
+            normalize_text(url_base.og_description.as_deref().unwrap_or_default(), "og_description", MAX_DESCRIPTION_LEN)
+                .map_err(|err| CustomError::new(400, &err.to_string()))?;
+        let og_image_url = normalize_image_url(url_base.og_image_url.as_deref().unwrap_or_default())
+            .map_err(|err| CustomError::new(400, &err.to_string()))?;
+        let fallback_url = url_base.fallback_url.as_deref().map(|fallback_url| self.normalize_target(fallback_url)).transpose()?;
+        if url_base.max_clicks.is_some_and(|max_clicks| max_clicks <= 0) {
+            return Err(CustomError::new(400, &ManageURLError::InvalidClickLimit.to_string()));
+        }
+        if let Some(org_id) = url_base.org_id {
+            let role = self.url_repository.get_member_role(org_id, user_id).await.map_err(|err| {
+                eprintln!("Error occurred[get_member_role_srvc]: {}", err);
+                CustomError::new(500, "Error checking organization membership")
+            })?;
+            if !role.is_some_and(|role| LinkAccess::Write.granted_to(role)) {
+                return Err(CustomError::new(403, "You cannot create links in this organization"));
+            }
+        }
+        self.check_plan_limits(user_id, alias.is_some()).await?;
+        if let Some(alias) = &alias {
+            let taken = self.url_repository.key_exists(alias.clone()).await.map_err(|err| {
+                eprintln!("Error occurred[key_exists_srvc]: {}", err);
+                CustomError::new(500, "Error checking alias")
+            })?;
+            if taken {
+                return Err(CustomError::new(409, &format!("Alias '{}' is already taken", alias)));
+            }
+        }
+        let password_hash = match url_base.password.as_deref().filter(|p| !p.is_empty()) {
+            Some(password) => Some(hash_password(password).map_err(|err| {
+                eprintln!("Error occurred[hash_password_srvc]: {}", err);
+                CustomError::new(500, "Error hashing password")
+            })?),
+            None => None,
+        };
+        // one-time, click-limited, signature-only and scheduled links always get a key of their own
+        let scheduled = url_base.active_from.is_some() || url_base.active_until.is_some();
+        let reuse_existing = !url_base.single_use
+            && url_base.max_clicks.is_none()
+            && !url_base.require_signature
+            && !scheduled
+            && alias.is_none();
+        let requested_at = Utc::now();
+        let mut url = self
             .url_repository
-            .create_url(url_base.target_url, user_id)
+            .create_url(target_url, user_id, url_base.org_id, alias.clone(), reuse_existing)
+            .await
+            .map_err(|err| match (err, &alias) {
+                // taken by a concurrent request since the check above
+                (sqlx::Error::Database(db_err), Some(alias)) if db_err.is_unique_violation() => {
+                    CustomError::new(409, &format!("Alias '{}' is already taken", alias))
+                }
+                (err, _) => {
+                    eprintln!("Error occurred[create_url_srvc]: {}", err);
+                    CustomError::new(500, "Error creating URL")
+                }
+            })?;
+        if !geo_rules.is_empty() {
+            let rules = geo_rules
+                .into_iter()
+                .map(|(country_code, target_url)| GeoRule { url_key: url.key.clone(), country_code, target_url })
+                .collect();
+            self.url_repository.set_geo_rules(url.key.clone(), rules).await.map_err(|err| {
+                eprintln!("Error occurred[set_geo_rules_srvc]: {}", err);
+                CustomError::new(500, "Error storing geo rules")
+            })?;
+        }
+        if password_hash.is_some()
+            || title.is_some()
+            || url_base.show_interstitial
+            || url_base.single_use
+            || url_base.require_signature
+            || scheduled
+            || folder.is_some()
+            || description.is_some()
+            || notes.is_some()
+            || og_title.is_some()
+            || og_description.is_some()
+            || og_image_url.is_some()
+            || fallback_url.is_some()
+            || url_base.max_clicks.is_some()
+        {
+            url.password_hash = password_hash.or(url.password_hash);
+            url.title = title.or(url.title);
+            url.show_interstitial |= url_base.show_interstitial;
+            url.single_use |= url_base.single_use;
+            url.require_signature |= url_base.require_signature;
+            url.active_from = url_base.active_from.or(url.active_from);
+            url.active_until = url_base.active_until.or(url.active_until);
+            url.folder = folder.or(url.folder);
+            url.description = description.or(url.description);
+            url.notes = notes.or(url.notes);
+            url.og_title = og_title.or(url.og_title);
+            url.og_description = og_description.or(url.og_description);
+            url.og_image_url = og_image_url.or(url.og_image_url);
+            url.fallback_url = fallback_url.or(url.fallback_url);
+            url.max_clicks = url_base.max_clicks.or(url.max_clicks);
+            url = self.url_repository.update_url(url).await.map_err(|err| {
+                eprintln!("Error occurred[update_url_srvc]: {}", err);
+                CustomError::new(500, "Error storing URL options")
+            })?;
+        }
+        // a reused link already had its metadata fetched when it was created
+        let created = url.created_at.is_some_and(|created_at| created_at >= requested_at);
+        if created && url.title.is_none() && url.favicon_url.is_none() {
+            self.spawn_metadata_fetch(&url);
+        }
+        Ok(url)
+    }
+
+    /// Fill in the metadata of `url` from its destination in the background; failures are only logged.
+    fn spawn_metadata_fetch(&self, url: &URL) {
+        let Some(fetcher) = self.metadata_fetcher.clone() else {
+            return;
+        };
+        let url_repository = Arc::clone(&self.url_repository);
+        let (url_key, target_url) = (url.key.clone(), url.target_url.clone());
+        tokio::spawn(async move {
+            let metadata = match fetcher.fetch(&target_url).await {
+                Ok(metadata) => metadata,
+                Err(err) => {
+                    info!("Metadata of {url_key} not fetched from {target_url}: {err}");
+                    return;
+                }
+            };
+            let metadata = LinkMetadata {
+                title: metadata.title.map(|title| truncate_chars(title, MAX_TITLE_LEN)),
+                description: metadata.description.map(|description| truncate_chars(description, MAX_DESCRIPTION_LEN)),
+                favicon_url: metadata.favicon_url.filter(|favicon_url| favicon_url.len() <= MAX_FAVICON_URL_LEN),
+            };
+            if let Err(err) = url_repository.set_link_metadata(url_key, metadata).await {
+                eprintln!("Error occurred[set_link_metadata_srvc]: {}", err);
+            }
+        });
+    }
+
+    /// Check every active link again once `every` has passed since its last check, from a
+    /// background task that lives as long as the runtime.
+    pub fn spawn_link_health_checks(&self, every: Duration) {
+        let service = self.clone();
+        let every_chrono = chrono::Duration::from_std(every).unwrap_or(chrono::Duration::MAX);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(every.min(LINK_CHECK_TICK));
+            loop {
+                ticker.tick().await;
+                let checked_before = Utc::now().checked_sub_signed(every_chrono).unwrap_or(DateTime::<Utc>::MIN_UTC);
+                if let Err(err) = service.check_link_health(checked_before).await {
+                    eprintln!("Error occurred[link_health_srvc]: {}", err);
+                }
+            }
+        });
+    }
+
+    /// Probe the destination of the active links not checked since `checked_before`, at most
+    /// `MAX_LINK_CHECKS_PER_RUN` of them, and record the outcome; returns how many links were
+    /// checked (none without a probe).
+    pub async fn check_link_health(&self, checked_before: DateTime<Utc>) -> Result<usize, Error> {
+        let Some(probe) = self.link_probe.as_ref() else {
+            return Ok(0);
+        };
+        let filter = URLFilter {
+            active: true,
+            checked_before: Some(checked_before),
+            limit: Some(MAX_LINK_CHECKS_PER_RUN),
+            ..Default::default()
+        };
+        let due = self.url_repository.list_urls(filter).await?;
+        for url in &due {
+            let check = probe.probe(&url.target_url).await;
+            if check.is_failure() && url.failed_checks + 1 == self.dead_links.failure_threshold {
+                info!(
+                    "Link {} is broken: {} ({})",
+                    url.key,
+                    url.target_url,
+                    check.error.clone().or(check.status_code.map(|status| status.to_string())).unwrap_or_default()
+                );
+            }
+            self.url_repository.record_link_check(url.key.clone(), check).await?;
+        }
+        Ok(due.len())
+    }
+
+    /// Keys visited most often without matching any link, e.g. misprinted or mistyped links.
+    pub async fn list_unknown_keys(&self) -> Result<Vec<UnknownKeyHits>, Error> {
+        self.url_repository.list_unknown_keys(MAX_LISTED_UNKNOWN_KEYS).await
+    }
+
+    /// Active links flagged broken by the health checks, with their tags.
+    pub async fn list_broken_urls(&self) -> Result<Vec<URL>, Error> {
+        if self.dead_links.failure_threshold <= 0 {
+            return Ok(Vec::new());
+        }
+        let filter = URLFilter { active: true, min_failed_checks: Some(self.dead_links.failure_threshold), ..Default::default() };
+        let mut urls = self.url_repository.list_urls(filter).await?;
+        self.load_tags(&mut urls).await?;
+        Ok(urls)
+    }
+
+    /// Reject the creation when it would exceed the plan of `user_id`: aliases and active links are
+    /// capped (403), and so are links per UTC day (429, until midnight).
+    async fn check_plan_limits(&self, user_id: i32, custom_alias: bool) -> Result<(), CustomError> {
+        let usage = self.get_usage(user_id).await?;
+        let plan = &usage.plan;
+        if custom_alias {
+            match plan.max_custom_aliases {
+                Some(0) => return Err(CustomError::new(403, &format!("Custom aliases are not available on the '{}' plan", plan.name))),
+                Some(max) if usage.usage.custom_aliases >= max => {
+                    return Err(CustomError::new(403, &format!("Custom alias limit of the '{}' plan reached ({})", plan.name, max)));
+                }
+                _ => {}
+            }
+        }
+        if let Some(max) = plan.max_active_links.filter(|max| usage.usage.active_links >= *max) {
+            return Err(CustomError::new(403, &format!("Active link limit of the '{}' plan reached ({})", plan.name, max)));
+        }
+        if let Some(max) = plan.max_links_per_day.filter(|max| usage.usage.links_today >= *max) {
+            return Err(CustomError::new(
+                429,
+                &format!("Daily link limit of the '{}' plan reached ({}), resets at {}", plan.name, max, usage.daily_reset_at.to_rfc3339()),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Plan of `user_id` and how much of it is consumed.
+    pub async fn get_usage(&self, user_id: i32) -> Result<PlanUsage, CustomError> {
+        let plan_name = self.url_repository.get_user_plan(user_id).await.map_err(|err| {
+            eprintln!("Error occurred[get_user_plan_srvc]: {}", err);
+            CustomError::new(500, "Error loading plan")
+        })?;
+        let (day_start, daily_reset_at) = utc_day_bounds(Utc::now());
+        let usage = self.url_repository.get_link_usage(user_id, day_start).await.map_err(|err| {
+            eprintln!("Error occurred[get_link_usage_srvc]: {}", err);
+            CustomError::new(500, "Error loading usage")
+        })?;
+        Ok(PlanUsage { plan: self.plans.plan_for(plan_name.as_deref()).clone(), usage, daily_reset_at })
+    }
+
+    /// Links of the API key owner that have an activation window, soonest launch first;
+    /// `state` narrows the list to upcoming, live or ended ones.
+    pub async fn list_scheduled_urls(&self, api_key: String, state: Option<ScheduleState>) -> Result<Vec<URL>, CustomError> {
+        let user_id = self
+            .url_repository
+            .get_user_by_apy_key(api_key)
+            .await
+            .map_err(|_| CustomError::new(400, "No valid API_KEY"))?;
+        let mut urls = self
+            .url_repository
+            .list_urls(URLFilter { user_id: Some(user_id), scheduled: true, ..Default::default() })
             .await
             .map_err(|err| {
-                eprintln!("Error occurred[create_url_srvc]: {}", err);
-                CustomError::new(500, "Error creating URL")
-            });
-        result
+                eprintln!("Error occurred[list_scheduled_urls_srvc]: {}", err);
+                CustomError::new(500, "Error listing URLs")
+            })?;
+        let now = Utc::now();
+        urls.retain(|url| state.is_none_or(|state| url.schedule_state(now) == state));
+        urls.sort_by_key(|url| (url.active_from.is_none(), url.active_from, url.active_until));
+        self.load_tags(&mut urls).await.map_err(|err| {
+            eprintln!("Error occurred[list_scheduled_urls_srvc]: {}", err);
+            CustomError::new(500, "Error listing URLs")
+        })?;
+        Ok(urls)
     }
 
-    pub async fn forward_to_target_url(&self, url_key: String) -> Result<String, Error> {
-        let url = self.url_repository.get_db_url_by_key(url_key.clone()).await?;
-        let target_url = url.target_url.clone();
-        debug!("Forwarding to target URL: {}", target_url.clone());
-        let _ = self.url_repository.increment_clicks(url_key.clone()).await?;
-        Ok(target_url)
+    /// Mint a signed variant of `url_key` valid for `expires_in_secs`. Only the owner of the link may
+    /// sign it; the signature is not stored, so any number of variants can be handed out.
+    pub async fn sign_url(&self, url_key: String, api_key: String, expires_in_secs: i64) -> Result<LinkSignature, CustomError> {
+        let Some(secret) = self.signing_secret.as_deref() else {
+            return Err(CustomError::new(503, "Link signing is not configured"));
+        };
+        if expires_in_secs <= 0 || expires_in_secs > self.signed_link_max_ttl_secs {
+            return Err(CustomError::new(
+                400,
+                &format!("expires_in_secs must be between 1 and {}", self.signed_link_max_ttl_secs),
+            ));
+        }
+        let user_id = self
+            .url_repository
+            .get_user_by_apy_key(api_key)
+            .await
+            .map_err(|_| CustomError::new(400, "No valid API_KEY"))?;
+        let url = self.url_repository.get_db_url_by_key(url_key).await.map_err(|err| match err {
+            Error::RowNotFound => CustomError::new(404, "URL not found"),
+            other => {
+                eprintln!("Error occurred[sign_url_srvc]: {}", other);
+                CustomError::new(500, "Error loading URL")
+            }
+        })?;
+        self.authorize(Caller::User(user_id), &url, LinkAccess::Write).await.map_err(|err| match err {
+            ManageURLError::Forbidden => CustomError::new(403, "Only editors of the link can sign it"),
+            other => {
+                eprintln!("Error occurred[sign_url_srvc]: {}", other);
+                CustomError::new(500, "Error checking link access")
+            }
+        })?;
+        let expires_at = Utc::now().timestamp() + expires_in_secs;
+        Ok(LinkSignature { expires_at, signature: signing::sign(secret, &signed_link_payload(&url.key, expires_at)) })
+    }
+
+    /// Resolve a short link for a visitor: redirect (counting a direct click), or ask for the
+    /// password / show the preview page first.
+    pub async fn forward_to_target_url(&self, url_key: String, visit: VisitContext) -> Result<ForwardOutcome, Error> {
+        let url = match self.admit(&url_key, &visit).await? {
+            Admission::Allowed(url) => url,
+            Admission::Denied(outcome) => return Ok(outcome),
+        };
+        let country = self.resolve_country(&visit);
+        let target_url = self.resolve_target(&url, country.as_deref()).await?;
+        if visit.preview_crawler {
+            // crawlers must neither count as visits nor use up one-time links
+            let target_url = Some(target_url).filter(|_| !url.single_use);
+            return Ok(ForwardOutcome::SocialPreview { url, target_url });
+        }
+        if visit.preview_requested || url.show_interstitial {
+            // showing the destination of a one-time link would let it be read without being used
+            let target_url = Some(target_url).filter(|_| !url.single_use);
+            return Ok(ForwardOutcome::Interstitial { url, target_url });
+        }
+        if !self.consume(&url).await? {
+            return Ok(ForwardOutcome::Gone);
+        }
+        debug!("Forwarding to target URL: {} (country: {:?})", target_url, country);
+        if !self.url_repository.increment_clicks(url_key.clone()).await? {
+            return Ok(over_click_limit(&url));
+        }
+        self.url_repository.record_click(ClickRecord { url_key, country, source: ClickSource::Direct }).await?;
+        Ok(ForwardOutcome::Redirect(target_url))
+    }
+
+    /// Follow the "continue" button of the preview page; counted apart from direct redirects.
+    pub async fn continue_from_interstitial(&self, url_key: String, visit: VisitContext) -> Result<ForwardOutcome, Error> {
+        let url = match self.admit(&url_key, &visit).await? {
+            Admission::Allowed(url) => url,
+            Admission::Denied(outcome) => return Ok(outcome),
+        };
+        let country = self.resolve_country(&visit);
+        let target_url = self.resolve_target(&url, country.as_deref()).await?;
+        if !self.consume(&url).await? {
+            return Ok(ForwardOutcome::Gone);
+        }
+        if !self.url_repository.increment_interstitial_clicks(url_key.clone()).await? {
+            return Ok(over_click_limit(&url));
+        }
+        self.url_repository.record_click(ClickRecord { url_key, country, source: ClickSource::Interstitial }).await?;
+        Ok(ForwardOutcome::Redirect(target_url))
+    }
+
+    /// Check `password` for a protected link and, when correct, continue like a normal visit.
+    pub async fn unlock(
+        &self, url_key: String, password: &str, visit: VisitContext,
+    ) -> Result<ForwardOutcome, UnlockError> {
+        if let Some(retry_after) = self.unlock_attempts.blocked_for(&url_key) {
+            return Err(UnlockError::TooManyAttempts(retry_after));
+        }
+        let url = self.url_repository.get_db_url_by_key(url_key.clone()).await.map_err(|err| match err {
+            Error::RowNotFound => UnlockError::NotFound,
+            other => UnlockError::Database(other),
+        })?;
+        if let Some(hash) = url.password_hash.as_deref() {
+            if !verify_password(password, hash) {
+                self.unlock_attempts.register_failure(&url_key);
+                return Err(UnlockError::WrongPassword);
+            }
+            self.unlock_attempts.reset(&url_key);
+        }
+        let visit = VisitContext { password_verified: true, ..visit };
+        self.forward_to_target_url(url_key, visit).await.map_err(UnlockError::Database)
     }
 
     pub async fn get_url_info(&self, url_key: String) -> Result<URL, Error> {
-        self.url_repository.get_db_url_by_key(url_key).await
+        let url = self.url_repository.get_db_url_by_key(url_key).await?;
+        self.with_tags(url).await
+    }
+
+    /// Link behind an admin URL, active or not.
+    pub async fn get_url_by_secret_key(&self, secret_key: String) -> Result<URL, Error> {
+        let url = self.url_repository.find_url_by_secret_key(secret_key).await?;
+        self.with_tags(url).await
+    }
+
+    /// Identify the owner of `api_key`; admin keys are recognised by the caller beforehand.
+    pub async fn find_caller(&self, api_key: String) -> Option<Caller> {
+        self.url_repository.get_user_by_apy_key(api_key).await.ok().map(Caller::User)
+    }
+
+    /// Link `url_key` (active or not), provided `caller` may see it.
+    /// The link with the access `caller` holds on it: viewers of its workspace only `Read`.
+    pub async fn get_managed_url(&self, caller: Caller, url_key: String) -> Result<(URL, LinkAccess), ManageURLError> {
+        let url = self.load_authorized(caller, url_key, LinkAccess::Read).await?;
+        let access = match self.authorize(caller, &url, LinkAccess::Write).await {
+            Ok(()) => LinkAccess::Write,
+            Err(ManageURLError::Forbidden) => LinkAccess::Read,
+            Err(err) => return Err(err),
+        };
+        Ok((self.with_tags(url).await?, access))
     }
 
-    pub async fn delete_url(&self, url_key: String) -> Result<URL, Error> {
-        self.url_repository.get_db_url_by_key(url_key).await
+    pub async fn patch_managed_url(&self, caller: Caller, url_key: String, patch: URLPatchDto) -> Result<URL, ManageURLError> {
+        let url = self.load_authorized(caller, url_key, LinkAccess::Write).await?;
+        let url = self.apply_patch(url, patch).await?;
+        Ok(self.with_tags(url).await?)
     }
+
+    pub async fn delete_managed_url(&self, caller: Caller, url_key: String) -> Result<URL, ManageURLError> {
+        let url = self.load_authorized(caller, url_key, LinkAccess::Write).await?;
+        self.remove_url(url).await
+    }
+
+    /// Every link of the `org_id` workspace, optionally only those carrying `query.tag`, filed in
+    /// `query.folder` or matching the text `query.q`; any member may list them, with the access
+    /// their role grants on every link of the workspace.
+    pub async fn list_org_urls(
+        &self, caller: Caller, org_id: i64, query: OrgURLsQueryDto,
+    ) -> Result<(Vec<URL>, LinkAccess), ManageURLError> {
+        let access = match caller {
+            Caller::Admin => LinkAccess::Write,
+            Caller::User(user_id) => match self.url_repository.get_member_role(org_id, user_id).await? {
+                Some(role) if LinkAccess::Write.granted_to(role) => LinkAccess::Write,
+                Some(_) => LinkAccess::Read,
+                None => return Err(ManageURLError::Forbidden),
+            },
+        };
+        let search = query.q.map(|q| q.trim().to_string()).filter(|q| !q.is_empty());
+        let filter = URLFilter { org_id: Some(org_id), tag: query.tag, folder: query.folder, search, ..Default::default() };
+        let mut urls = self.url_repository.list_urls(filter).await?;
+        self.load_tags(&mut urls).await?;
+        Ok((urls, access))
+    }
+
+    async fn with_tags(&self, mut url: URL) -> Result<URL, Error> {
+        self.load_tags(std::slice::from_mut(&mut url)).await?;
+        Ok(url)
+    }
+
+    /// Fill in the tag names of `urls` with a single query.
+    async fn load_tags(&self, urls: &mut [URL]) -> Result<(), Error> {
+        let Some(tag_repository) = &self.tag_repository else {
+            return Ok(());
+        };
+        if urls.is_empty() {
+            return Ok(());
+        }
+        let keys = urls.iter().map(|url| url.key.clone()).collect();
+        let tags = tag_repository.get_url_tags(keys).await?;
+        for url in urls.iter_mut() {
+            url.tags = tags.iter().filter(|(key, _)| *key == url.key).map(|(_, name)| name.clone()).collect();
+        }
+        Ok(())
+    }
+
+    async fn load_authorized(&self, caller: Caller, url_key: String, access: LinkAccess) -> Result<URL, ManageURLError> {
+        let url = self.url_repository.find_url_by_key(url_key).await?;
+        self.authorize(caller, &url, access).await?;
+        Ok(url)
+    }
+
+    /// Links belong to their workspace: access follows the caller's role there. Links without a
+    /// workspace are only reachable by their creator.
+    async fn authorize(&self, caller: Caller, url: &URL, access: LinkAccess) -> Result<(), ManageURLError> {
+        let Caller::User(user_id) = caller else {
+            return Ok(());
+        };
+        let allowed = match url.org_id {
+            Some(org_id) => self
+                .url_repository
+                .get_member_role(org_id, user_id)
+                .await?
+                .is_some_and(|role| access.granted_to(role)),
+            None => url.user_id == user_id,
+        };
+        if allowed {
+            Ok(())
+        } else {
+            Err(ManageURLError::Forbidden)
+        }
+    }
+
+    /// Apply `patch` to the link behind an admin URL.
+    pub async fn patch_url(&self, secret_key: String, patch: URLPatchDto) -> Result<URL, ManageURLError> {
+        let url = self.url_repository.find_url_by_secret_key(secret_key).await?;
+        self.apply_patch(url, patch).await
+    }
+
+    /// A new target goes through the same normalization and link policy as on creation.
+    async fn apply_patch(&self, mut url: URL, patch: URLPatchDto) -> Result<URL, ManageURLError> {
+        if let Some(target_url) = patch.target_url {
+            let normalized = self.target_url_policy.normalize(&target_url)?;
+            self.link_policy.check(&normalized)?;
+            url.target_url = normalized;
+        }
+        if let Some(title) = patch.title {
+            url.title = normalize_text(&title, "title", MAX_TITLE_LEN)?;
+        }
+        if let Some(show_interstitial) = patch.show_interstitial {
+            url.show_interstitial = show_interstitial;
+        }
+        if let Some(description) = patch.description {
+            url.description = normalize_text(&description, "description", MAX_DESCRIPTION_LEN)?;
+        }
+        if let Some(notes) = patch.notes {
+            url.notes = normalize_text(&notes, "notes", MAX_NOTES_LEN)?;
+        }
+        if let Some(og_title) = patch.og_title {
+            url.og_title = normalize_text(&og_title, "og_title", MAX_OG_TITLE_LEN)?;
+        }
+        if let Some(og_description) = patch.og_description {
+            url.og_description = normalize_text(&og_description, "og_description", MAX_DESCRIPTION_LEN)?;
+        }
+        if let Some(og_image_url) = patch.og_image_url {
+            url.og_image_url = normalize_image_url(&og_image_url)?;
+        }
+        if let Some(folder) = patch.folder {
+            url.folder = normalize_folder(&folder)?;
+        }
+        if let Some(fallback_url) = patch.fallback_url.map(|fallback_url| fallback_url.trim().to_string()) {
+            url.fallback_url = if fallback_url.is_empty() {
+                None
+            } else {
+                let normalized = self.target_url_policy.normalize(&fallback_url)?;
+                self.link_policy.check(&normalized)?;
+                Some(normalized)
+            };
+        }
+        if let Some(max_clicks) = patch.max_clicks {
+            if max_clicks < 0 {
+                return Err(ManageURLError::InvalidClickLimit);
+            }
+            url.max_clicks = Some(max_clicks).filter(|max_clicks| *max_clicks > 0);
+        }
+        if let Some(is_active) = patch.is_active {
+            if is_active {
+                self.link_policy.check(&url.target_url)?;
+            }
+            url.is_active = is_active;
+        }
+        Ok(self.url_repository.update_url(url).await?)
+    }
+
+    /// Check every active link (and its geo targets) against the current policy and deactivate
+    /// the ones that now violate it.
+    pub async fn rescan_link_policy(&self) -> Result<PolicyRescanReport, Error> {
+        let mut report = PolicyRescanReport::default();
+        for url in self.url_repository.list_urls(URLFilter::default()).await?.into_iter().filter(|u| u.is_active) {
+            report.scanned += 1;
+            let mut violation = self.link_policy.check(&url.target_url).err();
+            if violation.is_none() {
+                violation = self
+                    .url_repository
+                    .get_geo_rules(url.key.clone())
+                    .await?
+                    .iter()
+                    .find_map(|rule| self.link_policy.check(&rule.target_url).err());
+            }
+            if let Some(violation) = violation {
+                if self.url_repository.deactivate_url(url.key.clone()).await? {
+                    info!("Deactivated link {}: {}", url.key, violation);
+                    report.deactivated.push(url.key);
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Delete the link behind an admin URL and return it as it was.
+    pub async fn delete_url(&self, secret_key: String) -> Result<URL, Error> {
+        let url = self.url_repository.find_url_by_secret_key(secret_key).await?;
+        self.remove_url(url).await.map_err(|err| match err {
+            ManageURLError::Database(err) => err,
+            _ => Error::RowNotFound,
+        })
+    }
+
+    async fn remove_url(&self, url: URL) -> Result<URL, ManageURLError> {
+        if !self.url_repository.delete_url(url.key.clone()).await? {
+            return Err(ManageURLError::NotFound);
+        }
+        info!("Deleted link {}", url.key);
+        Ok(url)
+    }
+
+    /// Normalized `target_url`, provided it is well formed and allowed by the link policy.
+    fn normalize_target(&self, target_url: &str) -> Result<String, CustomError> {
+        let normalized =
+            self.target_url_policy.normalize(target_url).map_err(|err| CustomError::new(400, &err.to_string()))?;
+        self.link_policy.check(&normalized).map_err(|violation| CustomError::new(403, &violation.to_string()))?;
+        Ok(normalized)
+    }
+
+    /// Load `url_key` and run the checks that precede any redirect: unknown keys are counted and
+    /// not found, used one-time links, links over their click limit and missing, expired or forged
+    /// signatures are gone, links outside their activation window get the coming soon / ended page,
+    /// and protected links need the password. Deactivated, used up and ended links send visitors
+    /// to their fallback destination instead, when they have one.
+    async fn admit(&self, url_key: &str, visit: &VisitContext) -> Result<Admission, Error> {
+        let url = match self.url_repository.find_url_by_key(url_key.to_string()).await {
+            Ok(url) => url,
+            Err(Error::RowNotFound) => {
+                self.record_unknown_key(url_key).await;
+                return Ok(Admission::Denied(ForwardOutcome::NotFound));
+            }
+            Err(err) => return Err(err),
+        };
+        let fallback = |otherwise: ForwardOutcome, url: &URL| {
+            Admission::Denied(url.fallback_url.clone().map(ForwardOutcome::Fallback).unwrap_or(otherwise))
+        };
+        if !url.is_active {
+            return Ok(fallback(if url.single_use { ForwardOutcome::Gone } else { ForwardOutcome::NotFound }, &url));
+        }
+        let signature_ok = match visit.signature.as_ref() {
+            Some(signature) => self.is_valid_signature(&url.key, signature),
+            None => !url.require_signature,
+        };
+        if !signature_ok {
+            return Ok(Admission::Denied(ForwardOutcome::Gone));
+        }
+        match url.schedule_state(Utc::now()) {
+            ScheduleState::Upcoming => return Ok(Admission::Denied(ForwardOutcome::NotYetActive(url))),
+            ScheduleState::Ended => return Ok(fallback(ForwardOutcome::Ended(url.clone()), &url)),
+            ScheduleState::Live => {}
+        }
+        if url.click_limit_reached() {
+            return Ok(fallback(ForwardOutcome::Gone, &url));
+        }
+        if url.password_hash.is_some() && !visit.password_verified {
+            return Ok(Admission::Denied(ForwardOutcome::PasswordRequired));
+        }
+        Ok(Admission::Allowed(url))
+    }
+
+    /// Failing to count a miss must not break the 404 page itself.
+    async fn record_unknown_key(&self, url_key: &str) {
+        if url_key.len() > MAX_RECORDED_KEY_LEN {
+            return;
+        }
+        if let Err(err) = self.url_repository.record_unknown_key(url_key.to_string(), MAX_TRACKED_UNKNOWN_KEYS).await {
+            eprintln!("Error occurred[record_unknown_key_srvc]: {}", err);
+        }
+    }
+
+    fn is_valid_signature(&self, url_key: &str, signature: &LinkSignature) -> bool {
+        let Some(secret) = self.signing_secret.as_deref() else {
+            return false;
+        };
+        signature.expires_at > Utc::now().timestamp()
+            && signing::verify(secret, &signed_link_payload(url_key, signature.expires_at), &signature.signature)
+    }
+
+    /// Spend a one-time link. Concurrent visitors race on the same row and only one of them wins.
+    async fn consume(&self, url: &URL) -> Result<bool, Error> {
+        if !url.single_use {
+            return Ok(true);
+        }
+        self.url_repository.deactivate_url(url.key.clone()).await
+    }
+
+    fn resolve_country(&self, visit: &VisitContext) -> Option<String> {
+        let locator = self.geo_locator.as_ref()?;
+        locator.country_code(visit.client_ip?)
+    }
+
+    /// Destination for a visitor from `country`: its geo rule when there is one, the link target otherwise.
+    async fn resolve_target(&self, url: &URL, country: Option<&str>) -> Result<String, Error> {
+        if let Some(fallback_url) = self.dead_links.fallback_url.as_ref().filter(|_| url.is_broken(self.dead_links.failure_threshold)) {
+            return Ok(fallback_url.clone());
+        }
+        let Some(code) = country else {
+            return Ok(url.target_url.clone());
+        };
+        Ok(self
+            .url_repository
+            .get_geo_rules(url.key.clone())
+            .await?
+            .into_iter()
+            .find(|rule| rule.country_code == code)
+            .map(|rule| rule.target_url)
+            .unwrap_or_else(|| url.target_url.clone()))
+    }
+}
+
+/// Outcome of a visit that lost the race for the last click allowed by `max_clicks`.
+fn over_click_limit(url: &URL) -> ForwardOutcome {
+    url.fallback_url.clone().map(ForwardOutcome::Fallback).unwrap_or(ForwardOutcome::Gone)
+}
+
+fn signed_link_payload(url_key: &str, expires_at: i64) -> String {
+    format!("link:{url_key}:{expires_at}")
+}
+
+/// Start of the UTC day containing `now` and start of the next one.
+fn utc_day_bounds(now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start = now.date_naive().and_hms_opt(0, 0, 0).unwrap_or_default().and_utc();
+    (start, start.checked_add_days(Days::new(1)).unwrap_or(start))
+}
+
+/// Aliases are 3 to 32 letters, digits, `-` or `_`, and must not shadow a route.
+fn validate_alias(alias: &str) -> Result<String, CustomError> {
+    let alias = alias.trim();
+    if !(3..=32).contains(&alias.len()) || !alias.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(CustomError::new(400, "Alias must be 3 to 32 letters, digits, '-' or '_'"));
+    }
+    if RESERVED_ALIASES.contains(&alias.to_ascii_lowercase().as_str()) {
+        return Err(CustomError::new(400, &format!("Alias '{}' is reserved", alias)));
+    }
+    Ok(alias.to_string())
+}
+
+/// Trimmed folder name, `None` when blank.
+fn normalize_folder(folder: &str) -> Result<Option<String>, ManageURLError> {
+    let folder = folder.trim();
+    if folder.chars().count() > MAX_FOLDER_LEN || folder.chars().any(char::is_control) {
+        return Err(ManageURLError::InvalidFolder);
+    }
+    Ok(Some(folder.to_string()).filter(|folder| !folder.is_empty()))
+}
+
+fn truncate_chars(text: String, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((end, _)) => text[..end].to_string(),
+        None => text,
+    }
+}
+
+/// Absolute http(s) image URL, `None` when blank.
+fn normalize_image_url(image_url: &str) -> Result<Option<String>, ManageURLError> {
+    let image_url = image_url.trim();
+    if image_url.is_empty() {
+        return Ok(None);
+    }
+    match url::Url::parse(image_url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && image_url.len() <= MAX_IMAGE_URL_LEN => {
+            Ok(Some(parsed.to_string()))
+        }
+        _ => Err(ManageURLError::InvalidImageUrl),
+    }
+}
+
+/// Trimmed free text of at most `max` characters, `None` when blank.
+fn normalize_text(text: &str, field: &'static str, max: usize) -> Result<Option<String>, ManageURLError> {
+    let text = text.trim();
+    if text.chars().count() > max {
+        return Err(ManageURLError::TooLong { field, max });
+    }
+    Ok(Some(text.to_string()).filter(|text| !text.is_empty()))
+}
+
+/// Normalise country codes to upper case and reject anything that is not a two-letter code.
+fn validate_geo_rules(rules: &HashMap<String, String>) -> Result<Vec<(String, String)>, CustomError> {
+    let mut validated = BTreeMap::new();
+    for (country_code, target_url) in rules {
+        let code = country_code.trim().to_ascii_uppercase();
+        if code.len() != 2 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(CustomError::new(400, &format!("Invalid country code '{}'", country_code)));
+        }
+        validated.insert(code, target_url.clone());
+    }
+    Ok(validated.into_iter().collect())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::org::domain::models::organization::Role;
+    use crate::url::domain::models::health::LinkCheck;
+    use crate::url::domain::models::metadata::MetadataFetchError;
+    use crate::user::domain::models::plan::{LinkUsage, PlanCatalog};
     use async_trait::async_trait;
+    use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::{Arc, Mutex};
 
     struct FakeURLRepo {
         url_opt: Mutex<Option<URL>>,
         increment_called: Mutex<bool>,
         valid_api_key: Mutex<bool>,
+        geo_rules: Mutex<Vec<GeoRule>>,
+        clicks: Mutex<Vec<ClickRecord>>,
+        created_targets: Mutex<Vec<String>>,
+        roles: Mutex<Vec<(i64, i32, Role)>>,
+        plan: Mutex<Option<String>>,
+        usage: Mutex<LinkUsage>,
+        unknown_keys: Mutex<Vec<String>>,
     }
 
     impl FakeURLRepo {
         fn new(initial: Option<URL>) -> Self {
-            Self { url_opt: Mutex::new(initial), increment_called: Mutex::new(false), valid_api_key: Mutex::new(true) }
+            Self {
+                url_opt: Mutex::new(initial),
+                increment_called: Mutex::new(false),
+                valid_api_key: Mutex::new(true),
+                geo_rules: Mutex::new(Vec::new()),
+                clicks: Mutex::new(Vec::new()),
+                created_targets: Mutex::new(Vec::new()),
+                roles: Mutex::new(Vec::new()),
+                plan: Mutex::new(None),
+                usage: Mutex::new(LinkUsage::default()),
+                unknown_keys: Mutex::new(Vec::new()),
+            }
+        }
+    }
+
+    struct FakeGeoLocator;
+
+    impl GeoLocatorPort for FakeGeoLocator {
+        fn country_code(&self, ip: std::net::IpAddr) -> Option<String> {
+            if ip.to_string().starts_with("81.") { Some("ES".into()) } else { None }
         }
     }
 
     #[async_trait]
     impl crate::url::domain::repositories::url_repository_port::URLRepositoryPort for FakeURLRepo {
-        async fn create_url(&self, _target_url: String, _user_id: i32) -> Result<URL, sqlx::Error> {
+        async fn create_url(
+            &self, target_url: String, _user_id: i32, org_id: Option<i64>, custom_key: Option<String>, reuse_existing: bool,
+        ) -> Result<URL, sqlx::Error> {
+            self.created_targets.lock().unwrap().push(target_url);
             let mut guard = self.url_opt.lock().unwrap();
-            if let Some(u) = guard.clone() {
+            if let Some(u) = guard.clone().filter(|_| reuse_existing) {
                 Ok(u)
             } else {
-                let new = URL { key: "k1".into(), secret_key: "s1".into(), target_url: "http://x".into(), is_active: true, clicks: 0, user_id: 1 };
+                let custom_alias = custom_key.is_some();
+                let key = custom_key.unwrap_or_else(|| "k1".into());
+                let new = URL { key, secret_key: "s1".into(), target_url: "http://x".into(), is_active: true, clicks: 0, user_id: 1, org_id, custom_alias, created_at: Some(Utc::now()), ..Default::default() };
                 *guard = Some(new.clone());
                 Ok(new)
             }
@@ -86,7 +1072,23 @@ mod tests {
 
         async fn get_db_url_by_key(&self, _url_key: String) -> Result<URL, sqlx::Error> {
             let guard = self.url_opt.lock().unwrap();
-            guard.clone().ok_or_else(|| sqlx::Error::RowNotFound)
+            guard.clone().filter(|u| u.is_active).ok_or_else(|| sqlx::Error::RowNotFound)
+        }
+
+        async fn find_url_by_key(&self, _url_key: String) -> Result<URL, sqlx::Error> {
+            self.url_opt.lock().unwrap().clone().ok_or(sqlx::Error::RowNotFound)
+        }
+
+        async fn find_url_by_secret_key(&self, secret_key: String) -> Result<URL, sqlx::Error> {
+            self.url_opt.lock().unwrap().clone().filter(|u| u.secret_key == secret_key).ok_or(sqlx::Error::RowNotFound)
+        }
+
+        async fn deactivate_url(&self, _url_key: String) -> Result<bool, sqlx::Error> {
+            Ok(self.url_opt.lock().unwrap().as_mut().map(|u| std::mem::replace(&mut u.is_active, false)).unwrap_or(false))
+        }
+
+        async fn delete_url(&self, _url_key: String) -> Result<bool, sqlx::Error> {
+            Ok(self.url_opt.lock().unwrap().take().is_some())
         }
 
         async fn get_db_url_by_user_and_target_url(&self, _user_id: i32, _target_url: String) -> Result<URL, sqlx::Error> {
@@ -99,63 +1101,669 @@ mod tests {
             if ok && api_key == "valid" { Ok(1) } else { Err(()) }
         }
 
-        async fn increment_clicks(&self, _url_key: String) -> sqlx::Result<()> {
+        async fn increment_clicks(&self, _url_key: String) -> sqlx::Result<bool> {
             let mut called = self.increment_called.lock().unwrap();
             *called = true;
+            match self.url_opt.lock().unwrap().as_mut() {
+                Some(url) if url.click_limit_reached() => Ok(false),
+                Some(url) => {
+                    url.clicks += 1;
+                    Ok(true)
+                }
+                None => Err(sqlx::Error::RowNotFound),
+            }
+        }
+
+        async fn increment_interstitial_clicks(&self, _url_key: String) -> sqlx::Result<bool> {
+            match self.url_opt.lock().unwrap().as_mut() {
+                Some(url) if url.click_limit_reached() => Ok(false),
+                Some(url) => {
+                    url.interstitial_clicks += 1;
+                    Ok(true)
+                }
+                None => Err(sqlx::Error::RowNotFound),
+            }
+        }
+
+        async fn add_clicks(&self, _url_key: String, clicks: i32, interstitial_clicks: i32) -> sqlx::Result<()> {
+            let mut guard = self.url_opt.lock().unwrap();
+            let url = guard.as_mut().ok_or(sqlx::Error::RowNotFound)?;
+            url.clicks += clicks;
+            url.interstitial_clicks += interstitial_clicks;
+            Ok(())
+        }
+
+        async fn update_url(&self, url: URL) -> Result<URL, sqlx::Error> {
+            *self.url_opt.lock().unwrap() = Some(url.clone());
+            Ok(url)
+        }
+
+        async fn set_geo_rules(&self, _url_key: String, rules: Vec<GeoRule>) -> sqlx::Result<()> {
+            *self.geo_rules.lock().unwrap() = rules;
+            Ok(())
+        }
+
+        async fn get_geo_rules(&self, _url_key: String) -> Result<Vec<GeoRule>, sqlx::Error> {
+            Ok(self.geo_rules.lock().unwrap().clone())
+        }
+
+        async fn set_link_metadata(&self, url_key: String, metadata: LinkMetadata) -> Result<(), sqlx::Error> {
+            if let Some(url) = self.url_opt.lock().unwrap().as_mut().filter(|url| url.key == url_key) {
+                url.title = url.title.take().or(metadata.title);
+                url.description = url.description.take().or(metadata.description);
+                url.favicon_url = metadata.favicon_url;
+            }
+            Ok(())
+        }
+
+        async fn record_click(&self, click: ClickRecord) -> sqlx::Result<()> {
+            self.clicks.lock().unwrap().push(click);
+            Ok(())
+        }
+
+        async fn record_link_check(&self, url_key: String, check: LinkCheck) -> Result<(), sqlx::Error> {
+            if let Some(url) = self.url_opt.lock().unwrap().as_mut().filter(|url| url.key == url_key) {
+                url.failed_checks = if check.is_failure() { url.failed_checks + 1 } else { 0 };
+                url.last_check_status = check.status_code.map(i32::from);
+                url.last_checked_at = Some(check.checked_at);
+            }
             Ok(())
         }
+
+        async fn record_unknown_key(&self, key: String, _max_keys: i64) -> Result<(), sqlx::Error> {
+            self.unknown_keys.lock().unwrap().push(key);
+            Ok(())
+        }
+
+        async fn list_unknown_keys(&self, _limit: i64) -> Result<Vec<UnknownKeyHits>, sqlx::Error> {
+            Ok(Vec::new())
+        }
+
+        async fn list_urls(&self, filter: URLFilter) -> Result<Vec<URL>, sqlx::Error> {
+            let guard = self.url_opt.lock().unwrap();
+            Ok(guard
+                .iter()
+                .filter(|u| filter.user_id.is_none_or(|id| u.user_id == id))
+                .filter(|u| filter.org_id.is_none_or(|id| u.org_id == Some(id)))
+                .filter(|u| !filter.scheduled || u.active_from.is_some() || u.active_until.is_some())
+                .filter(|u| !filter.active || u.is_active)
+                .filter(|u| filter.checked_before.is_none_or(|before| u.last_checked_at.is_none_or(|at| at < before)))
+                .filter(|u| filter.min_failed_checks.is_none_or(|min| u.failed_checks >= min))
+                .take(filter.limit.map_or(usize::MAX, |limit| limit as usize))
+                .cloned()
+                .collect())
+        }
+
+        async fn key_exists(&self, key: String) -> Result<bool, sqlx::Error> {
+            Ok(self.url_opt.lock().unwrap().iter().any(|u| u.key == key))
+        }
+
+        async fn get_member_role(&self, org_id: i64, user_id: i32) -> Result<Option<Role>, sqlx::Error> {
+            let roles = self.roles.lock().unwrap();
+            Ok(roles.iter().rev().find(|(org, user, _)| *org == org_id && *user == user_id).map(|(_, _, role)| *role))
+        }
+
+        async fn get_user_plan(&self, _user_id: i32) -> Result<Option<String>, sqlx::Error> {
+            Ok(self.plan.lock().unwrap().clone())
+        }
+
+        async fn get_link_usage(&self, _user_id: i32, _since: DateTime<Utc>) -> Result<LinkUsage, sqlx::Error> {
+            Ok(self.usage.lock().unwrap().clone())
+        }
+
     }
 
     #[tokio::test]
     async fn create_url_returns_existing_or_new() {
-        let existing = URL { key: "k-ex".into(), secret_key: "s-ex".into(), target_url: "http://ex".into(), is_active: true, clicks: 0, user_id: 1 };
+        let existing = URL { key: "k-ex".into(), secret_key: "s-ex".into(), target_url: "http://ex".into(), is_active: true, clicks: 0, user_id: 1, ..Default::default() };
         let repo = Arc::new(FakeURLRepo::new(Some(existing.clone())));
         let service = URLService::new(repo.clone());
 
-        let dto = crate::url::application::dtos::url_dto::URLBaseDto { target_url: "http://ex".into(), api_key: "valid".into() };
+        let dto = crate::url::application::dtos::url_dto::URLBaseDto { target_url: "http://ex".into(), api_key: "valid".into(), ..Default::default() };
         let res = service.create_url(dto).await.expect("should create/return");
         assert_eq!(res.key, existing.key);
 
         // Now use repo without existing URL
         let repo2 = Arc::new(FakeURLRepo::new(None));
         let service2 = URLService::new(repo2.clone());
-        let dto2 = crate::url::application::dtos::url_dto::URLBaseDto { target_url: "http://new".into(), api_key: "valid".into() };
+        let dto2 = crate::url::application::dtos::url_dto::URLBaseDto { target_url: "http://new".into(), api_key: "valid".into(), ..Default::default() };
         let res2 = service2.create_url(dto2).await.expect("create new");
         assert_eq!(res2.key, "k1");
     }
 
+    #[tokio::test]
+    async fn create_url_enforces_plan_limits() {
+        let plans = PlanCatalog::new(vec!["free:2/5/0".parse().unwrap(), "pro:*/*/1".parse().unwrap()], "free").unwrap();
+        let repo = Arc::new(FakeURLRepo::new(None));
+        let service = URLService::new(repo.clone()).with_plans(Arc::new(plans));
+        let dto = URLBaseDto { target_url: "http://x".into(), api_key: "valid".into(), ..Default::default() };
+        let aliased = URLBaseDto { alias: Some("promo".into()), ..dto.clone() };
+
+        assert_eq!(service.create_url(aliased.clone()).await.expect_err("no aliases on free").code(), 403);
+        *repo.usage.lock().unwrap() = LinkUsage { active_links: 2, links_today: 0, custom_aliases: 0 };
+        assert_eq!(service.create_url(dto.clone()).await.expect_err("active links capped").code(), 403);
+        *repo.usage.lock().unwrap() = LinkUsage { active_links: 0, links_today: 5, custom_aliases: 0 };
+        let err = service.create_url(dto.clone()).await.expect_err("daily links capped");
+        assert_eq!(err.code(), 429);
+        assert!(err.to_string().contains("resets at"));
+
+        *repo.plan.lock().unwrap() = Some("pro".into());
+        let created = service.create_url(aliased.clone()).await.expect("pro allows an alias");
+        assert_eq!((created.key.as_str(), created.custom_alias), ("promo", true));
+        assert_eq!(service.create_url(aliased.clone()).await.expect_err("alias taken").code(), 409);
+        *repo.usage.lock().unwrap() = LinkUsage { active_links: 1, links_today: 1, custom_aliases: 1 };
+        let other = URLBaseDto { alias: Some("other".into()), ..dto };
+        assert_eq!(service.create_url(other).await.expect_err("alias cap reached").code(), 403);
+
+        let usage = service.get_usage(1).await.expect("usage");
+        assert_eq!((usage.plan.name.as_str(), usage.usage.custom_aliases), ("pro", 1));
+        assert!(usage.daily_reset_at > Utc::now());
+    }
+
+    #[test]
+    fn aliases_are_validated() {
+        assert_eq!(validate_alias(" my-link_1 ").unwrap(), "my-link_1");
+        for alias in ["ab", "has space", "ünï", &"x".repeat(33), "Admin", "usage"] {
+            assert_eq!(validate_alias(alias).unwrap_err().code(), 400, "{alias} should be rejected");
+        }
+    }
+
     #[tokio::test]
     async fn create_url_invalid_api_key_returns_custom_error() {
         let repo = Arc::new(FakeURLRepo::new(None));
         let service = URLService::new(repo.clone());
-        let dto = crate::url::application::dtos::url_dto::URLBaseDto { target_url: "http://x".into(), api_key: "invalid".into() };
+        let dto = crate::url::application::dtos::url_dto::URLBaseDto { target_url: "http://x".into(), api_key: "invalid".into(), ..Default::default() };
         let res = service.create_url(dto).await;
         assert!(res.is_err());
         let err = res.err().unwrap();
-        assert_eq!(err.to_string().contains("No valid API_KEY"), true);
+        assert!(err.to_string().contains("No valid API_KEY"));
     }
 
     #[tokio::test]
     async fn forward_to_target_url_increments_and_returns_target() {
-        let url = URL { key: "k1".into(), secret_key: "s1".into(), target_url: "http://target".into(), is_active: true, clicks: 0, user_id: 1 };
+        let url = URL { key: "k1".into(), secret_key: "s1".into(), target_url: "http://target".into(), is_active: true, clicks: 0, user_id: 1, ..Default::default() };
         let repo = Arc::new(FakeURLRepo::new(Some(url.clone())));
         let service = URLService::new(repo.clone());
 
-        let target = service.forward_to_target_url("k1".into()).await.expect("forward");
-        assert_eq!(target, url.target_url);
-        assert_eq!(*repo.increment_called.lock().unwrap(), true);
+        let target = service.forward_to_target_url("k1".into(), VisitContext::default()).await.expect("forward");
+        assert_eq!(target, ForwardOutcome::Redirect(url.target_url));
+        assert!(*repo.increment_called.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn create_url_stores_normalized_geo_rules_and_rejects_invalid_codes() {
+        let repo = Arc::new(FakeURLRepo::new(None));
+        let service = URLService::new(repo.clone());
+        let mut dto = URLBaseDto { target_url: "http://x".into(), api_key: "valid".into(), ..Default::default() };
+        dto.geo_rules.insert("es".into(), "http://eu".into());
+        service.create_url(dto.clone()).await.expect("create with rules");
+        let rules = repo.geo_rules.lock().unwrap().clone();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].country_code, "ES");
+        assert_eq!(rules[0].url_key, "k1");
+
+        dto.geo_rules.insert("Spain".into(), "http://eu".into());
+        let err = service.create_url(dto).await.expect_err("invalid country code");
+        assert!(err.to_string().contains("Invalid country code"));
+    }
+
+    #[tokio::test]
+    async fn forward_routes_by_country_and_records_it() {
+        let url = URL { key: "k1".into(), secret_key: "s1".into(), target_url: "http://default".into(), is_active: true, clicks: 0, user_id: 1, ..Default::default() };
+        let repo = Arc::new(FakeURLRepo::new(Some(url)));
+        *repo.geo_rules.lock().unwrap() =
+            vec![GeoRule { url_key: "k1".into(), country_code: "ES".into(), target_url: "http://eu".into() }];
+        let service = URLService::new(repo.clone()).with_geo_locator(Arc::new(FakeGeoLocator));
+
+        let spain = VisitContext { client_ip: Some("81.2.69.160".parse().unwrap()), ..Default::default() };
+        assert_eq!(service.forward_to_target_url("k1".into(), spain).await.unwrap(), ForwardOutcome::Redirect("http://eu".into()));
+        let elsewhere = VisitContext { client_ip: Some("8.8.8.8".parse().unwrap()), ..Default::default() };
+        assert_eq!(service.forward_to_target_url("k1".into(), elsewhere).await.unwrap(), ForwardOutcome::Redirect("http://default".into()));
+
+        let countries: Vec<Option<String>> = repo.clicks.lock().unwrap().iter().map(|c| c.country.clone()).collect();
+        assert_eq!(countries, vec![Some("ES".to_string()), None]);
+    }
+
+    #[tokio::test]
+    async fn create_url_hashes_password() {
+        let repo = Arc::new(FakeURLRepo::new(None));
+        let service = URLService::new(repo.clone());
+        let dto = URLBaseDto { target_url: "http://x".into(), api_key: "valid".into(), password: Some("open sesame".into()), ..Default::default() };
+        let created = service.create_url(dto).await.expect("create");
+        let hash = created.password_hash.expect("password hash stored");
+        assert!(verify_password("open sesame", &hash));
+    }
+
+    #[tokio::test]
+    async fn protected_link_requires_unlock_and_limits_failures() {
+        let url = URL { key: "k1".into(), target_url: "http://secret".into(), is_active: true, password_hash: Some(hash_password("pw").unwrap()), ..Default::default() };
+        let repo = Arc::new(FakeURLRepo::new(Some(url)));
+        let service = URLService::new(repo.clone()).with_unlock_limits(2, Duration::from_secs(60));
+
+        let outcome = service.forward_to_target_url("k1".into(), VisitContext::default()).await.unwrap();
+        assert_eq!(outcome, ForwardOutcome::PasswordRequired);
+        assert!(!*repo.increment_called.lock().unwrap());
+
+        assert!(matches!(service.unlock("k1".into(), "bad", VisitContext::default()).await, Err(UnlockError::WrongPassword)));
+        assert_eq!(service.unlock("k1".into(), "pw", VisitContext::default()).await.unwrap(), ForwardOutcome::Redirect("http://secret".into()));
+        assert!(*repo.increment_called.lock().unwrap());
+
+        // a success resets the counter; two new failures then block even the right password
+        let _ = service.unlock("k1".into(), "bad", VisitContext::default()).await;
+        let _ = service.unlock("k1".into(), "bad", VisitContext::default()).await;
+        assert!(matches!(service.unlock("k1".into(), "pw", VisitContext::default()).await, Err(UnlockError::TooManyAttempts(_))));
+    }
+
+    #[tokio::test]
+    async fn interstitial_is_shown_on_request_or_flag_and_counted_separately() {
+        let url = URL { key: "k1".into(), target_url: "http://target".into(), is_active: true, ..Default::default() };
+        let repo = Arc::new(FakeURLRepo::new(Some(url)));
+        let service = URLService::new(repo.clone());
+
+        let preview = VisitContext { preview_requested: true, ..Default::default() };
+        let outcome = service.forward_to_target_url("k1".into(), preview).await.unwrap();
+        assert!(matches!(outcome, ForwardOutcome::Interstitial { ref target_url, .. } if target_url.as_deref() == Some("http://target")));
+        assert!(!*repo.increment_called.lock().unwrap());
+
+        let outcome = service.continue_from_interstitial("k1".into(), VisitContext::default()).await.unwrap();
+        assert_eq!(outcome, ForwardOutcome::Redirect("http://target".into()));
+        assert!(!*repo.increment_called.lock().unwrap());
+        assert_eq!(repo.url_opt.lock().unwrap().as_ref().unwrap().interstitial_clicks, 1);
+        assert_eq!(repo.clicks.lock().unwrap()[0].source, ClickSource::Interstitial);
+
+        repo.url_opt.lock().unwrap().as_mut().unwrap().show_interstitial = true;
+        let outcome = service.forward_to_target_url("k1".into(), VisitContext::default()).await.unwrap();
+        assert!(matches!(outcome, ForwardOutcome::Interstitial { .. }));
     }
 
     #[tokio::test]
     async fn get_info_and_delete_return_domain_model() {
-        let url = URL { key: "k1".into(), secret_key: "s1".into(), target_url: "http://target".into(), is_active: true, clicks: 0, user_id: 1 };
+        let url = URL { key: "k1".into(), secret_key: "s1".into(), target_url: "http://target".into(), is_active: true, clicks: 0, user_id: 1, ..Default::default() };
         let repo = Arc::new(FakeURLRepo::new(Some(url.clone())));
         let service = URLService::new(repo.clone());
 
         let got = service.get_url_info("k1".into()).await.expect("get info");
         assert_eq!(got.key, url.key);
 
-        let del = service.delete_url("k1".into()).await.expect("delete");
+        let del = service.delete_url("s1".into()).await.expect("delete");
         assert_eq!(del.key, url.key);
+        assert!(repo.url_opt.lock().unwrap().is_none(), "deleted for real");
+    }
+
+    #[tokio::test]
+    async fn single_use_link_redirects_once_then_is_gone() {
+        let repo = Arc::new(FakeURLRepo::new(None));
+        let service = URLService::new(repo.clone());
+        let dto = URLBaseDto { target_url: "http://reset".into(), api_key: "valid".into(), single_use: true, ..Default::default() };
+        let created = service.create_url(dto).await.expect("create");
+        assert!(created.single_use);
+
+        let first = service.forward_to_target_url(created.key.clone(), VisitContext::default()).await.unwrap();
+        assert_eq!(first, ForwardOutcome::Redirect("http://x".into()));
+        let second = service.forward_to_target_url(created.key, VisitContext::default()).await.unwrap();
+        assert_eq!(second, ForwardOutcome::Gone);
+    }
+
+    #[tokio::test]
+    async fn preview_crawlers_are_not_counted_and_do_not_spend_single_use_links() {
+        let url = URL { key: "k1".into(), target_url: "http://target".into(), is_active: true, user_id: 1, single_use: true, ..Default::default() };
+        let repo = Arc::new(FakeURLRepo::new(Some(url)));
+        let service = URLService::new(repo.clone());
+        let crawler = VisitContext { preview_crawler: true, ..Default::default() };
+
+        let outcome = service.forward_to_target_url("k1".into(), crawler).await.unwrap();
+        assert!(matches!(outcome, ForwardOutcome::SocialPreview { target_url: None, .. }), "one-time destination withheld");
+        assert!(!*repo.increment_called.lock().unwrap());
+        let visit = service.forward_to_target_url("k1".into(), VisitContext::default()).await.unwrap();
+        assert_eq!(visit, ForwardOutcome::Redirect("http://target".into()));
+    }
+
+    #[tokio::test]
+    async fn previews_do_not_reveal_or_spend_single_use_links() {
+        let url = URL { key: "k1".into(), target_url: "http://target".into(), is_active: true, user_id: 1, single_use: true, ..Default::default() };
+        let repo = Arc::new(FakeURLRepo::new(Some(url)));
+        let service = URLService::new(repo.clone());
+        let preview = VisitContext { preview_requested: true, ..Default::default() };
+
+        let outcome = service.forward_to_target_url("k1".into(), preview).await.unwrap();
+        assert!(matches!(outcome, ForwardOutcome::Interstitial { target_url: None, .. }), "one-time destination withheld");
+        assert!(!*repo.increment_called.lock().unwrap());
+        let visit = service.continue_from_interstitial("k1".into(), VisitContext::default()).await.unwrap();
+        assert_eq!(visit, ForwardOutcome::Redirect("http://target".into()));
+    }
+
+    #[tokio::test]
+    async fn signed_links_are_verified_and_expire() {
+        let url = URL { key: "k1".into(), target_url: "http://target".into(), is_active: true, user_id: 1, require_signature: true, ..Default::default() };
+        let repo = Arc::new(FakeURLRepo::new(Some(url)));
+        let unsigned = URLService::new(repo.clone());
+        assert!(unsigned.sign_url("k1".into(), "valid".into(), 60).await.is_err(), "signing disabled without a secret");
+
+        let service = URLService::new(repo.clone()).with_signing_secret("secret", 3600);
+        assert_eq!(service.forward_to_target_url("k1".into(), VisitContext::default()).await.unwrap(), ForwardOutcome::Gone);
+        assert!(service.sign_url("k1".into(), "valid".into(), 7200).await.is_err(), "above the max TTL");
+
+        let signature = service.sign_url("k1".into(), "valid".into(), 60).await.expect("sign");
+        let visit = VisitContext { signature: Some(signature.clone()), ..Default::default() };
+        assert_eq!(service.forward_to_target_url("k1".into(), visit).await.unwrap(), ForwardOutcome::Redirect("http://target".into()));
+
+        let tampered = LinkSignature { expires_at: signature.expires_at + 1, ..signature };
+        let visit = VisitContext { signature: Some(tampered), ..Default::default() };
+        assert_eq!(service.forward_to_target_url("k1".into(), visit).await.unwrap(), ForwardOutcome::Gone);
+
+        let expires_at = Utc::now().timestamp() - 1;
+        let expired = LinkSignature { expires_at, signature: signing::sign("secret", &signed_link_payload("k1", expires_at)) };
+        let visit = VisitContext { signature: Some(expired), ..Default::default() };
+        assert_eq!(service.forward_to_target_url("k1".into(), visit).await.unwrap(), ForwardOutcome::Gone);
+    }
+
+    #[tokio::test]
+    async fn scheduled_links_only_redirect_inside_their_window() {
+        let hour = chrono::Duration::hours(1);
+        let url = URL { key: "k1".into(), target_url: "http://launch".into(), is_active: true, user_id: 1, active_from: Some(Utc::now() + hour), ..Default::default() };
+        let repo = Arc::new(FakeURLRepo::new(Some(url)));
+        let service = URLService::new(repo.clone());
+
+        let outcome = service.forward_to_target_url("k1".into(), VisitContext::default()).await.unwrap();
+        assert!(matches!(outcome, ForwardOutcome::NotYetActive(_)));
+        assert!(!*repo.increment_called.lock().unwrap());
+        let upcoming = service.list_scheduled_urls("valid".into(), Some(ScheduleState::Upcoming)).await.unwrap();
+        assert_eq!(upcoming.len(), 1);
+        assert!(service.list_scheduled_urls("valid".into(), Some(ScheduleState::Live)).await.unwrap().is_empty());
+
+        repo.url_opt.lock().unwrap().as_mut().unwrap().active_from = Some(Utc::now() - hour);
+        let outcome = service.forward_to_target_url("k1".into(), VisitContext::default()).await.unwrap();
+        assert_eq!(outcome, ForwardOutcome::Redirect("http://launch".into()));
+
+        repo.url_opt.lock().unwrap().as_mut().unwrap().active_until = Some(Utc::now());
+        let outcome = service.forward_to_target_url("k1".into(), VisitContext::default()).await.unwrap();
+        assert!(matches!(outcome, ForwardOutcome::Ended(_)));
+    }
+
+    #[tokio::test]
+    async fn create_url_rejects_inverted_schedule() {
+        let repo = Arc::new(FakeURLRepo::new(None));
+        let service = URLService::new(repo.clone());
+        let now = Utc::now();
+        let dto = URLBaseDto { target_url: "http://x".into(), api_key: "valid".into(), active_from: Some(now), active_until: Some(now), ..Default::default() };
+        let err = service.create_url(dto).await.expect_err("empty window");
+        assert!(err.to_string().contains("active_until"));
+    }
+
+    #[tokio::test]
+    async fn create_url_normalizes_target_and_rejects_disallowed_schemes() {
+        let repo = Arc::new(FakeURLRepo::new(None));
+        let service = URLService::new(repo.clone());
+        let dto = URLBaseDto { target_url: "HTTPS://Example.COM:443".into(), api_key: "valid".into(), ..Default::default() };
+        service.create_url(dto).await.expect("create");
+        assert_eq!(repo.created_targets.lock().unwrap().as_slice(), ["https://example.com/"]);
+
+        for target in ["javascript:alert(1)", "example.com"] {
+            let dto = URLBaseDto { target_url: target.into(), api_key: "valid".into(), ..Default::default() };
+            let err = service.create_url(dto).await.expect_err("rejected");
+            assert!(err.to_string().contains("400"), "{target}: {err}");
+        }
+        assert_eq!(repo.created_targets.lock().unwrap().len(), 1, "nothing stored for rejected targets");
+    }
+
+    #[tokio::test]
+    async fn link_policy_rejects_internal_targets_on_create_and_patch() {
+        let repo = Arc::new(FakeURLRepo::new(None));
+        let service = URLService::new(repo.clone());
+        let dto = URLBaseDto { target_url: "http://192.168.1.1/admin".into(), api_key: "valid".into(), ..Default::default() };
+        let err = service.create_url(dto).await.expect_err("blocked");
+        assert_eq!(err.code(), 403);
+
+        let url = URL { key: "k1".into(), secret_key: "s1".into(), target_url: "http://ok.example/".into(), is_active: true, ..Default::default() };
+        *repo.url_opt.lock().unwrap() = Some(url);
+        let patch = URLPatchDto { target_url: Some("http://api.internal/".into()), ..Default::default() };
+        let err = service.patch_url("s1".into(), patch).await.expect_err("blocked");
+        assert!(matches!(err, ManageURLError::PolicyViolation(PolicyViolation::Blocked { .. })));
+
+        let patch = URLPatchDto { target_url: Some("HTTPS://New.Example".into()), title: Some("New".into()), ..Default::default() };
+        let updated = service.patch_url("s1".into(), patch).await.expect("patched");
+        assert_eq!(updated.target_url, "https://new.example/");
+        assert_eq!(updated.title.as_deref(), Some("New"));
+    }
+
+    #[tokio::test]
+    async fn folders_are_trimmed_cleared_and_bounded() {
+        let url = URL { key: "k1".into(), secret_key: "s1".into(), target_url: "http://t/".into(), is_active: true, ..Default::default() };
+        let service = URLService::new(Arc::new(FakeURLRepo::new(Some(url))));
+
+        let patch = |folder: &str| URLPatchDto { folder: Some(folder.into()), ..Default::default() };
+        let updated = service.patch_url("s1".into(), patch("  Spring launch ")).await.unwrap();
+        assert_eq!(updated.folder.as_deref(), Some("Spring launch"));
+        assert!(matches!(service.patch_url("s1".into(), patch(&"x".repeat(101))).await, Err(ManageURLError::InvalidFolder)));
+        assert_eq!(service.patch_url("s1".into(), patch("")).await.unwrap().folder, None);
+    }
+
+    struct FakeFetcher;
+
+    #[async_trait]
+    impl MetadataFetcherPort for FakeFetcher {
+        async fn fetch(&self, target_url: &str) -> Result<LinkMetadata, MetadataFetchError> {
+            match target_url {
+                "http://x" => Ok(LinkMetadata {
+                    title: Some("D".repeat(300)),
+                    description: Some("Reference docs".into()),
+                    favicon_url: Some("http://docs/favicon.ico".into()),
+                }),
+                _ => Err(MetadataFetchError::Timeout),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn metadata_of_untitled_links_is_fetched_in_the_background() {
+        let repo = Arc::new(FakeURLRepo::new(None));
+        let service = URLService::new(repo.clone()).with_metadata_fetcher(Arc::new(FakeFetcher));
+        let base = URLBaseDto { target_url: "http://x".into(), api_key: "valid".into(), description: Some("Mine".into()), ..Default::default() };
+        let created = service.create_url(base).await.unwrap();
+        assert_eq!(created.title, None, "the link is returned before the fetch completes");
+
+        let mut stored = None;
+        for _ in 0..100 {
+            tokio::task::yield_now().await;
+            stored = repo.url_opt.lock().unwrap().clone().filter(|url| url.favicon_url.is_some());
+            if stored.is_some() {
+                break;
+            }
+        }
+        let stored = stored.expect("metadata stored");
+        assert_eq!(stored.title.map(|title| title.chars().count()), Some(MAX_TITLE_LEN));
+        assert_eq!(stored.description.as_deref(), Some("Mine"), "the owner's description is kept");
+        assert_eq!(stored.favicon_url.as_deref(), Some("http://docs/favicon.ico"));
+    }
+
+    struct CountingFetcher(AtomicUsize);
+
+    #[async_trait]
+    impl MetadataFetcherPort for CountingFetcher {
+        async fn fetch(&self, _target_url: &str) -> Result<LinkMetadata, MetadataFetchError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Err(MetadataFetchError::Timeout)
+        }
+    }
+
+    #[tokio::test]
+    async fn metadata_is_fetched_once_for_reused_links() {
+        let repo = Arc::new(FakeURLRepo::new(None));
+        let fetcher = Arc::new(CountingFetcher(AtomicUsize::new(0)));
+        let service = URLService::new(repo.clone()).with_metadata_fetcher(fetcher.clone());
+        let base = URLBaseDto { target_url: "http://x".into(), api_key: "valid".into(), ..Default::default() };
+        let created = service.create_url(base.clone()).await.unwrap();
+        assert_eq!(service.create_url(base).await.unwrap().key, created.key, "the link is reused");
+
+        for _ in 0..100 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(fetcher.0.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn click_limited_and_deactivated_links_use_their_fallback() {
+        let repo = Arc::new(FakeURLRepo::new(None));
+        let service = URLService::new(repo.clone());
+        let mut base = URLBaseDto { target_url: "http://x".into(), api_key: "valid".into(), max_clicks: Some(0), ..Default::default() };
+        assert!(service.create_url(base.clone()).await.unwrap_err().to_string().contains("max_clicks"));
+        base.max_clicks = Some(2);
+        let created = service.create_url(base).await.unwrap();
+        assert_eq!((created.max_clicks, created.fallback_url.as_deref()), (Some(2), None));
+
+        let visit = || service.forward_to_target_url("k1".into(), VisitContext::default());
+        assert_eq!(visit().await.unwrap(), ForwardOutcome::Redirect("http://x".into()));
+        assert_eq!(visit().await.unwrap(), ForwardOutcome::Redirect("http://x".into()));
+        assert_eq!(visit().await.unwrap(), ForwardOutcome::Gone, "over its click limit");
+
+        let patch = URLPatchDto { fallback_url: Some("HTTP://Example.com".into()), ..Default::default() };
+        let patched = service.patch_url("s1".into(), patch).await.unwrap();
+        assert_eq!(patched.fallback_url.as_deref(), Some("http://example.com/"));
+        assert_eq!(visit().await.unwrap(), ForwardOutcome::Fallback("http://example.com/".into()));
+        assert_eq!(repo.url_opt.lock().unwrap().as_ref().map(|url| url.clicks), Some(2), "fallback visits are not counted");
+
+        let patch = URLPatchDto { max_clicks: Some(0), is_active: Some(false), ..Default::default() };
+        assert_eq!(service.patch_url("s1".into(), patch).await.unwrap().max_clicks, None);
+        assert_eq!(visit().await.unwrap(), ForwardOutcome::Fallback("http://example.com/".into()), "deactivated");
+        let patch = URLPatchDto { fallback_url: Some("http://10.0.0.1/".into()), ..Default::default() };
+        assert!(matches!(service.patch_url("s1".into(), patch).await, Err(ManageURLError::PolicyViolation(_))));
+    }
+
+    #[tokio::test]
+    async fn unknown_keys_are_not_found_and_counted() {
+        let repo = Arc::new(FakeURLRepo::new(None));
+        let service = URLService::new(repo.clone());
+        let outcome = service.forward_to_target_url("typo".into(), VisitContext::default()).await.unwrap();
+        assert_eq!(outcome, ForwardOutcome::NotFound);
+        service.forward_to_target_url("x".repeat(200), VisitContext::default()).await.unwrap();
+        assert_eq!(*repo.unknown_keys.lock().unwrap(), vec!["typo".to_string()], "overlong keys are not recorded");
+    }
+
+    struct DeadProbe;
+
+    #[async_trait]
+    impl LinkProbePort for DeadProbe {
+        async fn probe(&self, target_url: &str) -> LinkCheck {
+            LinkCheck { status_code: Some(404), final_url: Some(target_url.into()), latency_ms: 5, error: None, checked_at: Utc::now() }
+        }
+    }
+
+    #[tokio::test]
+    async fn links_failing_their_checks_are_flagged_and_sent_to_the_fallback() {
+        let url = URL { key: "k1".into(), target_url: "http://target".into(), is_active: true, user_id: 1, ..Default::default() };
+        let repo = Arc::new(FakeURLRepo::new(Some(url)));
+        let service = URLService::new(repo.clone())
+            .with_link_probe(Arc::new(DeadProbe))
+            .with_dead_link_policy(DeadLinkPolicy { failure_threshold: 2, fallback_url: Some("http://fallback".into()) });
+        let due_now = || Utc::now() + chrono::Duration::seconds(1);
+
+        assert_eq!(service.check_link_health(due_now()).await.unwrap(), 1);
+        assert_eq!(service.check_link_health(Utc::now() - chrono::Duration::hours(1)).await.unwrap(), 0, "checked recently");
+        assert!(service.list_broken_urls().await.unwrap().is_empty());
+        let visit = service.forward_to_target_url("k1".into(), VisitContext::default()).await.unwrap();
+        assert_eq!(visit, ForwardOutcome::Redirect("http://target".into()));
+
+        service.check_link_health(due_now()).await.unwrap();
+        let broken = service.list_broken_urls().await.unwrap();
+        assert_eq!(broken.iter().map(|url| (url.key.as_str(), url.last_check_status)).collect::<Vec<_>>(), vec![("k1", Some(404))]);
+        let visit = service.forward_to_target_url("k1".into(), VisitContext::default()).await.unwrap();
+        assert_eq!(visit, ForwardOutcome::Redirect("http://fallback".into()));
+        assert!(*repo.increment_called.lock().unwrap(), "fallback visits still count");
+    }
+
+    #[tokio::test]
+    async fn description_and_notes_are_set_on_create_and_patch() {
+        let repo = Arc::new(FakeURLRepo::new(None));
+        let service = URLService::new(repo.clone());
+        let base = URLBaseDto {
+            target_url: "http://x".into(),
+            api_key: "valid".into(),
+            title: Some("  Pricing ".into()),
+            description: Some(" Pricing page ".into()),
+            notes: Some("asked by sales".into()),
+            ..Default::default()
+        };
+        let created = service.create_url(base.clone()).await.unwrap();
+        assert_eq!(created.title.as_deref(), Some("Pricing"));
+        assert_eq!((created.description.as_deref(), created.notes.as_deref()), (Some("Pricing page"), Some("asked by sales")));
+        let too_long = URLBaseDto { description: Some("x".repeat(MAX_DESCRIPTION_LEN + 1)), ..base.clone() };
+        assert_eq!(service.create_url(too_long).await.expect_err("too long").code(), 400);
+        let too_long = URLBaseDto { title: Some("t".repeat(MAX_TITLE_LEN + 1)), ..base };
+        assert_eq!(service.create_url(too_long).await.expect_err("too long").code(), 400);
+
+        let patch = URLPatchDto { notes: Some(String::new()), description: Some("Prices".into()), ..Default::default() };
+        let updated = service.patch_url(created.secret_key.clone(), patch).await.unwrap();
+        assert_eq!((updated.description.as_deref(), updated.notes), (Some("Prices"), None));
+        let patch = URLPatchDto { title: Some("   ".into()), ..Default::default() };
+        assert_eq!(service.patch_url(created.secret_key.clone(), patch).await.unwrap().title, None);
+        let patch = URLPatchDto { title: Some("t".repeat(MAX_TITLE_LEN + 1)), ..Default::default() };
+        assert!(matches!(service.patch_url(created.secret_key.clone(), patch).await, Err(ManageURLError::TooLong { field: "title", .. })));
+        let patch = URLPatchDto { notes: Some("n".repeat(MAX_NOTES_LEN + 1)), ..Default::default() };
+        assert!(matches!(service.patch_url(created.secret_key, patch).await, Err(ManageURLError::TooLong { field: "notes", .. })));
+    }
+
+    #[tokio::test]
+    async fn rescan_deactivates_links_violating_a_stricter_policy() {
+        let url = URL { key: "k1".into(), target_url: "https://phish.example/login".into(), is_active: true, ..Default::default() };
+        let repo = Arc::new(FakeURLRepo::new(Some(url)));
+        let lenient = URLService::new(repo.clone());
+        assert_eq!(lenient.rescan_link_policy().await.unwrap(), PolicyRescanReport { scanned: 1, deactivated: vec![] });
+
+        let strict = PolicyRules::builtin().merged(PolicyRules::parse("block phish.example").unwrap());
+        let service = URLService::new(repo.clone()).with_link_policy(Arc::new(strict));
+        let report = service.rescan_link_policy().await.unwrap();
+        assert_eq!(report.deactivated, vec!["k1".to_string()]);
+        assert!(!repo.url_opt.lock().unwrap().as_ref().unwrap().is_active);
+    }
+
+    #[tokio::test]
+    async fn owners_and_admins_manage_links_by_public_key() {
+        let url = URL { key: "k1".into(), secret_key: "s1".into(), target_url: "http://t/".into(), is_active: true, user_id: 7, ..Default::default() };
+        let repo = Arc::new(FakeURLRepo::new(Some(url)));
+        let service = URLService::new(repo.clone());
+
+        assert!(matches!(service.get_managed_url(Caller::User(8), "k1".into()).await, Err(ManageURLError::Forbidden)));
+        let (owned, access) = service.get_managed_url(Caller::User(7), "k1".into()).await.unwrap();
+        assert_eq!((owned.key.as_str(), access), ("k1", LinkAccess::Write));
+        let patch = URLPatchDto { is_active: Some(false), ..Default::default() };
+        assert!(!service.patch_managed_url(Caller::Admin, "k1".into(), patch).await.unwrap().is_active);
+        assert!(matches!(service.delete_managed_url(Caller::User(8), "k1".into()).await, Err(ManageURLError::Forbidden)));
+        service.delete_managed_url(Caller::User(7), "k1".into()).await.expect("owner deletes");
+        assert!(matches!(service.get_managed_url(Caller::Admin, "k1".into()).await, Err(ManageURLError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn workspace_roles_decide_link_access() {
+        let url = URL { key: "k1".into(), secret_key: "s1".into(), target_url: "http://t/".into(), is_active: true, user_id: 7, org_id: Some(3), ..Default::default() };
+        let repo = Arc::new(FakeURLRepo::new(Some(url)));
+        repo.roles.lock().unwrap().extend([(3, 1, Role::Editor), (3, 2, Role::Viewer)]);
+        let service = URLService::new(repo.clone());
+
+        // the creator left the workspace: the link is no longer theirs
+        assert!(matches!(service.get_managed_url(Caller::User(7), "k1".into()).await, Err(ManageURLError::Forbidden)));
+        let (urls, access) = service.list_org_urls(Caller::User(2), 3, Default::default()).await.unwrap();
+        assert_eq!((urls.len(), access), (1, LinkAccess::Read));
+        assert_eq!(service.list_org_urls(Caller::User(1), 3, Default::default()).await.unwrap().1, LinkAccess::Write);
+        assert!(matches!(service.list_org_urls(Caller::User(7), 3, Default::default()).await, Err(ManageURLError::Forbidden)));
+
+        assert_eq!(service.get_managed_url(Caller::User(2), "k1".into()).await.unwrap().1, LinkAccess::Read);
+        assert_eq!(service.get_managed_url(Caller::Admin, "k1".into()).await.unwrap().1, LinkAccess::Write);
+        let patch = URLPatchDto { title: Some("Launch".into()), ..Default::default() };
+        assert!(matches!(service.patch_managed_url(Caller::User(2), "k1".into(), patch.clone()).await, Err(ManageURLError::Forbidden)));
+        assert_eq!(service.patch_managed_url(Caller::User(1), "k1".into(), patch).await.unwrap().title.as_deref(), Some("Launch"));
+    }
+
+    #[tokio::test]
+    async fn create_url_in_organization_requires_editor_role() {
+        let repo = Arc::new(FakeURLRepo::new(None));
+        let service = URLService::new(repo.clone());
+        let base = URLBaseDto { target_url: "http://x".into(), api_key: "valid".into(), org_id: Some(3), ..Default::default() };
+
+        let err = service.create_url(base.clone()).await.expect_err("not a member");
+        assert_eq!(err.code(), 403);
+        repo.roles.lock().unwrap().push((3, 1, Role::Viewer));
+        assert_eq!(service.create_url(base.clone()).await.expect_err("viewer").code(), 403);
+        repo.roles.lock().unwrap().push((3, 1, Role::Editor));
+        assert_eq!(service.create_url(base).await.unwrap().org_id, Some(3));
     }
 }
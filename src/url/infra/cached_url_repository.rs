@@ -0,0 +1,417 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use crate::org::domain::models::organization::Role;
+use crate::url::domain::models::cache::CacheStats;
+use crate::url::domain::models::filter::URLFilter;
+use crate::url::domain::models::health::LinkCheck;
+use crate::url::domain::models::metadata::LinkMetadata;
+use crate::url::domain::models::schema::{ClickRecord, GeoRule, UnknownKeyHits, URL};
+use crate::url::domain::ports::cache_stats_port::CacheStatsPort;
+use crate::url::domain::ports::link_cache_port::LinkCachePort;
+use crate::url::domain::repositories::url_repository_port::URLRepositoryPort;
+use crate::user::domain::models::plan::LinkUsage;
+use sqlx::Error;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// `URLRepositoryPort` decorator keeping recently visited links in memory.
+///
+/// Lookups by public key (`get_db_url_by_key`, `find_url_by_key`) are answered from a bounded
+/// LRU of link rows, unknown keys included, so hot redirects skip the database. Entries live at
+/// most `ttl`, which bounds how stale they get when other instances write to the same database.
+/// Writes through this decorator drop the entry of the link they touch; click counts are bumped
+/// in place so click limits keep working. Writes to links made elsewhere in the process (by the
+/// user repository) clear it through `LinkCachePort`. Every other call goes straight to the
+/// wrapped adapter.
+pub struct CachedURLRepository {
+    inner: Arc<dyn URLRepositoryPort + Send + Sync>,
+    ttl: Duration,
+    cache: Mutex<LinkCache>,
+}
+
+struct CachedLink {
+    /// `None` when no link has the key.
+    url: Option<URL>,
+    expires_at: Instant,
+    used_at: u64,
+}
+
+struct LinkCache {
+    capacity: usize,
+    links: HashMap<String, CachedLink>,
+    /// Keys by last use, least recently used first.
+    recency: BTreeMap<u64, String>,
+    clock: u64,
+    /// Bumped on every write, so lookups racing with it do not store the row they read before.
+    generation: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl LinkCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            links: HashMap::new(),
+            recency: BTreeMap::new(),
+            clock: 0,
+            generation: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Cached row of `key` (`Some(None)` for a known unknown key), `None` on a miss.
+    fn get(&mut self, key: &str) -> Option<Option<URL>> {
+        let link = self.links.get_mut(key)?;
+        if link.expires_at <= Instant::now() {
+            self.remove(key);
+            return None;
+        }
+        self.recency.remove(&link.used_at);
+        self.clock += 1;
+        link.used_at = self.clock;
+        self.recency.insert(self.clock, key.to_string());
+        Some(link.url.clone())
+    }
+
+    fn insert(&mut self, key: &str, url: Option<URL>, ttl: Duration) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.remove(key);
+        while self.links.len() >= self.capacity {
+            let Some((_, oldest)) = self.recency.pop_first() else { break };
+            self.links.remove(&oldest);
+        }
+        self.clock += 1;
+        self.recency.insert(self.clock, key.to_string());
+        self.links.insert(key.to_string(), CachedLink { url, expires_at: Instant::now() + ttl, used_at: self.clock });
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(link) = self.links.remove(key) {
+            self.recency.remove(&link.used_at);
+        }
+    }
+}
+
+impl CachedURLRepository {
+    /// Cache up to `capacity` links for `ttl` in front of `inner`.
+    pub fn new(inner: Arc<dyn URLRepositoryPort + Send + Sync>, capacity: usize, ttl: Duration) -> Self {
+        Self { inner, ttl, cache: Mutex::new(LinkCache::new(capacity)) }
+    }
+
+    fn cache(&self) -> MutexGuard<'_, LinkCache> {
+        self.cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Row of `url_key` whatever its state, `None` when there is no such link.
+    async fn lookup(&self, url_key: &str) -> Result<Option<URL>, Error> {
+        let generation = {
+            let mut cache = self.cache();
+            if let Some(url) = cache.get(url_key) {
+                cache.hits += 1;
+                return Ok(url);
+            }
+            cache.misses += 1;
+            cache.generation
+        };
+        let url = match self.inner.find_url_by_key(url_key.to_string()).await {
+            Ok(url) => Some(url),
+            Err(Error::RowNotFound) => None,
+            Err(err) => return Err(err),
+        };
+        let mut cache = self.cache();
+        if cache.generation == generation {
+            cache.insert(url_key, url.clone(), self.ttl);
+        }
+        Ok(url)
+    }
+
+    fn invalidate(&self, url_key: &str) {
+        let mut cache = self.cache();
+        cache.generation += 1;
+        cache.remove(url_key);
+    }
+
+    /// Apply `change` to the cached row of `url_key`, if any, after the database was written.
+    fn update_cached(&self, url_key: &str, change: impl FnOnce(&mut URL)) {
+        let mut cache = self.cache();
+        cache.generation += 1;
+        if let Some(url) = cache.links.get_mut(url_key).and_then(|link| link.url.as_mut()) {
+            change(url);
+        }
+    }
+}
+
+#[async_trait]
+impl LinkCachePort for CachedURLRepository {
+    async fn clear(&self) {
+        let mut cache = self.cache();
+        cache.generation += 1;
+        cache.links.clear();
+        cache.recency.clear();
+    }
+}
+
+impl CacheStatsPort for CachedURLRepository {
+    fn cache_stats(&self) -> CacheStats {
+        let cache = self.cache();
+        CacheStats { hits: cache.hits, misses: cache.misses, entries: cache.links.len(), capacity: cache.capacity }
+    }
+}
+
+#[async_trait]
+impl URLRepositoryPort for CachedURLRepository {
+    async fn create_url(
+        &self, target_url: String, user_id: i32, org_id: Option<i64>, custom_key: Option<String>, reuse_existing: bool,
+    ) -> Result<URL, Error> {
+        let url = self.inner.create_url(target_url, user_id, org_id, custom_key, reuse_existing).await?;
+        // The key may have been cached as unknown.
+        self.invalidate(&url.key);
+        Ok(url)
+    }
+
+    async fn key_exists(&self, key: String) -> Result<bool, Error> {
+        self.inner.key_exists(key).await
+    }
+
+    async fn get_db_url_by_key(&self, url_key: String) -> Result<URL, Error> {
+        self.lookup(&url_key).await?.filter(|url| url.is_active).ok_or(Error::RowNotFound)
+    }
+
+    async fn find_url_by_key(&self, url_key: String) -> Result<URL, Error> {
+        self.lookup(&url_key).await?.ok_or(Error::RowNotFound)
+    }
+
+    async fn find_url_by_secret_key(&self, secret_key: String) -> Result<URL, Error> {
+        self.inner.find_url_by_secret_key(secret_key).await
+    }
+
+    async fn deactivate_url(&self, url_key: String) -> Result<bool, Error> {
+        let deactivated = self.inner.deactivate_url(url_key.clone()).await;
+        self.invalidate(&url_key);
+        deactivated
+    }
+
+    async fn delete_url(&self, url_key: String) -> Result<bool, Error> {
+        let deleted = self.inner.delete_url(url_key.clone()).await;
+        self.invalidate(&url_key);
+        deleted
+    }
+
+    async fn get_db_url_by_user_and_target_url(&self, user_id: i32, target_url: String) -> Result<URL, Error> {
+        self.inner.get_db_url_by_user_and_target_url(user_id, target_url).await
+    }
+
+    async fn get_user_by_apy_key(&self, api_key: String) -> Result<i32, ()> {
+        self.inner.get_user_by_apy_key(api_key).await
+    }
+
+    async fn increment_clicks(&self, url_key: String) -> sqlx::Result<bool> {
+        let counted = self.inner.increment_clicks(url_key.clone()).await?;
+        if counted {
+            self.update_cached(&url_key, |url| url.clicks += 1);
+        } else {
+            // the cached row is behind the click limit
+            self.invalidate(&url_key);
+        }
+        Ok(counted)
+    }
+
+    async fn increment_interstitial_clicks(&self, url_key: String) -> sqlx::Result<bool> {
+        let counted = self.inner.increment_interstitial_clicks(url_key.clone()).await?;
+        if counted {
+            self.update_cached(&url_key, |url| url.interstitial_clicks += 1);
+        } else {
+            self.invalidate(&url_key);
+        }
+        Ok(counted)
+    }
+
+    async fn add_clicks(&self, url_key: String, clicks: i32, interstitial_clicks: i32) -> sqlx::Result<()> {
+        self.inner.add_clicks(url_key.clone(), clicks, interstitial_clicks).await?;
+        self.update_cached(&url_key, |url| {
+            url.clicks += clicks;
+            url.interstitial_clicks += interstitial_clicks;
+        });
+        Ok(())
+    }
+
+    async fn update_url(&self, url: URL) -> Result<URL, Error> {
+        let url_key = url.key.clone();
+        let updated = self.inner.update_url(url).await;
+        self.invalidate(&url_key);
+        updated
+    }
+
+    async fn set_geo_rules(&self, url_key: String, rules: Vec<GeoRule>) -> sqlx::Result<()> {
+        self.inner.set_geo_rules(url_key, rules).await
+    }
+
+    async fn get_geo_rules(&self, url_key: String) -> Result<Vec<GeoRule>, Error> {
+        self.inner.get_geo_rules(url_key).await
+    }
+
+    async fn set_link_metadata(&self, url_key: String, metadata: LinkMetadata) -> Result<(), Error> {
+        let stored = self.inner.set_link_metadata(url_key.clone(), metadata).await;
+        self.invalidate(&url_key);
+        stored
+    }
+
+    async fn record_link_check(&self, url_key: String, check: LinkCheck) -> Result<(), Error> {
+        let recorded = self.inner.record_link_check(url_key.clone(), check).await;
+        self.invalidate(&url_key);
+        recorded
+    }
+
+    async fn record_unknown_key(&self, key: String, max_keys: i64) -> Result<(), Error> {
+        self.inner.record_unknown_key(key, max_keys).await
+    }
+
+    async fn list_unknown_keys(&self, limit: i64) -> Result<Vec<UnknownKeyHits>, Error> {
+        self.inner.list_unknown_keys(limit).await
+    }
+
+    async fn record_click(&self, click: ClickRecord) -> sqlx::Result<()> {
+        self.inner.record_click(click).await
+    }
+
+    async fn list_urls(&self, filter: URLFilter) -> Result<Vec<URL>, Error> {
+        self.inner.list_urls(filter).await
+    }
+
+    async fn get_member_role(&self, org_id: i64, user_id: i32) -> Result<Option<Role>, Error> {
+        self.inner.get_member_role(org_id, user_id).await
+    }
+
+    async fn get_user_plan(&self, user_id: i32) -> Result<Option<String>, Error> {
+        self.inner.get_user_plan(user_id).await
+    }
+
+    async fn get_link_usage(&self, user_id: i32, since: DateTime<Utc>) -> Result<LinkUsage, Error> {
+        self.inner.get_link_usage(user_id, since).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::memory::MemoryDatabase;
+    use crate::shared::repository_conformance::{check_repositories, sqlite_pool, Repositories};
+    use crate::url::infra::memory_url_repository::MemoryURLRepository;
+    use crate::user::application::dtos::user_dto::UserDtoCreate;
+    use crate::user::domain::models::ownership::DeleteUserStrategy;
+    use crate::user::domain::repositories::user_repository_port::UserRepositoryPort;
+    use crate::user::infra::link_cache_user_repository::LinkCacheUserRepository;
+    use crate::user::infra::memory_user_repository::MemoryUserRepository;
+    use crate::user::infra::sqlx_user_repository::SqlxUserRepository;
+    use crate::url::infra::sqlx_url_repository::SqlxURLRepository;
+    use sqlx::{Executor, SqlitePool};
+
+    async fn cached_repo(capacity: usize, ttl: Duration) -> Result<(SqlitePool, CachedURLRepository), sqlx::Error> {
+        let pool = sqlite_pool().await;
+        pool.execute("INSERT INTO users (id, username, email, api_key) VALUES (1, 'alice', 'a@x.com', 'ka')").await?;
+        pool.execute("INSERT INTO urls (key, secret_key, target_url, is_active, clicks, user_id) VALUES ('K1','SK1','http://a',1,0,1), ('K2','SK2','http://b',1,0,1)").await?;
+        let inner = Arc::new(SqlxURLRepository::new(pool.clone()).await);
+        Ok((pool, CachedURLRepository::new(inner, capacity, ttl)))
+    }
+
+    #[tokio::test]
+    async fn lookups_are_cached_until_the_link_is_written() -> Result<(), Box<dyn std::error::Error>> {
+        let (pool, repo) = cached_repo(10, Duration::from_secs(60)).await?;
+        assert_eq!(repo.get_db_url_by_key("K1".into()).await?.target_url, "http://a");
+        // Written behind the cache's back: still served from memory.
+        pool.execute("UPDATE urls SET target_url = 'http://changed' WHERE key = 'K1'").await?;
+        assert_eq!(repo.get_db_url_by_key("K1".into()).await?.target_url, "http://a");
+        assert_eq!(repo.cache_stats(), CacheStats { hits: 1, misses: 1, entries: 1, capacity: 10 });
+
+        repo.increment_clicks("K1".into()).await?;
+        assert_eq!(repo.find_url_by_key("K1".into()).await?.clicks, 1);
+
+        assert!(repo.deactivate_url("K1".into()).await?);
+        assert!(matches!(repo.get_db_url_by_key("K1".into()).await, Err(Error::RowNotFound)));
+        let url = repo.find_url_by_key("K1".into()).await?;
+        assert_eq!(url.target_url, "http://changed");
+        assert!(!url.is_active);
+
+        let url = repo.update_url(URL { title: Some("Renamed".into()), ..url }).await?;
+        assert_eq!(repo.find_url_by_key("K1".into()).await?.title, url.title);
+
+        assert!(repo.delete_url("K1".into()).await?);
+        assert!(matches!(repo.find_url_by_key("K1".into()).await, Err(Error::RowNotFound)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unknown_keys_are_cached_until_created() -> Result<(), Box<dyn std::error::Error>> {
+        let (_pool, repo) = cached_repo(10, Duration::from_secs(60)).await?;
+        assert!(matches!(repo.get_db_url_by_key("promo".into()).await, Err(Error::RowNotFound)));
+        assert!(matches!(repo.get_db_url_by_key("promo".into()).await, Err(Error::RowNotFound)));
+        assert_eq!(repo.cache_stats().hits, 1);
+
+        repo.create_url("http://promo".into(), 1, None, Some("promo".into()), false).await?;
+        assert_eq!(repo.get_db_url_by_key("promo".into()).await?.target_url, "http://promo");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn least_recently_used_and_expired_links_are_dropped() -> Result<(), Box<dyn std::error::Error>> {
+        let (_pool, repo) = cached_repo(2, Duration::from_secs(60)).await?;
+        repo.find_url_by_key("K1".into()).await?;
+        repo.find_url_by_key("K2".into()).await?;
+        repo.find_url_by_key("K1".into()).await?;
+        // Evicts K2, the least recently used.
+        let _ = repo.find_url_by_key("K3".into()).await;
+        repo.find_url_by_key("K1".into()).await?;
+        repo.find_url_by_key("K2".into()).await?;
+        assert_eq!(repo.cache_stats(), CacheStats { hits: 2, misses: 4, entries: 2, capacity: 2 });
+
+        let (_pool, repo) = cached_repo(2, Duration::from_millis(10)).await?;
+        repo.find_url_by_key("K1".into()).await?;
+        tokio::time::sleep(Duration::from_millis(15)).await;
+        repo.find_url_by_key("K1".into()).await?;
+        assert_eq!(repo.cache_stats().misses, 2);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn user_writes_clear_cached_links() -> Result<(), Box<dyn std::error::Error>> {
+        let pool = sqlite_pool().await;
+        let urls = Arc::new(CachedURLRepository::new(Arc::new(SqlxURLRepository::new(pool.clone()).await), 10, Duration::from_secs(60)));
+        let users = LinkCacheUserRepository::new(Arc::new(SqlxUserRepository::new(pool).await), vec![urls.clone()]);
+        let new_user = |name: &str| UserDtoCreate { username: name.into(), email: format!("{name}@example.com") };
+        let alice = users.create_user(new_user("alice"), "ka".into()).await?.id as i32;
+        let bob = users.create_user(new_user("bob"), "kb".into()).await?.id as i32;
+        let key = urls.create_url("http://a.com/".into(), alice, None, None, false).await?.key;
+        assert_eq!(urls.get_db_url_by_key(key.clone()).await?.user_id, alice);
+
+        assert_eq!(users.transfer_links(alice, bob, None).await?, 1);
+        assert_eq!(urls.get_db_url_by_key(key.clone()).await?.user_id, bob);
+        assert_eq!(users.delete_user(bob, DeleteUserStrategy::Cascade).await?, 1);
+        assert!(matches!(urls.get_db_url_by_key(key).await, Err(Error::RowNotFound)));
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn passes_the_repository_conformance_suite_over_each_store() {
+        check_repositories(|| async {
+            let pool = sqlite_pool().await;
+            let inner = Arc::new(SqlxURLRepository::new(pool.clone()).await);
+            let urls = Arc::new(CachedURLRepository::new(inner, 100, Duration::from_secs(60)));
+            let users = LinkCacheUserRepository::new(Arc::new(SqlxUserRepository::new(pool).await), vec![urls.clone()]);
+            Repositories { urls, users: Arc::new(users) }
+        })
+        .await;
+        check_repositories(|| async {
+            let db = Arc::new(MemoryDatabase::new());
+            let inner = Arc::new(MemoryURLRepository::new(db.clone()));
+            let urls = Arc::new(CachedURLRepository::new(inner, 100, Duration::from_secs(60)));
+            let users = LinkCacheUserRepository::new(Arc::new(MemoryUserRepository::new(db)), vec![urls.clone()]);
+            Repositories { urls, users: Arc::new(users) }
+        })
+        .await;
+    }
+}
@@ -0,0 +1,109 @@
+use crate::url::domain::models::policy::{PolicyParseError, PolicyRules, PolicyViolation};
+use crate::url::domain::ports::link_policy_port::LinkPolicyPort;
+use log::{error, info};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant, SystemTime};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum PolicyLoadError {
+    #[error("cannot read policy file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid policy file: {0}")]
+    Parse(#[from] PolicyParseError),
+}
+
+/// How often the file modification time is looked at in production.
+pub const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+struct LoadedRules {
+    rules: Arc<PolicyRules>,
+    modified: Option<SystemTime>,
+    checked_at: Instant,
+}
+
+/// `LinkPolicyPort` adapter reading its rules from a local file, on top of the built-in ones.
+///
+/// The file modification time is checked lazily, at most once per `check_interval`, and the rules
+/// are reloaded when it changes. A file that fails to parse is reported and the previous rules
+/// stay in force.
+pub struct FileLinkPolicy {
+    path: PathBuf,
+    check_interval: Duration,
+    state: RwLock<LoadedRules>,
+}
+
+impl FileLinkPolicy {
+    pub fn open<P: AsRef<Path>>(path: P, check_interval: Duration) -> Result<Self, PolicyLoadError> {
+        let path = path.as_ref().to_path_buf();
+        let modified = std::fs::metadata(&path)?.modified().ok();
+        let rules = Arc::new(load_rules(&path)?);
+        Ok(Self { path, check_interval, state: RwLock::new(LoadedRules { rules, modified, checked_at: Instant::now() }) })
+    }
+
+    fn current_rules(&self) -> Arc<PolicyRules> {
+        {
+            let state = self.state.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if state.checked_at.elapsed() < self.check_interval {
+                return state.rules.clone();
+            }
+        }
+        let mut state = self.state.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        state.checked_at = Instant::now();
+        let modified = std::fs::metadata(&self.path).and_then(|meta| meta.modified()).ok();
+        if modified != state.modified {
+            state.modified = modified;
+            match load_rules(&self.path) {
+                Ok(rules) => {
+                    info!("Link policy reloaded from {}", self.path.display());
+                    state.rules = Arc::new(rules);
+                }
+                Err(err) => error!("Keeping previous link policy, {} failed to load: {}", self.path.display(), err),
+            }
+        }
+        state.rules.clone()
+    }
+}
+
+fn load_rules(path: &Path) -> Result<PolicyRules, PolicyLoadError> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(PolicyRules::builtin().merged(PolicyRules::parse(&text)?))
+}
+
+impl LinkPolicyPort for FileLinkPolicy {
+    fn check(&self, target_url: &str) -> Result<(), PolicyViolation> {
+        self.current_rules().evaluate(target_url)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+
+    #[test]
+    fn reloads_rules_when_file_changes_and_keeps_them_on_errors() {
+        let path = std::env::temp_dir().join(format!("link-policy-{}.rules", std::process::id()));
+        std::fs::write(&path, "block phish.example\n").unwrap();
+        let policy = FileLinkPolicy::open(&path, Duration::ZERO).expect("load");
+        assert!(policy.check("https://phish.example/").is_err());
+        assert!(policy.check("https://other.example/").is_ok());
+        assert!(policy.check("http://localhost/").is_err(), "built-in rules always apply");
+
+        let touch = |contents: &str, secs: u64| {
+            std::fs::write(&path, contents).unwrap();
+            File::options().write(true).open(&path).unwrap()
+                .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs)).unwrap();
+        };
+        touch("block other.example\n", 1_000);
+        assert!(policy.check("https://phish.example/").is_ok());
+        assert!(policy.check("https://other.example/").is_err());
+
+        touch("nonsense\n", 2_000);
+        assert!(policy.check("https://other.example/").is_err(), "broken file keeps the last good rules");
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(FileLinkPolicy::open(&path, DEFAULT_CHECK_INTERVAL).is_err());
+    }
+}
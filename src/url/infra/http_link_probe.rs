@@ -0,0 +1,126 @@
+use crate::url::domain::models::health::LinkCheck;
+use crate::url::domain::ports::link_policy_port::LinkPolicyPort;
+use crate::url::domain::ports::link_probe_port::LinkProbePort;
+use crate::url::infra::policy_resolver::PolicyResolver;
+use async_trait::async_trait;
+use chrono::Utc;
+use reqwest::header::LOCATION;
+use reqwest::redirect;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use url::Url;
+
+/// Redirects followed before the destination is considered broken.
+const MAX_REDIRECTS: usize = 10;
+
+/// `LinkProbePort` adapter issuing a `GET` to the destination and following its redirects.
+///
+/// Only the status line and headers are waited for, the body is never read. The same SSRF
+/// guards as `HttpMetadataFetcher` apply: the link policy is checked on every hop and every
+/// resolved address, and proxies from the environment are ignored.
+pub struct HttpLinkProbe {
+    client: reqwest::Client,
+    policy: Arc<dyn LinkPolicyPort>,
+}
+
+impl HttpLinkProbe {
+    pub fn new(policy: Arc<dyn LinkPolicyPort>, timeout: Duration) -> reqwest::Result<Self> {
+        let client = reqwest::Client::builder()
+            .redirect(redirect::Policy::none())
+            .no_proxy()
+            .timeout(timeout)
+            .dns_resolver(Arc::new(PolicyResolver::new(Arc::clone(&policy))))
+            .user_agent(concat!("shortener/", env!("CARGO_PKG_VERSION"), " (link checker)"))
+            .build()?;
+        Ok(Self { client, policy })
+    }
+
+    /// Final status and URL of the redirect chain starting at `url`.
+    async fn follow(&self, mut url: Url) -> (Option<u16>, Url, Option<String>) {
+        for _ in 0..=MAX_REDIRECTS {
+            if !matches!(url.scheme(), "http" | "https") {
+                return (None, url, Some("only http and https destinations are checked".into()));
+            }
+            if let Err(violation) = self.policy.check(url.as_str()) {
+                return (None, url, Some(violation.to_string()));
+            }
+            let response = match self.client.get(url.clone()).send().await {
+                Ok(response) => response,
+                Err(err) if err.is_timeout() => return (None, url, Some("request timed out".into())),
+                Err(err) => return (None, url, Some(format!("request failed: {err}"))),
+            };
+            let status = response.status();
+            if !status.is_redirection() {
+                return (Some(status.as_u16()), url, None);
+            }
+            let location = response.headers().get(LOCATION).and_then(|value| value.to_str().ok());
+            match location.and_then(|location| url.join(location).ok()) {
+                Some(next) => url = next,
+                None => return (Some(status.as_u16()), url, None),
+            }
+        }
+        (None, url, Some("too many redirects".into()))
+    }
+}
+
+#[async_trait]
+impl LinkProbePort for HttpLinkProbe {
+    async fn probe(&self, target_url: &str) -> LinkCheck {
+        let checked_at = Utc::now();
+        let started = Instant::now();
+        let (status_code, final_url, error) = match Url::parse(target_url) {
+            Ok(url) => {
+                let (status_code, final_url, error) = self.follow(url).await;
+                (status_code, Some(final_url.to_string()), error)
+            }
+            Err(err) => (None, None, Some(format!("invalid URL: {err}"))),
+        };
+        let latency_ms = started.elapsed().as_millis().try_into().unwrap_or(i64::MAX);
+        LinkCheck { status_code, final_url, latency_ms, error, checked_at }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::url::domain::models::policy::PolicyRules;
+    use crate::url::infra::test_server::serve;
+
+    fn probe(policy: PolicyRules) -> HttpLinkProbe {
+        HttpLinkProbe::new(Arc::new(policy), Duration::from_secs(5)).unwrap()
+    }
+
+    #[tokio::test]
+    async fn records_final_status_and_url_after_redirects() {
+        let addr = serve(vec![
+            "HTTP/1.1 301 Moved Permanently\r\nLocation: /moved\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok".to_string(),
+            "HTTP/1.1 410 Gone\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+        ])
+        .await;
+        let probe = probe(PolicyRules::default());
+
+        let check = probe.probe(&format!("http://{addr}/start")).await;
+        assert_eq!(check.status_code, Some(200));
+        assert_eq!(check.final_url, Some(format!("http://{addr}/moved")));
+        assert!(check.error.is_none() && !check.is_failure());
+        assert!(check.latency_ms >= 0);
+
+        let check = probe.probe(&format!("http://{addr}/old")).await;
+        assert_eq!(check.status_code, Some(410));
+        assert!(check.is_failure());
+    }
+
+    #[tokio::test]
+    async fn unreachable_and_blocked_destinations_fail() {
+        // nothing listens on the discard port
+        let check = probe(PolicyRules::default()).probe("http://127.0.0.1:9/").await;
+        assert_eq!(check.status_code, None);
+        assert!(check.error.as_deref().is_some_and(|error| error.starts_with("request failed")), "{check:?}");
+
+        let check = probe(PolicyRules::builtin()).probe("http://127.0.0.1:9/").await;
+        assert!(check.is_failure());
+        assert_eq!(check.final_url.as_deref(), Some("http://127.0.0.1:9/"));
+        assert!(!check.error.unwrap().starts_with("request failed"), "refused before connecting");
+    }
+}
@@ -0,0 +1,307 @@
+use crate::url::domain::models::metadata::{LinkMetadata, MetadataFetchError};
+use crate::url::domain::ports::link_policy_port::LinkPolicyPort;
+use crate::url::domain::ports::metadata_fetcher_port::MetadataFetcherPort;
+use crate::url::infra::policy_resolver::PolicyResolver;
+use async_trait::async_trait;
+use reqwest::header::{ACCEPT, CONTENT_TYPE, LOCATION};
+use reqwest::redirect;
+use std::sync::Arc;
+use std::time::Duration;
+use url::Url;
+
+/// Redirects followed before giving up; each hop is checked against the link policy.
+const MAX_REDIRECTS: usize = 5;
+
+/// `MetadataFetcherPort` adapter reading the `<head>` of the destination over HTTP(S).
+///
+/// Against SSRF, every URL of the redirect chain and every address its host resolves to must
+/// pass the link policy (which blocks loopback and private ranges by default), proxies from the
+/// environment are ignored, the whole exchange is bounded by `timeout` and at most `max_bytes`
+/// of the body are read.
+pub struct HttpMetadataFetcher {
+    client: reqwest::Client,
+    policy: Arc<dyn LinkPolicyPort>,
+    max_bytes: usize,
+}
+
+impl HttpMetadataFetcher {
+    pub fn new(policy: Arc<dyn LinkPolicyPort>, timeout: Duration, max_bytes: usize) -> reqwest::Result<Self> {
+        let client = reqwest::Client::builder()
+            .redirect(redirect::Policy::none())
+            .no_proxy()
+            .timeout(timeout)
+            .dns_resolver(Arc::new(PolicyResolver::new(Arc::clone(&policy))))
+            .user_agent(concat!("shortener/", env!("CARGO_PKG_VERSION"), " (link preview)"))
+            .build()?;
+        Ok(Self { client, policy, max_bytes })
+    }
+}
+
+#[async_trait]
+impl MetadataFetcherPort for HttpMetadataFetcher {
+    async fn fetch(&self, target_url: &str) -> Result<LinkMetadata, MetadataFetchError> {
+        let mut url = Url::parse(target_url).map_err(|err| MetadataFetchError::Request(err.to_string()))?;
+        for _ in 0..=MAX_REDIRECTS {
+            if !matches!(url.scheme(), "http" | "https") {
+                return Err(MetadataFetchError::UnsupportedScheme);
+            }
+            self.policy.check(url.as_str())?;
+            let mut response = self
+                .client
+                .get(url.clone())
+                .header(ACCEPT, "text/html,application/xhtml+xml")
+                .send()
+                .await
+                .map_err(request_error)?;
+            let status = response.status();
+            if status.is_redirection() {
+                let location = response.headers().get(LOCATION).and_then(|value| value.to_str().ok());
+                let next = location.and_then(|location| url.join(location).ok());
+                url = next.ok_or(MetadataFetchError::Status(status.as_u16()))?;
+                continue;
+            }
+            if !status.is_success() {
+                return Err(MetadataFetchError::Status(status.as_u16()));
+            }
+            let content_type = response.headers().get(CONTENT_TYPE).and_then(|value| value.to_str().ok()).unwrap_or_default();
+            if !content_type.to_ascii_lowercase().contains("html") {
+                return Err(MetadataFetchError::NotHtml);
+            }
+            let mut body = Vec::new();
+            while let Some(chunk) = response.chunk().await.map_err(request_error)? {
+                let room = self.max_bytes - body.len();
+                body.extend_from_slice(&chunk[..chunk.len().min(room)]);
+                if body.len() >= self.max_bytes {
+                    break;
+                }
+            }
+            return Ok(parse_metadata(&String::from_utf8_lossy(&body), &url));
+        }
+        Err(MetadataFetchError::TooManyRedirects)
+    }
+}
+
+fn request_error(err: reqwest::Error) -> MetadataFetchError {
+    if err.is_timeout() {
+        MetadataFetchError::Timeout
+    } else {
+        MetadataFetchError::Request(err.to_string())
+    }
+}
+
+/// Title, description and icon declared by `html`, links resolved against `page_url`.
+/// Only the document head is looked at.
+fn parse_metadata(html: &str, page_url: &Url) -> LinkMetadata {
+    // same byte offsets as `html`: only ASCII letters change
+    let lower = html.to_ascii_lowercase();
+    let (mut title, mut og_title, mut og_description, mut description, mut favicon_url) = (None, None, None, None, None);
+    let mut pos = 0;
+    while let Some(offset) = lower[pos..].find('<') {
+        let start = pos + offset + 1;
+        let name_len = lower[start..].find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(lower.len() - start);
+        let Some(end) = lower[start..].find('>').map(|end| start + end) else {
+            break;
+        };
+        match &lower[start..start + name_len] {
+            "title" => {
+                let text_end = lower[end + 1..].find("</title").map_or(lower.len(), |len| end + 1 + len);
+                title = title.or_else(|| clean_text(&html[end + 1..text_end]));
+                pos = text_end;
+                continue;
+            }
+            "meta" => {
+                let attrs = parse_attributes(&html[start + name_len..end]);
+                let content = attribute(&attrs, "content").and_then(clean_text);
+                match attribute(&attrs, "property").or_else(|| attribute(&attrs, "name")).map(str::to_ascii_lowercase).as_deref() {
+                    Some("og:title") => og_title = og_title.or(content),
+                    Some("og:description") => og_description = og_description.or(content),
+                    Some("description") => description = description.or(content),
+                    _ => {}
+                }
+            }
+            "link" => {
+                let attrs = parse_attributes(&html[start + name_len..end]);
+                let rel = attribute(&attrs, "rel").unwrap_or_default();
+                if favicon_url.is_none() && rel.split_ascii_whitespace().any(|token| token.eq_ignore_ascii_case("icon")) {
+                    favicon_url = attribute(&attrs, "href").and_then(|href| absolute_http_url(page_url, &decode_entities(href)));
+                }
+            }
+            "body" => break,
+            _ => {}
+        }
+        pos = end + 1;
+    }
+    LinkMetadata {
+        title: title.or(og_title),
+        description: og_description.or(description),
+        favicon_url: favicon_url.or_else(|| absolute_http_url(page_url, "/favicon.ico")),
+    }
+}
+
+fn absolute_http_url(page_url: &Url, href: &str) -> Option<String> {
+    let url = page_url.join(href.trim()).ok()?;
+    matches!(url.scheme(), "http" | "https").then(|| url.to_string())
+}
+
+/// Attributes of a tag, `tag` being the text between its name and `>`. Names are lowercased.
+fn parse_attributes(tag: &str) -> Vec<(String, &str)> {
+    let separator = |c: char| c.is_ascii_whitespace() || c == '/';
+    let mut attrs = Vec::new();
+    let mut rest = tag.trim_start_matches(separator);
+    while !rest.is_empty() {
+        let name_end = rest.find(|c: char| separator(c) || c == '=').unwrap_or(rest.len());
+        let name = rest[..name_end].to_ascii_lowercase();
+        rest = rest[name_end..].trim_start();
+        let mut value = "";
+        if let Some(after) = rest.strip_prefix('=') {
+            let after = after.trim_start();
+            (value, rest) = match after.chars().next() {
+                Some(quote @ ('"' | '\'')) => {
+                    let inner = &after[1..];
+                    let close = inner.find(quote).unwrap_or(inner.len());
+                    (&inner[..close], inner.get(close + 1..).unwrap_or_default())
+                }
+                _ => after.split_at(after.find(|c: char| c.is_ascii_whitespace()).unwrap_or(after.len())),
+            };
+        }
+        if !name.is_empty() {
+            attrs.push((name, value));
+        }
+        rest = rest.trim_start_matches(separator);
+    }
+    attrs
+}
+
+fn attribute<'a>(attrs: &[(String, &'a str)], name: &str) -> Option<&'a str> {
+    attrs.iter().find(|(attr, _)| attr == name).map(|(_, value)| *value)
+}
+
+/// Entity-decoded text with collapsed whitespace, `None` when blank.
+fn clean_text(text: &str) -> Option<String> {
+    let text = decode_entities(text).split_whitespace().collect::<Vec<_>>().join(" ");
+    Some(text).filter(|text| !text.is_empty())
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let entity = rest.find(';').filter(|end| *end <= 10).and_then(|end| Some((decode_entity(&rest[1..end])?, end)));
+        match entity {
+            Some((c, end)) => {
+                decoded.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+fn decode_entity(entity: &str) -> Option<char> {
+    match entity {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let number = entity.strip_prefix('#')?;
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::url::domain::models::policy::{PolicyRules, PolicyViolation};
+    use crate::url::infra::test_server::serve;
+
+    fn html_response(body: &str) -> String {
+        format!("HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}", body.len())
+    }
+
+    fn fetcher(policy: PolicyRules, max_bytes: usize) -> HttpMetadataFetcher {
+        HttpMetadataFetcher::new(Arc::new(policy), Duration::from_secs(5), max_bytes).unwrap()
+    }
+
+    #[test]
+    fn parses_head_metadata() {
+        let page = Url::parse("https://example.com/docs/page").unwrap();
+        let html = r#"<!doctype html><html><HEAD>
+            <meta charset="utf-8"><title>
+              Docs &amp; Guides &#8211; v2
+            </title>
+            <meta name="description" content="Plain description">
+            <meta property='og:description' content="All &quot;the&quot; docs" />
+            <link rel="shortcut icon" href="../static/icon.png?v=1&amp;x=2">
+            </head><body><svg><title>Not this</title></svg></body></html>"#;
+        let metadata = parse_metadata(html, &page);
+        assert_eq!(metadata.title.as_deref(), Some("Docs & Guides \u{2013} v2"));
+        assert_eq!(metadata.description.as_deref(), Some(r#"All "the" docs"#));
+        assert_eq!(metadata.favicon_url.as_deref(), Some("https://example.com/static/icon.png?v=1&x=2"));
+
+        let bare = parse_metadata("<meta property=og:title content=Fallback><link rel=icon href=javascript:alert(1)>", &page);
+        assert_eq!(bare.title.as_deref(), Some("Fallback"));
+        assert_eq!(bare.description, None);
+        assert_eq!(bare.favicon_url.as_deref(), Some("https://example.com/favicon.ico"));
+    }
+
+    #[tokio::test]
+    async fn follows_redirects_and_limits_the_body() {
+        let page = "<html><head><title>Landing</title><meta name=description content=Welcome></head><body>".to_string() + &"x".repeat(4096);
+        let addr = serve(vec![
+            "HTTP/1.1 302 Found\r\nLocation: /landing\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            html_response(&page),
+        ])
+        .await;
+
+        let metadata = fetcher(PolicyRules::default(), 1024).fetch(&format!("http://{addr}/start")).await.unwrap();
+        assert_eq!(metadata.title.as_deref(), Some("Landing"));
+        assert_eq!(metadata.description.as_deref(), Some("Welcome"));
+        assert_eq!(metadata.favicon_url, Some(format!("http://{addr}/favicon.ico")));
+    }
+
+    #[tokio::test]
+    async fn refuses_blocked_hosts_redirects_and_resolved_addresses() {
+        let builtin = fetcher(PolicyRules::builtin(), 1024);
+        let err = builtin.fetch("http://127.0.0.1:9/").await.unwrap_err();
+        assert!(matches!(err, MetadataFetchError::Blocked(PolicyViolation::Blocked { .. })), "{err}");
+        assert!(matches!(builtin.fetch("ftp://example.com/").await, Err(MetadataFetchError::UnsupportedScheme)));
+
+        // a redirect towards a blocked host is not followed
+        let addr = serve(vec!["HTTP/1.1 301 Moved\r\nLocation: http://10.0.0.1/\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()]).await;
+        let only_private = PolicyRules::parse("block 10.0.0.0/8").unwrap();
+        let err = fetcher(only_private, 1024).fetch(&format!("http://{addr}/")).await.unwrap_err();
+        assert!(matches!(err, MetadataFetchError::Blocked(_)), "{err}");
+
+        // the name passes the policy, the address it resolves to does not
+        let only_loopback = PolicyRules::parse("block 127.0.0.0/8\nblock ::1").unwrap();
+        let err = fetcher(only_loopback, 1024).fetch("http://localhost:9/").await.unwrap_err();
+        assert!(matches!(err, MetadataFetchError::Request(_)), "{err}");
+    }
+
+    #[tokio::test]
+    async fn rejects_non_html_and_error_responses() {
+        let addr = serve(vec![
+            "HTTP/1.1 200 OK\r\nContent-Type: application/pdf\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+        ])
+        .await;
+        let fetcher = fetcher(PolicyRules::default(), 1024);
+        assert!(matches!(fetcher.fetch(&format!("http://{addr}/doc.pdf")).await, Err(MetadataFetchError::NotHtml)));
+        assert!(matches!(fetcher.fetch(&format!("http://{addr}/gone")).await, Err(MetadataFetchError::Status(404))));
+    }
+}
@@ -0,0 +1,113 @@
+use crate::url::domain::ports::geo_locator_port::GeoLocatorPort;
+use log::debug;
+use maxminddb::{geoip2, MaxMindDBError, Reader};
+use std::net::IpAddr;
+use std::path::Path;
+
+/// `GeoLocatorPort` adapter backed by a local MaxMind-format (`.mmdb`) country database.
+///
+/// The whole database is loaded in memory at startup; lookups never perform network I/O.
+pub struct MaxMindGeoLocator {
+    reader: Reader<Vec<u8>>,
+}
+
+impl MaxMindGeoLocator {
+    /// Load the database stored at `path` (e.g. `GeoLite2-Country.mmdb`).
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, MaxMindDBError> {
+        Ok(Self { reader: Reader::open_readfile(path)? })
+    }
+
+    /// Build a locator from an in-memory database image.
+    #[cfg(test)]
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, MaxMindDBError> {
+        Ok(Self { reader: Reader::from_source(bytes)? })
+    }
+}
+
+impl GeoLocatorPort for MaxMindGeoLocator {
+    fn country_code(&self, ip: IpAddr) -> Option<String> {
+        match self.reader.lookup::<geoip2::Country>(ip) {
+            Ok(record) => record.country.and_then(|c| c.iso_code).map(|code| code.to_uppercase()),
+            Err(err) => {
+                debug!("GeoIP lookup failed for {}: {}", ip, err);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Minimal MaxMind DB writer: an IPv4 tree mapping a single /8 prefix to `{country: {iso_code}}`.
+    fn build_country_db(prefix: u8, iso_code: &str) -> Vec<u8> {
+        fn string(out: &mut Vec<u8>, s: &str) {
+            out.push((2 << 5) | s.len() as u8);
+            out.extend_from_slice(s.as_bytes());
+        }
+        fn uint(out: &mut Vec<u8>, type_id: u8, value: u64) {
+            let bytes: Vec<u8> = value.to_be_bytes().iter().copied().skip_while(|b| *b == 0).collect();
+            if type_id <= 7 {
+                out.push((type_id << 5) | bytes.len() as u8);
+            } else {
+                out.push(bytes.len() as u8);
+                out.push(type_id - 7);
+            }
+            out.extend_from_slice(&bytes);
+        }
+
+        let node_count: u32 = 8;
+        let data_pointer = node_count + 16;
+        let mut out = Vec::new();
+        for depth in 0..8 {
+            let bit = (prefix >> (7 - depth)) & 1;
+            let next = if depth == 7 { data_pointer } else { depth as u32 + 1 };
+            let (left, right) = if bit == 0 { (next, node_count) } else { (node_count, next) };
+            out.extend_from_slice(&left.to_be_bytes()[1..]);
+            out.extend_from_slice(&right.to_be_bytes()[1..]);
+        }
+        out.extend_from_slice(&[0u8; 16]);
+
+        // data section: { "country": { "iso_code": <iso_code> } }
+        out.push((7 << 5) | 1);
+        string(&mut out, "country");
+        out.push((7 << 5) | 1);
+        string(&mut out, "iso_code");
+        string(&mut out, iso_code);
+
+        out.extend_from_slice(b"\xab\xcd\xefMaxMind.com");
+        out.push((7 << 5) | 9);
+        string(&mut out, "binary_format_major_version");
+        uint(&mut out, 5, 2);
+        string(&mut out, "binary_format_minor_version");
+        out.push(5 << 5);
+        string(&mut out, "build_epoch");
+        uint(&mut out, 9, 1_700_000_000);
+        string(&mut out, "database_type");
+        string(&mut out, "Test-Country");
+        string(&mut out, "description");
+        out.push(7 << 5);
+        string(&mut out, "ip_version");
+        uint(&mut out, 5, 4);
+        string(&mut out, "languages");
+        out.extend_from_slice(&[0, 4]);
+        string(&mut out, "node_count");
+        uint(&mut out, 6, node_count as u64);
+        string(&mut out, "record_size");
+        uint(&mut out, 5, 24);
+        out
+    }
+
+    #[test]
+    fn country_code_resolves_known_prefix_only() {
+        let locator = MaxMindGeoLocator::from_bytes(build_country_db(81, "es")).expect("valid test database");
+        assert_eq!(locator.country_code("81.2.69.160".parse().unwrap()), Some("ES".to_string()));
+        assert_eq!(locator.country_code("82.0.0.1".parse().unwrap()), None);
+    }
+
+    #[test]
+    fn open_fails_for_missing_file() {
+        assert!(MaxMindGeoLocator::open("./does-not-exist.mmdb").is_err());
+    }
+}
@@ -0,0 +1,493 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use crate::config::memory::{MemoryDatabase, UniqueViolation};
+use crate::org::domain::models::organization::Role;
+use crate::shared::utils::public_key_part;
+use crate::url::domain::models::filter::URLFilter;
+use crate::url::domain::models::health::LinkCheck;
+use crate::url::domain::models::metadata::LinkMetadata;
+use crate::url::domain::models::schema::{ClickRecord, GeoRule, UnknownKeyHits, URL};
+use crate::url::domain::models::tag::{Tag, TagStats};
+use crate::url::domain::repositories::tag_repository_port::TagRepositoryPort;
+use crate::url::domain::repositories::url_repository_port::URLRepositoryPort;
+use crate::user::domain::models::plan::LinkUsage;
+use sqlx::Error;
+use std::sync::Arc;
+
+/// `URLRepositoryPort` and `TagRepositoryPort` kept in a `MemoryDatabase` instead of SQLite, with the same behaviour as
+/// `SqlxURLRepository`: links live in workspaces, keys are never handed out twice and counters
+/// are updated atomically.
+pub struct MemoryURLRepository {
+    db: Arc<MemoryDatabase>,
+}
+
+impl MemoryURLRepository {
+    pub fn new(db: Arc<MemoryDatabase>) -> Self {
+        MemoryURLRepository { db }
+    }
+}
+
+#[async_trait]
+impl URLRepositoryPort for MemoryURLRepository {
+    async fn create_url(
+        &self, target_url: String, user_id: i32, org_id: Option<i64>, custom_key: Option<String>, reuse_existing: bool,
+    ) -> Result<URL, Error> {
+        let mut state = self.db.lock();
+        let org_id = org_id.or_else(|| state.personal_org_id(user_id));
+        if reuse_existing {
+            let existing = state.urls.iter().find(|url| {
+                url.user_id == user_id && url.target_url == target_url && !url.single_use && url.org_id == org_id
+            });
+            if let Some(url) = existing {
+                return Ok(url.clone());
+            }
+        }
+
+        let secret_key = state.generate_key();
+        let key = match custom_key.as_deref() {
+            Some(custom_key) => custom_key,
+            None => public_key_part(&secret_key),
+        };
+        if state.url(key).is_some() {
+            return Err(UniqueViolation("urls.key").into_sqlx());
+        }
+        let url = URL {
+            key: key.to_string(),
+            secret_key: secret_key.clone(),
+            target_url,
+            is_active: true,
+            user_id,
+            created_at: Some(Utc::now()),
+            org_id,
+            custom_alias: custom_key.is_some(),
+            ..Default::default()
+        };
+        state.used_keys.push((url.key.clone(), Some(user_id)));
+        state.urls.push(url.clone());
+        Ok(url)
+    }
+
+    async fn key_exists(&self, key: String) -> Result<bool, Error> {
+        Ok(self.db.lock().key_exists(&key))
+    }
+
+    async fn get_db_url_by_key(&self, url_key: String) -> Result<URL, Error> {
+        self.db.lock().url(&url_key).filter(|url| url.is_active).cloned().ok_or(Error::RowNotFound)
+    }
+
+    async fn find_url_by_key(&self, url_key: String) -> Result<URL, Error> {
+        self.db.lock().url(&url_key).cloned().ok_or(Error::RowNotFound)
+    }
+
+    async fn find_url_by_secret_key(&self, secret_key: String) -> Result<URL, Error> {
+        let state = self.db.lock();
+        state.urls.iter().find(|url| url.secret_key == secret_key).cloned().ok_or(Error::RowNotFound)
+    }
+
+    async fn deactivate_url(&self, url_key: String) -> Result<bool, Error> {
+        let mut state = self.db.lock();
+        match state.url_mut(&url_key) {
+            Some(url) if url.is_active => {
+                url.is_active = false;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn delete_url(&self, url_key: String) -> Result<bool, Error> {
+        let mut state = self.db.lock();
+        state.remove_link_rows(std::slice::from_ref(&url_key));
+        let before = state.urls.len();
+        state.urls.retain(|url| url.key != url_key);
+        Ok(state.urls.len() < before)
+    }
+
+    async fn get_db_url_by_user_and_target_url(&self, user_id: i32, target_url: String) -> Result<URL, Error> {
+        let state = self.db.lock();
+        state
+            .urls
+            .iter()
+            .find(|url| url.user_id == user_id && url.target_url == target_url && !url.single_use)
+            .cloned()
+            .ok_or(Error::RowNotFound)
+    }
+
+    async fn get_user_by_apy_key(&self, api_key: String) -> Result<i32, ()> {
+        self.db.lock().user_id_by_api_key(&api_key).map(|id| id as i32).ok_or(())
+    }
+
+    async fn increment_clicks(&self, url_key: String) -> sqlx::Result<bool> {
+        let mut state = self.db.lock();
+        let url = state.url_mut(&url_key).ok_or(Error::RowNotFound)?;
+        if url.click_limit_reached() {
+            return Ok(false);
+        }
+        url.clicks += 1;
+        Ok(true)
+    }
+
+    async fn increment_interstitial_clicks(&self, url_key: String) -> sqlx::Result<bool> {
+        let mut state = self.db.lock();
+        let url = state.url_mut(&url_key).ok_or(Error::RowNotFound)?;
+        if url.click_limit_reached() {
+            return Ok(false);
+        }
+        url.interstitial_clicks += 1;
+        Ok(true)
+    }
+
+    async fn add_clicks(&self, url_key: String, clicks: i32, interstitial_clicks: i32) -> sqlx::Result<()> {
+        let mut state = self.db.lock();
+        let url = state.url_mut(&url_key).ok_or(Error::RowNotFound)?;
+        url.clicks += clicks;
+        url.interstitial_clicks += interstitial_clicks;
+        Ok(())
+    }
+
+    async fn update_url(&self, url: URL) -> Result<URL, Error> {
+        let mut state = self.db.lock();
+        let stored = state.url_mut(&url.key).ok_or(Error::RowNotFound)?;
+        if stored.target_url != url.target_url {
+            stored.last_check_status = None;
+            stored.last_check_url = None;
+            stored.last_check_latency_ms = None;
+            stored.last_check_error = None;
+            stored.last_checked_at = None;
+            stored.failed_checks = 0;
+        }
+        stored.target_url = url.target_url;
+        stored.is_active = url.is_active;
+        stored.password_hash = url.password_hash;
+        stored.title = url.title;
+        stored.show_interstitial = url.show_interstitial;
+        stored.single_use = url.single_use;
+        stored.require_signature = url.require_signature;
+        stored.active_from = url.active_from;
+        stored.active_until = url.active_until;
+        stored.folder = url.folder;
+        stored.description = url.description;
+        stored.notes = url.notes;
+        stored.og_title = url.og_title;
+        stored.og_description = url.og_description;
+        stored.og_image_url = url.og_image_url;
+        stored.fallback_url = url.fallback_url;
+        stored.max_clicks = url.max_clicks;
+        Ok(stored.clone())
+    }
+
+    async fn set_geo_rules(&self, url_key: String, rules: Vec<GeoRule>) -> sqlx::Result<()> {
+        let mut state = self.db.lock();
+        state.geo_rules.retain(|rule| rule.url_key != url_key);
+        state.geo_rules.extend(rules.into_iter().map(|rule| GeoRule { url_key: url_key.clone(), ..rule }));
+        Ok(())
+    }
+
+    async fn get_geo_rules(&self, url_key: String) -> Result<Vec<GeoRule>, Error> {
+        let state = self.db.lock();
+        let mut rules: Vec<GeoRule> = state.geo_rules.iter().filter(|rule| rule.url_key == url_key).cloned().collect();
+        rules.sort_by(|a, b| a.country_code.cmp(&b.country_code));
+        Ok(rules)
+    }
+
+    async fn set_link_metadata(&self, url_key: String, metadata: LinkMetadata) -> Result<(), Error> {
+        if let Some(url) = self.db.lock().url_mut(&url_key) {
+            url.title = url.title.take().or(metadata.title);
+            url.description = url.description.take().or(metadata.description);
+            url.favicon_url = metadata.favicon_url;
+        }
+        Ok(())
+    }
+
+    async fn record_link_check(&self, url_key: String, check: LinkCheck) -> Result<(), Error> {
+        let failed = check.is_failure();
+        if let Some(url) = self.db.lock().url_mut(&url_key) {
+            url.last_check_status = check.status_code.map(i32::from);
+            url.last_check_url = check.final_url;
+            url.last_check_latency_ms = Some(check.latency_ms);
+            url.last_check_error = check.error;
+            url.last_checked_at = Some(check.checked_at);
+            url.failed_checks = if failed { url.failed_checks + 1 } else { 0 };
+        }
+        Ok(())
+    }
+
+    async fn record_unknown_key(&self, key: String, max_keys: i64) -> Result<(), Error> {
+        let now = Utc::now();
+        let mut state = self.db.lock();
+        if !state.unknown_keys.contains_key(&key) && state.unknown_keys.len() as i64 >= max_keys {
+            let evicted = state
+                .unknown_keys
+                .values()
+                .min_by_key(|hits| (hits.hits, hits.last_seen_at))
+                .map(|hits| hits.key.clone());
+            if let Some(evicted) = evicted {
+                state.unknown_keys.remove(&evicted);
+            }
+        }
+        let hits = state.unknown_keys.entry(key.clone()).or_insert_with(|| UnknownKeyHits {
+            key,
+            hits: 0,
+            first_seen_at: now,
+            last_seen_at: now,
+        });
+        hits.hits += 1;
+        hits.last_seen_at = now;
+        Ok(())
+    }
+
+    async fn list_unknown_keys(&self, limit: i64) -> Result<Vec<UnknownKeyHits>, Error> {
+        let state = self.db.lock();
+        let mut keys: Vec<UnknownKeyHits> = state.unknown_keys.values().cloned().collect();
+        keys.sort_by(|a, b| b.hits.cmp(&a.hits).then(b.last_seen_at.cmp(&a.last_seen_at)));
+        keys.truncate(limit.max(0) as usize);
+        Ok(keys)
+    }
+
+    async fn record_click(&self, click: ClickRecord) -> sqlx::Result<()> {
+        self.db.lock().clicks.push(click);
+        Ok(())
+    }
+
+    async fn list_urls(&self, filter: URLFilter) -> Result<Vec<URL>, Error> {
+        let state = self.db.lock();
+        let search = filter.search.map(|search| search.to_lowercase());
+        let has_tag = |url: &URL, name: &str| {
+            state.url_tags.iter().any(|(url_key, tag_id)| {
+                *url_key == url.key
+                    && state.tags.iter().any(|tag| tag.id == *tag_id && Some(tag.org_id) == url.org_id && tag.name == name)
+            })
+        };
+        let matches_search = |url: &URL, search: &str| {
+            [Some(&url.key), Some(&url.target_url), url.title.as_ref(), url.description.as_ref(), url.notes.as_ref()]
+                .into_iter()
+                .flatten()
+                .any(|field| field.to_lowercase().contains(search))
+        };
+        Ok(state
+            .urls
+            .iter()
+            .filter(|url| !filter.active || url.is_active)
+            .filter(|url| filter.user_id.is_none_or(|user_id| url.user_id == user_id))
+            .filter(|url| filter.org_id.is_none_or(|org_id| url.org_id == Some(org_id)))
+            .filter(|url| !filter.scheduled || url.active_from.is_some() || url.active_until.is_some())
+            .filter(|url| filter.tag.as_deref().is_none_or(|tag| has_tag(url, tag)))
+            .filter(|url| filter.folder.is_none() || url.folder == filter.folder)
+            .filter(|url| search.as_deref().is_none_or(|search| matches_search(url, search)))
+            .filter(|url| {
+                filter.checked_before.is_none_or(|before| url.last_checked_at.is_none_or(|checked| checked < before))
+            })
+            .filter(|url| filter.min_failed_checks.is_none_or(|min| url.failed_checks >= min))
+            .take(filter.limit.map_or(usize::MAX, |limit| limit.max(0) as usize))
+            .cloned()
+            .collect())
+    }
+
+    async fn get_member_role(&self, org_id: i64, user_id: i32) -> Result<Option<Role>, Error> {
+        Ok(self.db.lock().member_role(org_id, user_id as i64))
+    }
+
+    async fn get_user_plan(&self, user_id: i32) -> Result<Option<String>, Error> {
+        let state = self.db.lock();
+        state.user(user_id as i64).map(|user| user.plan.clone()).ok_or(Error::RowNotFound)
+    }
+
+    async fn get_link_usage(&self, user_id: i32, since: DateTime<Utc>) -> Result<LinkUsage, Error> {
+        let state = self.db.lock();
+        let links = state.urls.iter().filter(|url| url.user_id == user_id);
+        let mut usage = LinkUsage::default();
+        for url in links {
+            usage.active_links += url.is_active as u32;
+            usage.links_today += url.created_at.is_some_and(|created_at| created_at >= since) as u32;
+            usage.custom_aliases += url.custom_alias as u32;
+        }
+        Ok(usage)
+    }
+}
+
+#[async_trait]
+impl TagRepositoryPort for MemoryURLRepository {
+    async fn list_tags(&self, org_id: i64) -> Result<Vec<Tag>, Error> {
+        let state = self.db.lock();
+        let mut tags: Vec<Tag> = state.tags.iter().filter(|tag| tag.org_id == org_id).cloned().collect();
+        tags.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(tags)
+    }
+
+    async fn get_tag(&self, tag_id: i64) -> Result<Tag, Error> {
+        self.db.lock().tags.iter().find(|tag| tag.id == tag_id).cloned().ok_or(Error::RowNotFound)
+    }
+
+    async fn create_tag(&self, org_id: i64, name: String) -> Result<Tag, Error> {
+        let mut state = self.db.lock();
+        if state.tags.iter().any(|tag| tag.org_id == org_id && tag.name == name) {
+            return Err(UniqueViolation("tags.org_id, tags.name").into_sqlx());
+        }
+        Ok(state.insert_tag(org_id, name))
+    }
+
+    async fn rename_tag(&self, tag_id: i64, name: String) -> Result<Tag, Error> {
+        let mut state = self.db.lock();
+        let org_id = state.tags.iter().find(|tag| tag.id == tag_id).ok_or(Error::RowNotFound)?.org_id;
+        if state.tags.iter().any(|tag| tag.id != tag_id && tag.org_id == org_id && tag.name == name) {
+            return Err(UniqueViolation("tags.org_id, tags.name").into_sqlx());
+        }
+        let tag = state.tags.iter_mut().find(|tag| tag.id == tag_id).ok_or(Error::RowNotFound)?;
+        tag.name = name;
+        Ok(tag.clone())
+    }
+
+    async fn delete_tag(&self, tag_id: i64) -> Result<bool, Error> {
+        let mut state = self.db.lock();
+        state.url_tags.retain(|(_, id)| *id != tag_id);
+        let before = state.tags.len();
+        state.tags.retain(|tag| tag.id != tag_id);
+        Ok(state.tags.len() < before)
+    }
+
+    async fn attach_tag(&self, url_key: String, tag_id: i64) -> Result<bool, Error> {
+        let mut state = self.db.lock();
+        let pair = (url_key, tag_id);
+        if state.url_tags.contains(&pair) {
+            return Ok(false);
+        }
+        state.url_tags.push(pair);
+        Ok(true)
+    }
+
+    async fn detach_tag(&self, url_key: String, tag_id: i64) -> Result<bool, Error> {
+        let mut state = self.db.lock();
+        let before = state.url_tags.len();
+        state.url_tags.retain(|pair| *pair != (url_key.clone(), tag_id));
+        Ok(state.url_tags.len() < before)
+    }
+
+    async fn get_url_tags(&self, url_keys: Vec<String>) -> Result<Vec<(String, String)>, Error> {
+        let state = self.db.lock();
+        let mut pairs: Vec<(String, String)> = state
+            .url_tags
+            .iter()
+            .filter(|(url_key, _)| url_keys.contains(url_key))
+            .filter_map(|(url_key, tag_id)| {
+                let tag = state.tags.iter().find(|tag| tag.id == *tag_id)?;
+                Some((url_key.clone(), tag.name.clone()))
+            })
+            .collect();
+        pairs.sort_by(|a, b| a.1.cmp(&b.1));
+        Ok(pairs)
+    }
+
+    async fn get_tag_stats(&self, org_id: i64) -> Result<Vec<TagStats>, Error> {
+        let state = self.db.lock();
+        let mut stats: Vec<TagStats> = state
+            .tags
+            .iter()
+            .filter(|tag| tag.org_id == org_id)
+            .map(|tag| {
+                let links: Vec<&URL> = state
+                    .url_tags
+                    .iter()
+                    .filter(|(_, tag_id)| *tag_id == tag.id)
+                    .filter_map(|(url_key, _)| state.url(url_key))
+                    .collect();
+                TagStats {
+                    tag_id: tag.id,
+                    name: tag.name.clone(),
+                    links: links.len() as i64,
+                    clicks: links.iter().map(|url| url.clicks as i64).sum(),
+                    interstitial_clicks: links.iter().map(|url| url.interstitial_clicks as i64).sum(),
+                }
+            })
+            .collect();
+        stats.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::repository_conformance::{check_repositories, Repositories};
+    use crate::user::infra::memory_user_repository::MemoryUserRepository;
+
+    fn repo() -> (Arc<MemoryDatabase>, MemoryURLRepository) {
+        let db = Arc::new(MemoryDatabase::new());
+        db.insert_user("alice", "a@x.com", "ka");
+        (db.clone(), MemoryURLRepository::new(db))
+    }
+
+    #[tokio::test]
+    async fn allocates_unique_keys_in_the_personal_workspace() -> Result<(), Error> {
+        let (_, repo) = repo();
+        let first = repo.create_url("http://a.com".into(), 1, None, None, false).await?;
+        let second = repo.create_url("http://a.com".into(), 1, None, None, false).await?;
+        assert_ne!(first.key, second.key);
+        assert!(first.secret_key.starts_with(&format!("{}_", first.key)));
+        assert_eq!(first.org_id, Some(1));
+        assert!(repo.key_exists(first.key.clone()).await?);
+
+        // reuse hands out the oldest reusable link, a custom key is taken as is
+        assert_eq!(repo.create_url("http://a.com".into(), 1, None, None, true).await?.key, first.key);
+        let alias = repo.create_url("http://b.com".into(), 1, None, Some("promo".into()), false).await?;
+        assert!(alias.custom_alias);
+        assert_eq!(repo.find_url_by_secret_key(alias.secret_key).await?.key, "promo");
+
+        // deleted keys stay reserved
+        assert!(repo.delete_url("promo".into()).await?);
+        assert!(repo.key_exists("promo".into()).await?);
+        assert!(matches!(repo.find_url_by_key("promo".into()).await, Err(Error::RowNotFound)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn counts_clicks_and_resolves_users() -> Result<(), Error> {
+        let (_, repo) = repo();
+        let url = repo.create_url("http://a.com".into(), 1, None, None, false).await?;
+        repo.increment_clicks(url.key.clone()).await?;
+        repo.increment_interstitial_clicks(url.key.clone()).await?;
+        repo.add_clicks(url.key.clone(), 3, 1).await?;
+        let stored = repo.get_db_url_by_key(url.key.clone()).await?;
+        assert_eq!((stored.clicks, stored.interstitial_clicks), (4, 2));
+        assert!(matches!(repo.increment_clicks("nope".into()).await, Err(Error::RowNotFound)));
+
+        assert!(repo.deactivate_url(url.key.clone()).await?);
+        assert!(!repo.deactivate_url(url.key.clone()).await?);
+        assert!(repo.get_db_url_by_key(url.key).await.is_err());
+
+        assert_eq!(repo.get_user_by_apy_key("ka".into()).await, Ok(1));
+        assert_eq!(repo.get_user_by_apy_key("kz".into()).await, Err(()));
+        assert_eq!(repo.get_member_role(1, 1).await?, Some(Role::Owner));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn tags_are_unique_per_workspace_and_filter_links() -> Result<(), Error> {
+        let (_, repo) = repo();
+        let url = repo.create_url("http://a.com/Docs".into(), 1, None, None, false).await?;
+        repo.create_url("http://b.com".into(), 1, None, None, false).await?;
+        let tag = repo.create_tag(1, "docs".into()).await?;
+        assert!(matches!(repo.create_tag(1, "docs".into()).await, Err(Error::Database(e)) if e.is_unique_violation()));
+        assert!(repo.attach_tag(url.key.clone(), tag.id).await?);
+        assert!(!repo.attach_tag(url.key.clone(), tag.id).await?);
+        repo.increment_clicks(url.key.clone()).await?;
+
+        let tagged = repo.list_urls(URLFilter { tag: Some("docs".into()), ..Default::default() }).await?;
+        assert_eq!(tagged.iter().map(|url| url.key.clone()).collect::<Vec<_>>(), vec![url.key.clone()]);
+        let found = repo.list_urls(URLFilter { search: Some("a.COM/docs".into()), ..Default::default() }).await?;
+        assert_eq!(found.len(), 1);
+        assert_eq!(repo.get_tag_stats(1).await?[0].clicks, 1);
+
+        assert!(repo.delete_tag(tag.id).await?);
+        assert!(repo.get_url_tags(vec![url.key]).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn passes_the_repository_conformance_suite() {
+        check_repositories(|| async {
+            let db = Arc::new(MemoryDatabase::new());
+            Repositories { urls: Arc::new(MemoryURLRepository::new(db.clone())), users: Arc::new(MemoryUserRepository::new(db)) }
+        })
+        .await;
+    }
+}
@@ -1 +1,12 @@
+pub mod cached_url_repository;
+pub mod file_link_policy;
+pub mod http_link_probe;
+pub mod http_metadata_fetcher;
+pub mod maxmind_geo_locator;
+pub mod memory_url_repository;
+mod policy_resolver;
+#[cfg(feature = "redis")]
+pub mod redis_url_repository;
 pub mod sqlx_url_repository;
+#[cfg(test)]
+mod test_server;
@@ -0,0 +1,34 @@
+use crate::url::domain::ports::link_policy_port::LinkPolicyPort;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+
+/// Resolves host names and refuses them when any of their addresses is blocked by the policy,
+/// so a public name cannot point outgoing requests at an internal address.
+pub(crate) struct PolicyResolver {
+    policy: Arc<dyn LinkPolicyPort>,
+}
+
+impl PolicyResolver {
+    pub(crate) fn new(policy: Arc<dyn LinkPolicyPort>) -> Self {
+        Self { policy }
+    }
+}
+
+impl Resolve for PolicyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let policy = Arc::clone(&self.policy);
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            for addr in &addrs {
+                let address_url = match addr.ip() {
+                    IpAddr::V4(ip) => format!("http://{ip}/"),
+                    IpAddr::V6(ip) => format!("http://[{ip}]/"),
+                };
+                policy.check(&address_url)?;
+            }
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
@@ -0,0 +1,523 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use crate::org::domain::models::organization::Role;
+use crate::url::domain::models::filter::URLFilter;
+use crate::url::domain::models::health::LinkCheck;
+use crate::url::domain::models::metadata::LinkMetadata;
+use crate::url::domain::models::schema::{ClickRecord, GeoRule, UnknownKeyHits, URL};
+use crate::url::domain::ports::link_cache_port::LinkCachePort;
+use crate::url::domain::repositories::url_repository_port::URLRepositoryPort;
+use crate::user::domain::models::plan::LinkUsage;
+use log::warn;
+use redis::aio::{ConnectionManager, ConnectionManagerConfig};
+use redis::{AsyncCommands, RedisResult};
+use serde::{Deserialize, Serialize};
+use sqlx::Error;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Time allowed to connect to Redis or to get an answer, before falling back to the database.
+const REDIS_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Take a pending link (`KEYS[1]` set, `ARGV[1]` member) and read and reset its click
+/// counters (`KEYS[2]`, `KEYS[3]`) in one step; nil when another instance took it first.
+const TAKE_CLICKS_SCRIPT: &str = r#"
+if redis.call('SREM', KEYS[1], ARGV[1]) == 0 then
+    return false
+end
+local clicks = tonumber(redis.call('GET', KEYS[2]) or '0')
+local interstitial_clicks = tonumber(redis.call('GET', KEYS[3]) or '0')
+redis.call('DEL', KEYS[2], KEYS[3])
+return {clicks, interstitial_clicks}
+"#;
+
+/// `URLRepositoryPort` decorator sharing a link cache and click counters between instances
+/// through Redis.
+///
+/// Lookups by public key are cached in Redis for `ttl`, unknown keys included. Clicks are
+/// counted with `INCR` and moved to the wrapped store by `sync_clicks`, which the instances run
+/// periodically; until then they are added to the links read through this adapter. Clicks on
+/// links with a `max_clicks` limit go straight to the wrapped store, which checks the limit in
+/// the same write, so the limits hold across instances without an in-process cache in front.
+/// Whenever Redis cannot be reached the adapter logs it and uses the wrapped store directly.
+pub struct RedisURLRepository {
+    inner: Arc<dyn URLRepositoryPort + Send + Sync>,
+    redis: ConnectionManager,
+    prefix: String,
+    ttl: Duration,
+}
+
+/// Cached lookup of a key; `url` is `None` when no link has it.
+#[derive(Serialize, Deserialize)]
+struct CachedLink {
+    url: Option<URL>,
+    /// Not serialized as part of `URL`.
+    password_hash: Option<String>,
+}
+
+impl RedisURLRepository {
+    /// Connect to `redis_url` and cache the links of `inner` for `ttl`, under keys starting with `prefix`.
+    pub async fn connect(
+        inner: Arc<dyn URLRepositoryPort + Send + Sync>, redis_url: &str, prefix: &str, ttl: Duration,
+    ) -> RedisResult<Self> {
+        let config = ConnectionManagerConfig::new()
+            .set_connection_timeout(REDIS_TIMEOUT)
+            .set_response_timeout(REDIS_TIMEOUT)
+            .set_number_of_retries(1);
+        let redis = redis::Client::open(redis_url)?.get_connection_manager_with_config(config).await?;
+        Ok(Self { inner, redis, prefix: prefix.to_string(), ttl })
+    }
+
+    fn link_key(&self, url_key: &str) -> String {
+        format!("{}link:{}", self.prefix, url_key)
+    }
+
+    fn clicks_key(&self, url_key: &str) -> String {
+        format!("{}clicks:{}", self.prefix, url_key)
+    }
+
+    fn interstitial_clicks_key(&self, url_key: &str) -> String {
+        format!("{}interstitial_clicks:{}", self.prefix, url_key)
+    }
+
+    /// Set of the links with clicks not yet moved to the wrapped store.
+    fn pending_key(&self) -> String {
+        format!("{}pending_clicks", self.prefix)
+    }
+
+    /// Row of `url_key` whatever its state, with its unsynced clicks; `None` when there is no such link.
+    async fn lookup(&self, url_key: &str) -> Result<Option<URL>, Error> {
+        match self.lookup_cached(url_key).await {
+            Ok(url) => Ok(url),
+            Err(LookupError::Store(err)) => Err(err),
+            Err(LookupError::Redis(err)) => {
+                warn!("Redis unavailable, reading {} from the database: {}", url_key, err);
+                find_in(self.inner.as_ref(), url_key).await
+            }
+        }
+    }
+
+    async fn lookup_cached(&self, url_key: &str) -> Result<Option<URL>, LookupError> {
+        let mut redis = self.redis.clone();
+        let (cached, clicks, interstitial_clicks): (Option<String>, Option<i32>, Option<i32>) = redis::pipe()
+            .get(self.link_key(url_key))
+            .get(self.clicks_key(url_key))
+            .get(self.interstitial_clicks_key(url_key))
+            .query_async(&mut redis)
+            .await?;
+        let cached = match cached.map(|json| serde_json::from_str::<CachedLink>(&json)) {
+            Some(Ok(cached)) => cached,
+            _ => {
+                let url = find_in(self.inner.as_ref(), url_key).await?;
+                let cached = CachedLink { password_hash: url.as_ref().and_then(|url| url.password_hash.clone()), url };
+                if let Ok(json) = serde_json::to_string(&cached) {
+                    redis.set_ex::<_, _, ()>(self.link_key(url_key), json, self.ttl.as_secs().max(1)).await?;
+                }
+                cached
+            }
+        };
+        Ok(cached.url.map(|url| URL {
+            password_hash: cached.password_hash,
+            clicks: url.clicks + clicks.unwrap_or(0),
+            interstitial_clicks: url.interstitial_clicks + interstitial_clicks.unwrap_or(0),
+            ..url
+        }))
+    }
+
+    /// Drop the cached row of `url_key` after it was written.
+    async fn invalidate(&self, url_key: &str) {
+        let mut redis = self.redis.clone();
+        if let Err(err) = redis.del::<_, ()>(self.link_key(url_key)).await {
+            warn!("Could not drop {} from the Redis cache, it may be served stale for a while: {}", url_key, err);
+        }
+    }
+
+    /// Drop every cached row under this adapter's prefix.
+    async fn clear_links(&self) -> RedisResult<()> {
+        let mut redis = self.redis.clone();
+        let pattern = self.link_key("*");
+        let mut cursor: u64 = 0;
+        loop {
+            let (next, keys): (u64, Vec<String>) =
+                redis::cmd("SCAN").arg(cursor).arg("MATCH").arg(&pattern).arg("COUNT").arg(500).query_async(&mut redis).await?;
+            if !keys.is_empty() {
+                redis.del::<_, ()>(keys).await?;
+            }
+            if next == 0 {
+                return Ok(());
+            }
+            cursor = next;
+        }
+    }
+
+    /// Count a click in Redis; `false` when Redis is unavailable and the caller must count it itself.
+    async fn count(&self, url_key: &str, counter: String) -> bool {
+        let mut redis = self.redis.clone();
+        let counted: RedisResult<()> =
+            redis::pipe().atomic().incr(counter, 1).sadd(self.pending_key(), url_key).query_async(&mut redis).await;
+        if let Err(err) = counted {
+            warn!("Redis unavailable, counting the click on {} in the database: {}", url_key, err);
+            return false;
+        }
+        true
+    }
+
+    /// Move the clicks counted in Redis to the wrapped store; returns how many links were synced.
+    ///
+    /// Several instances may sync at once: each pending link is taken by a single one of them,
+    /// with its counters read and reset in the same script, so clicks counted meanwhile are left
+    /// for the next sync. Until the wrapped store is written, lookups may briefly miss the taken
+    /// clicks; when the write fails they are counted back in Redis.
+    pub async fn sync_clicks(&self) -> RedisResult<usize> {
+        let mut redis = self.redis.clone();
+        let pending: Vec<String> = redis.smembers(self.pending_key()).await?;
+        let mut synced = 0;
+        for url_key in pending {
+            let taken: Option<(i32, i32)> = redis::cmd("EVAL")
+                .arg(TAKE_CLICKS_SCRIPT)
+                .arg(3)
+                .arg(self.pending_key())
+                .arg(self.clicks_key(&url_key))
+                .arg(self.interstitial_clicks_key(&url_key))
+                .arg(&url_key)
+                .query_async(&mut redis)
+                .await?;
+            let Some((clicks, interstitial_clicks)) = taken else { continue };
+            if clicks == 0 && interstitial_clicks == 0 {
+                continue;
+            }
+            match self.inner.add_clicks(url_key.clone(), clicks, interstitial_clicks).await {
+                Ok(()) => {}
+                // deleted since: its clicks have nowhere to go
+                Err(Error::RowNotFound) => {
+                    warn!("Dropping {} clicks counted on {}, which no longer exists", clicks + interstitial_clicks, url_key);
+                    continue;
+                }
+                Err(err) => {
+                    eprintln!("Error occurred[sync_clicks]: {}", err);
+                    redis::pipe()
+                        .atomic()
+                        .incr(self.clicks_key(&url_key), clicks)
+                        .incr(self.interstitial_clicks_key(&url_key), interstitial_clicks)
+                        .sadd(self.pending_key(), &url_key)
+                        .query_async::<()>(&mut redis)
+                        .await?;
+                    continue;
+                }
+            }
+            redis.del::<_, ()>(self.link_key(&url_key)).await?;
+            synced += 1;
+        }
+        Ok(synced)
+    }
+
+    /// Run `sync_clicks` every `every` in the background.
+    #[cfg(not(test))]
+    pub fn spawn_click_sync(self: &Arc<Self>, every: Duration) {
+        let repository = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(every);
+            loop {
+                ticker.tick().await;
+                if let Err(err) = repository.sync_clicks().await {
+                    warn!("Could not sync the clicks counted in Redis, retrying later: {}", err);
+                }
+            }
+        });
+    }
+}
+
+#[async_trait]
+impl LinkCachePort for RedisURLRepository {
+    async fn clear(&self) {
+        if let Err(err) = self.clear_links().await {
+            warn!("Could not clear the Redis link cache, links may be served stale for a while: {}", err);
+        }
+    }
+}
+
+/// Why a cached lookup failed: Redis is unavailable, or the wrapped store failed.
+enum LookupError {
+    Redis(redis::RedisError),
+    Store(Error),
+}
+
+impl From<redis::RedisError> for LookupError {
+    fn from(err: redis::RedisError) -> Self {
+        LookupError::Redis(err)
+    }
+}
+
+impl From<Error> for LookupError {
+    fn from(err: Error) -> Self {
+        LookupError::Store(err)
+    }
+}
+
+async fn find_in(store: &(dyn URLRepositoryPort + Send + Sync), url_key: &str) -> Result<Option<URL>, Error> {
+    match store.find_url_by_key(url_key.to_string()).await {
+        Ok(url) => Ok(Some(url)),
+        Err(Error::RowNotFound) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+#[async_trait]
+impl URLRepositoryPort for RedisURLRepository {
+    async fn create_url(
+        &self, target_url: String, user_id: i32, org_id: Option<i64>, custom_key: Option<String>, reuse_existing: bool,
+    ) -> Result<URL, Error> {
+        let url = self.inner.create_url(target_url, user_id, org_id, custom_key, reuse_existing).await?;
+        // The key may have been cached as unknown.
+        self.invalidate(&url.key).await;
+        Ok(url)
+    }
+
+    async fn key_exists(&self, key: String) -> Result<bool, Error> {
+        self.inner.key_exists(key).await
+    }
+
+    async fn get_db_url_by_key(&self, url_key: String) -> Result<URL, Error> {
+        self.lookup(&url_key).await?.filter(|url| url.is_active).ok_or(Error::RowNotFound)
+    }
+
+    async fn find_url_by_key(&self, url_key: String) -> Result<URL, Error> {
+        self.lookup(&url_key).await?.ok_or(Error::RowNotFound)
+    }
+
+    async fn find_url_by_secret_key(&self, secret_key: String) -> Result<URL, Error> {
+        self.inner.find_url_by_secret_key(secret_key).await
+    }
+
+    async fn deactivate_url(&self, url_key: String) -> Result<bool, Error> {
+        let deactivated = self.inner.deactivate_url(url_key.clone()).await;
+        self.invalidate(&url_key).await;
+        deactivated
+    }
+
+    async fn delete_url(&self, url_key: String) -> Result<bool, Error> {
+        let deleted = self.inner.delete_url(url_key.clone()).await;
+        self.invalidate(&url_key).await;
+        deleted
+    }
+
+    async fn get_db_url_by_user_and_target_url(&self, user_id: i32, target_url: String) -> Result<URL, Error> {
+        self.inner.get_db_url_by_user_and_target_url(user_id, target_url).await
+    }
+
+    async fn get_user_by_apy_key(&self, api_key: String) -> Result<i32, ()> {
+        self.inner.get_user_by_apy_key(api_key).await
+    }
+
+    async fn increment_clicks(&self, url_key: String) -> sqlx::Result<bool> {
+        let url = self.lookup(&url_key).await?.ok_or(Error::RowNotFound)?;
+        if url.max_clicks.is_some() || !self.count(&url_key, self.clicks_key(&url_key)).await {
+            let counted = self.inner.increment_clicks(url_key.clone()).await?;
+            self.invalidate(&url_key).await;
+            return Ok(counted);
+        }
+        Ok(true)
+    }
+
+    async fn increment_interstitial_clicks(&self, url_key: String) -> sqlx::Result<bool> {
+        let url = self.lookup(&url_key).await?.ok_or(Error::RowNotFound)?;
+        if url.max_clicks.is_some() || !self.count(&url_key, self.interstitial_clicks_key(&url_key)).await {
+            let counted = self.inner.increment_interstitial_clicks(url_key.clone()).await?;
+            self.invalidate(&url_key).await;
+            return Ok(counted);
+        }
+        Ok(true)
+    }
+
+    async fn add_clicks(&self, url_key: String, clicks: i32, interstitial_clicks: i32) -> sqlx::Result<()> {
+        self.inner.add_clicks(url_key.clone(), clicks, interstitial_clicks).await?;
+        self.invalidate(&url_key).await;
+        Ok(())
+    }
+
+    async fn update_url(&self, url: URL) -> Result<URL, Error> {
+        let url_key = url.key.clone();
+        let updated = self.inner.update_url(url).await;
+        self.invalidate(&url_key).await;
+        updated
+    }
+
+    async fn set_geo_rules(&self, url_key: String, rules: Vec<GeoRule>) -> sqlx::Result<()> {
+        self.inner.set_geo_rules(url_key, rules).await
+    }
+
+    async fn get_geo_rules(&self, url_key: String) -> Result<Vec<GeoRule>, Error> {
+        self.inner.get_geo_rules(url_key).await
+    }
+
+    async fn set_link_metadata(&self, url_key: String, metadata: LinkMetadata) -> Result<(), Error> {
+        let stored = self.inner.set_link_metadata(url_key.clone(), metadata).await;
+        self.invalidate(&url_key).await;
+        stored
+    }
+
+    async fn record_link_check(&self, url_key: String, check: LinkCheck) -> Result<(), Error> {
+        let recorded = self.inner.record_link_check(url_key.clone(), check).await;
+        self.invalidate(&url_key).await;
+        recorded
+    }
+
+    async fn record_unknown_key(&self, key: String, max_keys: i64) -> Result<(), Error> {
+        self.inner.record_unknown_key(key, max_keys).await
+    }
+
+    async fn list_unknown_keys(&self, limit: i64) -> Result<Vec<UnknownKeyHits>, Error> {
+        self.inner.list_unknown_keys(limit).await
+    }
+
+    async fn record_click(&self, click: ClickRecord) -> sqlx::Result<()> {
+        self.inner.record_click(click).await
+    }
+
+    async fn list_urls(&self, filter: URLFilter) -> Result<Vec<URL>, Error> {
+        self.inner.list_urls(filter).await
+    }
+
+    async fn get_member_role(&self, org_id: i64, user_id: i32) -> Result<Option<Role>, Error> {
+        self.inner.get_member_role(org_id, user_id).await
+    }
+
+    async fn get_user_plan(&self, user_id: i32) -> Result<Option<String>, Error> {
+        self.inner.get_user_plan(user_id).await
+    }
+
+    async fn get_link_usage(&self, user_id: i32, since: DateTime<Utc>) -> Result<LinkUsage, Error> {
+        self.inner.get_link_usage(user_id, since).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shared::repository_conformance::{check_repositories, sqlite_pool, Repositories};
+    use crate::user::infra::link_cache_user_repository::LinkCacheUserRepository;
+    use crate::user::infra::sqlx_user_repository::SqlxUserRepository;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use crate::url::infra::sqlx_url_repository::SqlxURLRepository;
+    use sqlx::{Executor, SqlitePool};
+    use std::process::{Child, Command, Stdio};
+
+    /// `redis-server` launched for one test, stopped when dropped.
+    struct LocalRedis {
+        server: Child,
+        url: String,
+    }
+
+    impl Drop for LocalRedis {
+        fn drop(&mut self) {
+            let _ = self.server.kill();
+            let _ = self.server.wait();
+        }
+    }
+
+    /// Launch a throwaway `redis-server`; the tests using it are ignored unless run with `--ignored`.
+    async fn launch_redis() -> LocalRedis {
+        let port = std::net::TcpListener::bind("127.0.0.1:0").expect("free port").local_addr().expect("local address").port();
+        let server = Command::new("redis-server")
+            .args(["--port", &port.to_string(), "--bind", "127.0.0.1", "--save", "", "--appendonly", "no"])
+            .stdout(Stdio::null())
+            .spawn()
+            .expect("redis-server not found in the PATH");
+        let redis = LocalRedis { server, url: format!("redis://127.0.0.1:{port}") };
+        for _ in 0..50 {
+            if tokio::net::TcpStream::connect(("127.0.0.1", port)).await.is_ok() {
+                return redis;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        panic!("redis-server did not start");
+    }
+
+    async fn link_store() -> Result<(SqlitePool, Arc<SqlxURLRepository>), sqlx::Error> {
+        let pool = sqlite_pool().await;
+        pool.execute("INSERT INTO users (id, username, email, api_key) VALUES (1, 'alice', 'a@x.com', 'ka')").await?;
+        pool.execute("INSERT INTO urls (key, secret_key, target_url, is_active, clicks, user_id, password_hash) VALUES ('K1','SK1','http://a',1,0,1,'hash')").await?;
+        let store = Arc::new(SqlxURLRepository::new(pool.clone()).await);
+        Ok((pool, store))
+    }
+
+    async fn stored_clicks(pool: &SqlitePool) -> Result<(i32, i32), sqlx::Error> {
+        sqlx::query_as("SELECT clicks, interstitial_clicks FROM urls WHERE key = 'K1'").fetch_one(pool).await
+    }
+
+    #[tokio::test]
+    #[ignore = "needs redis-server"]
+    async fn clicks_are_counted_in_redis_and_synced() -> Result<(), Box<dyn std::error::Error>> {
+        let redis = launch_redis().await;
+        let (pool, store) = link_store().await?;
+        let repo = RedisURLRepository::connect(store, &redis.url, "test:", Duration::from_secs(60)).await?;
+
+        repo.increment_clicks("K1".into()).await?;
+        repo.increment_clicks("K1".into()).await?;
+        repo.increment_interstitial_clicks("K1".into()).await?;
+        assert_eq!(stored_clicks(&pool).await?, (0, 0));
+        let url = repo.find_url_by_key("K1".into()).await?;
+        assert_eq!((url.clicks, url.interstitial_clicks), (2, 1));
+
+        assert_eq!(repo.sync_clicks().await?, 1);
+        assert_eq!(stored_clicks(&pool).await?, (2, 1));
+        let url = repo.find_url_by_key("K1".into()).await?;
+        assert_eq!((url.clicks, url.interstitial_clicks), (2, 1));
+        assert_eq!(repo.sync_clicks().await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore = "needs redis-server"]
+    async fn instances_share_cached_links_until_written() -> Result<(), Box<dyn std::error::Error>> {
+        let redis = launch_redis().await;
+        let (pool, store) = link_store().await?;
+        let first = RedisURLRepository::connect(store.clone(), &redis.url, "test:", Duration::from_secs(60)).await?;
+        let second = RedisURLRepository::connect(store, &redis.url, "test:", Duration::from_secs(60)).await?;
+
+        assert_eq!(first.get_db_url_by_key("K1".into()).await?.target_url, "http://a");
+        // Written behind the cache's back: the second instance is served the cached row.
+        pool.execute("UPDATE urls SET target_url = 'http://changed' WHERE key = 'K1'").await?;
+        let url = second.get_db_url_by_key("K1".into()).await?;
+        assert_eq!(url.target_url, "http://a");
+        assert_eq!(url.password_hash.as_deref(), Some("hash"));
+
+        assert!(first.deactivate_url("K1".into()).await?);
+        assert!(matches!(second.get_db_url_by_key("K1".into()).await, Err(Error::RowNotFound)));
+        assert_eq!(second.find_url_by_key("K1".into()).await?.target_url, "http://changed");
+        assert!(matches!(second.find_url_by_key("nope".into()).await, Err(Error::RowNotFound)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[ignore = "needs redis-server"]
+    async fn falls_back_to_the_database_when_redis_is_down() -> Result<(), Box<dyn std::error::Error>> {
+        let redis = launch_redis().await;
+        let (pool, store) = link_store().await?;
+        let repo = RedisURLRepository::connect(store, &redis.url, "test:", Duration::from_secs(60)).await?;
+        drop(redis);
+
+        repo.increment_clicks("K1".into()).await?;
+        assert_eq!(stored_clicks(&pool).await?, (1, 0));
+        assert_eq!(repo.find_url_by_key("K1".into()).await?.clicks, 1);
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    #[ignore = "needs redis-server"]
+    async fn passes_the_repository_conformance_suite() {
+        let redis = launch_redis().await;
+        // one server for every check: each store gets its own key prefix
+        let stores = AtomicUsize::new(0);
+        check_repositories(|| async {
+            let pool = sqlite_pool().await;
+            let store = Arc::new(SqlxURLRepository::new(pool.clone()).await);
+            let prefix = format!("conformance{}:", stores.fetch_add(1, Ordering::Relaxed));
+            let urls = RedisURLRepository::connect(store, &redis.url, &prefix, Duration::from_secs(60)).await.expect("connect to Redis");
+            let urls = Arc::new(urls);
+            let users = LinkCacheUserRepository::new(Arc::new(SqlxUserRepository::new(pool).await), vec![urls.clone()]);
+            Repositories { urls, users: Arc::new(users) }
+        })
+        .await;
+    }
+}
@@ -1,11 +1,21 @@
 use async_trait::async_trait;
-use crate::url::domain::models::schema::{GeneratedKey, URL};
+use chrono::{DateTime, Utc};
+use crate::org::domain::models::organization::Role;
+use crate::org::infra::sqlx_org_repository::member_role;
+use crate::url::domain::models::filter::URLFilter;
+use crate::url::domain::models::health::LinkCheck;
+use crate::url::domain::models::metadata::LinkMetadata;
+use crate::url::domain::models::schema::{ClickRecord, GeneratedKey, GeoRule, UnknownKeyHits, URL};
+use crate::url::domain::models::tag::{Tag, TagStats};
+use crate::url::domain::repositories::tag_repository_port::TagRepositoryPort;
 use crate::url::domain::repositories::url_repository_port::URLRepositoryPort;
+use crate::shared::utils::public_key_part;
+use crate::user::domain::models::plan::LinkUsage;
 use log::debug;
-use sqlx::sqlite::SqlitePool;
-use sqlx::Row;
+use sqlx::sqlite::{Sqlite, SqliteConnection, SqlitePool};
+use sqlx::{QueryBuilder, Row};
 
-/// SQLx implementation of the `URLRepositoryPort` domain port.
+/// SQLx implementation of the `URLRepositoryPort` and `TagRepositoryPort` domain ports.
 ///
 /// This adapter performs all database operations related to URLs
 /// (creation, lookup, click counting and key management). It belongs
@@ -24,44 +34,77 @@ impl SqlxURLRepository {
         SqlxURLRepository { db_pool }
     }
 
-    /// Fetch the next available generated key from the `generated_keys` table.
+    /// Take the next available key out of the `generated_keys` pool, `None` when it is empty.
     ///
-    /// Returns the key string (for example "key_1234") or an `sqlx::Error`.
-    async fn get_generated_key(&self) -> Result<String, sqlx::Error> {
-        let key = sqlx::query_as::<_, GeneratedKey>("SELECT * FROM generated_keys ORDER BY key_value ASC LIMIT 1;")
-            .fetch_one(&self.db_pool)
-            .await
-            .map_err(|err| {
-                eprintln!("Error occurred[get_generated_key]: {}", err);
-                err
-            })?;
-        Ok(key.key_value)
+    /// Being a write, this is the statement that makes the surrounding transaction take the
+    /// database's write lock, so two transactions can never claim the same key.
+    async fn claim_generated_key(conn: &mut SqliteConnection) -> Result<Option<String>, sqlx::Error> {
+        let key = sqlx::query_as::<_, GeneratedKey>(
+            "DELETE FROM generated_keys WHERE key_value = (SELECT key_value FROM generated_keys ORDER BY key_value ASC LIMIT 1) RETURNING *",
+        )
+        .fetch_optional(conn)
+        .await
+        .map_err(|err| {
+            eprintln!("Error occurred[claim_generated_key]: {}", err);
+            err
+        })?;
+        Ok(key.map(|key| key.key_value))
     }
 
     /// Create a shortened URL for the given `user_id` and `target_url`.
     ///
     /// Behaviour:
-    /// - If the user already has the same target URL, return the existing URL DTO.
+    /// - The link goes to the `org_id` workspace, or to the user's personal one when `None`.
+    /// - If `reuse_existing` and the user already has the same target URL in that workspace,
+    ///   return the existing URL DTO.
     /// - Otherwise obtain a generated secret key, insert the new URL row and related
     ///   auxiliary records, then return the mapped DTO.
-    pub async fn create_url(&self, target_url: String, user_id: i32) -> Result<URL, sqlx::Error> {
+    /// - A `custom_key` replaces the generated public key; the secret key is still generated.
+    ///
+    /// Everything runs in one transaction that starts by claiming the generated key: concurrent
+    /// calls are serialized, and a failure at any step puts the key back in the pool. A
+    /// `custom_key` that another link took in the meantime fails with a unique violation.
+    pub async fn create_url(
+        &self, target_url: String, user_id: i32, org_id: Option<i64>, custom_key: Option<String>, reuse_existing: bool,
+    ) -> Result<URL, sqlx::Error> {
         debug!("Creating URL");
+        let mut tx = self.db_pool.begin().await?;
+        let claimed_key = Self::claim_generated_key(&mut tx).await?;
+        let org_id = match org_id {
+            Some(org_id) => Some(org_id),
+            None => Self::get_personal_org_id(&mut tx, user_id).await?,
+        };
         // check if the user already has this target URL
-        let url = self
-            .get_db_url_by_user_and_target_url(user_id, target_url.clone())
-            .await;
-        if let Ok(db_url) = url {
-            debug!("URL already exists: {:?}", db_url);
-            return Ok(db_url);
+        if reuse_existing {
+            let url = sqlx::query_as::<_, URL>(
+                "SELECT * FROM urls WHERE user_id = $1 AND target_url = $2 AND single_use = false AND org_id IS $3 LIMIT 1",
+            )
+            .bind(user_id)
+            .bind(target_url.clone())
+            .bind(org_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+            if let Some(db_url) = url {
+                debug!("URL already exists: {:?}", db_url);
+                // dropping the transaction rolls it back: the claimed key goes back to the pool
+                return Ok(db_url);
+            }
         }
 
-        let secret_key = &self.get_generated_key().await?;
+        let secret_key = &claimed_key.ok_or(sqlx::Error::RowNotFound)?;
         debug!("Secret key: {}", secret_key.clone());
-        // secret_key is expected to be in the format "key_1234" — use splitn and a safe fallback
-        let key = secret_key.splitn(2, '_').next().unwrap_or(secret_key.as_str());
-        let db_url = get_response_url_local(target_url, key, secret_key, user_id);
+        // secret_key is expected to be in the format "key_1234"
+        let key = match custom_key.as_deref() {
+            Some(custom_key) => custom_key,
+            None => public_key_part(secret_key),
+        };
+        let db_url = URL {
+            org_id,
+            custom_alias: custom_key.is_some(),
+            ..get_response_url_local(target_url, key, secret_key, user_id)
+        };
         let result_insert = sqlx::query_as::<_, URL>(
-            "INSERT INTO urls (key, secret_key, target_url, is_active, clicks, user_id) VALUES ($1, $2, $3, $4, $5, $6) RETURNING *",
+            "INSERT INTO urls (key, secret_key, target_url, is_active, clicks, user_id, created_at, org_id, custom_alias) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9) RETURNING *",
         )
         .bind(db_url.key.clone())
         .bind(db_url.secret_key.clone())
@@ -69,49 +112,54 @@ impl SqlxURLRepository {
         .bind(db_url.is_active)
         .bind(db_url.clicks)
         .bind(db_url.user_id)
-        .fetch_one(&self.db_pool)
+        .bind(db_url.created_at)
+        .bind(db_url.org_id)
+        .bind(db_url.custom_alias)
+        .fetch_one(&mut *tx)
         .await.map_err(|err| {
             eprintln!("Error occurred[_insert]: {}", err);
             err
         })?;
 
-        if !result_insert.secret_key.is_empty() {
-            // Record the key as "used" and remove it from the pool of generated keys
-            // to avoid future reuse. This is part of the key allocation workflow.
-            let _used_keys_insert = sqlx::query(
-                r#"
-                INSERT INTO used_keys (key_value, user_id)
-                VALUES ($1, $2)
-                "#,
-            )
-            .bind(key)
-            .bind(user_id)
-            .execute(&self.db_pool)
-            .await
-            .map_err(|err| {
-                eprintln!("Error occurred[_used_keys_insert]: {}", err);
-                err
-            })?;
-
-            let _delete_key = sqlx::query(
-                r#"
-                DELETE FROM generated_keys
-                WHERE key_value = $1
-                "#,
-            )
-            .bind(secret_key)
-            .execute(&self.db_pool)
-            .await
-            .map_err(|err| {
-                eprintln!("Error occurred[_delete_key]: {}", err);
-                err
-            })?;
-            debug!("Key {} deleted", secret_key);
+        // Record the key as "used" so it is never handed out again, even once the link is
+        // deleted. This is part of the key allocation workflow.
+        let _used_keys_insert = sqlx::query(
+            r#"
+            INSERT INTO used_keys (key_value, user_id)
+            VALUES ($1, $2)
+            "#,
+        )
+        .bind(key)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|err| {
+            eprintln!("Error occurred[_used_keys_insert]: {}", err);
+            err
+        })?;
+
+        if custom_key.is_some() {
+            // a pooled key whose public part matches the alias can no longer be handed out
+            sqlx::query("DELETE FROM generated_keys WHERE substr(key_value, 1, length($1) + 1) = $1 || '_'")
+                .bind(key)
+                .execute(&mut *tx)
+                .await?;
         }
+        tx.commit().await?;
+        debug!("Key {} claimed", secret_key);
         // Return inserted domain model (application layer will map to DTO)
         Ok(result_insert)
     }
 
+    /// Personal workspace of `user_id`, if it has one.
+    async fn get_personal_org_id(conn: &mut SqliteConnection, user_id: i32) -> Result<Option<i64>, sqlx::Error> {
+        let row: Option<(i64,)> = sqlx::query_as("SELECT id FROM organizations WHERE personal_user_id = $1")
+            .bind(user_id)
+            .fetch_optional(conn)
+            .await?;
+        Ok(row.map(|(id,)| id))
+    }
+
     /// Return the target URL string for an active short `url_key`.
     /// Returns `sqlx::Error` if the key is not found or the query fails.
     pub async fn get_db_url_by_key(&self, url_key: String) -> Result<URL, sqlx::Error> {
@@ -128,15 +176,57 @@ impl SqlxURLRepository {
         Ok(result)
     }
 
+    /// Return the URL row for `url_key` whether it is active or not.
+    pub async fn find_url_by_key(&self, url_key: String) -> Result<URL, sqlx::Error> {
+        sqlx::query_as::<_, URL>("SELECT * FROM urls WHERE key = $1 LIMIT 1")
+            .bind(url_key)
+            .fetch_one(&self.db_pool)
+            .await
+    }
+
+    /// Return the URL row whose admin key is `secret_key`, whether it is active or not.
+    pub async fn find_url_by_secret_key(&self, secret_key: String) -> Result<URL, sqlx::Error> {
+        sqlx::query_as::<_, URL>("SELECT * FROM urls WHERE secret_key = $1 LIMIT 1")
+            .bind(secret_key)
+            .fetch_one(&self.db_pool)
+            .await
+    }
+
+    /// Flip `is_active` off for `url_key`. The `is_active = true` guard makes concurrent callers
+    /// race on the row: only one of them sees `true`.
+    pub async fn deactivate_url(&self, url_key: String) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("UPDATE urls SET is_active = false WHERE key = $1 AND is_active = true")
+            .bind(url_key)
+            .execute(&self.db_pool)
+            .await?;
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Delete `url_key` and the rows that hang from it in one transaction. Its key stays in
+    /// `used_keys`, so it is never handed out again.
+    pub async fn delete_url(&self, url_key: String) -> Result<bool, sqlx::Error> {
+        let mut tx = self.db_pool.begin().await?;
+        for table in ["url_geo_rules", "url_clicks", "url_tags"] {
+            sqlx::query(&format!("DELETE FROM {table} WHERE url_key = $1"))
+                .bind(url_key.clone())
+                .execute(&mut *tx)
+                .await?;
+        }
+        let deleted = sqlx::query("DELETE FROM urls WHERE key = $1").bind(url_key).execute(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(deleted.rows_affected() > 0)
+    }
+
     /// Find an existing URL row for `user_id` that matches `target_url`.
-    /// Used to avoid creating duplicate shortened URLs for the same user+target.
+    /// Used to avoid creating duplicate shortened URLs for the same user+target; one-time
+    /// links are never handed out again.
     pub async fn get_db_url_by_user_and_target_url(
         &self, user_id: i32, target_url: String,
     ) -> Result<URL, sqlx::Error> {
         let result = sqlx::query_as::<_, URL>(
             "
             SELECT * FROM urls
-            WHERE user_id = $1 AND target_url = $2
+            WHERE user_id = $1 AND target_url = $2 AND single_use = false
             LIMIT 1
             ",
         )
@@ -153,7 +243,7 @@ impl SqlxURLRepository {
         let result_api_key = sqlx::query(
             "
             SELECT id FROM users
-            WHERE api_key = $1
+            WHERE api_key = $1 AND api_key != ''
             LIMIT 1
             ",
         )
@@ -162,54 +252,427 @@ impl SqlxURLRepository {
         .await
         .map_err(|err| {
             eprintln!("Error occurred[result_api_key]: {}", err);
-            ()
         })?;
 
         Ok(result_api_key.get("id"))
     }
 
-    /// Increment the click counter for the short URL identified by `url_key`.
-    /// This performs a read-modify-write operation on the `urls` table.
-    pub async fn increment_clicks(&self, url_key: String) -> sqlx::Result<()> {
-        let mut url = sqlx::query_as::<_, URL>("SELECT * FROM urls WHERE key = $1")
+    /// Increment the click counter for the short URL identified by `url_key`, in a single
+    /// statement that also checks `max_clicks`, so concurrent visits are all counted and never
+    /// exceed the limit; `false` once it is used up.
+    pub async fn increment_clicks(&self, url_key: String) -> sqlx::Result<bool> {
+        self.count_visit("clicks", url_key).await
+    }
+
+    /// Count a visit that went through the preview page.
+    pub async fn increment_interstitial_clicks(&self, url_key: String) -> sqlx::Result<bool> {
+        self.count_visit("interstitial_clicks", url_key).await
+    }
+
+    /// Add one to the `counter` column of `url_key`, unless the link used up its `max_clicks`.
+    async fn count_visit(&self, counter: &str, url_key: String) -> sqlx::Result<bool> {
+        let result = sqlx::query(&format!(
+            "UPDATE urls SET {counter} = {counter} + 1 \
+             WHERE key = $1 AND (max_clicks IS NULL OR clicks + interstitial_clicks < max_clicks)"
+        ))
+        .bind(url_key.clone())
+        .execute(&self.db_pool)
+        .await?;
+        if result.rows_affected() == 0 {
+            // over its limit, or no such link (`RowNotFound`)
+            self.find_url_by_key(url_key).await?;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    /// Add `clicks` and `interstitial_clicks` to the counters of `url_key`.
+    pub async fn add_clicks(&self, url_key: String, clicks: i32, interstitial_clicks: i32) -> sqlx::Result<()> {
+        let result = sqlx::query("UPDATE urls SET clicks = clicks + $1, interstitial_clicks = interstitial_clicks + $2 WHERE key = $3")
+            .bind(clicks)
+            .bind(interstitial_clicks)
+            .bind(url_key)
+            .execute(&self.db_pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+        Ok(())
+    }
+
+    /// Persist the mutable fields of `url` (matched by its public `key`) and return the stored row.
+    pub async fn update_url(&self, url: URL) -> Result<URL, sqlx::Error> {
+        sqlx::query_as::<_, URL>(
+            "
+            UPDATE urls
+            SET target_url = $1, is_active = $2, password_hash = $3, title = $4, show_interstitial = $5,
+                single_use = $6, require_signature = $7, active_from = $8, active_until = $9, folder = $10,
+                description = $11, notes = $12, og_title = $13, og_description = $14, og_image_url = $15,
+                fallback_url = $16, max_clicks = $17,
+                last_check_status = CASE WHEN target_url = $1 THEN last_check_status END,
+                last_check_url = CASE WHEN target_url = $1 THEN last_check_url END,
+                last_check_latency_ms = CASE WHEN target_url = $1 THEN last_check_latency_ms END,
+                last_check_error = CASE WHEN target_url = $1 THEN last_check_error END,
+                last_checked_at = CASE WHEN target_url = $1 THEN last_checked_at END,
+                failed_checks = CASE WHEN target_url = $1 THEN failed_checks ELSE 0 END
+            WHERE key = $18
+            RETURNING *
+            ",
+        )
+        .bind(url.target_url)
+        .bind(url.is_active)
+        .bind(url.password_hash)
+        .bind(url.title)
+        .bind(url.show_interstitial)
+        .bind(url.single_use)
+        .bind(url.require_signature)
+        .bind(url.active_from)
+        .bind(url.active_until)
+        .bind(url.folder)
+        .bind(url.description)
+        .bind(url.notes)
+        .bind(url.og_title)
+        .bind(url.og_description)
+        .bind(url.og_image_url)
+        .bind(url.fallback_url)
+        .bind(url.max_clicks)
+        .bind(url.key)
+        .fetch_one(&self.db_pool)
+        .await
+    }
+
+    /// Replace the country routing rules of `url_key` in a single transaction.
+    pub async fn set_geo_rules(&self, url_key: String, rules: Vec<GeoRule>) -> sqlx::Result<()> {
+        let mut tx = self.db_pool.begin().await?;
+        sqlx::query("DELETE FROM url_geo_rules WHERE url_key = $1")
             .bind(url_key.clone())
+            .execute(&mut *tx)
+            .await?;
+        for rule in rules {
+            sqlx::query("INSERT INTO url_geo_rules (url_key, country_code, target_url) VALUES ($1, $2, $3)")
+                .bind(url_key.clone())
+                .bind(rule.country_code)
+                .bind(rule.target_url)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await
+    }
+
+    /// Return the country routing rules configured for `url_key` (empty when none).
+    pub async fn get_geo_rules(&self, url_key: String) -> Result<Vec<GeoRule>, sqlx::Error> {
+        sqlx::query_as::<_, GeoRule>(
+            "SELECT url_key, country_code, target_url FROM url_geo_rules WHERE url_key = $1 ORDER BY country_code",
+        )
+        .bind(url_key)
+        .fetch_all(&self.db_pool)
+        .await
+    }
+
+    /// Store fetched metadata: title and description only fill in empty columns, the favicon is
+    /// always replaced.
+    pub async fn set_link_metadata(&self, url_key: String, metadata: LinkMetadata) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE urls SET title = COALESCE(title, $1), description = COALESCE(description, $2), favicon_url = $3 WHERE key = $4",
+        )
+        .bind(metadata.title)
+        .bind(metadata.description)
+        .bind(metadata.favicon_url)
+        .bind(url_key)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Count a visit to `key`, which matches no link. A new key beyond `max_keys` tracked ones
+    /// evicts the least visited, least recently seen keys.
+    pub async fn record_unknown_key(&self, key: String, max_keys: i64) -> Result<(), sqlx::Error> {
+        let now = Utc::now();
+        let (hits,): (i64,) = sqlx::query_as(
+            "INSERT INTO unknown_key_hits (key, hits, first_seen_at, last_seen_at) VALUES ($1, 1, $2, $2) \
+             ON CONFLICT (key) DO UPDATE SET hits = hits + 1, last_seen_at = excluded.last_seen_at RETURNING hits",
+        )
+        .bind(key)
+        .bind(now)
+        .fetch_one(&self.db_pool)
+        .await?;
+        if hits == 1 {
+            sqlx::query(
+                "DELETE FROM unknown_key_hits WHERE key IN \
+                 (SELECT key FROM unknown_key_hits ORDER BY hits DESC, last_seen_at DESC LIMIT -1 OFFSET $1)",
+            )
+            .bind(max_keys)
+            .execute(&self.db_pool)
+            .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn list_unknown_keys(&self, limit: i64) -> Result<Vec<UnknownKeyHits>, sqlx::Error> {
+        sqlx::query_as::<_, UnknownKeyHits>("SELECT * FROM unknown_key_hits ORDER BY hits DESC, last_seen_at DESC LIMIT $1")
+            .bind(limit)
+            .fetch_all(&self.db_pool)
+            .await
+    }
+
+    pub async fn record_link_check(&self, url_key: String, check: LinkCheck) -> Result<(), sqlx::Error> {
+        let failed = check.is_failure();
+        sqlx::query(
+            "UPDATE urls SET last_check_status = $1, last_check_url = $2, last_check_latency_ms = $3, last_check_error = $4, \
+             last_checked_at = $5, failed_checks = CASE WHEN $6 THEN failed_checks + 1 ELSE 0 END WHERE key = $7",
+        )
+        .bind(check.status_code.map(i32::from))
+        .bind(check.final_url)
+        .bind(check.latency_ms)
+        .bind(check.error)
+        .bind(check.checked_at)
+        .bind(failed)
+        .bind(url_key)
+        .execute(&self.db_pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Append a click event to `url_clicks`; the timestamp is assigned by the database.
+    pub async fn record_click(&self, click: ClickRecord) -> sqlx::Result<()> {
+        sqlx::query("INSERT INTO url_clicks (url_key, country, source) VALUES ($1, $2, $3)")
+            .bind(click.url_key)
+            .bind(click.country)
+            .bind(click.source)
+            .execute(&self.db_pool)
+            .await?;
+        Ok(())
+    }
+
+    /// List the links matching `filter`, ordered by creation (row id).
+    pub async fn list_urls(&self, filter: URLFilter) -> Result<Vec<URL>, sqlx::Error> {
+        let mut query = QueryBuilder::<Sqlite>::new("SELECT * FROM urls WHERE 1 = 1");
+        if filter.active {
+            query.push(" AND is_active = true");
+        }
+        if let Some(user_id) = filter.user_id {
+            query.push(" AND user_id = ").push_bind(user_id);
+        }
+        if let Some(org_id) = filter.org_id {
+            query.push(" AND org_id = ").push_bind(org_id);
+        }
+        if filter.scheduled {
+            query.push(" AND (active_from IS NOT NULL OR active_until IS NOT NULL)");
+        }
+        if let Some(tag) = filter.tag {
+            query
+                .push(" AND key IN (SELECT ut.url_key FROM url_tags ut JOIN tags t ON t.id = ut.tag_id WHERE t.org_id IS urls.org_id AND t.name = ")
+                .push_bind(tag)
+                .push(")");
+        }
+        if let Some(folder) = filter.folder {
+            query.push(" AND folder = ").push_bind(folder);
+        }
+        if let Some(search) = filter.search {
+            let pattern = format!("%{}%", search.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+            query.push(" AND (");
+            for (i, column) in ["key", "target_url", "title", "description", "notes"].into_iter().enumerate() {
+                if i > 0 {
+                    query.push(" OR ");
+                }
+                query.push(column).push(" LIKE ").push_bind(pattern.clone()).push(" ESCAPE '\\'");
+            }
+            query.push(")");
+        }
+        if let Some(checked_before) = filter.checked_before {
+            query.push(" AND (last_checked_at IS NULL OR last_checked_at < ").push_bind(checked_before).push(")");
+        }
+        if let Some(min_failed_checks) = filter.min_failed_checks {
+            query.push(" AND failed_checks >= ").push_bind(min_failed_checks);
+        }
+        query.push(" ORDER BY id");
+        if let Some(limit) = filter.limit {
+            query.push(" LIMIT ").push_bind(limit);
+        }
+        query.build_query_as::<URL>().fetch_all(&self.db_pool).await
+    }
+
+    /// Role of `user_id` in the `org_id` workspace, `None` for non-members.
+    pub async fn get_member_role(&self, org_id: i64, user_id: i32) -> Result<Option<Role>, sqlx::Error> {
+        member_role(&self.db_pool, org_id, user_id).await
+    }
+
+    pub async fn list_tags(&self, org_id: i64) -> Result<Vec<Tag>, sqlx::Error> {
+        sqlx::query_as::<_, Tag>("SELECT id, org_id, name FROM tags WHERE org_id = $1 ORDER BY name")
+            .bind(org_id)
+            .fetch_all(&self.db_pool)
+            .await
+    }
+
+    pub async fn get_tag(&self, tag_id: i64) -> Result<Tag, sqlx::Error> {
+        sqlx::query_as::<_, Tag>("SELECT id, org_id, name FROM tags WHERE id = $1")
+            .bind(tag_id)
+            .fetch_one(&self.db_pool)
+            .await
+    }
+
+    pub async fn create_tag(&self, org_id: i64, name: String) -> Result<Tag, sqlx::Error> {
+        sqlx::query_as::<_, Tag>("INSERT INTO tags (org_id, name) VALUES ($1, $2) RETURNING id, org_id, name")
+            .bind(org_id)
+            .bind(name)
+            .fetch_one(&self.db_pool)
+            .await
+    }
+
+    pub async fn rename_tag(&self, tag_id: i64, name: String) -> Result<Tag, sqlx::Error> {
+        sqlx::query_as::<_, Tag>("UPDATE tags SET name = $1 WHERE id = $2 RETURNING id, org_id, name")
+            .bind(name)
+            .bind(tag_id)
             .fetch_one(&self.db_pool)
+            .await
+    }
+
+    /// Delete the tag and its attachments in one transaction.
+    pub async fn delete_tag(&self, tag_id: i64) -> Result<bool, sqlx::Error> {
+        let mut tx = self.db_pool.begin().await?;
+        sqlx::query("DELETE FROM url_tags WHERE tag_id = $1").bind(tag_id).execute(&mut *tx).await?;
+        let deleted = sqlx::query("DELETE FROM tags WHERE id = $1").bind(tag_id).execute(&mut *tx).await?;
+        tx.commit().await?;
+        Ok(deleted.rows_affected() > 0)
+    }
+
+    pub async fn attach_tag(&self, url_key: String, tag_id: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("INSERT OR IGNORE INTO url_tags (url_key, tag_id) VALUES ($1, $2)")
+            .bind(url_key)
+            .bind(tag_id)
+            .execute(&self.db_pool)
             .await?;
-        url.clicks += 1;
-        sqlx::query("UPDATE urls SET clicks = $1 WHERE key = $2")
-            .bind(url.clicks)
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn detach_tag(&self, url_key: String, tag_id: i64) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM url_tags WHERE url_key = $1 AND tag_id = $2")
             .bind(url_key)
+            .bind(tag_id)
             .execute(&self.db_pool)
             .await?;
+        Ok(result.rows_affected() > 0)
+    }
 
-        Ok(())
+    /// `(url_key, tag name)` pairs of `url_keys`, by tag name.
+    pub async fn get_url_tags(&self, url_keys: Vec<String>) -> Result<Vec<(String, String)>, sqlx::Error> {
+        if url_keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut query = QueryBuilder::<Sqlite>::new(
+            "SELECT ut.url_key, t.name FROM url_tags ut JOIN tags t ON t.id = ut.tag_id WHERE ut.url_key IN (",
+        );
+        let mut keys = query.separated(", ");
+        for key in url_keys {
+            keys.push_bind(key);
+        }
+        query.push(") ORDER BY t.name");
+        query.build_query_as::<(String, String)>().fetch_all(&self.db_pool).await
+    }
+
+    /// Links, clicks and preview clicks per tag of `org_id`, tags without links included.
+    pub async fn get_tag_stats(&self, org_id: i64) -> Result<Vec<TagStats>, sqlx::Error> {
+        sqlx::query_as::<_, TagStats>(
+            "SELECT t.id AS tag_id, t.name, COUNT(u.id) AS links,
+                    COALESCE(SUM(u.clicks), 0) AS clicks, COALESCE(SUM(u.interstitial_clicks), 0) AS interstitial_clicks
+             FROM tags t
+             LEFT JOIN url_tags ut ON ut.tag_id = t.id
+             LEFT JOIN urls u ON u.key = ut.url_key
+             WHERE t.org_id = $1
+             GROUP BY t.id, t.name
+             ORDER BY t.name",
+        )
+        .bind(org_id)
+        .fetch_all(&self.db_pool)
+        .await
+    }
+
+    /// Whether `key` is taken by a link or reserved in `used_keys`.
+    pub async fn key_exists(&self, key: String) -> Result<bool, sqlx::Error> {
+        let row: (bool,) = sqlx::query_as(
+            "SELECT EXISTS (SELECT 1 FROM urls WHERE key = $1) OR EXISTS (SELECT 1 FROM used_keys WHERE key_value = $1)",
+        )
+        .bind(key)
+        .fetch_one(&self.db_pool)
+        .await?;
+        Ok(row.0)
+    }
+
+    /// Name of the plan assigned to `user_id`, if any.
+    pub async fn get_user_plan(&self, user_id: i32) -> Result<Option<String>, sqlx::Error> {
+        let row: (Option<String>,) = sqlx::query_as("SELECT plan FROM users WHERE id = $1")
+            .bind(user_id)
+            .fetch_one(&self.db_pool)
+            .await?;
+        Ok(row.0)
+    }
+
+    /// Active links, links created from `since` and custom aliases of `user_id`.
+    pub async fn get_link_usage(&self, user_id: i32, since: DateTime<Utc>) -> Result<LinkUsage, sqlx::Error> {
+        let row: (i64, i64, i64) = sqlx::query_as(
+            "SELECT COALESCE(SUM(is_active), 0), COALESCE(SUM(julianday(created_at) >= julianday($2)), 0), COALESCE(SUM(custom_alias), 0)
+             FROM urls WHERE user_id = $1",
+        )
+        .bind(user_id)
+        .bind(since)
+        .fetch_one(&self.db_pool)
+        .await?;
+        Ok(LinkUsage {
+            active_links: row.0 as u32,
+            links_today: row.1 as u32,
+            custom_aliases: row.2 as u32,
+        })
     }
 }
 
 /// Build a `URL` value used by the repository insert logic.
 /// This is duplicated here as a private helper for the infra adapter.
-fn get_response_url_local(target_url: String, key: &str, secret_key: &String, user_id: i32) -> URL {
+fn get_response_url_local(target_url: String, key: &str, secret_key: &str, user_id: i32) -> URL {
     URL {
         target_url: target_url.clone(),
         key: key.to_string(),
-        secret_key: secret_key.clone(),
+        secret_key: secret_key.to_string(),
         is_active: true,
         clicks: 0,
         user_id,
+        created_at: Some(Utc::now()),
+        ..Default::default()
     }
 }
 
 #[async_trait]
 /// `URLRepositoryPort` implementation that delegates to the SQLx-backed methods above.
 impl URLRepositoryPort for SqlxURLRepository {
-    async fn create_url(&self, target_url: String, user_id: i32) -> Result<URL, sqlx::Error> {
-        self.create_url(target_url, user_id).await
+    async fn create_url(
+        &self, target_url: String, user_id: i32, org_id: Option<i64>, custom_key: Option<String>, reuse_existing: bool,
+    ) -> Result<URL, sqlx::Error> {
+        self.create_url(target_url, user_id, org_id, custom_key, reuse_existing).await
+    }
+
+    async fn key_exists(&self, key: String) -> Result<bool, sqlx::Error> {
+        self.key_exists(key).await
     }
 
     async fn get_db_url_by_key(&self, url_key: String) -> Result<URL, sqlx::Error> {
         self.get_db_url_by_key(url_key).await
     }
 
+    async fn find_url_by_key(&self, url_key: String) -> Result<URL, sqlx::Error> {
+        self.find_url_by_key(url_key).await
+    }
+
+    async fn find_url_by_secret_key(&self, secret_key: String) -> Result<URL, sqlx::Error> {
+        self.find_url_by_secret_key(secret_key).await
+    }
+
+    async fn deactivate_url(&self, url_key: String) -> Result<bool, sqlx::Error> {
+        self.deactivate_url(url_key).await
+    }
+
+    async fn delete_url(&self, url_key: String) -> Result<bool, sqlx::Error> {
+        self.delete_url(url_key).await
+    }
+
     async fn get_db_url_by_user_and_target_url(&self, user_id: i32, target_url: String) -> Result<URL, sqlx::Error> {
         self.get_db_url_by_user_and_target_url(user_id, target_url).await
     }
@@ -218,55 +681,127 @@ impl URLRepositoryPort for SqlxURLRepository {
         self.get_user_by_apy_key(api_key).await
     }
 
-    async fn increment_clicks(&self, url_key: String) -> sqlx::Result<()> {
+    async fn increment_clicks(&self, url_key: String) -> sqlx::Result<bool> {
         self.increment_clicks(url_key).await
     }
+
+    async fn increment_interstitial_clicks(&self, url_key: String) -> sqlx::Result<bool> {
+        self.increment_interstitial_clicks(url_key).await
+    }
+
+    async fn add_clicks(&self, url_key: String, clicks: i32, interstitial_clicks: i32) -> sqlx::Result<()> {
+        self.add_clicks(url_key, clicks, interstitial_clicks).await
+    }
+
+    async fn update_url(&self, url: URL) -> Result<URL, sqlx::Error> {
+        self.update_url(url).await
+    }
+
+    async fn set_geo_rules(&self, url_key: String, rules: Vec<GeoRule>) -> sqlx::Result<()> {
+        self.set_geo_rules(url_key, rules).await
+    }
+
+    async fn get_geo_rules(&self, url_key: String) -> Result<Vec<GeoRule>, sqlx::Error> {
+        self.get_geo_rules(url_key).await
+    }
+
+    async fn set_link_metadata(&self, url_key: String, metadata: LinkMetadata) -> Result<(), sqlx::Error> {
+        self.set_link_metadata(url_key, metadata).await
+    }
+
+    async fn record_link_check(&self, url_key: String, check: LinkCheck) -> Result<(), sqlx::Error> {
+        self.record_link_check(url_key, check).await
+    }
+
+    async fn record_unknown_key(&self, key: String, max_keys: i64) -> Result<(), sqlx::Error> {
+        self.record_unknown_key(key, max_keys).await
+    }
+
+    async fn list_unknown_keys(&self, limit: i64) -> Result<Vec<UnknownKeyHits>, sqlx::Error> {
+        self.list_unknown_keys(limit).await
+    }
+
+    async fn record_click(&self, click: ClickRecord) -> sqlx::Result<()> {
+        self.record_click(click).await
+    }
+
+    async fn list_urls(&self, filter: URLFilter) -> Result<Vec<URL>, sqlx::Error> {
+        self.list_urls(filter).await
+    }
+
+    async fn get_member_role(&self, org_id: i64, user_id: i32) -> Result<Option<Role>, sqlx::Error> {
+        self.get_member_role(org_id, user_id).await
+    }
+
+    async fn get_user_plan(&self, user_id: i32) -> Result<Option<String>, sqlx::Error> {
+        self.get_user_plan(user_id).await
+    }
+
+    async fn get_link_usage(&self, user_id: i32, since: DateTime<Utc>) -> Result<LinkUsage, sqlx::Error> {
+        self.get_link_usage(user_id, since).await
+    }
+}
+
+#[async_trait]
+impl TagRepositoryPort for SqlxURLRepository {
+    async fn list_tags(&self, org_id: i64) -> Result<Vec<Tag>, sqlx::Error> {
+        self.list_tags(org_id).await
+    }
+
+    async fn get_tag(&self, tag_id: i64) -> Result<Tag, sqlx::Error> {
+        self.get_tag(tag_id).await
+    }
+
+    async fn create_tag(&self, org_id: i64, name: String) -> Result<Tag, sqlx::Error> {
+        self.create_tag(org_id, name).await
+    }
+
+    async fn rename_tag(&self, tag_id: i64, name: String) -> Result<Tag, sqlx::Error> {
+        self.rename_tag(tag_id, name).await
+    }
+
+    async fn delete_tag(&self, tag_id: i64) -> Result<bool, sqlx::Error> {
+        self.delete_tag(tag_id).await
+    }
+
+    async fn attach_tag(&self, url_key: String, tag_id: i64) -> Result<bool, sqlx::Error> {
+        self.attach_tag(url_key, tag_id).await
+    }
+
+    async fn detach_tag(&self, url_key: String, tag_id: i64) -> Result<bool, sqlx::Error> {
+        self.detach_tag(url_key, tag_id).await
+    }
+
+    async fn get_url_tags(&self, url_keys: Vec<String>) -> Result<Vec<(String, String)>, sqlx::Error> {
+        self.get_url_tags(url_keys).await
+    }
+
+    async fn get_tag_stats(&self, org_id: i64) -> Result<Vec<TagStats>, sqlx::Error> {
+        self.get_tag_stats(org_id).await
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use sqlx::sqlite::SqlitePoolOptions;
+    use crate::shared::repository_conformance::{check_repositories, sqlite_pool, Repositories};
+    use crate::user::infra::sqlx_user_repository::SqlxUserRepository;
+    use std::sync::Arc;
+    use crate::url::domain::models::schema::ClickSource;
     use sqlx::Executor;
 
     #[tokio::test]
     async fn sqlx_url_repository_create_get_and_increment() -> Result<(), Box<dyn std::error::Error>> {
-        let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await?;
-
-        // Create minimal schema required by the repository
-        pool.execute(r#"
-            CREATE TABLE users (
-                id INTEGER PRIMARY KEY,
-                username TEXT NOT NULL,
-                email TEXT NOT NULL,
-                api_key TEXT NOT NULL
-            );
-            CREATE TABLE urls (
-                id INTEGER PRIMARY KEY,
-                key TEXT NOT NULL,
-                secret_key TEXT NOT NULL,
-                target_url TEXT NOT NULL,
-                is_active BOOLEAN NOT NULL,
-                clicks INTEGER NOT NULL,
-                user_id INTEGER NOT NULL
-            );
-            CREATE TABLE generated_keys (
-                key_value TEXT PRIMARY KEY
-            );
-            CREATE TABLE used_keys (
-                id INTEGER PRIMARY KEY,
-                key_value VARCHAR(50),
-                user_id INTEGER
-            );
-        "#).await?;
-
-        // Insert a generated key that will be consumed by create_url
-        pool.execute("INSERT INTO generated_keys (key_value) VALUES ('key_ABC')").await?;
+        let pool = sqlite_pool().await;
+        pool.execute("INSERT INTO users (id, username, email, api_key) VALUES (1, 'alice', 'a@x.com', 'ka')").await?;
+        pool.execute("INSERT INTO organizations (id, name, personal_user_id) VALUES (5, 'alice', 1)").await?;
 
         let repo = SqlxURLRepository::new(pool.clone()).await;
 
-        let created = repo.create_url("http://ex".into(), 1).await?;
+        let created = repo.create_url("http://ex".into(), 1, None, None, true).await?;
         assert_eq!(created.target_url, "http://ex");
+        assert!(created.created_at.is_some());
+        assert_eq!(created.org_id, Some(5));
 
         let fetched = repo.get_db_url_by_key(created.key.clone()).await?;
         assert_eq!(fetched.key, created.key);
@@ -278,29 +813,311 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn update_url_persists_mutable_fields() -> Result<(), Box<dyn std::error::Error>> {
+        let pool = sqlite_pool().await;
+        pool.execute("INSERT INTO users (id, username, email, api_key) VALUES (1, 'alice', 'a@x.com', 'ka')").await?;
+        pool.execute("INSERT INTO urls (key, secret_key, target_url, is_active, clicks, user_id) VALUES ('K1','SK1','http://a',1,0,1)").await?;
+        let repo = SqlxURLRepository::new(pool.clone()).await;
+
+        let mut url = repo.get_db_url_by_key("K1".into()).await?;
+        assert!(url.password_hash.is_none());
+        url.password_hash = Some("hash".into());
+        url.title = Some("Launch".into());
+        url.show_interstitial = true;
+        let updated = repo.update_url(url).await?;
+        assert_eq!(updated.password_hash.as_deref(), Some("hash"));
+        let stored = repo.get_db_url_by_key("K1".into()).await?;
+        assert_eq!(stored.password_hash.as_deref(), Some("hash"));
+        assert_eq!(stored.title.as_deref(), Some("Launch"));
+        assert!(stored.show_interstitial);
+
+        repo.increment_interstitial_clicks("K1".into()).await?;
+        assert_eq!(repo.get_db_url_by_key("K1".into()).await?.interstitial_clicks, 1);
+
+        assert!(repo.deactivate_url("K1".into()).await?);
+        assert!(!repo.deactivate_url("K1".into()).await?, "second deactivation must report no change");
+        assert!(matches!(repo.get_db_url_by_key("K1".into()).await, Err(sqlx::Error::RowNotFound)));
+        assert!(!repo.find_url_by_key("K1".into()).await?.is_active);
+        assert_eq!(repo.find_url_by_secret_key("SK1".into()).await?.key, "K1");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn list_urls_filters_by_owner_and_schedule() -> Result<(), Box<dyn std::error::Error>> {
+        let pool = sqlite_pool().await;
+        pool.execute("INSERT INTO users (id, username, email, api_key) VALUES (1, 'alice', 'a@x.com', 'ka'), (2, 'bob', 'b@x.com', 'kb')").await?;
+        pool.execute("INSERT INTO urls (key, secret_key, target_url, is_active, clicks, user_id) VALUES ('K1','SK1','http://a',1,0,1), ('K2','SK2','http://b',1,0,1), ('K3','SK3','http://c',1,0,2)").await?;
+        let repo = SqlxURLRepository::new(pool.clone()).await;
+
+        let mut launch = repo.get_db_url_by_key("K2".into()).await?;
+        launch.active_from = Some("2030-01-01T09:00:00Z".parse()?);
+        repo.update_url(launch.clone()).await?;
+
+        let mine = repo.list_urls(URLFilter { user_id: Some(1), ..Default::default() }).await?;
+        assert_eq!(mine.iter().map(|u| u.key.as_str()).collect::<Vec<_>>(), vec!["K1", "K2"]);
+        let scheduled = repo.list_urls(URLFilter { user_id: Some(1), scheduled: true, ..Default::default() }).await?;
+        assert_eq!(scheduled, vec![launch.clone()]);
+
+        launch.description = Some("Spring LAUNCH page".into());
+        launch.notes = Some("owner: 100% marketing".into());
+        repo.update_url(launch).await?;
+        let search = |text: &str| URLFilter { search: Some(text.into()), ..Default::default() };
+        let found = repo.list_urls(search("launch")).await?;
+        assert_eq!(found.iter().map(|u| u.key.as_str()).collect::<Vec<_>>(), vec!["K2"]);
+        assert_eq!(repo.list_urls(search("100%")).await?.len(), 1);
+        assert!(repo.list_urls(search("0%m")).await?.is_empty(), "wildcards in the text are literal");
+        assert_eq!(repo.list_urls(search("http://c")).await?[0].key, "K3");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn unknown_key_hits_are_counted_per_key() -> Result<(), Box<dyn std::error::Error>> {
+        let pool = sqlite_pool().await;
+        let repo = SqlxURLRepository::new(pool.clone()).await;
+        for key in ["flyer", "typo", "flyer"] {
+            repo.record_unknown_key(key.into(), 10).await?;
+        }
+        let hits = repo.list_unknown_keys(10).await?;
+        assert_eq!(hits.iter().map(|hit| (hit.key.as_str(), hit.hits)).collect::<Vec<_>>(), vec![("flyer", 2), ("typo", 1)]);
+        assert!(hits[0].first_seen_at <= hits[0].last_seen_at);
+        assert_eq!(repo.list_unknown_keys(1).await?.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn link_checks_count_consecutive_failures() -> Result<(), Box<dyn std::error::Error>> {
+        let pool = sqlite_pool().await;
+        pool.execute("INSERT INTO users (id, username, email, api_key) VALUES (1, 'alice', 'a@x.com', 'ka')").await?;
+        pool.execute("INSERT INTO urls (key, secret_key, target_url, is_active, clicks, user_id) VALUES ('K1','SK1','http://a',1,0,1), ('K2','SK2','http://b',1,0,1), ('K3','SK3','http://c',0,0,1)").await?;
+        let repo = SqlxURLRepository::new(pool.clone()).await;
+        let now = Utc::now();
+        let dead = LinkCheck { status_code: Some(404), final_url: Some("http://a/".into()), latency_ms: 12, error: None, checked_at: now };
+
+        repo.record_link_check("K1".into(), dead.clone()).await?;
+        repo.record_link_check("K1".into(), dead.clone()).await?;
+        let stored = repo.find_url_by_key("K1".into()).await?;
+        assert_eq!((stored.last_check_status, stored.last_check_latency_ms, stored.failed_checks), (Some(404), Some(12), 2));
+        assert_eq!(stored.last_check_url.as_deref(), Some("http://a/"));
+
+        let due = |checked_before| URLFilter { active: true, checked_before: Some(checked_before), ..Default::default() };
+        let keys = |urls: Vec<URL>| urls.into_iter().map(|u| u.key).collect::<Vec<_>>();
+        assert_eq!(keys(repo.list_urls(due(now)).await?), vec!["K2"]);
+        assert_eq!(keys(repo.list_urls(due(now + chrono::Duration::seconds(1))).await?), vec!["K1", "K2"]);
+        let broken = URLFilter { active: true, min_failed_checks: Some(2), ..Default::default() };
+        assert_eq!(keys(repo.list_urls(broken.clone()).await?), vec!["K1"]);
+
+        repo.record_link_check("K1".into(), LinkCheck { status_code: Some(200), ..dead }).await?;
+        assert_eq!(repo.find_url_by_key("K1".into()).await?.failed_checks, 0);
+        assert!(repo.list_urls(broken).await?.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn geo_rules_are_replaced_and_clicks_recorded() -> Result<(), Box<dyn std::error::Error>> {
+        let pool = sqlite_pool().await;
+        pool.execute("INSERT INTO users (id, username, email, api_key) VALUES (1, 'alice', 'a@x.com', 'ka')").await?;
+        pool.execute("INSERT INTO urls (key, secret_key, target_url, is_active, clicks, user_id) VALUES ('K','SK','http://a',1,0,1)").await?;
+        let repo = SqlxURLRepository::new(pool.clone()).await;
+        let rule = |cc: &str, t: &str| GeoRule { url_key: "K".into(), country_code: cc.into(), target_url: t.into() };
+
+        repo.set_geo_rules("K".into(), vec![rule("US", "http://us"), rule("ES", "http://eu")]).await?;
+        repo.set_geo_rules("K".into(), vec![rule("ES", "http://es")]).await?;
+        assert_eq!(repo.get_geo_rules("K".into()).await?, vec![rule("ES", "http://es")]);
+
+        repo.record_click(ClickRecord { url_key: "K".into(), country: Some("ES".into()), source: ClickSource::Interstitial }).await?;
+        let row: (i64, Option<String>, String) =
+            sqlx::query_as("SELECT COUNT(*), MAX(country), MAX(source) FROM url_clicks WHERE url_key = 'K'")
+                .fetch_one(&pool)
+                .await?;
+        assert_eq!(row, (1, Some("ES".to_string()), "interstitial".to_string()));
+
+        assert!(repo.delete_url("K".into()).await?);
+        assert!(!repo.delete_url("K".into()).await?);
+        assert!(repo.get_geo_rules("K".into()).await?.is_empty());
+        let clicks: (i64,) = sqlx::query_as("SELECT COUNT(*) FROM url_clicks").fetch_one(&pool).await?;
+        assert_eq!(clicks.0, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn tags_are_attached_filtered_and_aggregated() -> Result<(), Box<dyn std::error::Error>> {
+        let pool = sqlite_pool().await;
+        pool.execute("INSERT INTO users (id, username, email, api_key) VALUES (1, 'alice', 'a@x.com', 'ka')").await?;
+        pool.execute("INSERT INTO organizations (id, name) VALUES (5, 'Team'), (6, 'Other'); INSERT INTO urls (key, secret_key, target_url, is_active, clicks, user_id, org_id, folder) VALUES ('K1','S1','http://a',1,3,1,5,'launch'), ('K2','S2','http://b',1,4,1,5,NULL), ('K3','S3','http://c',1,9,1,6,NULL)").await?;
+        let repo = SqlxURLRepository::new(pool.clone()).await;
+
+        let promo = repo.create_tag(5, "promo".into()).await?;
+        let news = repo.create_tag(5, "news".into()).await?;
+        let other = repo.create_tag(6, "promo".into()).await?;
+        let duplicate = repo.create_tag(5, "promo".into()).await.expect_err("names are unique per workspace");
+        assert!(duplicate.as_database_error().is_some_and(|err| err.is_unique_violation()));
+        assert_eq!(repo.list_tags(5).await?.iter().map(|t| t.name.as_str()).collect::<Vec<_>>(), vec!["news", "promo"]);
+
+        assert!(repo.attach_tag("K1".into(), promo.id).await?);
+        assert!(!repo.attach_tag("K1".into(), promo.id).await?);
+        repo.attach_tag("K1".into(), news.id).await?;
+        repo.attach_tag("K2".into(), promo.id).await?;
+        repo.attach_tag("K3".into(), other.id).await?;
+        let tags = repo.get_url_tags(vec!["K1".into(), "K2".into()]).await?;
+        assert_eq!(tags, vec![("K1".to_string(), "news".to_string()), ("K1".into(), "promo".into()), ("K2".into(), "promo".into())]);
+
+        let keys = |urls: Vec<URL>| urls.into_iter().map(|u| u.key).collect::<Vec<_>>();
+        let tagged = repo.list_urls(URLFilter { org_id: Some(5), tag: Some("promo".into()), ..Default::default() }).await?;
+        assert_eq!(keys(tagged), vec!["K1", "K2"]);
+        let filed = repo.list_urls(URLFilter { folder: Some("launch".into()), ..Default::default() }).await?;
+        assert_eq!(keys(filed), vec!["K1"]);
+
+        let stats = repo.get_tag_stats(5).await?;
+        assert_eq!(stats[1], TagStats { tag_id: promo.id, name: "promo".into(), links: 2, clicks: 7, interstitial_clicks: 0 });
+        assert_eq!(stats[0].links, 1);
+
+        assert_eq!(repo.rename_tag(news.id, "press".into()).await?.name, "press");
+        assert!(repo.detach_tag("K2".into(), promo.id).await?);
+        assert!(repo.delete_tag(promo.id).await?);
+        assert!(matches!(repo.get_tag(promo.id).await, Err(sqlx::Error::RowNotFound)));
+        assert_eq!(repo.get_url_tags(vec!["K1".into()]).await?, vec![("K1".to_string(), "press".to_string())]);
+        assert!(repo.delete_url("K1".into()).await?);
+        assert!(repo.get_url_tags(vec!["K1".into()]).await?.is_empty());
+        Ok(())
+    }
+
     #[tokio::test]
     async fn create_url_returns_existing_if_present() -> Result<(), Box<dyn std::error::Error>> {
-        let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await?;
-        pool.execute(r#"CREATE TABLE urls (id INTEGER PRIMARY KEY, key TEXT NOT NULL, secret_key TEXT NOT NULL, target_url TEXT NOT NULL, is_active BOOLEAN NOT NULL, clicks INTEGER NOT NULL, user_id INTEGER NOT NULL);"#).await?;
-        pool.execute("INSERT INTO urls (key, secret_key, target_url, is_active, clicks, user_id) VALUES ('K1','SK1','http://same',1,0,1)").await?;
+        let pool = sqlite_pool().await;
+        pool.execute("INSERT INTO users (id, username, email, api_key) VALUES (1, 'alice', 'a@x.com', 'ka')").await?;
+        pool.execute("INSERT INTO organizations (id, name, personal_user_id) VALUES (5, 'alice', 1), (6, 'Team', NULL); INSERT INTO organization_members (org_id, user_id, role) VALUES (5, 1, 'owner'), (6, 1, 'viewer');").await?;
+        pool.execute("INSERT INTO urls (key, secret_key, target_url, is_active, clicks, user_id, org_id) VALUES ('K1','SK1','http://same',1,0,1,5)").await?;
+        pool.execute("DELETE FROM generated_keys").await?;
+        pool.execute("INSERT INTO generated_keys (key_value) VALUES ('K2_SK2')").await?;
         let repo = SqlxURLRepository::new(pool.clone()).await;
 
-        let res = repo.create_url("http://same".into(), 1).await?;
+        let res = repo.create_url("http://same".into(), 1, None, None, true).await?;
         assert_eq!(res.key, "K1");
+        // the same target in another workspace is a link of its own
+        let team = repo.create_url("http://same".into(), 1, Some(6), None, true).await?;
+        assert_eq!((team.key.as_str(), team.org_id), ("K2", Some(6)));
+        assert_eq!(repo.list_urls(URLFilter { org_id: Some(6), ..Default::default() }).await?.len(), 1);
+
+        assert_eq!(repo.get_member_role(6, 1).await?, Some(Role::Viewer));
+        assert_eq!(repo.get_member_role(6, 2).await?, None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn custom_aliases_and_link_usage() -> Result<(), Box<dyn std::error::Error>> {
+        let pool = sqlite_pool().await;
+        pool.execute("INSERT INTO users (id, username, email, api_key, plan) VALUES (1, 'alice', 'a@x.com', 'ka', 'pro'), (2, 'bob', 'b@x.com', 'kb', NULL)").await?;
+        pool.execute("INSERT INTO urls (key, secret_key, target_url, is_active, clicks, user_id, created_at) VALUES ('OLD','OLD_S','http://old',0,0,1,'2020-01-01 00:00:00')").await?;
+        pool.execute("DELETE FROM generated_keys").await?;
+        pool.execute("INSERT INTO generated_keys (key_value) VALUES ('AAA_S1'), ('BBB_S2'), ('promo_S3')").await?;
+        let repo = SqlxURLRepository::new(pool.clone()).await;
+
+        let aliased = repo.create_url("http://promo".into(), 1, None, Some("promo".into()), false).await?;
+        assert_eq!((aliased.key.as_str(), aliased.secret_key.as_str(), aliased.custom_alias), ("promo", "AAA_S1", true));
+        // the pooled key with the same public part is gone, the next one is still available
+        let left: Vec<(String,)> = sqlx::query_as("SELECT key_value FROM generated_keys ORDER BY key_value").fetch_all(&pool).await?;
+        assert_eq!(left, vec![("BBB_S2".to_string(),)]);
+        repo.create_url("http://plain".into(), 1, None, None, false).await?;
+
+        assert!(repo.key_exists("promo".into()).await?);
+        assert!(repo.key_exists("BBB".into()).await?);
+        assert!(!repo.key_exists("free".into()).await?);
+
+        let since = Utc::now() - chrono::Duration::hours(1);
+        let usage = repo.get_link_usage(1, since).await?;
+        assert_eq!(usage, LinkUsage { active_links: 2, links_today: 2, custom_aliases: 1 });
+        assert_eq!(repo.get_link_usage(2, since).await?, LinkUsage::default());
+
+        assert_eq!(repo.get_user_plan(1).await?.as_deref(), Some("pro"));
+        assert_eq!(repo.get_user_plan(2).await?, None);
+        assert!(matches!(repo.get_user_plan(9).await, Err(sqlx::Error::RowNotFound)));
         Ok(())
     }
 
     #[tokio::test]
     async fn create_url_errors_when_no_generated_key() -> Result<(), Box<dyn std::error::Error>> {
-        let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await?;
-        pool.execute(r#"CREATE TABLE users (id INTEGER PRIMARY KEY, username TEXT NOT NULL, email TEXT NOT NULL, api_key TEXT NOT NULL); CREATE TABLE urls (id INTEGER PRIMARY KEY, key TEXT NOT NULL, secret_key TEXT NOT NULL, target_url TEXT NOT NULL, is_active BOOLEAN NOT NULL, clicks INTEGER NOT NULL, user_id INTEGER NOT NULL); CREATE TABLE generated_keys (key_value TEXT PRIMARY KEY); CREATE TABLE used_keys (id INTEGER PRIMARY KEY, key_value VARCHAR(50), user_id INTEGER);"#).await?;
+        let pool = sqlite_pool().await;
+        pool.execute("DELETE FROM generated_keys").await?;
         let repo = SqlxURLRepository::new(pool.clone()).await;
 
-        let err = repo.create_url("http://no-key".into(), 1).await.expect_err("expected error when no generated key");
+        let err = repo.create_url("http://no-key".into(), 1, None, None, true).await.expect_err("expected error when no generated key");
         match err {
             sqlx::Error::RowNotFound => Ok(()),
             _ => Ok(()),
         }
     }
-}
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn parallel_creations_never_share_a_key() -> Result<(), Box<dyn std::error::Error>> {
+        // a file, so that every pooled connection really runs side by side
+        let path = std::env::temp_dir().join(format!("parallel-links-{}.db", std::process::id()));
+        let options = sqlx::sqlite::SqliteConnectOptions::new().filename(&path).create_if_missing(true);
+        let pool = sqlx::sqlite::SqlitePoolOptions::new().max_connections(8).connect_with(options).await?;
+        crate::config::database::create_schema(&pool).await?;
+        for i in 0..40 {
+            sqlx::query("INSERT INTO generated_keys (key_value) VALUES ($1)").bind(format!("K{i:02}_S{i:02}")).execute(&pool).await?;
+        }
+        pool.execute("INSERT INTO users (id, username, email, api_key) VALUES (1, 'alice', 'a@x.com', 'ka')").await?;
+        let repo = Arc::new(SqlxURLRepository::new(pool.clone()).await);
+
+        let create = |target: String, alias: Option<&str>| {
+            let (repo, alias) = (repo.clone(), alias.map(String::from));
+            tokio::spawn(async move { repo.create_url(target, 1, None, alias, false).await })
+        };
+        let links: Vec<_> = (0..24).map(|i| create(format!("http://a.com/{i}"), None)).collect();
+        let promos: Vec<_> = (0..8).map(|i| create(format!("http://promo.com/{i}"), Some("promo"))).collect();
+        let mut keys = Vec::new();
+        for link in links {
+            keys.push(link.await??.key);
+        }
+        let mut promo_created = 0;
+        for promo in promos {
+            match promo.await? {
+                Ok(url) => promo_created += usize::from(url.key == "promo"),
+                Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {}
+                Err(err) => return Err(err.into()),
+            }
+        }
+        assert_eq!(promo_created, 1, "an alias goes to exactly one link");
+        keys.sort();
+        keys.dedup();
+        assert_eq!(keys.len(), 24);
+
+        // only the keys of created links left the pool, each recorded once
+        let (pooled,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM generated_keys").fetch_one(&pool).await?;
+        let (used, distinct): (i64, i64) =
+            sqlx::query_as("SELECT COUNT(*), COUNT(DISTINCT key_value) FROM used_keys").fetch_one(&pool).await?;
+        assert_eq!((pooled, used, distinct), (40 - 25, 25, 25));
+
+        pool.close().await;
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn urls_keys_are_unique() -> Result<(), Box<dyn std::error::Error>> {
+        let pool = sqlite_pool().await;
+        pool.execute("INSERT INTO users (id, username, email, api_key) VALUES (1, 'alice', 'a@x.com', 'ka')").await?;
+        pool.execute("INSERT INTO urls (key, secret_key, target_url, is_active, clicks, user_id) VALUES ('K1','SK1','http://a',1,0,1)").await?;
+        for duplicate in [
+            "INSERT INTO urls (key, secret_key, target_url, is_active, clicks, user_id) VALUES ('K1','SK2','http://b',1,0,1)",
+            "INSERT INTO urls (key, secret_key, target_url, is_active, clicks, user_id) VALUES ('K2','SK1','http://b',1,0,1)",
+        ] {
+            assert!(matches!(pool.execute(duplicate).await, Err(sqlx::Error::Database(e)) if e.is_unique_violation()));
+        }
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn passes_the_repository_conformance_suite() {
+        check_repositories(|| async {
+            let pool = sqlite_pool().await;
+            Repositories {
+                urls: Arc::new(SqlxURLRepository::new(pool.clone()).await),
+                users: Arc::new(SqlxUserRepository::new(pool).await),
+            }
+        })
+        .await;
+    }
+}
@@ -1,7 +1,11 @@
-use actix_web::{delete, get, post, web, HttpResponse, Responder};
+use actix_web::{delete, get, patch, post, put, web, HttpResponse, Responder};
 
-use crate::user::application::dtos::user_dto::UserDtoCreate;
-use crate::user::domain::services::user_service::UserService;
+use crate::config::env::AppConfig;
+use crate::shared::api_key::ApiKey;
+use crate::user::application::dtos::user_dto::{
+    DeleteUserQueryDto, LinksAffectedDto, SetPlanDto, TransferLinksDto, UserDtoCreate, UserDtoUpdate,
+};
+use crate::user::domain::services::user_service::{OwnershipError, SetPlanError, UserError, UserService};
 
 use std::sync::Arc;
 
@@ -11,72 +15,169 @@ pub async fn create_user(
 ) -> impl Responder {
     match user_service.create_user(user_dto.into_inner()).await {
         Ok(user_dto_response) => HttpResponse::Ok().json(user_dto_response),
-        Err(_) => HttpResponse::InternalServerError().finish(),
+        Err(err) => user_error_response(err, "Error creating user"),
     }
 }
 
+/// List every user; restricted to admin API keys.
 #[get("/users")]
-pub async fn get_users(user_service: web::Data<Arc<UserService>>) -> impl Responder {
+pub async fn get_users(
+    api_key: ApiKey, user_service: web::Data<Arc<UserService>>, config: web::Data<AppConfig>,
+) -> impl Responder {
+    if !config.is_admin_key(&api_key.0) {
+        return HttpResponse::Forbidden().body("Admin API key required");
+    }
     match user_service.get_users().await {
         Ok(users_dto) => HttpResponse::Ok().json(users_dto),
         Err(_) => HttpResponse::InternalServerError().finish(),
     }
 }
 
-#[delete("/users/{id}")]
-async fn delete_user(user_service: web::Data<Arc<UserService>>, id: web::Path<i32>) -> impl Responder {
-    match user_service.delete_user(id.into_inner()).await {
-        Ok(_) => HttpResponse::Ok().body("User deleted successfully"),
-        Err(_) => HttpResponse::InternalServerError().body("Error deleting user"),
+/// Read the user; restricted to the user itself and admin API keys.
+#[get("/users/{id}")]
+async fn get_user(
+    api_key: ApiKey, user_service: web::Data<Arc<UserService>>, config: web::Data<AppConfig>, id: web::Path<i32>,
+) -> impl Responder {
+    let id = id.into_inner();
+    if let Err(response) = authorize(&api_key, id, &user_service, &config).await {
+        return response;
+    }
+    match user_service.get_user(id).await {
+        Ok(user_dto) => HttpResponse::Ok().json(user_dto),
+        Err(err) => user_error_response(err, "Error getting user"),
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use actix_web::test::{init_service, call_service, TestRequest, read_body_json};
-    use actix_web::App;
-    use std::sync::{Arc, Mutex};
-    use async_trait::async_trait;
-    use crate::user::application::dtos::user_dto::{UserDtoCreate, UserDto, UserDtoCreateResponse};
+/// Change the username and/or email of the user; restricted to the user itself and admin API keys.
+#[patch("/users/{id}")]
+async fn update_user(
+    api_key: ApiKey, user_service: web::Data<Arc<UserService>>, config: web::Data<AppConfig>, id: web::Path<i32>,
+    body: web::Json<UserDtoUpdate>,
+) -> impl Responder {
+    let id = id.into_inner();
+    if let Err(response) = authorize(&api_key, id, &user_service, &config).await {
+        return response;
+    }
+    match user_service.update_user(id, body.into_inner()).await {
+        Ok(user_dto) => HttpResponse::Ok().json(user_dto),
+        Err(err) => user_error_response(err, "Error updating user"),
+    }
+}
 
-    struct FakeUserRepo {
-        users: Mutex<Vec<UserDto>>,
-        next_id: Mutex<i64>,
+/// Delete the user; restricted to the user itself and admin API keys.
+#[delete("/users/{id}")]
+async fn delete_user(
+    api_key: ApiKey, user_service: web::Data<Arc<UserService>>, config: web::Data<AppConfig>, id: web::Path<i32>,
+    query: web::Query<DeleteUserQueryDto>,
+) -> impl Responder {
+    let id = id.into_inner();
+    if let Err(response) = authorize(&api_key, id, &user_service, &config).await {
+        return response;
+    }
+    let strategy = match query.to_strategy() {
+        Ok(strategy) => strategy,
+        Err(message) => return HttpResponse::BadRequest().body(message),
+    };
+    match user_service.delete_user(id, strategy).await {
+        Ok(links) => HttpResponse::Ok().json(LinksAffectedDto { links }),
+        Err(err) => ownership_error_response(err, "Error deleting user"),
     }
+}
 
-    impl FakeUserRepo {
-        fn new() -> Self { Self { users: Mutex::new(Vec::new()), next_id: Mutex::new(1) } }
+/// Give links of the user away; restricted to the user itself and admin API keys.
+#[post("/users/{id}/links/transfer")]
+async fn transfer_links(
+    api_key: ApiKey, user_service: web::Data<Arc<UserService>>, config: web::Data<AppConfig>, id: web::Path<i32>,
+    body: web::Json<TransferLinksDto>,
+) -> impl Responder {
+    let id = id.into_inner();
+    if let Err(response) = authorize(&api_key, id, &user_service, &config).await {
+        return response;
     }
+    let TransferLinksDto { to_user_id, key } = body.into_inner();
+    match user_service.transfer_links(id, to_user_id, key).await {
+        Ok(links) => HttpResponse::Ok().json(LinksAffectedDto { links }),
+        Err(err) => ownership_error_response(err, "Error transferring links"),
+    }
+}
 
-    #[async_trait]
-    impl crate::user::domain::repositories::user_repository_port::UserRepositoryPort for FakeUserRepo {
-        async fn create_user(&self, user_dto: UserDtoCreate, api_key: String) -> Result<UserDtoCreateResponse, sqlx::Error> {
-            let mut users = self.users.lock().unwrap();
-            let mut id = self.next_id.lock().unwrap();
-            let user = UserDto { id: *id, username: user_dto.username.clone(), email: user_dto.email.clone() };
-            *id += 1;
-            users.push(user);
-            Ok(UserDtoCreateResponse { user: user_dto, api_key })
+/// Assign a plan to the user; restricted to admin API keys.
+#[put("/users/{id}/plan")]
+async fn set_user_plan(
+    api_key: ApiKey, user_service: web::Data<Arc<UserService>>, config: web::Data<AppConfig>, id: web::Path<i32>,
+    body: web::Json<SetPlanDto>,
+) -> impl Responder {
+    if !config.is_admin_key(&api_key.0) {
+        return HttpResponse::Forbidden().body("Admin API key required");
+    }
+    match user_service.set_plan(id.into_inner(), &body.plan).await {
+        Ok(plan) => HttpResponse::Ok().json(plan),
+        Err(err @ SetPlanError::UnknownPlan(_)) => HttpResponse::BadRequest().body(err.to_string()),
+        Err(err @ SetPlanError::NotFound) => HttpResponse::NotFound().body(err.to_string()),
+        Err(SetPlanError::Database(db_err)) => {
+            eprintln!("Error occurred[user_ctrl]: {}", db_err);
+            HttpResponse::InternalServerError().body("Error assigning plan")
         }
+    }
+}
+
+/// Admin API keys may act on every account; any other key only on the account it belongs to.
+async fn authorize(api_key: &ApiKey, user_id: i32, user_service: &UserService, config: &AppConfig) -> Result<(), HttpResponse> {
+    if config.is_admin_key(&api_key.0) {
+        return Ok(());
+    }
+    match user_service.find_user_id(api_key.0.clone()).await {
+        Some(caller_id) if caller_id == user_id => Ok(()),
+        Some(_) => Err(HttpResponse::Forbidden().body("You can only manage your own account")),
+        None => Err(HttpResponse::Unauthorized().body("Invalid API key")),
+    }
+}
 
-        async fn get_users(&self) -> Result<Vec<UserDto>, sqlx::Error> {
-            let users = self.users.lock().unwrap();
-            Ok(users.clone())
+fn user_error_response(err: UserError, failure: &str) -> HttpResponse {
+    match err {
+        UserError::Invalid(_) => HttpResponse::BadRequest().body(err.to_string()),
+        UserError::NotFound => HttpResponse::NotFound().body(err.to_string()),
+        UserError::UsernameTaken | UserError::EmailTaken => HttpResponse::Conflict().body(err.to_string()),
+        UserError::Database(db_err) => {
+            eprintln!("Error occurred[user_ctrl]: {}", db_err);
+            HttpResponse::InternalServerError().body(failure.to_string())
         }
+    }
+}
 
-        async fn delete_user(&self, id: i32) -> Result<(), sqlx::Error> {
-            let mut users = self.users.lock().unwrap();
-            users.retain(|u| u.id != id as i64);
-            Ok(())
+fn ownership_error_response(err: OwnershipError, failure: &str) -> HttpResponse {
+    match err {
+        OwnershipError::NotFound => HttpResponse::NotFound().body(err.to_string()),
+        OwnershipError::SameUser => HttpResponse::BadRequest().body(err.to_string()),
+        OwnershipError::Database(db_err) => {
+            eprintln!("Error occurred[user_ctrl]: {}", db_err);
+            HttpResponse::InternalServerError().body(failure.to_string())
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::{init_service, call_service, TestRequest, read_body_json};
+    use actix_web::App;
+    use std::sync::Arc;
+    use crate::config::memory::MemoryDatabase;
+    use crate::url::domain::models::schema::URL;
+    use crate::user::application::dtos::user_dto::{UserDtoCreate, UserDto, UserDtoCreateResponse};
+    use crate::user::infra::memory_user_repository::MemoryUserRepository;
+
+    fn memory_repo() -> (Arc<MemoryDatabase>, Arc<MemoryUserRepository>) {
+        let db = Arc::new(MemoryDatabase::new());
+        (db.clone(), Arc::new(MemoryUserRepository::new(db)))
+    }
 
     #[actix_web::test]
     async fn controller_create_get_delete_user() {
-        let repo = Arc::new(FakeUserRepo::new());
-        let service = crate::user::domain::services::user_service::UserService::new(repo.clone());
-        let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).service(create_user).service(get_users).service(delete_user)).await;
+        let (_, repo) = memory_repo();
+        let service = crate::user::domain::services::user_service::UserService::new(repo);
+        let cfg = AppConfig { admin_api_keys: vec!["admin".into()], ..Default::default() };
+        let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(cfg)).service(create_user).service(get_users).service(delete_user)).await;
 
         let dto = UserDtoCreate { username: "testuser".into(), email: "t@e.com".into() };
         let req = TestRequest::post().uri("/users").set_json(&dto).to_request();
@@ -84,15 +185,152 @@ mod tests {
         assert!(resp.status().is_success());
         let created: UserDtoCreateResponse = read_body_json(resp).await;
         assert_eq!(created.user.username, "testuser");
+        let other = UserDtoCreate { username: "other".into(), email: "o@e.com".into() };
+        let other: UserDtoCreateResponse = read_body_json(call_service(&app, TestRequest::post().uri("/users").set_json(&other).to_request()).await).await;
 
-        let req2 = TestRequest::get().uri("/users").to_request();
-        let resp2 = call_service(&app, req2).await;
+        let list = |key: &str| TestRequest::get().uri("/users").insert_header(("X-API-Key", key)).to_request();
+        assert_eq!(call_service(&app, TestRequest::get().uri("/users").to_request()).await.status(), 401);
+        assert_eq!(call_service(&app, list(&created.api_key)).await.status(), 403);
+        let resp2 = call_service(&app, list("admin")).await;
         let users: Vec<UserDto> = read_body_json(resp2).await;
-        assert_eq!(users.len(), 1);
+        assert_eq!(users.len(), 2);
 
-        let id = users[0].id;
-        let req3 = TestRequest::delete().uri(&format!("/users/{}", id)).to_request();
-        let resp3 = call_service(&app, req3).await;
+        let id = created.id;
+        let delete = |query: &str, key: Option<&str>| {
+            let req = TestRequest::delete().uri(&format!("/users/{id}{query}"));
+            match key {
+                Some(key) => req.insert_header(("X-API-Key", key)).to_request(),
+                None => req.to_request(),
+            }
+        };
+        assert_eq!(call_service(&app, delete("?strategy=cascade", None)).await.status(), 401);
+        assert_eq!(call_service(&app, delete("?strategy=cascade", Some("unknown"))).await.status(), 401);
+        assert_eq!(call_service(&app, delete("?strategy=cascade", Some(&other.api_key))).await.status(), 403);
+        assert_eq!(call_service(&app, delete("", Some(&created.api_key))).await.status(), 400);
+        let resp3 = call_service(&app, delete("?strategy=cascade", Some(&created.api_key))).await;
         assert!(resp3.status().is_success());
+        assert_eq!(call_service(&app, delete("?strategy=deactivate", Some("admin"))).await.status(), 404);
+    }
+
+    #[actix_web::test]
+    async fn controller_transfers_links_between_users() {
+        let (db, repo) = memory_repo();
+        let service = crate::user::domain::services::user_service::UserService::new(repo);
+        let cfg = AppConfig { admin_api_keys: vec!["admin".into()], ..Default::default() };
+        let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(cfg)).service(create_user).service(transfer_links).service(delete_user)).await;
+        let mut keys = Vec::new();
+        for name in ["a", "b"] {
+            let dto = UserDtoCreate { username: name.into(), email: format!("{name}@e.com") };
+            let created: UserDtoCreateResponse = read_body_json(call_service(&app, TestRequest::post().uri("/users").set_json(&dto).to_request()).await).await;
+            keys.push(created.api_key);
+        }
+        for key in ["k1", "k2"] {
+            db.insert_url(URL { key: key.into(), target_url: "http://t".into(), is_active: true, user_id: 1, ..Default::default() });
+        }
+        let transfer = |key: &str, body: &TransferLinksDto| {
+            TestRequest::post().uri("/users/1/links/transfer").insert_header(("X-API-Key", key)).set_json(body).to_request()
+        };
+
+        // b cannot take a's links
+        let body = TransferLinksDto { to_user_id: 2, key: None };
+        assert_eq!(call_service(&app, transfer(&keys[1], &body)).await.status(), 403);
+        let resp = call_service(&app, TestRequest::post().uri("/users/1/links/transfer").set_json(&body).to_request()).await;
+        assert_eq!(resp.status(), 401);
+        let resp = call_service(&app, transfer(&keys[0], &body)).await;
+        assert!(resp.status().is_success());
+        let moved: LinksAffectedDto = read_body_json(resp).await;
+        assert_eq!(moved.links, 2);
+
+        let to_self = TransferLinksDto { to_user_id: 1, key: Some("k".into()) };
+        assert_eq!(call_service(&app, transfer(&keys[0], &to_self)).await.status(), 400);
+
+        let to_missing = TransferLinksDto { to_user_id: 9, key: None };
+        assert_eq!(call_service(&app, transfer("admin", &to_missing)).await.status(), 404);
+
+        let delete = |query: &str, key: &str| TestRequest::delete().uri(&format!("/users/1{query}")).insert_header(("X-API-Key", key)).to_request();
+        assert_eq!(call_service(&app, delete("?strategy=cascade", &keys[1])).await.status(), 403);
+        assert_eq!(call_service(&app, delete("?strategy=transfer", &keys[0])).await.status(), 400);
+        let resp = call_service(&app, delete("?strategy=transfer&to_user_id=2", "admin")).await;
+        assert!(resp.status().is_success());
+    }
+
+    #[actix_web::test]
+    async fn controller_gets_and_updates_users() {
+        let (_, repo) = memory_repo();
+        let service = crate::user::domain::services::user_service::UserService::new(repo);
+        let cfg = AppConfig { admin_api_keys: vec!["admin".into()], ..Default::default() };
+        let app = init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(service)))
+                .app_data(web::Data::new(cfg))
+                .service(create_user)
+                .service(get_user)
+                .service(update_user),
+        )
+        .await;
+        let create = |username: &str, email: &str| {
+            TestRequest::post().uri("/users").set_json(UserDtoCreate { username: username.into(), email: email.into() }).to_request()
+        };
+        let resp = call_service(&app, create("alice", "a@e.com")).await;
+        let created: UserDtoCreateResponse = read_body_json(resp).await;
+        assert_eq!(created.id, 1);
+        let alice_key = created.api_key;
+        let resp = call_service(&app, create("bob", "b@e.com")).await;
+        let bob_key = read_body_json::<UserDtoCreateResponse, _>(resp).await.api_key;
+        assert_eq!(call_service(&app, create("bob", "other@e.com")).await.status(), 409);
+        assert_eq!(call_service(&app, create("carol", "B@E.com")).await.status(), 409);
+        assert_eq!(call_service(&app, create("carol", "not-an-email")).await.status(), 400);
+        assert_eq!(call_service(&app, create("", "c@e.com")).await.status(), 400);
+
+        let get = |key: &str, id: i32| TestRequest::get().uri(&format!("/users/{id}")).insert_header(("X-API-Key", key)).to_request();
+        let resp = call_service(&app, get(&alice_key, 1)).await;
+        let alice: UserDto = read_body_json(resp).await;
+        assert_eq!((alice.username.as_str(), alice.email.as_str()), ("alice", "a@e.com"));
+        assert!(call_service(&app, get("admin", 1)).await.status().is_success());
+        assert_eq!(call_service(&app, get("admin", 9)).await.status(), 404);
+        // other users' accounts are off limits, unknown or missing keys are rejected
+        assert_eq!(call_service(&app, get(&bob_key, 1)).await.status(), 403);
+        assert_eq!(call_service(&app, get("nope", 1)).await.status(), 401);
+        assert_eq!(call_service(&app, TestRequest::get().uri("/users/1").to_request()).await.status(), 401);
+
+        let patch = |key: &str, id: i32, body: serde_json::Value| {
+            TestRequest::patch().uri(&format!("/users/{id}")).insert_header(("X-API-Key", key)).set_json(body).to_request()
+        };
+        let resp = call_service(&app, patch(&alice_key, 1, serde_json::json!({ "email": "alice@e.com" }))).await;
+        assert!(resp.status().is_success());
+        let alice: UserDto = read_body_json(resp).await;
+        assert_eq!((alice.username.as_str(), alice.email.as_str()), ("alice", "alice@e.com"));
+        assert_eq!(call_service(&app, patch(&bob_key, 2, serde_json::json!({ "username": "Alice" }))).await.status(), 409);
+        assert_eq!(call_service(&app, patch(&bob_key, 2, serde_json::json!({ "email": "b@" }))).await.status(), 400);
+        assert_eq!(call_service(&app, patch("admin", 9, serde_json::json!({ "username": "x" }))).await.status(), 404);
+        assert_eq!(call_service(&app, patch(&bob_key, 1, serde_json::json!({ "username": "mallory" }))).await.status(), 403);
+        assert_eq!(call_service(&app, patch("nope", 1, serde_json::json!({ "username": "mallory" }))).await.status(), 401);
+        let resp = call_service(&app, get("admin", 1)).await;
+        assert_eq!(read_body_json::<UserDto, _>(resp).await.username, "alice", "rejected updates change nothing");
+    }
+
+    #[actix_web::test]
+    async fn controller_sets_plan_with_admin_key_only() {
+        let (_, repo) = memory_repo();
+        let service = crate::user::domain::services::user_service::UserService::new(repo);
+        let cfg = AppConfig { admin_api_keys: vec!["admin".into()], ..Default::default() };
+        let app = init_service(App::new().app_data(web::Data::new(Arc::new(service))).app_data(web::Data::new(cfg)).service(create_user).service(set_user_plan)).await;
+        let dto = UserDtoCreate { username: "a".into(), email: "a@e.com".into() };
+        call_service(&app, TestRequest::post().uri("/users").set_json(&dto).to_request()).await;
+
+        let plan = |key: &str, id: i32, name: &str| {
+            TestRequest::put()
+                .uri(&format!("/users/{id}/plan"))
+                .insert_header(("X-API-Key", key))
+                .set_json(SetPlanDto { plan: name.into() })
+                .to_request()
+        };
+        assert_eq!(call_service(&app, plan("user", 1, "unlimited")).await.status(), 403);
+        assert_eq!(call_service(&app, plan("admin", 1, "gold")).await.status(), 400);
+        assert_eq!(call_service(&app, plan("admin", 9, "unlimited")).await.status(), 404);
+        let resp = call_service(&app, plan("admin", 1, "unlimited")).await;
+        assert!(resp.status().is_success());
+        let body: serde_json::Value = read_body_json(resp).await;
+        assert_eq!(body["name"], "unlimited");
     }
 }
@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use crate::user::domain::models::ownership::DeleteUserStrategy;
+use crate::user::domain::models::user::User;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserDto {
@@ -7,6 +9,12 @@ pub struct UserDto {
     pub email: String,
 }
 
+impl From<User> for UserDto {
+    fn from(user: User) -> Self {
+        UserDto { id: user.id, username: user.username, email: user.email }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserDtoCreate {
     pub username: String,
@@ -15,6 +23,52 @@ pub struct UserDtoCreate {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UserDtoCreateResponse {
+    pub id: i64,
     pub user: UserDtoCreate,
     pub api_key: String,
 }
+
+/// Body of `PATCH /users/{id}`; fields left out are not changed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserDtoUpdate {
+    pub username: Option<String>,
+    pub email: Option<String>,
+}
+
+/// Query of `DELETE /users/{id}`: `strategy` is `transfer` (with `to_user_id`), `deactivate` or `cascade`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeleteUserQueryDto {
+    pub strategy: Option<String>,
+    pub to_user_id: Option<i32>,
+}
+
+impl DeleteUserQueryDto {
+    pub fn to_strategy(&self) -> Result<DeleteUserStrategy, String> {
+        match (self.strategy.as_deref(), self.to_user_id) {
+            (Some("transfer"), Some(to_user_id)) => Ok(DeleteUserStrategy::Transfer { to_user_id }),
+            (Some("transfer"), None) => Err("strategy=transfer requires to_user_id".to_string()),
+            (Some("deactivate"), _) => Ok(DeleteUserStrategy::Deactivate),
+            (Some("cascade"), _) => Ok(DeleteUserStrategy::Cascade),
+            (Some(other), _) => Err(format!("unknown strategy '{other}', expected transfer, deactivate or cascade")),
+            (None, _) => Err("missing strategy: transfer, deactivate or cascade".to_string()),
+        }
+    }
+}
+
+/// Body of `POST /users/{id}/links/transfer`; without `key` every link of the user is moved.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TransferLinksDto {
+    pub to_user_id: i32,
+    pub key: Option<String>,
+}
+
+/// Body of `PUT /users/{id}/plan`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetPlanDto {
+    pub plan: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinksAffectedDto {
+    pub links: u64,
+}
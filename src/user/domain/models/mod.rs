@@ -1 +1,3 @@
+pub mod ownership;
+pub mod plan;
 pub mod user;
@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+/// Username of the placeholder account that keeps links of users deleted with
+/// `DeleteUserStrategy::Deactivate`; no real user may take it. The placeholder has an empty API
+/// key, which never authenticates, and is left out of user listings.
+pub const DELETED_USER_NAME: &str = "deleted-user";
+
+/// What happens to a user's links when the user is deleted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "strategy", rename_all = "snake_case")]
+pub enum DeleteUserStrategy {
+    /// Reassign every link to another, existing user.
+    Transfer { to_user_id: i32 },
+    /// Keep the links (and their keys) but stop redirecting them.
+    Deactivate,
+    /// Delete the links together with their geo rules and click history.
+    Cascade,
+}
@@ -0,0 +1,142 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq)]
+pub enum PlanError {
+    #[error("invalid plan '{0}': expected name:max_active_links/max_links_per_day/max_custom_aliases ('*' = unlimited)")]
+    InvalidSpec(String),
+    #[error("plan '{0}' is defined more than once")]
+    Duplicate(String),
+    #[error("unknown plan '{0}'")]
+    Unknown(String),
+}
+
+/// Limits applied to the links a user creates; `None` means unlimited.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Plan {
+    pub name: String,
+    pub max_active_links: Option<u32>,
+    pub max_links_per_day: Option<u32>,
+    pub max_custom_aliases: Option<u32>,
+}
+
+impl Plan {
+    pub fn unlimited(name: &str) -> Self {
+        Self { name: name.to_string(), max_active_links: None, max_links_per_day: None, max_custom_aliases: None }
+    }
+}
+
+/// `name:max_active_links/max_links_per_day/max_custom_aliases`, e.g. `free:100/20/0` or `pro:*/*/50`.
+impl FromStr for Plan {
+    type Err = PlanError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let invalid = || PlanError::InvalidSpec(spec.trim().to_string());
+        let (name, limits) = spec.trim().split_once(':').ok_or_else(invalid)?;
+        let limits = limits
+            .split('/')
+            .map(|limit| match limit.trim() {
+                "*" => Ok(None),
+                number => number.parse::<u32>().map(Some).map_err(|_| invalid()),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        match (name.trim(), limits.as_slice()) {
+            ("", _) => Err(invalid()),
+            (name, [active, daily, aliases]) => Ok(Plan {
+                name: name.to_string(),
+                max_active_links: *active,
+                max_links_per_day: *daily,
+                max_custom_aliases: *aliases,
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// The configured plans; users without a (known) plan fall back to the default one.
+#[derive(Clone, Debug)]
+pub struct PlanCatalog {
+    plans: Vec<Plan>,
+    default_plan: String,
+}
+
+impl Default for PlanCatalog {
+    /// A single plan without limits.
+    fn default() -> Self {
+        Self { plans: vec![Plan::unlimited("unlimited")], default_plan: "unlimited".to_string() }
+    }
+}
+
+impl PlanCatalog {
+    pub fn new(plans: Vec<Plan>, default_plan: &str) -> Result<Self, PlanError> {
+        for (index, plan) in plans.iter().enumerate() {
+            if plans[..index].iter().any(|other| other.name == plan.name) {
+                return Err(PlanError::Duplicate(plan.name.clone()));
+            }
+        }
+        if !plans.iter().any(|plan| plan.name == default_plan) {
+            return Err(PlanError::Unknown(default_plan.to_string()));
+        }
+        Ok(Self { plans, default_plan: default_plan.to_string() })
+    }
+
+    pub fn find(&self, name: &str) -> Option<&Plan> {
+        self.plans.iter().find(|plan| plan.name == name)
+    }
+
+    /// Plan of a user whose `plan` column holds `name`.
+    pub fn plan_for(&self, name: Option<&str>) -> &Plan {
+        name.and_then(|name| self.find(name))
+            .or_else(|| self.find(&self.default_plan))
+            .unwrap_or(&self.plans[0])
+    }
+}
+
+/// What a user currently consumes from its plan.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct LinkUsage {
+    pub active_links: u32,
+    /// Links created since midnight UTC.
+    pub links_today: u32,
+    pub custom_aliases: u32,
+}
+
+/// Response of the usage endpoint.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct PlanUsage {
+    pub plan: Plan,
+    pub usage: LinkUsage,
+    /// When `links_today` goes back to zero.
+    pub daily_reset_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_plan_specs() {
+        let free: Plan = "free:100/20/0".parse().unwrap();
+        assert_eq!(free, Plan { name: "free".into(), max_active_links: Some(100), max_links_per_day: Some(20), max_custom_aliases: Some(0) });
+        assert_eq!(" pro : */* / 5".parse::<Plan>().unwrap().max_custom_aliases, Some(5));
+        assert_eq!("big:*/*/*".parse::<Plan>().unwrap(), Plan::unlimited("big"));
+        for spec in ["free", "free:1/2", "free:1/2/3/4", ":1/2/3", "free:a/2/3", "free:-1/2/3"] {
+            assert!(spec.parse::<Plan>().is_err(), "{spec} should be rejected");
+        }
+    }
+
+    #[test]
+    fn catalog_falls_back_to_default_plan() {
+        let plans = vec!["free:10/5/0".parse().unwrap(), "pro:*/*/10".parse().unwrap()];
+        let catalog = PlanCatalog::new(plans.clone(), "free").unwrap();
+        assert_eq!(catalog.plan_for(Some("pro")).name, "pro");
+        assert_eq!(catalog.plan_for(Some("gone")).name, "free");
+        assert_eq!(catalog.plan_for(None).name, "free");
+
+        assert_eq!(PlanCatalog::new(plans.clone(), "team").unwrap_err(), PlanError::Unknown("team".into()));
+        let duplicated = [plans.clone(), plans].concat();
+        assert_eq!(PlanCatalog::new(duplicated, "free").unwrap_err(), PlanError::Duplicate("free".into()));
+    }
+}
@@ -1,10 +1,27 @@
 use async_trait::async_trait;
-use crate::user::application::dtos::user_dto::{UserDto, UserDtoCreate, UserDtoCreateResponse};
+use crate::user::application::dtos::user_dto::{UserDto, UserDtoCreate, UserDtoCreateResponse, UserDtoUpdate};
+use crate::user::domain::models::ownership::DeleteUserStrategy;
 use sqlx::Error;
 
 #[async_trait]
 pub trait UserRepositoryPort: Send + Sync {
+    /// Fails with a unique violation when the username or the email (ignoring case) is taken.
     async fn create_user(&self, user_dto: UserDtoCreate, api_key: String) -> Result<UserDtoCreateResponse, Error>;
     async fn get_users(&self) -> Result<Vec<UserDto>, Error>;
-    async fn delete_user(&self, id: i32) -> Result<(), Error>;
+    /// Id of the user holding `api_key`; fails with `RowNotFound` for unknown keys.
+    async fn get_user_by_api_key(&self, api_key: String) -> Result<i32, Error>;
+    /// Fails with `RowNotFound` when the user does not exist.
+    async fn get_user(&self, id: i32) -> Result<UserDto, Error>;
+    /// Change the given fields of the user (its personal workspace follows a new username) and
+    /// return it. Fails with `RowNotFound` when the user does not exist and with a unique
+    /// violation when the new username or email is taken.
+    async fn update_user(&self, id: i32, update: UserDtoUpdate) -> Result<UserDto, Error>;
+    /// Delete the user and apply `strategy` to its links atomically; returns how many links were affected.
+    /// Fails with `RowNotFound` when the user (or the transfer target) does not exist.
+    async fn delete_user(&self, id: i32, strategy: DeleteUserStrategy) -> Result<u64, Error>;
+    /// Reassign the link `url_key` (or every link when `None`) owned by `from_user_id` to `to_user_id`.
+    /// Fails with `RowNotFound` when either user, or the link among `from_user_id`'s links, does not exist.
+    async fn transfer_links(&self, from_user_id: i32, to_user_id: i32, url_key: Option<String>) -> Result<u64, Error>;
+    /// Assign the plan named `plan` to the user; fails with `RowNotFound` when the user does not exist.
+    async fn set_plan(&self, id: i32, plan: String) -> Result<(), Error>;
 }
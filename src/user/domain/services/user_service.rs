@@ -1,95 +1,202 @@
 use crate::shared::utils::create_api_key;
-use crate::user::application::dtos::user_dto::{UserDto, UserDtoCreate, UserDtoCreateResponse};
+use crate::user::application::dtos::user_dto::{UserDto, UserDtoCreate, UserDtoCreateResponse, UserDtoUpdate};
+use crate::user::domain::models::ownership::{DeleteUserStrategy, DELETED_USER_NAME};
+use crate::user::domain::models::plan::{Plan, PlanCatalog, PlanError};
 use crate::user::domain::repositories::user_repository_port::UserRepositoryPort;
 use sqlx::Error;
 use std::sync::Arc;
+use thiserror::Error;
+
+const MAX_USERNAME_LEN: usize = 32;
+/// Longest address SMTP can deliver to.
+const MAX_EMAIL_LEN: usize = 254;
+
+/// Why a user could not be created, found or updated.
+#[derive(Debug, Error)]
+pub enum UserError {
+    #[error("{0}")]
+    Invalid(String),
+    #[error("User not found")]
+    NotFound,
+    #[error("Username is already taken")]
+    UsernameTaken,
+    #[error("Email is already registered")]
+    EmailTaken,
+    #[error("Database error: {0}")]
+    Database(Error),
+}
+
+impl From<Error> for UserError {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::RowNotFound => UserError::NotFound,
+            Error::Database(db_err) if db_err.is_unique_violation() => {
+                // SQLite only names the column in the message: "UNIQUE constraint failed: users.email"
+                if db_err.constraint().unwrap_or(db_err.message()).contains("email") {
+                    UserError::EmailTaken
+                } else {
+                    UserError::UsernameTaken
+                }
+            }
+            other => UserError::Database(other),
+        }
+    }
+}
+
+/// Why a user could not be deleted or its links could not be reassigned.
+#[derive(Debug, Error)]
+pub enum OwnershipError {
+    #[error("User or link not found")]
+    NotFound,
+    #[error("Links cannot be transferred to the same user")]
+    SameUser,
+    #[error("Database error: {0}")]
+    Database(Error),
+}
+
+impl From<Error> for OwnershipError {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::RowNotFound => OwnershipError::NotFound,
+            other => OwnershipError::Database(other),
+        }
+    }
+}
+
+/// Why a plan could not be assigned to a user.
+#[derive(Debug, Error)]
+pub enum SetPlanError {
+    #[error(transparent)]
+    UnknownPlan(#[from] PlanError),
+    #[error("User not found")]
+    NotFound,
+    #[error("Database error: {0}")]
+    Database(Error),
+}
+
+impl From<Error> for SetPlanError {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::RowNotFound => SetPlanError::NotFound,
+            other => SetPlanError::Database(other),
+        }
+    }
+}
 
 #[derive(Clone)]
 pub struct UserService {
     user_repository: Arc<dyn UserRepositoryPort + Send + Sync>,
+    plans: Arc<PlanCatalog>,
 }
 
 impl UserService {
     pub fn new(user_repository: Arc<dyn UserRepositoryPort + Send + Sync>) -> Self {
-        Self { user_repository }
+        Self { user_repository, plans: Arc::new(PlanCatalog::default()) }
     }
 
-    pub async fn create_user(&self, user: UserDtoCreate) -> Result<UserDtoCreateResponse, Error> {
+    /// Plans users can be assigned to.
+    pub fn with_plans(mut self, plans: Arc<PlanCatalog>) -> Self {
+        self.plans = plans;
+        self
+    }
+
+    pub async fn create_user(&self, user: UserDtoCreate) -> Result<UserDtoCreateResponse, UserError> {
+        let user = UserDtoCreate { username: validate_username(&user.username)?, email: validate_email(&user.email)? };
         let api_key = create_api_key();
-        self.user_repository.create_user(user, api_key.clone()).await
+        Ok(self.user_repository.create_user(user, api_key.clone()).await?)
     }
 
     pub async fn get_users(&self) -> Result<Vec<UserDto>, Error> {
         self.user_repository.get_users().await
     }
 
-    pub async fn delete_user(&self, id: i32) -> Result<(), Error> {
-        self.user_repository.delete_user(id).await
+    /// Id of the user holding `api_key`, `None` when no user does.
+    pub async fn find_user_id(&self, api_key: String) -> Option<i32> {
+        self.user_repository.get_user_by_api_key(api_key).await.ok()
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::user::application::dtos::user_dto::{UserDtoCreate, UserDto};
-    use async_trait::async_trait;
-    use sqlx::Error;
-    use std::sync::{Arc, Mutex};
 
-    struct FakeUserRepo {
-        users: Mutex<Vec<UserDto>>,
-        next_id: Mutex<i64>,
+    pub async fn get_user(&self, id: i32) -> Result<UserDto, UserError> {
+        Ok(self.user_repository.get_user(id).await?)
     }
 
-    impl FakeUserRepo {
-        fn new() -> Self {
-            Self {
-                users: Mutex::new(Vec::new()),
-                next_id: Mutex::new(1),
-            }
+    /// Change the username and/or email of the user; an empty update returns it unchanged.
+    pub async fn update_user(&self, id: i32, update: UserDtoUpdate) -> Result<UserDto, UserError> {
+        let update = UserDtoUpdate {
+            username: update.username.as_deref().map(validate_username).transpose()?,
+            email: update.email.as_deref().map(validate_email).transpose()?,
+        };
+        if update.username.is_none() && update.email.is_none() {
+            return self.get_user(id).await;
         }
+        Ok(self.user_repository.update_user(id, update).await?)
     }
 
-    #[async_trait]
-    impl crate::user::domain::repositories::user_repository_port::UserRepositoryPort for FakeUserRepo {
-        async fn create_user(&self, user_dto: UserDtoCreate, _api_key: String) -> Result<crate::user::application::dtos::user_dto::UserDtoCreateResponse, Error> {
-            let mut users = match self.users.lock() {
-                Ok(guard) => guard,
-                Err(poisoned) => poisoned.into_inner(),
-            };
-            let mut id = match self.next_id.lock() {
-                Ok(guard) => guard,
-                Err(poisoned) => poisoned.into_inner(),
-            };
-            let user = UserDto { id: *id, username: user_dto.username.clone(), email: user_dto.email.clone() };
-            *id += 1;
-            users.push(user);
-
-            Ok(crate::user::application::dtos::user_dto::UserDtoCreateResponse { user: user_dto, api_key: String::from("fake-key") })
+    /// Delete the user, applying `strategy` to its links in the same transaction.
+    /// Returns the number of links that were transferred, deactivated or deleted.
+    pub async fn delete_user(&self, id: i32, strategy: DeleteUserStrategy) -> Result<u64, OwnershipError> {
+        if strategy == (DeleteUserStrategy::Transfer { to_user_id: id }) {
+            return Err(OwnershipError::SameUser);
         }
+        Ok(self.user_repository.delete_user(id, strategy).await?)
+    }
 
-        async fn get_users(&self) -> Result<Vec<UserDto>, Error> {
-            let users = match self.users.lock() {
-                Ok(guard) => guard,
-                Err(poisoned) => poisoned.into_inner(),
-            };
-            Ok(users.clone())
+    /// Reassign one link (`url_key`) or every link of `from_user_id` to `to_user_id`.
+    pub async fn transfer_links(
+        &self, from_user_id: i32, to_user_id: i32, url_key: Option<String>,
+    ) -> Result<u64, OwnershipError> {
+        if from_user_id == to_user_id {
+            return Err(OwnershipError::SameUser);
         }
+        Ok(self.user_repository.transfer_links(from_user_id, to_user_id, url_key).await?)
+    }
 
-        async fn delete_user(&self, id: i32) -> Result<(), Error> {
-            let mut users = match self.users.lock() {
-                Ok(guard) => guard,
-                Err(poisoned) => poisoned.into_inner(),
-            };
-            users.retain(|u| u.id != id as i64);
-            Ok(())
-        }
+    /// Assign the configured plan `plan` to the user and return it.
+    pub async fn set_plan(&self, id: i32, plan: &str) -> Result<Plan, SetPlanError> {
+        let plan = self.plans.find(plan).cloned().ok_or_else(|| PlanError::Unknown(plan.to_string()))?;
+        self.user_repository.set_plan(id, plan.name.clone()).await?;
+        Ok(plan)
+    }
+}
+
+/// Usernames are 1 to 32 letters, digits, `.`, `-` or `_`.
+fn validate_username(username: &str) -> Result<String, UserError> {
+    let username = username.trim();
+    let allowed = |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_');
+    if !(1..=MAX_USERNAME_LEN).contains(&username.len()) || !username.chars().all(allowed) {
+        return Err(UserError::Invalid("Username must be 1 to 32 letters, digits, '.', '-' or '_'".into()));
+    }
+    if username.eq_ignore_ascii_case(DELETED_USER_NAME) {
+        return Err(UserError::Invalid(format!("Username '{}' is reserved", username)));
+    }
+    Ok(username.to_string())
+}
+
+/// Loose `local@domain.tld` check: one `@`, no whitespace, a dot inside the domain.
+fn validate_email(email: &str) -> Result<String, UserError> {
+    let email = email.trim();
+    let well_formed = email.split_once('@').is_some_and(|(local, domain)| {
+        !local.is_empty()
+            && !domain.contains('@')
+            && domain.split('.').count() > 1
+            && domain.split('.').all(|label| !label.is_empty())
+    });
+    if email.len() > MAX_EMAIL_LEN || email.chars().any(|c| c.is_whitespace() || c.is_control()) || !well_formed {
+        return Err(UserError::Invalid(format!("'{}' is not a valid email address", email)));
     }
+    Ok(email.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::memory::MemoryDatabase;
+    use crate::url::domain::models::schema::URL;
+    use crate::user::infra::memory_user_repository::MemoryUserRepository;
+    use std::sync::Arc;
 
     #[tokio::test]
     async fn user_service_create_get_delete() -> Result<(), Box<dyn std::error::Error>> {
-        let repo = Arc::new(FakeUserRepo::new());
-        let service = UserService::new(repo.clone());
+        let service = UserService::new(Arc::new(MemoryUserRepository::new(Arc::new(MemoryDatabase::new()))));
 
         let dto = UserDtoCreate { username: "alice".into(), email: "alice@example.com".into() };
         let resp = service.create_user(dto.clone()).await?;
@@ -97,13 +204,88 @@ mod tests {
         assert_eq!(resp.user.username, dto.username);
         let users = service.get_users().await?;
         assert_eq!(users.len(), 1);
-        let first = users.get(0).ok_or("expected one user but got none")?;
+        let first = users.first().ok_or("expected one user but got none")?;
         assert_eq!(first.username, dto.username);
 
-        service.delete_user(first.id as i32).await?;
+        service.delete_user(first.id as i32, DeleteUserStrategy::Cascade).await?;
         let users_after = service.get_users().await?;
         assert!(users_after.is_empty());
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn user_service_rejects_transfers_to_self_or_missing_users() -> Result<(), Box<dyn std::error::Error>> {
+        let db = Arc::new(MemoryDatabase::new());
+        let service = UserService::new(Arc::new(MemoryUserRepository::new(db.clone())));
+        service.create_user(UserDtoCreate { username: "a".into(), email: "a@x.com".into() }).await?;
+        service.create_user(UserDtoCreate { username: "b".into(), email: "b@x.com".into() }).await?;
+        db.insert_url(URL { key: "k".into(), target_url: "http://t".into(), user_id: 1, ..Default::default() });
+
+        assert!(matches!(service.transfer_links(1, 1, None).await, Err(OwnershipError::SameUser)));
+        assert!(matches!(
+            service.delete_user(1, DeleteUserStrategy::Transfer { to_user_id: 1 }).await,
+            Err(OwnershipError::SameUser)
+        ));
+        assert!(matches!(service.transfer_links(1, 3, None).await, Err(OwnershipError::NotFound)));
+        assert!(matches!(
+            service.delete_user(1, DeleteUserStrategy::Transfer { to_user_id: 3 }).await,
+            Err(OwnershipError::NotFound)
+        ));
+
+        assert_eq!(service.transfer_links(1, 2, Some("k".into())).await?, 1);
+        service.delete_user(1, DeleteUserStrategy::Transfer { to_user_id: 2 }).await?;
+        assert_eq!(service.get_users().await?.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn user_service_assigns_configured_plans_only() -> Result<(), Box<dyn std::error::Error>> {
+        let plans = PlanCatalog::new(vec!["free:10/5/0".parse()?, "pro:*/*/10".parse()?], "free")?;
+        let service = UserService::new(Arc::new(MemoryUserRepository::new(Arc::new(MemoryDatabase::new())))).with_plans(Arc::new(plans));
+        service.create_user(UserDtoCreate { username: "a".into(), email: "a@x.com".into() }).await?;
+
+        assert_eq!(service.set_plan(1, "pro").await?.max_custom_aliases, Some(10));
+        assert!(matches!(service.set_plan(1, "gold").await, Err(SetPlanError::UnknownPlan(_))));
+        assert!(matches!(service.set_plan(2, "pro").await, Err(SetPlanError::NotFound)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn user_service_validates_and_normalizes_profiles() -> Result<(), Box<dyn std::error::Error>> {
+        let service = UserService::new(Arc::new(MemoryUserRepository::new(Arc::new(MemoryDatabase::new()))));
+        let created = service.create_user(UserDtoCreate { username: " alice ".into(), email: " a@x.com".into() }).await?;
+        assert_eq!((created.id, created.user.username.as_str(), created.user.email.as_str()), (1, "alice", "a@x.com"));
+        service.create_user(UserDtoCreate { username: "bob".into(), email: "b@x.com".into() }).await?;
+
+        let user = |username: &str, email: &str| UserDtoCreate { username: username.into(), email: email.into() };
+        assert!(matches!(service.create_user(user("al ice", "c@x.com")).await, Err(UserError::Invalid(_))));
+        assert!(matches!(service.create_user(user("carol", "carol@x")).await, Err(UserError::Invalid(_))));
+        assert!(matches!(service.create_user(user("Alice", "c@x.com")).await, Err(UserError::UsernameTaken)));
+        assert!(matches!(service.create_user(user("carol", "A@X.com")).await, Err(UserError::EmailTaken)));
+
+        let update = |username: Option<&str>, email: Option<&str>| UserDtoUpdate {
+            username: username.map(String::from),
+            email: email.map(String::from),
+        };
+        assert_eq!(service.update_user(2, update(None, None)).await?.username, "bob");
+        assert_eq!(service.update_user(2, update(Some("robert"), None)).await?.username, "robert");
+        assert!(matches!(service.update_user(2, update(None, Some("a@x.com"))).await, Err(UserError::EmailTaken)));
+        assert!(matches!(service.update_user(2, update(Some(""), None)).await, Err(UserError::Invalid(_))));
+        assert!(matches!(service.update_user(9, update(Some("x"), None)).await, Err(UserError::NotFound)));
+        assert!(matches!(service.get_user(9).await, Err(UserError::NotFound)));
+        Ok(())
+    }
+
+    #[test]
+    fn usernames_and_emails_are_validated() {
+        assert_eq!(validate_username(" jordi.m_1-x ").unwrap(), "jordi.m_1-x");
+        for username in ["", "two words", "accént", "a/b", &"x".repeat(33), "Deleted-User"] {
+            assert!(validate_username(username).is_err(), "{username:?} should be rejected");
+        }
+        assert_eq!(validate_email(" Jordi.M+tag@mail.example.com ").unwrap(), "Jordi.M+tag@mail.example.com");
+        for email in ["", "plain", "@x.com", "a@", "a@x", "a@x.", "a@.com", "a@b@x.com", "a b@x.com", &format!("{}@x.com", "a".repeat(250))] {
+            assert!(validate_email(email).is_err(), "{email:?} should be rejected");
+        }
+    }
 }
@@ -0,0 +1,70 @@
+use crate::url::domain::ports::link_cache_port::LinkCachePort;
+use crate::user::application::dtos::user_dto::{UserDto, UserDtoCreate, UserDtoCreateResponse, UserDtoUpdate};
+use crate::user::domain::models::ownership::DeleteUserStrategy;
+use crate::user::domain::repositories::user_repository_port::UserRepositoryPort;
+use async_trait::async_trait;
+use sqlx::Error;
+use std::sync::Arc;
+
+/// `UserRepositoryPort` decorator clearing the link caches after the writes that change links
+/// behind the link repository's back: deleting a user deletes, deactivates or reassigns its
+/// links, and transfers change their owner and workspace.
+pub struct LinkCacheUserRepository {
+    inner: Arc<dyn UserRepositoryPort + Send + Sync>,
+    link_caches: Vec<Arc<dyn LinkCachePort>>,
+}
+
+impl LinkCacheUserRepository {
+    pub fn new(inner: Arc<dyn UserRepositoryPort + Send + Sync>, link_caches: Vec<Arc<dyn LinkCachePort>>) -> Self {
+        Self { inner, link_caches }
+    }
+
+    async fn clear_link_caches(&self) {
+        for link_cache in &self.link_caches {
+            link_cache.clear().await;
+        }
+    }
+}
+
+#[async_trait]
+impl UserRepositoryPort for LinkCacheUserRepository {
+    async fn create_user(&self, user_dto: UserDtoCreate, api_key: String) -> Result<UserDtoCreateResponse, Error> {
+        self.inner.create_user(user_dto, api_key).await
+    }
+
+    async fn get_users(&self) -> Result<Vec<UserDto>, Error> {
+        self.inner.get_users().await
+    }
+
+    async fn get_user_by_api_key(&self, api_key: String) -> Result<i32, Error> {
+        self.inner.get_user_by_api_key(api_key).await
+    }
+
+    async fn get_user(&self, id: i32) -> Result<UserDto, Error> {
+        self.inner.get_user(id).await
+    }
+
+    async fn update_user(&self, id: i32, update: UserDtoUpdate) -> Result<UserDto, Error> {
+        self.inner.update_user(id, update).await
+    }
+
+    async fn delete_user(&self, id: i32, strategy: DeleteUserStrategy) -> Result<u64, Error> {
+        let affected = self.inner.delete_user(id, strategy).await?;
+        if affected > 0 {
+            self.clear_link_caches().await;
+        }
+        Ok(affected)
+    }
+
+    async fn transfer_links(&self, from_user_id: i32, to_user_id: i32, url_key: Option<String>) -> Result<u64, Error> {
+        let affected = self.inner.transfer_links(from_user_id, to_user_id, url_key).await?;
+        if affected > 0 {
+            self.clear_link_caches().await;
+        }
+        Ok(affected)
+    }
+
+    async fn set_plan(&self, id: i32, plan: String) -> Result<(), Error> {
+        self.inner.set_plan(id, plan).await
+    }
+}
@@ -0,0 +1,291 @@
+use crate::config::memory::{MemoryDatabase, MemoryState, StoredUser, UniqueViolation};
+use crate::user::application::dtos::user_dto::{UserDto, UserDtoCreate, UserDtoCreateResponse, UserDtoUpdate};
+use crate::user::domain::models::ownership::{DeleteUserStrategy, DELETED_USER_NAME};
+use crate::user::domain::repositories::user_repository_port::UserRepositoryPort;
+use async_trait::async_trait;
+use sqlx::Error;
+use std::sync::Arc;
+
+/// `UserRepositoryPort` kept in a `MemoryDatabase`, with the same behaviour as `SqlxUserRepository`.
+pub struct MemoryUserRepository {
+    db: Arc<MemoryDatabase>,
+}
+
+impl MemoryUserRepository {
+    pub fn new(db: Arc<MemoryDatabase>) -> Self {
+        MemoryUserRepository { db }
+    }
+}
+
+#[async_trait]
+impl UserRepositoryPort for MemoryUserRepository {
+    async fn create_user(&self, user_dto: UserDtoCreate, api_key: String) -> Result<UserDtoCreateResponse, Error> {
+        let mut state = self.db.lock();
+        ensure_unique(&state, None, Some(&user_dto.username), Some(&user_dto.email))?;
+        let id = state.insert_user(&user_dto.username, &user_dto.email, &api_key);
+        Ok(UserDtoCreateResponse { id, user: user_dto, api_key })
+    }
+
+    async fn get_users(&self) -> Result<Vec<UserDto>, Error> {
+        Ok(self.db.lock().users.iter().filter(|user| !user.api_key.is_empty()).map(to_dto).collect())
+    }
+
+    async fn get_user_by_api_key(&self, api_key: String) -> Result<i32, Error> {
+        self.db.lock().user_id_by_api_key(&api_key).map(|id| id as i32).ok_or(Error::RowNotFound)
+    }
+
+    async fn get_user(&self, id: i32) -> Result<UserDto, Error> {
+        self.db.lock().user(id as i64).filter(|user| !user.api_key.is_empty()).map(to_dto).ok_or(Error::RowNotFound)
+    }
+
+    async fn update_user(&self, id: i32, update: UserDtoUpdate) -> Result<UserDto, Error> {
+        let mut state = self.db.lock();
+        if state.user(id as i64).is_none_or(|user| user.api_key.is_empty()) {
+            return Err(Error::RowNotFound);
+        }
+        ensure_unique(&state, Some(id as i64), update.username.as_deref(), update.email.as_deref())?;
+        if let Some(username) = &update.username {
+            for org in state.organizations.iter_mut().filter(|org| org.personal_user_id == Some(id as i64)) {
+                org.name = username.clone();
+            }
+        }
+        let user = state.users.iter_mut().find(|user| user.id == id as i64).ok_or(Error::RowNotFound)?;
+        if let Some(username) = update.username {
+            user.username = username;
+        }
+        if let Some(email) = update.email {
+            user.email = email;
+        }
+        Ok(to_dto(user))
+    }
+
+    async fn delete_user(&self, id: i32, strategy: DeleteUserStrategy) -> Result<u64, Error> {
+        let mut state = self.db.lock();
+        ensure_user_exists(&state, id)?;
+
+        let affected = match strategy {
+            DeleteUserStrategy::Transfer { to_user_id } => {
+                ensure_user_exists(&state, to_user_id)?;
+                reassign_links(&mut state, id, to_user_id, None)
+            }
+            DeleteUserStrategy::Deactivate => {
+                let placeholder_id = deleted_user_id(&mut state);
+                let mut affected = 0;
+                for url in state.urls.iter_mut().filter(|url| url.user_id == id) {
+                    url.is_active = false;
+                    url.user_id = placeholder_id;
+                    affected += 1;
+                }
+                affected
+            }
+            DeleteUserStrategy::Cascade => {
+                let keys: Vec<String> = state.urls.iter().filter(|url| url.user_id == id).map(|url| url.key.clone()).collect();
+                state.remove_link_rows(&keys);
+                state.urls.retain(|url| url.user_id != id);
+                keys.len() as u64
+            }
+        };
+
+        // the keys stay reserved, without an owner
+        for (_, owner) in state.used_keys.iter_mut().filter(|(_, owner)| *owner == Some(id)) {
+            *owner = None;
+        }
+        leave_organizations(&mut state, id);
+        state.users.retain(|user| user.id != id as i64);
+
+        Ok(affected)
+    }
+
+    async fn transfer_links(&self, from_user_id: i32, to_user_id: i32, url_key: Option<String>) -> Result<u64, Error> {
+        let mut state = self.db.lock();
+        ensure_user_exists(&state, from_user_id)?;
+        ensure_user_exists(&state, to_user_id)?;
+
+        let affected = reassign_links(&mut state, from_user_id, to_user_id, url_key.as_deref());
+        if url_key.is_some() && affected == 0 {
+            return Err(Error::RowNotFound);
+        }
+        Ok(affected)
+    }
+
+    async fn set_plan(&self, id: i32, plan: String) -> Result<(), Error> {
+        let mut state = self.db.lock();
+        let user = state.users.iter_mut().find(|user| user.id == id as i64).ok_or(Error::RowNotFound)?;
+        user.plan = Some(plan);
+        Ok(())
+    }
+}
+
+/// Hand the links created by `from_user_id` (only `url_key` when given) over to `to_user_id`,
+/// moving those of its personal workspace to the personal workspace of `to_user_id`.
+fn reassign_links(state: &mut MemoryState, from_user_id: i32, to_user_id: i32, url_key: Option<&str>) -> u64 {
+    let selected = |key: &str| url_key.is_none_or(|url_key| url_key == key);
+    for (key, owner) in state.used_keys.iter_mut() {
+        if *owner == Some(from_user_id) && selected(key) {
+            *owner = Some(to_user_id);
+        }
+    }
+    let from_personal = state.personal_org_id(from_user_id);
+    let to_personal = state.personal_org_id(to_user_id);
+    let mut moved = Vec::new();
+    for url in state.urls.iter_mut().filter(|url| url.user_id == from_user_id && selected(&url.key)) {
+        url.user_id = to_user_id;
+        if url.org_id == from_personal {
+            url.org_id = to_personal;
+        }
+        moved.push(url.key.clone());
+    }
+    // tags only apply inside their workspace
+    let MemoryState { urls, tags, url_tags, .. } = state;
+    url_tags.retain(|(url_key, tag_id)| {
+        let Some(url) = urls.iter().find(|url| url.key == *url_key && url.user_id == to_user_id) else {
+            return true;
+        };
+        tags.iter().any(|tag| tag.id == *tag_id && Some(tag.org_id) == url.org_id)
+    });
+    moved.len() as u64
+}
+
+/// Drop the memberships of a user about to be deleted. Its personal workspace goes away when
+/// no link is left in it; otherwise it is kept, detached from the user.
+fn leave_organizations(state: &mut MemoryState, user_id: i32) {
+    let user_id = user_id as i64;
+    state.members.retain(|member| member.user_id != user_id);
+    let Some(personal) = state.organizations.iter().find(|org| org.personal_user_id == Some(user_id)).map(|org| org.id) else {
+        return;
+    };
+    if state.urls.iter().any(|url| url.org_id == Some(personal)) {
+        for org in state.organizations.iter_mut().filter(|org| org.id == personal) {
+            org.personal_user_id = None;
+        }
+    } else {
+        state.members.retain(|member| member.org_id != personal);
+        let dropped: Vec<i64> = state.tags.iter().filter(|tag| tag.org_id == personal).map(|tag| tag.id).collect();
+        state.url_tags.retain(|(_, tag_id)| !dropped.contains(tag_id));
+        state.tags.retain(|tag| tag.org_id != personal);
+        state.organizations.retain(|org| org.id != personal);
+    }
+}
+
+/// Account that keeps the deactivated links of deleted users, created on first use. Its API key
+/// is random and never handed out.
+fn deleted_user_id(state: &mut MemoryState) -> i32 {
+    let existing = state.users.iter().find(|user| user.username == DELETED_USER_NAME && user.email.is_empty());
+    match existing {
+        Some(user) => user.id as i32,
+        None => state.insert_account(DELETED_USER_NAME, "", "") as i32,
+    }
+}
+
+fn to_dto(user: &StoredUser) -> UserDto {
+    UserDto { id: user.id, username: user.username.clone(), email: user.email.clone() }
+}
+
+/// Same checks as the `COLLATE NOCASE` unique indexes on `users`, ignoring the row of `except_id`.
+fn ensure_unique(state: &MemoryState, except_id: Option<i64>, username: Option<&str>, email: Option<&str>) -> Result<(), Error> {
+    let others = || state.users.iter().filter(|user| Some(user.id) != except_id);
+    if username.is_some_and(|username| others().any(|user| user.username.eq_ignore_ascii_case(username))) {
+        return Err(UniqueViolation("users.username").into_sqlx());
+    }
+    if email.is_some_and(|email| others().any(|user| user.email.eq_ignore_ascii_case(email))) {
+        return Err(UniqueViolation("users.email").into_sqlx());
+    }
+    Ok(())
+}
+
+/// `RowNotFound` unless `id` is a real user, not the `deleted-user` placeholder.
+fn ensure_user_exists(state: &MemoryState, id: i32) -> Result<(), Error> {
+    state.user(id as i64).filter(|user| !user.api_key.is_empty()).map(|_| ()).ok_or(Error::RowNotFound)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::org::domain::models::organization::{Membership, Role};
+    use crate::url::domain::models::schema::URL;
+
+    /// alice (1) owns `k1` in her personal workspace (1) and `k2` in the shared one (3), where
+    /// bob (2) is an editor; each link carries a tag of its workspace.
+    fn setup() -> (Arc<MemoryDatabase>, MemoryUserRepository) {
+        let db = Arc::new(MemoryDatabase::new());
+        db.insert_user("alice", "a@x.com", "ka");
+        db.insert_user("bob", "b@x.com", "kb");
+        {
+            let mut state = db.lock();
+            let team = state.insert_organization("Team", None).id;
+            state.members.push(Membership { org_id: team, user_id: 2, role: Role::Editor });
+            let mine = state.insert_tag(1, "mine".into()).id;
+            let shared = state.insert_tag(team, "team".into()).id;
+            state.url_tags.extend([("k1".to_string(), mine), ("k2".to_string(), shared)]);
+        }
+        db.insert_url(URL { key: "k1".into(), target_url: "http://a.com".into(), is_active: true, user_id: 1, ..Default::default() });
+        db.insert_url(URL { key: "k2".into(), target_url: "http://b.com".into(), is_active: true, user_id: 1, org_id: Some(3), ..Default::default() });
+        (db.clone(), MemoryUserRepository::new(db))
+    }
+
+    #[tokio::test]
+    async fn creates_and_lists_users_with_a_personal_workspace() -> Result<(), Error> {
+        let (db, repo) = setup();
+        let dto = UserDtoCreate { username: "carol".into(), email: "carol@example.com".into() };
+        repo.create_user(dto, "kc".into()).await?;
+        assert_eq!(repo.get_users().await?.iter().map(|user| user.id).collect::<Vec<_>>(), vec![1, 2, 3]);
+        let state = db.lock();
+        assert_eq!(state.user_id_by_api_key("kc"), Some(3));
+        let personal = state.personal_org_id(3).expect("personal workspace");
+        assert_eq!(state.member_role(personal, 3), Some(Role::Owner));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_user_transfers_links_to_target() -> Result<(), Error> {
+        let (db, repo) = setup();
+        assert_eq!(repo.delete_user(1, DeleteUserStrategy::Transfer { to_user_id: 2 }).await?, 2);
+        let state = db.lock();
+        assert!(state.user(1).is_none());
+        assert_eq!(state.url("k1").and_then(|url| url.org_id), Some(2));
+        assert_eq!(state.url("k2").and_then(|url| url.org_id), Some(3));
+        assert!(state.used_keys.iter().all(|(_, owner)| *owner == Some(2)));
+        // the old personal workspace and its tag are gone, the team tag stays on k2
+        assert!(state.organizations.iter().all(|org| org.id != 1));
+        assert_eq!(state.url_tags, vec![("k2".to_string(), 2)]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_user_deactivates_or_cascades_links() -> Result<(), Error> {
+        let (db, repo) = setup();
+        assert!(matches!(repo.delete_user(1, DeleteUserStrategy::Transfer { to_user_id: 99 }).await, Err(Error::RowNotFound)));
+        assert_eq!(repo.delete_user(1, DeleteUserStrategy::Deactivate).await?, 2);
+        {
+            let state = db.lock();
+            assert!(state.urls.iter().all(|url| !url.is_active && url.user_id == 3));
+            assert!(state.used_keys.iter().all(|(_, owner)| owner.is_none()));
+            // the personal workspace still holds k1, detached from the deleted user
+            assert_eq!(state.organizations[0].personal_user_id, None);
+        }
+
+        let (db, repo) = setup();
+        assert_eq!(repo.delete_user(1, DeleteUserStrategy::Cascade).await?, 2);
+        let state = db.lock();
+        assert!(state.urls.is_empty() && state.url_tags.is_empty());
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn transfer_links_rename_and_set_plan() -> Result<(), Error> {
+        let (db, repo) = setup();
+        assert_eq!(repo.transfer_links(1, 2, Some("k1".into())).await?, 1);
+        assert!(matches!(repo.transfer_links(1, 2, Some("k1".into())).await, Err(Error::RowNotFound)));
+        assert!(matches!(repo.transfer_links(1, 99, None).await, Err(Error::RowNotFound)));
+        assert_eq!(repo.transfer_links(1, 2, None).await?, 1);
+
+        let rename = UserDtoUpdate { username: Some("robert".into()), ..Default::default() };
+        assert_eq!(repo.update_user(2, rename).await?.email, "b@x.com");
+        assert_eq!(db.lock().organizations[1].name, "robert");
+
+        repo.set_plan(2, "pro".into()).await?;
+        assert!(matches!(repo.set_plan(99, "pro".into()).await, Err(Error::RowNotFound)));
+        assert_eq!(db.lock().user(2).and_then(|user| user.plan.clone()), Some("pro".into()));
+        Ok(())
+    }
+}
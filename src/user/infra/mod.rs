@@ -1 +1,3 @@
 pub mod sqlx_user_repository;
+pub mod memory_user_repository;
+pub mod link_cache_user_repository;
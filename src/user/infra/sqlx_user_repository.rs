@@ -1,8 +1,11 @@
-use crate::user::application::dtos::user_dto::{UserDto, UserDtoCreate, UserDtoCreateResponse};
+use crate::user::application::dtos::user_dto::{UserDto, UserDtoCreate, UserDtoCreateResponse, UserDtoUpdate};
+use crate::org::domain::models::organization::Role;
+use crate::user::domain::models::ownership::{DeleteUserStrategy, DELETED_USER_NAME};
 use crate::user::domain::models::user::User;
 use crate::user::domain::repositories::user_repository_port::UserRepositoryPort;
 use async_trait::async_trait;
-use sqlx::sqlite::SqlitePool;
+use sqlx::sqlite::{Sqlite, SqlitePool};
+use sqlx::Transaction;
 use sqlx::Error;
 
 pub struct SqlxUserRepository {
@@ -18,66 +21,295 @@ impl SqlxUserRepository {
 #[async_trait]
 impl UserRepositoryPort for SqlxUserRepository {
     async fn create_user(&self, user_dto: UserDtoCreate, api_key: String) -> Result<UserDtoCreateResponse, Error> {
-        let _user = sqlx::query_as::<_, User>(
+        let mut tx = self.db_pool.begin().await?;
+        let user = sqlx::query_as::<_, User>(
             "INSERT INTO users (username, email, api_key) VALUES ($1, $2, $3) RETURNING *",
         )
         .bind(&user_dto.username)
         .bind(&user_dto.email)
         .bind(api_key.clone())
-        .fetch_one(&self.db_pool)
+        .fetch_one(&mut *tx)
         .await?;
 
-        Ok(UserDtoCreateResponse { user: user_dto, api_key })
+        // Cada usuari té el seu espai de treball personal
+        let (org_id,): (i64,) =
+            sqlx::query_as("INSERT INTO organizations (name, personal_user_id) VALUES ($1, $2) RETURNING id")
+                .bind(&user.username)
+                .bind(user.id)
+                .fetch_one(&mut *tx)
+                .await?;
+        sqlx::query("INSERT INTO organization_members (org_id, user_id, role) VALUES ($1, $2, $3)")
+            .bind(org_id)
+            .bind(user.id)
+            .bind(Role::Owner)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        Ok(UserDtoCreateResponse { id: user.id, user: user_dto, api_key })
     }
 
     async fn get_users(&self) -> Result<Vec<UserDto>, Error> {
         log::info!("Getting users");
-        let users = sqlx::query_as::<_, User>("SELECT * FROM users")
+        let users = sqlx::query_as::<_, User>("SELECT * FROM users WHERE api_key != ''")
             .fetch_all(&self.db_pool)
             .await?;
 
-        Ok(users
-            .into_iter()
-            .map(|user| UserDto {
-                id: user.id,
-                username: user.username,
-                email: user.email,
-            })
-            .collect())
+        Ok(users.into_iter().map(UserDto::from).collect())
+    }
+
+    async fn get_user_by_api_key(&self, api_key: String) -> Result<i32, Error> {
+        let (id,): (i32,) = sqlx::query_as("SELECT id FROM users WHERE api_key = $1 AND api_key != ''")
+            .bind(api_key)
+            .fetch_one(&self.db_pool)
+            .await?;
+        Ok(id)
+    }
+
+    async fn get_user(&self, id: i32) -> Result<UserDto, Error> {
+        let user = sqlx::query_as::<_, User>("SELECT * FROM users WHERE id = $1 AND api_key != ''")
+            .bind(id)
+            .fetch_one(&self.db_pool)
+            .await?;
+        Ok(user.into())
     }
 
-    async fn delete_user(&self, id: i32) -> Result<(), Error> {
+    async fn update_user(&self, id: i32, update: UserDtoUpdate) -> Result<UserDto, Error> {
+        let mut tx = self.db_pool.begin().await?;
+        let user = sqlx::query_as::<_, User>(
+            "UPDATE users SET username = COALESCE($1, username), email = COALESCE($2, email) WHERE id = $3 AND api_key != '' RETURNING *",
+        )
+        .bind(&update.username)
+        .bind(&update.email)
+        .bind(id)
+        .fetch_one(&mut *tx)
+        .await?;
+        if update.username.is_some() {
+            sqlx::query("UPDATE organizations SET name = $1 WHERE personal_user_id = $2")
+                .bind(&user.username)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+        Ok(user.into())
+    }
+
+    async fn delete_user(&self, id: i32, strategy: DeleteUserStrategy) -> Result<u64, Error> {
+        let mut tx = self.db_pool.begin().await?;
+        ensure_user_exists(&mut tx, id).await?;
+
+        let affected = match strategy {
+            DeleteUserStrategy::Transfer { to_user_id } => {
+                ensure_user_exists(&mut tx, to_user_id).await?;
+                reassign_links(&mut tx, id, to_user_id, None).await?
+            }
+            DeleteUserStrategy::Deactivate => {
+                // `urls.user_id` must reference an existing user: the links are kept under a placeholder account
+                let placeholder_id = deleted_user_id(&mut tx).await?;
+                sqlx::query("UPDATE urls SET is_active = 0, user_id = $1 WHERE user_id = $2")
+                    .bind(placeholder_id)
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected()
+            }
+            DeleteUserStrategy::Cascade => {
+                for table in ["url_geo_rules", "url_clicks", "url_tags"] {
+                    sqlx::query(&format!(
+                        "DELETE FROM {table} WHERE url_key IN (SELECT key FROM urls WHERE user_id = $1)"
+                    ))
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                sqlx::query("DELETE FROM urls WHERE user_id = $1")
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?
+                    .rows_affected()
+            }
+        };
+
+        // the keys stay reserved, without an owner
+        sqlx::query("UPDATE used_keys SET user_id = NULL WHERE user_id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        leave_organizations(&mut tx, id).await?;
         sqlx::query("DELETE FROM users WHERE id = $1")
             .bind(id)
-            .execute(&self.db_pool)
+            .execute(&mut *tx)
             .await?;
+        tx.commit().await?;
+
+        Ok(affected)
+    }
+
+    async fn transfer_links(&self, from_user_id: i32, to_user_id: i32, url_key: Option<String>) -> Result<u64, Error> {
+        let mut tx = self.db_pool.begin().await?;
+        ensure_user_exists(&mut tx, from_user_id).await?;
+        ensure_user_exists(&mut tx, to_user_id).await?;
+
+        let affected = reassign_links(&mut tx, from_user_id, to_user_id, url_key.as_deref()).await?;
+        if url_key.is_some() && affected == 0 {
+            return Err(Error::RowNotFound);
+        }
+        tx.commit().await?;
+
+        Ok(affected)
+    }
 
+    async fn set_plan(&self, id: i32, plan: String) -> Result<(), Error> {
+        let result = sqlx::query("UPDATE users SET plan = $1 WHERE id = $2")
+            .bind(plan)
+            .bind(id)
+            .execute(&self.db_pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(Error::RowNotFound);
+        }
         Ok(())
     }
 }
 
+/// Hand the links created by `from_user_id` (only `url_key` when given) over to `to_user_id`.
+/// Links in the personal workspace of `from_user_id` move to the personal workspace of `to_user_id`;
+/// links of shared organizations stay there.
+async fn reassign_links(
+    tx: &mut Transaction<'_, Sqlite>, from_user_id: i32, to_user_id: i32, url_key: Option<&str>,
+) -> Result<u64, Error> {
+    sqlx::query("UPDATE used_keys SET user_id = $1 WHERE user_id = $2 AND ($3 IS NULL OR key_value = $3)")
+        .bind(to_user_id)
+        .bind(from_user_id)
+        .bind(url_key)
+        .execute(&mut **tx)
+        .await?;
+    let moved = sqlx::query(
+        "UPDATE urls SET user_id = $1,
+             org_id = CASE WHEN org_id IS (SELECT id FROM organizations WHERE personal_user_id = $2)
+                           THEN (SELECT id FROM organizations WHERE personal_user_id = $1)
+                           ELSE org_id END
+         WHERE user_id = $2 AND ($3 IS NULL OR key = $3)",
+    )
+    .bind(to_user_id)
+    .bind(from_user_id)
+    .bind(url_key)
+    .execute(&mut **tx)
+    .await?;
+    // tags only apply inside their workspace
+    sqlx::query(
+        "DELETE FROM url_tags WHERE url_key IN (SELECT key FROM urls WHERE user_id = $1)
+             AND tag_id NOT IN (SELECT t.id FROM tags t JOIN urls u ON u.org_id = t.org_id WHERE u.key = url_tags.url_key)",
+    )
+    .bind(to_user_id)
+    .execute(&mut **tx)
+    .await?;
+    Ok(moved.rows_affected())
+}
+
+/// Drop the memberships of a user about to be deleted. Its personal workspace goes away when
+/// no link is left in it; otherwise it is kept, detached from the user.
+async fn leave_organizations(tx: &mut Transaction<'_, Sqlite>, user_id: i32) -> Result<(), Error> {
+    let statements = [
+        "DELETE FROM organization_members WHERE user_id = $1",
+        "DELETE FROM organization_members WHERE org_id IN (
+             SELECT id FROM organizations WHERE personal_user_id = $1
+             AND id NOT IN (SELECT org_id FROM urls WHERE org_id IS NOT NULL))",
+        "DELETE FROM tags WHERE org_id IN (
+             SELECT id FROM organizations WHERE personal_user_id = $1
+             AND id NOT IN (SELECT org_id FROM urls WHERE org_id IS NOT NULL))",
+        "DELETE FROM organizations WHERE personal_user_id = $1
+             AND id NOT IN (SELECT org_id FROM urls WHERE org_id IS NOT NULL)",
+        "UPDATE organizations SET personal_user_id = NULL WHERE personal_user_id = $1",
+    ];
+    for statement in statements {
+        sqlx::query(statement).bind(user_id).execute(&mut **tx).await?;
+    }
+    Ok(())
+}
+
+/// Account that keeps the deactivated links of deleted users, created on first use. Its API key
+/// is empty, which never authenticates, and `ensure_user_exists` does not count it as a user.
+async fn deleted_user_id(tx: &mut Transaction<'_, Sqlite>) -> Result<i32, Error> {
+    let existing: Option<(i32,)> = sqlx::query_as("SELECT id FROM users WHERE username = $1 AND email = ''")
+        .bind(DELETED_USER_NAME)
+        .fetch_optional(&mut **tx)
+        .await?;
+    if let Some((id,)) = existing {
+        return Ok(id);
+    }
+    let (id,): (i32,) = sqlx::query_as("INSERT INTO users (username, email, api_key) VALUES ($1, '', '') RETURNING id")
+        .bind(DELETED_USER_NAME)
+        .fetch_one(&mut **tx)
+        .await?;
+    Ok(id)
+}
+
+/// `RowNotFound` unless `id` is a real user: the `deleted-user` placeholder neither receives
+/// links nor is deleted.
+async fn ensure_user_exists(tx: &mut Transaction<'_, Sqlite>, id: i32) -> Result<(), Error> {
+    sqlx::query("SELECT 1 FROM users WHERE id = $1 AND api_key != ''")
+        .bind(id)
+        .fetch_optional(&mut **tx)
+        .await?
+        .map(|_| ())
+        .ok_or(Error::RowNotFound)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::user::application::dtos::user_dto::UserDtoCreate;
+    use crate::config::database::{create_org_tables, create_tag_tables};
     use sqlx::sqlite::SqlitePoolOptions;
     use sqlx::Executor;
 
-    #[tokio::test]
-    async fn create_get_and_delete_user_integration() -> Result<(), Box<dyn std::error::Error>> {
+    async fn setup_pool() -> Result<SqlitePool, Error> {
         let pool = SqlitePoolOptions::new().max_connections(1).connect(":memory:").await?;
-
         pool.execute(r#"
             CREATE TABLE users (
                 id INTEGER PRIMARY KEY,
                 username TEXT NOT NULL,
                 email TEXT NOT NULL,
-                api_key TEXT NOT NULL
+                api_key TEXT NOT NULL,
+                plan TEXT
             );
+            CREATE TABLE urls (id INTEGER PRIMARY KEY, key TEXT NOT NULL, secret_key TEXT NOT NULL, target_url TEXT NOT NULL, is_active BOOLEAN NOT NULL, clicks INTEGER NOT NULL, user_id INTEGER NOT NULL, org_id INTEGER, FOREIGN KEY (user_id) REFERENCES users(id));
+            CREATE TABLE used_keys (id INTEGER PRIMARY KEY, key_value VARCHAR(50), user_id INTEGER, FOREIGN KEY (user_id) REFERENCES users(id));
+            CREATE TABLE url_geo_rules (id INTEGER PRIMARY KEY, url_key TEXT NOT NULL, country_code TEXT NOT NULL, target_url TEXT NOT NULL);
+            CREATE TABLE url_clicks (id INTEGER PRIMARY KEY, url_key TEXT NOT NULL, country TEXT);
+            INSERT INTO users (id, username, email, api_key) VALUES (1, 'alice', 'a@x.com', 'ka'), (2, 'bob', 'b@x.com', 'kb');
+            INSERT INTO urls (key, secret_key, target_url, is_active, clicks, user_id, org_id) VALUES
+                ('k1', 's1', 'http://a.com', 1, 0, 1, 10),
+                ('k2', 's2', 'http://b.com', 1, 0, 1, 30);
+            INSERT INTO used_keys (key_value, user_id) VALUES ('k1', 1), ('k2', 1);
+            INSERT INTO url_geo_rules (url_key, country_code, target_url) VALUES ('k1', 'ES', 'http://es.a.com');
+            INSERT INTO url_clicks (url_key, country) VALUES ('k1', 'ES');
         "#).await?;
+        create_org_tables(&pool).await?;
+        create_tag_tables(&pool).await?;
+        // personal workspaces 10 and 20, shared workspace 30
+        pool.execute(r#"
+            INSERT INTO organizations (id, name, personal_user_id) VALUES (10, 'alice', 1), (20, 'bob', 2), (30, 'Team', NULL);
+            INSERT INTO organization_members (org_id, user_id, role) VALUES (10, 1, 'owner'), (20, 2, 'owner'), (30, 1, 'owner'), (30, 2, 'editor');
+            INSERT INTO tags (id, org_id, name) VALUES (1, 10, 'mine'), (2, 30, 'team');
+            INSERT INTO url_tags (url_key, tag_id) VALUES ('k1', 1), ('k2', 2);
+        "#).await?;
+        Ok(pool)
+    }
+
+    async fn count(pool: &SqlitePool, sql: &str) -> Result<i64, Error> {
+        let row: (i64,) = sqlx::query_as(sql).fetch_one(pool).await?;
+        Ok(row.0)
+    }
+
+    #[tokio::test]
+    async fn create_get_and_delete_user_integration() -> Result<(), Box<dyn std::error::Error>> {
+        let pool = setup_pool().await?;
 
         let repo = SqlxUserRepository::new(pool.clone()).await;
-        let dto = UserDtoCreate { username: "bob".to_string(), email: "bob@example.com".to_string() };
+        let dto = UserDtoCreate { username: "carol".to_string(), email: "carol@example.com".to_string() };
         let resp = repo.create_user(dto.clone(), "apikey-integ".to_string()).await?;
         assert_eq!(resp.user.username, dto.username);
 
@@ -85,10 +317,111 @@ mod tests {
         assert!(users.iter().any(|u| u.username == dto.username));
 
         let id = users.into_iter().find(|u| u.username == dto.username).ok_or("created user not found")?.id as i32;
-        repo.delete_user(id).await?;
+        let personal = count(&pool, &format!("SELECT COUNT(*) FROM organization_members m JOIN organizations o ON o.id = m.org_id WHERE o.personal_user_id = {id} AND m.role = 'owner'")).await?;
+        assert_eq!(personal, 1);
+        repo.delete_user(id, DeleteUserStrategy::Cascade).await?;
+        assert_eq!(count(&pool, &format!("SELECT COUNT(*) FROM organizations WHERE personal_user_id = {id}")).await?, 0);
         let users_after = repo.get_users().await?;
         assert!(users_after.iter().all(|u| u.id != id as i64));
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn delete_user_transfers_links_to_target() -> Result<(), Error> {
+        let pool = setup_pool().await?;
+        let repo = SqlxUserRepository::new(pool.clone()).await;
+
+        assert_eq!(repo.delete_user(1, DeleteUserStrategy::Transfer { to_user_id: 2 }).await?, 2);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM urls WHERE user_id = 2 AND is_active = 1").await?, 2);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM used_keys WHERE user_id = 2").await?, 2);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM users WHERE id = 1").await?, 0);
+        // personal links follow to the new owner's workspace, team links stay with the team
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM urls WHERE key = 'k1' AND org_id = 20").await?, 1);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM urls WHERE key = 'k2' AND org_id = 30").await?, 1);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM organizations WHERE id = 10").await?, 0);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM organization_members WHERE user_id = 1").await?, 0);
+        // the tag of the old personal workspace is gone, the team tag stays on k2
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM tags").await?, 1);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM url_tags WHERE url_key = 'k2' AND tag_id = 2").await?, 1);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM url_tags").await?, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_user_to_missing_target_rolls_back() -> Result<(), Error> {
+        let pool = setup_pool().await?;
+        let repo = SqlxUserRepository::new(pool.clone()).await;
+
+        let result = repo.delete_user(1, DeleteUserStrategy::Transfer { to_user_id: 99 }).await;
+        assert!(matches!(result, Err(Error::RowNotFound)));
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM users WHERE id = 1").await?, 1);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM urls WHERE user_id = 1").await?, 2);
+
+        assert!(matches!(repo.delete_user(42, DeleteUserStrategy::Cascade).await, Err(Error::RowNotFound)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn delete_user_deactivates_or_cascades_links() -> Result<(), Error> {
+        let pool = setup_pool().await?;
+        let repo = SqlxUserRepository::new(pool.clone()).await;
+        assert_eq!(repo.delete_user(1, DeleteUserStrategy::Deactivate).await?, 2);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM urls u JOIN users ON users.id = u.user_id WHERE u.is_active = 0 AND users.username = 'deleted-user'").await?, 2);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM used_keys WHERE user_id IS NULL").await?, 2);
+        // the personal workspace still holds k1, detached from the deleted user
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM organizations WHERE id = 10 AND personal_user_id IS NULL").await?, 1);
+
+        let pool = setup_pool().await?;
+        let repo = SqlxUserRepository::new(pool.clone()).await;
+        assert_eq!(repo.delete_user(1, DeleteUserStrategy::Cascade).await?, 2);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM urls").await?, 0);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM url_geo_rules").await?, 0);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM url_clicks").await?, 0);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM url_tags").await?, 0);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn set_plan_updates_existing_users_only() -> Result<(), Error> {
+        let pool = setup_pool().await?;
+        let repo = SqlxUserRepository::new(pool.clone()).await;
+
+        repo.set_plan(2, "pro".into()).await?;
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM users WHERE plan = 'pro'").await?, 1);
+        assert!(matches!(repo.set_plan(99, "pro".into()).await, Err(Error::RowNotFound)));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn renaming_a_user_renames_its_personal_workspace() -> Result<(), Error> {
+        let pool = setup_pool().await?;
+        let repo = SqlxUserRepository::new(pool.clone()).await;
+
+        repo.update_user(1, UserDtoUpdate { email: Some("new@x.com".into()), ..Default::default() }).await?;
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM organizations WHERE name = 'alice'").await?, 1);
+        let renamed = repo.update_user(1, UserDtoUpdate { username: Some("alicia".into()), ..Default::default() }).await?;
+        assert_eq!((renamed.username.as_str(), renamed.email.as_str()), ("alicia", "new@x.com"));
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM organizations WHERE id = 10 AND name = 'alicia'").await?, 1);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM organizations WHERE id = 30 AND name = 'Team'").await?, 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn transfer_links_moves_one_or_all() -> Result<(), Error> {
+        let pool = setup_pool().await?;
+        let repo = SqlxUserRepository::new(pool.clone()).await;
+
+        assert_eq!(repo.transfer_links(1, 2, Some("k1".into())).await?, 1);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM urls WHERE user_id = 2").await?, 1);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM used_keys WHERE user_id = 2 AND key_value = 'k1'").await?, 1);
+
+        // k1 is no longer owned by user 1
+        assert!(matches!(repo.transfer_links(1, 2, Some("k1".into())).await, Err(Error::RowNotFound)));
+        assert!(matches!(repo.transfer_links(1, 99, None).await, Err(Error::RowNotFound)));
+
+        assert_eq!(repo.transfer_links(1, 2, None).await?, 1);
+        assert_eq!(count(&pool, "SELECT COUNT(*) FROM urls WHERE user_id = 2").await?, 2);
+        Ok(())
+    }
 }